@@ -1,5 +1,23 @@
 //! OpenCL extensions which don't have external (OpenGL, D3D) dependencies.
 
+#![allow(non_camel_case_types)]
+
+pub use cl_h::{cl_uint, cl_queue_properties};
+
+// cl_khr_priority_hints:
+pub type cl_queue_priority_khr = cl_uint;
+pub const CL_QUEUE_PRIORITY_KHR:                        cl_queue_properties = 0x1096;
+pub const CL_QUEUE_PRIORITY_HIGH_KHR:                   cl_queue_priority_khr = 1 << 0;
+pub const CL_QUEUE_PRIORITY_MED_KHR:                    cl_queue_priority_khr = 1 << 1;
+pub const CL_QUEUE_PRIORITY_LOW_KHR:                    cl_queue_priority_khr = 1 << 2;
+
+// cl_khr_throttle_hints:
+pub type cl_queue_throttle_khr = cl_uint;
+pub const CL_QUEUE_THROTTLE_KHR:                        cl_queue_properties = 0x1097;
+pub const CL_QUEUE_THROTTLE_HIGH_KHR:                   cl_queue_throttle_khr = 1 << 0;
+pub const CL_QUEUE_THROTTLE_MED_KHR:                    cl_queue_throttle_khr = 1 << 1;
+pub const CL_QUEUE_THROTTLE_LOW_KHR:                    cl_queue_throttle_khr = 1 << 2;
+
 // /*******************************************************************************
 //  * Copyright (c) 2008-2015 The Khronos Group Inc.
 //  *
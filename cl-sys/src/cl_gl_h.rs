@@ -63,12 +63,8 @@ pub type clGetGLContextInfoKHR_fn = *mut extern "system" fn(
     param_value_size_ret: *mut size_t)
     -> cl_int;
 
-//#[link_args = "-L$OPENCL_LIB -lOpenCL"]
-#[cfg_attr(target_os = "macos", link(name = "OpenCL", kind = "framework"))]
-#[cfg_attr(target_os = "windows", link(name = "OpenCL"))]
-#[cfg_attr(not(target_os = "macos"), link(name = "OpenCL"))]
 #[cfg(not(feature="opencl_vendor_mesa"))]  // Mesa does not support context sharing with OpenGL.
-extern "system" {
+cl_api! {
     pub fn clCreateFromGLBuffer(context: cl_context,
                                 flags: cl_mem_flags,
                                 bufobj: cl_GLuint,
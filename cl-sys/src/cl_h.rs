@@ -177,6 +177,8 @@ pub const CL_INVALID_LINKER_OPTIONS:                       cl_int = -67;
 pub const CL_INVALID_DEVICE_PARTITION_COUNT:               cl_int = -68;
 pub const CL_INVALID_PIPE_SIZE:                            cl_int = -69;
 pub const CL_INVALID_DEVICE_QUEUE:                         cl_int = -70;
+pub const CL_INVALID_SPEC_ID:                              cl_int = -71;
+pub const CL_MAX_SIZE_RESTRICTION_EXCEEDED:                cl_int = -72;
 pub const CL_PLATFORM_NOT_FOUND_KHR:                       cl_int = -1001;
 
 
@@ -186,6 +188,7 @@ pub const CL_VERSION_1_1:                               cl_bool = 1;
 pub const CL_VERSION_1_2:                               cl_bool = 1;
 pub const CL_VERSION_2_0:                               cl_bool = 1;
 pub const CL_VERSION_2_1:                               cl_bool = 1;
+pub const CL_VERSION_2_2:                               cl_bool = 1;
 
 // cl_bool:
 pub const CL_FALSE:                                     cl_bool = 0;
@@ -310,6 +313,8 @@ pub const CL_DEVICE_IMAGE_BASE_ADDRESS_ALIGNMENT:           cl_uint = 0x104B;
     pub const CL_DEVICE_IL_VERSION:                             cl_uint = 0x105B;
     pub const CL_DEVICE_MAX_NUM_SUB_GROUPS:                     cl_uint = 0x105C;
     pub const CL_DEVICE_SUB_GROUP_INDEPENDENT_FORWARD_PROGRESS: cl_uint = 0x105D;
+    // OpenCL 3.0:
+    pub const CL_DEVICE_NON_UNIFORM_WORK_GROUP_SUPPORT:         cl_uint = 0x1065;
 
 // cl_device_fp_config - bitfield:
 pub const CL_FP_DENORM:                                 cl_bitfield = 1 << 0;
@@ -661,11 +666,9 @@ pub const CL_PROFILING_COMMAND_END:                     cl_uint = 0x1283;
     pub const CL_PROFILING_COMMAND_COMPLETE:                cl_uint = 0x1284;
 
 
-//#[link_args = "-L$OPENCL_LIB -lOpenCL"]
-#[cfg_attr(target_os = "macos", link(name = "OpenCL", kind = "framework"))]
-#[cfg_attr(target_os = "windows", link(name = "OpenCL"))]
-#[cfg_attr(not(target_os = "macos"), link(name = "OpenCL"))]
-extern "system" {
+// Linked against the OpenCL library or, with the `dynamic` feature, loaded at
+// runtime (see `cl_api!`).
+cl_api! {
     // Platform API:
     pub fn clGetPlatformIDs(num_entries: cl_uint,
                             platforms: *mut cl_platform_id,
@@ -733,8 +736,8 @@ extern "system" {
     //############################### NEW 2.1 #################################
     #[cfg(feature = "opencl_version_2_1")]
     pub fn clGetDeviceAndHostTimer(device: cl_device_id,
-                                   device_timestamp: cl_ulong,
-                                   host_timestamp: cl_ulong) -> cl_int;
+                                   device_timestamp: *mut cl_ulong,
+                                   host_timestamp: *mut cl_ulong) -> cl_int;
 
     // extern CL_API_ENTRY cl_int CL_API_CALL
     // clGetHostTimer(cl_device_id /* device */,
@@ -742,7 +745,7 @@ extern "system" {
     //############################### NEW 2.1 #################################
     #[cfg(feature = "opencl_version_2_1")]
     pub fn clGetHostTimer(device: cl_device_id,
-                          host_timestamp: cl_ulong) -> cl_int;
+                          host_timestamp: *mut cl_ulong) -> cl_int;
 
     // Context APIs:
     pub fn clCreateContext(properties: *const cl_context_properties,
@@ -987,6 +990,18 @@ extern "system" {
                                  length: size_t,
                                  errcode_ret: *mut cl_int) -> cl_program;
 
+    // extern CL_API_ENTRY cl_int CL_API_CALL
+    // clSetProgramSpecializationConstant(cl_program  /* program */,
+    //                                    cl_uint     /* spec_id */,
+    //                                    size_t      /* spec_size */,
+    //                                    const void* /* spec_value */) CL_API_SUFFIX__VERSION_2_2;
+    //############################### NEW 2.2 #################################
+    #[cfg(feature = "opencl_version_2_2")]
+    pub fn clSetProgramSpecializationConstant(program: cl_program,
+                                              spec_id: cl_uint,
+                                              spec_size: size_t,
+                                              spec_value: *const c_void) -> cl_int;
+
     pub fn clRetainProgram(program: cl_program) -> cl_int;
 
     pub fn clReleaseProgram(program: cl_program) -> cl_int;
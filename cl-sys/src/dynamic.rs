@@ -0,0 +1,210 @@
+//! Runtime loading of the OpenCL library (`dynamic` feature).
+//!
+//! Rather than linking against `libOpenCL`/`OpenCL.dll`/`OpenCL.framework`,
+//! the library is opened the first time an API function is called and each
+//! `cl*` symbol is resolved by name. This allows a binary to start (and fall
+//! back to other code paths) on a machine without an ICD loader installed.
+//!
+//! A custom resolver can be installed instead of the system library with
+//! `load_library_with` (used by `ocl-mock`).
+
+use std::fmt;
+use std::error::Error;
+use std::ffi::CString;
+use std::ptr;
+use std::mem;
+use std::sync::Once;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use libc::{c_void, c_char};
+
+
+#[cfg(target_os = "windows")]
+const LIBRARY_NAMES: &'static [&'static str] = &["OpenCL.dll"];
+
+#[cfg(target_os = "macos")]
+const LIBRARY_NAMES: &'static [&'static str] = &[
+    "/System/Library/Frameworks/OpenCL.framework/OpenCL",
+    "libOpenCL.dylib",
+];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const LIBRARY_NAMES: &'static [&'static str] = &["libOpenCL.so.1", "libOpenCL.so"];
+
+
+/// A function returning the address of the API function named `name` (without
+/// a nul terminator) or null if it is not available.
+pub type SymbolResolver = fn(name: &str) -> *mut c_void;
+
+/// The resolver passed to `load_library_with`, if any.
+static RESOLVER: AtomicUsize = AtomicUsize::new(0);
+
+
+#[derive(Debug, Clone)]
+enum LoadLibraryErrorKind {
+    NotFound(&'static [&'static str]),
+    AlreadyLoaded,
+}
+
+/// An error returned when the OpenCL library can not be loaded.
+#[derive(Debug, Clone)]
+pub struct LoadLibraryError {
+    kind: LoadLibraryErrorKind,
+}
+
+impl LoadLibraryError {
+    /// Returns the list of library names which were tried.
+    pub fn tried(&self) -> &'static [&'static str] {
+        match self.kind {
+            LoadLibraryErrorKind::NotFound(tried) => tried,
+            LoadLibraryErrorKind::AlreadyLoaded => &[],
+        }
+    }
+
+    /// Returns true if this error was caused by calling `load_library_with`
+    /// after the library had already been loaded from elsewhere.
+    pub fn is_already_loaded(&self) -> bool {
+        match self.kind {
+            LoadLibraryErrorKind::AlreadyLoaded => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for LoadLibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            LoadLibraryErrorKind::NotFound(tried) => write!(f, "OpenCL library not found \
+                (tried: {}). Ensure that an OpenCL ICD loader is installed.", tried.join(", ")),
+            LoadLibraryErrorKind::AlreadyLoaded => write!(f, "The OpenCL library has already \
+                been loaded and can not be replaced by a custom resolver."),
+        }
+    }
+}
+
+impl Error for LoadLibraryError {
+    fn description(&self) -> &str {
+        match self.kind {
+            LoadLibraryErrorKind::NotFound(_) => "OpenCL library not found",
+            LoadLibraryErrorKind::AlreadyLoaded => "OpenCL library already loaded",
+        }
+    }
+}
+
+
+/// The loaded library (never closed) or a custom resolver.
+enum Library {
+    Handle(*mut c_void),
+    Resolver(SymbolResolver),
+}
+
+unsafe impl Send for Library {}
+unsafe impl Sync for Library {}
+
+
+#[cfg(unix)]
+unsafe fn open(name: &CString) -> *mut c_void {
+    ::libc::dlopen(name.as_ptr(), ::libc::RTLD_NOW | ::libc::RTLD_LOCAL)
+}
+
+#[cfg(unix)]
+unsafe fn sym(handle: *mut c_void, name: *const c_char) -> *mut c_void {
+    ::libc::dlsym(handle, name)
+}
+
+#[cfg(windows)]
+extern "system" {
+    fn LoadLibraryA(name: *const c_char) -> *mut c_void;
+    fn GetProcAddress(module: *mut c_void, name: *const c_char) -> *mut c_void;
+}
+
+#[cfg(windows)]
+unsafe fn open(name: &CString) -> *mut c_void {
+    LoadLibraryA(name.as_ptr())
+}
+
+#[cfg(windows)]
+unsafe fn sym(handle: *mut c_void, name: *const c_char) -> *mut c_void {
+    GetProcAddress(handle, name)
+}
+
+
+/// Returns the loaded library or the error encountered while loading it.
+fn library() -> &'static Result<Library, LoadLibraryError> {
+    static INIT: Once = Once::new();
+    static mut LIBRARY: *const Result<Library, LoadLibraryError> = 0 as *const _;
+
+    unsafe {
+        INIT.call_once(|| {
+            let resolver = RESOLVER.load(Ordering::SeqCst);
+
+            let library = if resolver != 0 {
+                Ok(Library::Resolver(mem::transmute::<usize, SymbolResolver>(resolver)))
+            } else {
+                let handle = LIBRARY_NAMES.iter()
+                    .map(|name| open(&CString::new(*name).unwrap()))
+                    .find(|handle| !handle.is_null());
+
+                match handle {
+                    Some(handle) => Ok(Library::Handle(handle)),
+                    None => Err(LoadLibraryError {
+                        kind: LoadLibraryErrorKind::NotFound(LIBRARY_NAMES)
+                    }),
+                }
+            };
+
+            LIBRARY = Box::into_raw(Box::new(library));
+        });
+        &*LIBRARY
+    }
+}
+
+/// Loads the OpenCL library if it has not already been loaded.
+///
+/// Calling this is never necessary, as the library is loaded automatically
+/// the first time any API function is called. It can, however, be used to
+/// determine whether or not OpenCL is available before doing so (calling an
+/// API function when the library is not available will panic).
+pub fn load_library() -> Result<(), LoadLibraryError> {
+    library().as_ref().map(|_| ()).map_err(|err| err.clone())
+}
+
+/// Resolves all API functions using `resolver` instead of loading the
+/// system OpenCL library.
+///
+/// Must be called before any API function. Calling this again with the same
+/// resolver has no effect. Returns an error if the system library (or a
+/// different resolver) is already in use.
+pub fn load_library_with(resolver: SymbolResolver) -> Result<(), LoadLibraryError> {
+    // Only the first resolver is ever used:
+    let _ = RESOLVER.compare_exchange(0, resolver as usize, Ordering::SeqCst, Ordering::SeqCst);
+
+    match *library() {
+        Ok(Library::Resolver(r)) if r as usize == resolver as usize => Ok(()),
+        _ => Err(LoadLibraryError { kind: LoadLibraryErrorKind::AlreadyLoaded }),
+    }
+}
+
+/// Returns the address of the nul-terminated symbol `name` or null if either
+/// the library or the symbol is unavailable.
+pub fn symbol(name: &str) -> *mut c_void {
+    debug_assert!(name.ends_with('\0'));
+
+    match *library() {
+        Ok(Library::Handle(handle)) => unsafe { sym(handle, name.as_ptr() as *const c_char) },
+        Ok(Library::Resolver(resolver)) => resolver(&name[..name.len() - 1]),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Panics with an explanation of why the API function `name` can not be
+/// called.
+#[cold]
+pub fn missing_symbol(name: &str) -> ! {
+    match *library() {
+        Ok(Library::Handle(_)) => panic!("cl-sys: The function '{}' is not exported by \
+            the loaded OpenCL library (it may be too old to support it).", name),
+        Ok(Library::Resolver(_)) => panic!("cl-sys: The function '{}' is not provided \
+            by the installed resolver.", name),
+        Err(ref err) => panic!("cl-sys: Unable to call '{}': {}", name, err),
+    }
+}
@@ -9,11 +9,14 @@
 
 pub extern crate libc;
 
+#[macro_use] mod macros;
+#[cfg(feature = "dynamic")] mod dynamic;
 mod platform_h;
 mod glcorearb_h;
 mod cl_gl_h;
 mod cl_egl_h;
 mod cl_gl_ext_h;
+mod cl_ext_h;
 mod cl_dx9_media_sharing_h;
 mod cl_d3d10_h;
 mod cl_d3d11_h;
@@ -22,6 +25,9 @@ mod cl_h;
 pub use libc::{c_void, size_t, c_char, c_double, c_float, c_int, c_longlong, c_short, c_uchar,
     c_uint, c_ulonglong, c_ushort};
 
+#[cfg(feature = "dynamic")]
+pub use self::dynamic::{load_library, load_library_with, LoadLibraryError, SymbolResolver};
+
 pub use self::platform_h::{cl_GLuint, cl_GLint, cl_GLenum};
 
 pub use self::glcorearb_h::{GL_TEXTURE_1D, GL_TEXTURE_1D_ARRAY, GL_TEXTURE_BUFFER,
@@ -50,6 +56,11 @@ pub use self::cl_egl_h::{CLeglImageKHR, CLeglDisplayKHR, CLeglSyncKHR, cl_egl_im
 
 pub use self::cl_gl_ext_h::{CL_CONTEXT_PROPERTY_USE_CGL_SHAREGROUP_APPLE};
 
+pub use self::cl_ext_h::{cl_queue_priority_khr, cl_queue_throttle_khr, CL_QUEUE_PRIORITY_KHR,
+    CL_QUEUE_PRIORITY_HIGH_KHR, CL_QUEUE_PRIORITY_MED_KHR, CL_QUEUE_PRIORITY_LOW_KHR,
+    CL_QUEUE_THROTTLE_KHR, CL_QUEUE_THROTTLE_HIGH_KHR, CL_QUEUE_THROTTLE_MED_KHR,
+    CL_QUEUE_THROTTLE_LOW_KHR};
+
 pub use self::cl_d3d10_h::{CL_CONTEXT_D3D10_DEVICE_KHR, cl_d3d10_device_source_khr,
     cl_d3d10_device_set_khr};
 
@@ -98,10 +109,10 @@ pub use self::cl_h::{CL_SUCCESS, CL_DEVICE_NOT_FOUND, CL_DEVICE_NOT_AVAILABLE,
     CL_INVALID_MIP_LEVEL, CL_INVALID_GLOBAL_WORK_SIZE, CL_INVALID_PROPERTY,
     CL_INVALID_IMAGE_DESCRIPTOR, CL_INVALID_COMPILER_OPTIONS, CL_INVALID_LINKER_OPTIONS,
     CL_INVALID_DEVICE_PARTITION_COUNT, CL_INVALID_PIPE_SIZE, CL_INVALID_DEVICE_QUEUE,
-    CL_PLATFORM_NOT_FOUND_KHR,};
+    CL_INVALID_SPEC_ID, CL_MAX_SIZE_RESTRICTION_EXCEEDED, CL_PLATFORM_NOT_FOUND_KHR,};
 
 pub use self::cl_h::{CL_VERSION_1_0, CL_VERSION_1_1, CL_VERSION_1_2, CL_VERSION_2_0,
-    CL_VERSION_2_1, CL_FALSE, CL_TRUE, CL_BLOCKING, CL_NON_BLOCKING, CL_PLATFORM_PROFILE,
+    CL_VERSION_2_1, CL_VERSION_2_2, CL_FALSE, CL_TRUE, CL_BLOCKING, CL_NON_BLOCKING, CL_PLATFORM_PROFILE,
     CL_PLATFORM_VERSION, CL_PLATFORM_NAME, CL_PLATFORM_VENDOR, CL_PLATFORM_EXTENSIONS,
     CL_PLATFORM_HOST_TIMER_RESOLUTION, CL_DEVICE_TYPE_DEFAULT, CL_DEVICE_TYPE_CPU,
     CL_DEVICE_TYPE_GPU, CL_DEVICE_TYPE_ACCELERATOR, CL_DEVICE_TYPE_CUSTOM, CL_DEVICE_TYPE_ALL,
@@ -143,9 +154,10 @@ pub use self::cl_h::{CL_VERSION_1_0, CL_VERSION_1_1, CL_VERSION_1_2, CL_VERSION_
     CL_DEVICE_PIPE_MAX_ACTIVE_RESERVATIONS, CL_DEVICE_PIPE_MAX_PACKET_SIZE,
     CL_DEVICE_PREFERRED_PLATFORM_ATOMIC_ALIGNMENT, CL_DEVICE_PREFERRED_GLOBAL_ATOMIC_ALIGNMENT,
     CL_DEVICE_PREFERRED_LOCAL_ATOMIC_ALIGNMENT, CL_DEVICE_IL_VERSION, CL_DEVICE_MAX_NUM_SUB_GROUPS,
-    CL_DEVICE_SUB_GROUP_INDEPENDENT_FORWARD_PROGRESS, CL_FP_DENORM, CL_FP_INF_NAN,
-    CL_FP_ROUND_TO_NEAREST, CL_FP_ROUND_TO_ZERO, CL_FP_ROUND_TO_INF, CL_FP_FMA, CL_FP_SOFT_FLOAT,
-    CL_FP_CORRECTLY_ROUNDED_DIVIDE_SQRT, CL_NONE, CL_READ_ONLY_CACHE, CL_READ_WRITE_CACHE,
+    CL_DEVICE_SUB_GROUP_INDEPENDENT_FORWARD_PROGRESS, CL_DEVICE_NON_UNIFORM_WORK_GROUP_SUPPORT,
+    CL_FP_DENORM, CL_FP_INF_NAN, CL_FP_ROUND_TO_NEAREST, CL_FP_ROUND_TO_ZERO, CL_FP_ROUND_TO_INF,
+    CL_FP_FMA, CL_FP_SOFT_FLOAT, CL_FP_CORRECTLY_ROUNDED_DIVIDE_SQRT, CL_NONE, CL_READ_ONLY_CACHE,
+    CL_READ_WRITE_CACHE,
     CL_LOCAL, CL_GLOBAL, CL_EXEC_KERNEL, CL_EXEC_NATIVE_KERNEL,
     CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE, CL_QUEUE_PROFILING_ENABLE, CL_QUEUE_ON_DEVICE,
     CL_QUEUE_ON_DEVICE_DEFAULT, CL_CONTEXT_REFERENCE_COUNT, CL_CONTEXT_DEVICES,
@@ -249,3 +261,6 @@ pub use self::cl_h::{clCreateCommandQueueWithProperties, clCreatePipe, clGetPipe
 #[cfg(feature = "opencl_version_2_1")]
 pub use self::cl_h::{clSetDefaultDeviceCommandQueue, clGetDeviceAndHostTimer, clGetHostTimer,
     clCreateProgramWithIL, clCloneKernel, clGetKernelSubGroupInfo, clEnqueueSVMMigrateMem};
+
+#[cfg(feature = "opencl_version_2_2")]
+pub use self::cl_h::clSetProgramSpecializationConstant;
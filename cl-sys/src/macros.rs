@@ -0,0 +1,76 @@
+//! Macros used to declare the OpenCL API functions.
+
+
+/// Declares a block of OpenCL API functions.
+///
+/// Without the `dynamic` feature this expands to an ordinary `extern` block
+/// linked against the OpenCL library (framework on macOS).
+#[cfg(not(feature = "dynamic"))]
+macro_rules! cl_api {
+    ($( $(#[$attr:meta])* pub fn $name:ident($($arg:ident: $ty:ty),* $(,)*) $(-> $ret:ty)*; )*) => (
+        #[cfg_attr(target_os = "macos", link(name = "OpenCL", kind = "framework"))]
+        #[cfg_attr(target_os = "windows", link(name = "OpenCL"))]
+        #[cfg_attr(not(target_os = "macos"), link(name = "OpenCL"))]
+        extern "system" {
+            $(
+                $(#[$attr])*
+                pub fn $name($($arg: $ty),*) $(-> $ret)*;
+            )*
+        }
+    )
+}
+
+
+/// Declares a block of OpenCL API functions.
+///
+/// With the `dynamic` feature enabled, each function becomes a thin wrapper
+/// calling through a table of function pointers. The table is filled in from
+/// the OpenCL library (loaded with `dlopen`/`LoadLibrary`) the first time any
+/// function in the block is called.
+///
+/// Calling a function whose symbol could not be resolved (because the library
+/// is not installed or is too old to export it) will panic. Use
+/// `::load_library` to check availability beforehand.
+#[cfg(feature = "dynamic")]
+macro_rules! cl_api {
+    ($( $(#[$attr:meta])* pub fn $name:ident($($arg:ident: $ty:ty),* $(,)*) $(-> $ret:ty)*; )*) => (
+        #[allow(non_snake_case)]
+        struct Functions {
+            $(
+                $(#[$attr])*
+                $name: Option<unsafe extern "system" fn($($ty),*) $(-> $ret)*>,
+            )*
+        }
+
+        fn functions() -> &'static Functions {
+            static INIT: ::std::sync::Once = ::std::sync::Once::new();
+            static mut FUNCTIONS: *const Functions = 0 as *const Functions;
+
+            unsafe {
+                INIT.call_once(|| {
+                    let functions = Functions {
+                        $(
+                            $(#[$attr])*
+                            $name: ::std::mem::transmute(
+                                ::dynamic::symbol(concat!(stringify!($name), "\0"))),
+                        )*
+                    };
+                    FUNCTIONS = Box::into_raw(Box::new(functions));
+                });
+                &*FUNCTIONS
+            }
+        }
+
+        $(
+            $(#[$attr])*
+            #[inline]
+            #[allow(non_snake_case)]
+            pub unsafe fn $name($($arg: $ty),*) $(-> $ret)* {
+                match functions().$name {
+                    Some(f) => f($($arg),*),
+                    None => ::dynamic::missing_symbol(stringify!($name)),
+                }
+            }
+        )*
+    )
+}
@@ -21,7 +21,7 @@ use std::time::Duration;
 use std::env;
 use std::fmt;
 use failure::Fail;
-use ffi::{size_t, c_void};
+use ffi::{size_t, c_void, c_char};
 use num_traits::FromPrimitive;
 
 #[cfg(not(feature="opencl_vendor_mesa"))]
@@ -38,6 +38,12 @@ use ffi::{self, cl_bool, cl_int, cl_uint, cl_platform_id, cl_device_id, cl_devic
     cl_addressing_mode, cl_filter_mode, cl_command_queue_info, cl_command_queue, cl_image_info,
     cl_sampler, cl_sampler_info, cl_program_info, cl_kernel_info, cl_kernel_arg_info,
     cl_kernel_work_group_info, cl_event_info, cl_profiling_info};
+#[cfg(feature = "opencl_version_2_0")]
+use ffi::{cl_svm_mem_flags, cl_kernel_exec_info, cl_pipe_info, cl_pipe_properties};
+#[cfg(feature = "opencl_version_2_1")]
+use ffi::{cl_ulong, cl_kernel_sub_group_info};
+#[cfg(feature = "opencl_version_2_1")]
+use ::{KernelSubGroupInfo, KernelSubGroupInfoResult};
 
 use error::{Error as OclCoreError, Result as OclCoreResult};
 
@@ -53,7 +59,10 @@ use ::{OclPrm, PlatformId, DeviceId, Context, ContextProperties, ContextInfo,
     CreateContextCallbackFn, UserDataPtr, ClPlatformIdPtr, ClDeviceIdPtr, ClContextPtr,
     EventCallbackFn, BuildProgramCallbackFn, MemMigrationFlags, MapFlags, BufferRegion,
     BufferCreateType, OpenclVersion, ClVersions, Status, CommandQueueProperties, MemMap, AsMem,
-    MemCmdRw, MemCmdAll, Event, ImageFormatParseResult};
+    MemCmdRw, MemCmdAll, Event, ImageFormatParseResult, DevicePartition, NativeKernelFn,
+    MemDestructorCallbackFn};
+#[cfg(feature = "opencl_version_2_0")]
+use ::{PipeInfo, PipeInfoResult, QueueProperties};
 
 #[cfg(not(feature="opencl_vendor_mesa"))]
 use ::{GlContextInfo, GlContextInfoResult};
@@ -225,6 +234,218 @@ pub enum ProgramBuildError {
     InfoResult(Box<OclCoreError>),
 }
 
+impl ProgramBuildError {
+    /// Returns the build log, if any.
+    pub fn build_log(&self) -> Option<&str> {
+        match *self {
+            ProgramBuildError::BuildLog(ref log) => Some(log),
+            _ => None,
+        }
+    }
+
+    /// Returns the diagnostics parsed from the build log (see
+    /// `BuildDiagnostic::parse_log`).
+    pub fn diagnostics(&self) -> Vec<BuildDiagnostic> {
+        self.build_log().map(BuildDiagnostic::parse_log).unwrap_or(Vec::new())
+    }
+}
+
+
+/// The severity labels used by the supported compilers.
+const DIAGNOSTIC_LABELS: [(&'static str, DiagnosticSeverity); 6] = [
+    ("catastrophic error", DiagnosticSeverity::Error),
+    ("fatal error", DiagnosticSeverity::Error),
+    ("error", DiagnosticSeverity::Error),
+    ("warning", DiagnosticSeverity::Warning),
+    ("remark", DiagnosticSeverity::Note),
+    ("note", DiagnosticSeverity::Note),
+];
+
+/// Splits a trailing `:<number>` from `location`.
+fn split_line_number(location: &str) -> Option<(&str, u32)> {
+    let colon = location.rfind(':')?;
+    let number = location[colon + 1..].trim().parse::<u32>().ok()?;
+    Some((&location[..colon], number))
+}
+
+
+/// The severity of a program build diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl DiagnosticSeverity {
+    fn from_label(label: &str) -> Option<DiagnosticSeverity> {
+        DIAGNOSTIC_LABELS.iter().find(|&&(l, _)| l == label).map(|&(_, sev)| sev)
+    }
+}
+
+impl fmt::Display for DiagnosticSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DiagnosticSeverity::Error => write!(f, "error"),
+            DiagnosticSeverity::Warning => write!(f, "warning"),
+            DiagnosticSeverity::Note => write!(f, "note"),
+        }
+    }
+}
+
+
+/// An error, warning or note parsed from a program build log.
+///
+/// Line and column numbers are one-based and, for diagnostics within the
+/// program source, refer to the concatenation of all source strings passed
+/// to `::create_program_with_source`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildDiagnostic {
+    severity: DiagnosticSeverity,
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    message: String,
+}
+
+impl BuildDiagnostic {
+    /// Parses a program build log, returning each diagnostic it contains.
+    ///
+    /// Two formats are recognized, covering the common compilers:
+    ///
+    /// * Clang-based (Intel, Apple, Mesa, POCL, AMD ROCm and current NVIDIA
+    ///   drivers): `<file>:<line>:<column>: error: <message>`.
+    /// * EDG-based (older AMD and NVIDIA drivers):
+    ///   `"<file>", line <line>: error: <message>`.
+    ///
+    /// Any other lines, such as source excerpts, carets and summaries, are
+    /// skipped.
+    pub fn parse_log(log: &str) -> Vec<BuildDiagnostic> {
+        log.lines()
+            .filter_map(|line| BuildDiagnostic::parse_edg(line)
+                .or_else(|| BuildDiagnostic::parse_clang(line)))
+            .collect()
+    }
+
+    /// Parses `<file>:<line>:<column>: <severity>: <message>`, where the
+    /// location may be partially or entirely absent.
+    fn parse_clang(line: &str) -> Option<BuildDiagnostic> {
+        // The earliest label wins as messages may themselves contain labels:
+        let (loc_end, label, severity) = DIAGNOSTIC_LABELS.iter()
+            .filter_map(|&(label, severity)| {
+                if line.starts_with(label) && line[label.len()..].starts_with(": ") {
+                    Some((0, label, severity))
+                } else {
+                    line.find(&format!(": {}: ", label)).map(|idx| (idx, label, severity))
+                }
+            })
+            .min_by_key(|&(idx, _, _)| idx)?;
+
+        let (file, line_num, column) = if loc_end == 0 {
+            (None, None, None)
+        } else {
+            let location = &line[..loc_end];
+            match split_line_number(location) {
+                Some((rest, last)) => match split_line_number(rest) {
+                    Some((file, line_num)) => (Some(file), Some(line_num), Some(last)),
+                    None => (Some(rest), Some(last), None),
+                },
+                None => (Some(location), None, None),
+            }
+        };
+
+        let msg_start = if loc_end == 0 { label.len() + 2 } else { loc_end + label.len() + 4 };
+
+        Some(BuildDiagnostic {
+            severity: severity,
+            file: file.map(|f| f.trim().to_owned()),
+            line: line_num,
+            column: column,
+            message: line[msg_start..].trim().to_owned(),
+        })
+    }
+
+    /// Parses `"<file>", line <line>: <severity>: <message>`.
+    fn parse_edg(line: &str) -> Option<BuildDiagnostic> {
+        const LINE_MARKER: &'static str = "\", line ";
+
+        let line = line.trim_left();
+        if !line.starts_with('"') { return None; }
+
+        let file_end = line.find(LINE_MARKER)?;
+        let rest = &line[file_end + LINE_MARKER.len()..];
+        let line_end = rest.find(':')?;
+        let line_num = rest[..line_end].trim().parse::<u32>().ok()?;
+
+        let rest = rest[line_end + 1..].trim_left();
+        let label_end = rest.find(": ")?;
+        let severity = DiagnosticSeverity::from_label(&rest[..label_end])?;
+
+        Some(BuildDiagnostic {
+            severity: severity,
+            file: Some(line[1..file_end].to_owned()),
+            line: Some(line_num),
+            column: None,
+            message: rest[label_end + 2..].trim().to_owned(),
+        })
+    }
+
+    /// Returns the severity of this diagnostic.
+    pub fn severity(&self) -> DiagnosticSeverity {
+        self.severity
+    }
+
+    /// Returns the file name reported by the compiler, if any.
+    ///
+    /// Compilers give the program source a placeholder name (`<source>`,
+    /// `<kernel>`, a temporary file, etc.). See `::is_program_source`.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_ref().map(|f| f.as_str())
+    }
+
+    /// Returns the line number, if any.
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// Returns the column number, if any.
+    pub fn column(&self) -> Option<u32> {
+        self.column
+    }
+
+    /// Returns the diagnostic message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns true if this diagnostic refers to a location within the
+    /// program source rather than within a header included from disk or no
+    /// location at all.
+    pub fn is_program_source(&self) -> bool {
+        let file = match self.file {
+            Some(ref file) => file.trim(),
+            None => return false,
+        };
+        let name = file.rsplit(|c| c == '/' || c == '\\').next().unwrap_or(file);
+
+        // Placeholder names (`<source>`, `<kernel>`, ...), an empty name or
+        // the temporary files used by AMD's legacy compiler (`OCL1234.cl`,
+        // `OCL1234T5.cl`, ...):
+        file.is_empty() || (file.starts_with('<') && file.ends_with('>')) ||
+            (name.starts_with("OCL") && name.ends_with(".cl"))
+    }
+}
+
+impl fmt::Display for BuildDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref file) = self.file { write!(f, "{}:", file)?; }
+        if let Some(line) = self.line { write!(f, "{}:", line)?; }
+        if let Some(column) = self.column { write!(f, "{}:", column)?; }
+        if self.file.is_some() || self.line.is_some() { write!(f, " ")?; }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
 
 /// If the program pointed to by `cl_program` for any of the devices listed in
 /// `device_ids` has a build log of any length, it will be returned as an
@@ -256,6 +477,7 @@ pub fn program_build_err<D: ClDeviceIdPtr>(program: &Program, device_ids: &[D])
 #[derive(Debug)]
 pub(crate) enum ApiFunction {
     None,
+    CreateSubDevices,
     RetainDevice,
     ReleaseDevice,
     CreateImage,
@@ -266,7 +488,25 @@ pub(crate) enum ApiFunction {
     EnqueueMigrateMemObjects,
     EnqueueMarkerWithWaitList,
     EnqueueBarrierWithWaitList,
+    CompileProgram,
+    LinkProgram,
     GetExtensionFunctionAddressForPlatform,
+    #[cfg(feature = "opencl_version_2_0")] CreateCommandQueueWithProperties,
+    #[cfg(feature = "opencl_version_2_0")] CreatePipe,
+    #[cfg(feature = "opencl_version_2_0")] SvmAlloc,
+    #[cfg(feature = "opencl_version_2_0")] SetKernelArgSvmPointer,
+    #[cfg(feature = "opencl_version_2_0")] SetKernelExecInfo,
+    #[cfg(feature = "opencl_version_2_0")] EnqueueSvmFree,
+    #[cfg(feature = "opencl_version_2_0")] EnqueueSvmMemcpy,
+    #[cfg(feature = "opencl_version_2_0")] EnqueueSvmMemFill,
+    #[cfg(feature = "opencl_version_2_0")] EnqueueSvmMap,
+    #[cfg(feature = "opencl_version_2_0")] EnqueueSvmUnmap,
+    #[cfg(feature = "opencl_version_2_1")] GetDeviceAndHostTimer,
+    #[cfg(feature = "opencl_version_2_1")] GetHostTimer,
+    #[cfg(feature = "opencl_version_2_1")] CreateProgramWithIl,
+    #[cfg(feature = "opencl_version_2_1")] CloneKernel,
+    #[cfg(feature = "opencl_version_2_1")] GetKernelSubGroupInfo,
+    #[cfg(feature = "opencl_version_2_2")] SetProgramSpecializationConstant,
 }
 
 
@@ -295,6 +535,8 @@ pub struct VersionLowError {
 pub enum ApiWrapperError {
     #[fail(display = "Unable to get platform id list after {} seconds of waiting.", _0)]
     GetPlatformIdsPlatformListUnavailable(u64),
+    #[fail(display = "{}", _0)]
+    GetPlatformIdsLibraryNotFound(String),
     #[fail(display = "`devices_max` can not be zero.")]
     GetDeviceIdsDevicesMaxZero,
     #[fail(display = "No devices specified.")]
@@ -311,6 +553,20 @@ pub enum ApiWrapperError {
     #[fail(display = "Length of 'devices' must equal the length of 'binaries' \
         (e.g. one binary per device).")]
     CreateProgramWithBinaryDevicesLenMismatch,
+    #[fail(display = "Length of 'input_headers' must equal the length of \
+        'header_include_names' (e.g. one name per header).")]
+    CompileProgramHeadersLenMismatch,
+    #[fail(display = "At least one input program must be specified.")]
+    LinkProgramNoInputPrograms,
+    #[fail(display = "Length of 'mem_list' must equal the length of 'args_mem_loc' \
+        (e.g. one location per memory object).")]
+    EnqueueNativeKernelMemLenMismatch,
+    #[fail(display = "A memory object location ({}) lies outside of 'args'.", _0)]
+    EnqueueNativeKernelMemLocOutOfRange(usize),
+    #[fail(display = "Unable to allocate {} bytes of shared virtual memory. The \
+        size, alignment or flags may be invalid or unsupported by the devices in the \
+        context.", _0)]
+    SvmAllocFailed(usize),
     #[fail(display = "The specified function does not exist for the implementation or \
         'platform' is not a valid platform.")]
     GetExtensionFunctionAddressForPlatformInvalidFunction,
@@ -435,7 +691,19 @@ fn verify_device_versions<V: ClVersions>(provided_versions: Option<&[OpenclVersi
 //============================================================================
 
 /// Returns a list of available platforms as 'core' objects.
+///
+/// When built with the `dynamic` feature, an error is returned if the OpenCL
+/// library can not be loaded at runtime.
 pub fn get_platform_ids() -> OclCoreResult<Vec<PlatformId>> {
+    // Ensure the library is present before calling into it (calling an
+    // unresolved function would otherwise panic):
+    #[cfg(feature = "dynamic")]
+    {
+        if let Err(err) = ffi::load_library() {
+            return Err(ApiWrapperError::GetPlatformIdsLibraryNotFound(err.to_string()).into());
+        }
+    }
+
     let mut num_platforms = 0 as cl_uint;
 
     // Get a count of available platforms:
@@ -645,18 +913,39 @@ pub fn get_device_info<D: ClDeviceIdPtr>(device: D, request: DeviceInfo)
     }
 }
 
-/// [UNIMPLEMENTED: Please implement me]
+/// Creates sub-devices by partitioning `device` according to `partition`.
+///
+/// Each returned sub-device has a reference count of one and must eventually
+/// be released using `release_device`.
 ///
 /// [Version Controlled: OpenCL 1.2+] See module docs for more info.
-pub fn create_sub_devices(device_version: Option<&OpenclVersion>) -> OclCoreResult<()> {
-    // clCreateSubDevices(in_device: cl_device_id,
-    //                    properties: *const cl_device_partition_property,
-    //                    num_devices: cl_uint,
-    //                    out_devices: *mut cl_device_id,
-    //                    num_devices_ret: *mut cl_uint) -> cl_int;
-
-    let _ = device_version;
-    unimplemented!();
+pub fn create_sub_devices(device: &DeviceId, partition: &DevicePartition,
+        device_version: Option<&OpenclVersion>) -> OclCoreResult<Vec<DeviceId>> {
+    verify_device_version(device_version, [1, 2], device, ApiFunction::CreateSubDevices)?;
+
+    let properties = partition.to_raw();
+    let mut num_devices: cl_uint = 0;
+
+    let errcode = unsafe { ffi::clCreateSubDevices(
+        device.as_ptr(),
+        properties.as_ptr(),
+        0,
+        ptr::null_mut(),
+        &mut num_devices,
+    ) };
+    eval_errcode(errcode, (), "clCreateSubDevices", None::<String>)?;
+
+    let mut sub_devices: Vec<DeviceId> = iter::repeat(unsafe { DeviceId::null() })
+        .take(num_devices as usize).collect();
+
+    let errcode = unsafe { ffi::clCreateSubDevices(
+        device.as_ptr(),
+        properties.as_ptr(),
+        num_devices,
+        sub_devices.as_mut_ptr() as *mut cl_device_id,
+        ptr::null_mut(),
+    ) };
+    eval_errcode(errcode, sub_devices, "clCreateSubDevices", None::<String>)
 }
 
 /// Increments the reference count of a device.
@@ -677,6 +966,50 @@ pub unsafe fn release_device(device: &DeviceId, device_version: Option<&OpenclVe
     eval_errcode(ffi::clReleaseDevice(device.as_ptr()), (), "clReleaseDevice", None::<String>)
 }
 
+/// Returns a reasonably synchronized pair of timestamps, `(device, host)`,
+/// from the device timer and the host timer, in nanoseconds.
+///
+/// The device timestamp shares a timebase with event profiling info (see
+/// `get_event_profiling_info`), allowing profiling timestamps to be
+/// correlated with the host timer.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.1/docs/man/xhtml/clGetDeviceAndHostTimer.html)
+///
+/// [Version Controlled: OpenCL 2.1+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_1")]
+pub fn get_device_and_host_timer(device: &DeviceId, device_version: Option<&OpenclVersion>)
+            -> OclCoreResult<(u64, u64)> {
+    verify_device_version(device_version, [2, 1], device, ApiFunction::GetDeviceAndHostTimer)?;
+
+    let mut device_timestamp: cl_ulong = 0;
+    let mut host_timestamp: cl_ulong = 0;
+
+    let errcode = unsafe { ffi::clGetDeviceAndHostTimer(
+        device.as_ptr(),
+        &mut device_timestamp,
+        &mut host_timestamp,
+    ) };
+    eval_errcode(errcode, (device_timestamp, host_timestamp), "clGetDeviceAndHostTimer",
+        None::<String>)
+}
+
+/// Returns the current value of the host timer, as seen by `device`, in
+/// nanoseconds.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.1/docs/man/xhtml/clGetHostTimer.html)
+///
+/// [Version Controlled: OpenCL 2.1+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_1")]
+pub fn get_host_timer(device: &DeviceId, device_version: Option<&OpenclVersion>)
+            -> OclCoreResult<u64> {
+    verify_device_version(device_version, [2, 1], device, ApiFunction::GetHostTimer)?;
+
+    let mut host_timestamp: cl_ulong = 0;
+
+    let errcode = unsafe { ffi::clGetHostTimer(device.as_ptr(), &mut host_timestamp) };
+    eval_errcode(errcode, host_timestamp, "clGetHostTimer", None::<String>)
+}
+
 //============================================================================
 //============================= Context APIs  ================================
 //============================================================================
@@ -1022,6 +1355,18 @@ pub fn create_command_queue<C, D>(
         None => 0,
     };
 
+    // Use the non-deprecated properties-list based function when available:
+    #[cfg(feature = "opencl_version_2_0")]
+    {
+        let device_version = get_device_info(device, DeviceInfo::Version)?.as_opencl_version()?;
+
+        if device_version >= [2, 0].into() {
+            return create_command_queue_with_properties(context, device,
+                &QueueProperties::from(properties.unwrap_or_default()),
+                Some(&device_version));
+        }
+    }
+
     let mut errcode: cl_int = 0;
 
     let cq_ptr = unsafe { ffi::clCreateCommandQueue(
@@ -1035,6 +1380,41 @@ pub fn create_command_queue<C, D>(
 
 }
 
+/// Returns a new command queue created using a list of properties.
+///
+/// Unlike `create_command_queue`, on-device queues (see
+/// `CommandQueueProperties::ON_DEVICE`), on-device queue sizes and the
+/// `cl_khr_priority_hints` and `cl_khr_throttle_hints` scheduling hints may
+/// be specified.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clCreateCommandQueueWithProperties.html)
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub fn create_command_queue_with_properties<C, D>(
+            context: C,
+            device: D,
+            properties: &QueueProperties,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<CommandQueue>
+        where C: ClContextPtr, D: ClDeviceIdPtr
+{
+    verify_device_version(device_version, [2, 0], unsafe { &DeviceId::from_raw(device.as_ptr()) },
+        ApiFunction::CreateCommandQueueWithProperties)?;
+
+    let props_raw = properties.to_raw();
+    let mut errcode: cl_int = 0;
+
+    let cq_ptr = unsafe { ffi::clCreateCommandQueueWithProperties(
+        context.as_ptr(),
+        device.as_ptr(),
+        props_raw.as_ptr(),
+        &mut errcode
+    ) };
+    eval_errcode(errcode, cq_ptr, "clCreateCommandQueueWithProperties", None::<String>)
+        .map(|cq_ptr| unsafe { CommandQueue::from_raw_create_ptr(cq_ptr) })
+}
+
 /// Increments the reference count of a command queue.
 pub unsafe fn retain_command_queue(queue: &CommandQueue) -> OclCoreResult<()> {
     eval_errcode(ffi::clRetainCommandQueue(queue.as_ptr()), (), "clRetainCommandQueue", None::<String>)
@@ -1525,12 +1905,155 @@ pub fn get_image_info(obj: &Mem, request: ImageInfo) -> OclCoreResult<ImageInfoR
     ImageInfoResult::from_bytes(request, result)
 }
 
-/// [UNIMPLEMENTED: Please implement me]
-pub fn set_mem_object_destructor_callback() -> OclCoreResult<()> {
-    // ffi::clSetMemObjectDestructorCallback(memobj: cl_mem,
-    //                                     pfn_notify: extern fn (cl_mem, *mut c_void),
-    //                                     user_data: *mut c_void) -> cl_int;
-    unimplemented!();
+/// Registers a callback function to be called when a memory object has been
+/// released and its resources freed.
+///
+/// Callbacks are called in the reverse order of their registration. Memory
+/// passed when creating the object with `MemFlags::USE_HOST_PTR` may be
+/// safely freed or reused once the callback has been called.
+///
+/// [SDK Docs](https://www.khronos.org/registry/cl/sdk/1.2/docs/man/xhtml/clSetMemObjectDestructorCallback.html)
+///
+pub unsafe fn set_mem_object_destructor_callback(
+            mem: &Mem,
+            pfn_notify: MemDestructorCallbackFn,
+            user_data: *mut c_void,
+        ) -> OclCoreResult<()>
+{
+    eval_errcode(ffi::clSetMemObjectDestructorCallback(
+        mem.as_ptr(),
+        Some(pfn_notify),
+        user_data,
+    ), (), "clSetMemObjectDestructorCallback", None::<String>)
+}
+
+/// Creates a pipe memory object which stores up to `max_packets` packets of
+/// `packet_size` bytes each.
+///
+/// Pipes can only be accessed by kernels (using the OpenCL C
+/// `read_pipe`/`write_pipe` built-ins), allowing the output of one kernel to
+/// be consumed by another without an intermediate buffer. `flags` may only
+/// contain `MemFlags::READ_WRITE` and `MemFlags::HOST_NO_ACCESS` (the
+/// default when empty).
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clCreatePipe.html)
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub fn create_pipe<C>(
+            context: C,
+            flags: MemFlags,
+            packet_size: u32,
+            max_packets: u32,
+            device_versions: Option<&[OpenclVersion]>,
+        ) -> OclCoreResult<Mem>
+        where C: ClContextPtr
+{
+    verify_device_versions(device_versions, [2, 0], &context.as_ptr(),
+        ApiFunction::CreatePipe)?;
+
+    let mut errcode: cl_int = 0;
+
+    let pipe_ptr = unsafe { ffi::clCreatePipe(
+        context.as_ptr(),
+        flags.bits() as cl_mem_flags,
+        packet_size,
+        max_packets,
+        ptr::null() as *const cl_pipe_properties,
+        &mut errcode,
+    ) };
+
+    eval_errcode(errcode, pipe_ptr, "clCreatePipe", None::<String>)
+        .map(|ptr| unsafe { Mem::from_raw_create_ptr(ptr) })
+}
+
+/// Get pipe info.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clGetPipeInfo.html)
+#[cfg(feature = "opencl_version_2_0")]
+pub fn get_pipe_info(obj: &Mem, request: PipeInfo) -> OclCoreResult<PipeInfoResult> {
+    let mut result_size: size_t = 0;
+
+    let errcode = unsafe { ffi::clGetPipeInfo(
+        obj.as_ptr() as cl_mem,
+        request as cl_pipe_info,
+        0 as size_t,
+        0 as *mut c_void,
+        &mut result_size as *mut size_t,
+    ) };
+
+    eval_errcode(errcode, (), "clGetPipeInfo", None::<String>)?;
+
+    // If result size is zero, return an empty info result directly:
+    if result_size == 0 {
+        return PipeInfoResult::from_bytes(request, vec![]);
+    }
+
+    let mut result: Vec<u8> = iter::repeat(0u8).take(result_size).collect();
+
+    let errcode = unsafe { ffi::clGetPipeInfo(
+        obj.as_ptr() as cl_mem,
+        request as cl_pipe_info,
+        result_size as size_t,
+        result.as_mut_ptr() as *mut _ as *mut c_void,
+        0 as *mut size_t,
+    ) };
+
+    let result = eval_errcode(errcode, result, "clGetPipeInfo", None::<String>)?;
+    PipeInfoResult::from_bytes(request, result)
+}
+
+/// Allocates a shared virtual memory (SVM) buffer of `size` bytes which can
+/// be shared by the host and all devices in `context`.
+///
+/// `flags` may contain `MemFlags::SVM_FINE_GRAIN_BUFFER` and
+/// `MemFlags::SVM_ATOMICS` (see `DeviceInfo::SvmCapabilities`) in addition
+/// to the usual access flags. An `alignment` of zero uses the default
+/// alignment (the size of the largest OpenCL C data type).
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clSVMAlloc.html)
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub fn svm_alloc<C>(
+            context: C,
+            flags: MemFlags,
+            size: usize,
+            alignment: u32,
+            device_versions: Option<&[OpenclVersion]>,
+        ) -> OclCoreResult<*mut c_void>
+        where C: ClContextPtr
+{
+    verify_device_versions(device_versions, [2, 0], &context.as_ptr(),
+        ApiFunction::SvmAlloc)?;
+
+    let svm_ptr = unsafe { ffi::clSVMAlloc(
+        context.as_ptr(),
+        flags.bits() as cl_svm_mem_flags,
+        size,
+        alignment,
+    ) };
+
+    if svm_ptr.is_null() {
+        Err(ApiWrapperError::SvmAllocFailed(size).into())
+    } else {
+        Ok(svm_ptr)
+    }
+}
+
+/// Frees a shared virtual memory buffer allocated with `svm_alloc`.
+///
+/// ## Safety
+///
+/// The buffer is freed immediately, regardless of any enqueued commands
+/// which may still be using it. Use `enqueue_svm_free` to free a buffer
+/// once previously enqueued commands have completed.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clSVMFree.html)
+///
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn svm_free<C: ClContextPtr>(context: C, svm_pointer: *mut c_void) {
+    ffi::clSVMFree(context.as_ptr(), svm_pointer)
 }
 
 //============================================================================
@@ -1666,6 +2189,7 @@ pub fn create_program_with_binary<C, D>(
     }
 
     let lengths: Vec<usize> = binaries.iter().map(|bin| bin.len()).collect();
+    let binary_ptrs: Vec<*const u8> = binaries.iter().map(|bin| bin.as_ptr()).collect();
     let mut binary_status: Vec<i32> = iter::repeat(0).take(devices.len()).collect();
     let mut errcode: cl_int = 0;
 
@@ -1674,7 +2198,7 @@ pub fn create_program_with_binary<C, D>(
         devices.len() as u32,
         devices.as_ptr() as *const _ as *const cl_device_id,
         lengths.as_ptr(),
-        binaries.as_ptr() as *const *const u8,
+        binary_ptrs.as_ptr(),
         binary_status.as_mut_ptr(),
         &mut errcode,
     ) };
@@ -1713,8 +2237,8 @@ pub fn create_program_with_il<C>(
         ) -> OclCoreResult<Program>
         where C: ClContextPtr + ClVersions
 {
-    verify_device_versions(device_versions, [2, 1], &context)
-        .chain_err(|| "::create_program_with_il")?;
+    verify_device_versions(device_versions, [2, 1], &context,
+        ApiFunction::CreateProgramWithIl)?;
 
     let mut errcode: cl_int = 0;
 
@@ -1729,6 +2253,36 @@ pub fn create_program_with_il<C>(
         .map(|ptr| unsafe { Program::from_raw_create_ptr(ptr) })
 }
 
+/// Sets the value of the specialization constant identified by `spec_id` in
+/// a program created from SPIR-V.
+///
+/// `spec_value` must contain the bytes of a value of the constant's type
+/// (a single byte for booleans). Values take effect the next time the
+/// program is built.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/specs/opencl-2.2.html#clSetProgramSpecializationConstant)
+///
+/// [Version Controlled: OpenCL 2.2+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_2")]
+pub fn set_program_specialization_constant(
+        program: &Program,
+        spec_id: u32,
+        spec_value: &[u8],
+        device_versions: Option<&[OpenclVersion]>,
+        ) -> OclCoreResult<()>
+{
+    verify_device_versions(device_versions, [2, 2], program,
+        ApiFunction::SetProgramSpecializationConstant)?;
+
+    let errcode = unsafe { ffi::clSetProgramSpecializationConstant(
+        program.as_ptr(),
+        spec_id,
+        spec_value.len(),
+        spec_value.as_ptr() as *const c_void,
+    ) };
+    eval_errcode(errcode, (), "clSetProgramSpecializationConstant", None::<String>)
+}
+
 /// Increments a program reference counter.
 pub unsafe fn retain_program(program: &Program) -> OclCoreResult<()> {
     eval_errcode(ffi::clRetainProgram(program.as_ptr()), (), "clRetainProgram", None::<String>)
@@ -1739,18 +2293,13 @@ pub unsafe fn release_program(program: &Program) -> OclCoreResult<()> {
     eval_errcode(ffi::clReleaseProgram(program.as_ptr()), (), "clReleaseKernel", None::<String>)
 }
 
-pub struct UserDataPh(usize);
-
-impl UserDataPh {
-    fn unwrapped(&self) -> *mut c_void {
-        ptr::null_mut()
-    }
-}
-
 /// Builds a program.
 ///
-/// Callback functions are not yet supported. Please file an issue if you have
-/// need of this functionality.
+/// If `pfn_notify` is specified, the build may proceed asynchronously, this
+/// function returning as soon as the build has begun. `pfn_notify` is then
+/// called, with `user_data`, once the build has completed (successfully or
+/// not). Use `get_program_build_info` with `ProgramBuildInfo::BuildStatus`
+/// to determine the outcome.
 ///
 //
 // [NOTE]: Despite what the spec says, some platforms segfault when `null` is
@@ -1760,21 +2309,15 @@ pub fn build_program<D: ClDeviceIdPtr>(
             devices: Option<&[D]>,
             options: &CString,
             pfn_notify: Option<BuildProgramCallbackFn>,
-            user_data: Option<Box<UserDataPh>>,
+            user_data: Option<UserDataPtr>,
         ) -> OclCoreResult<()>
 {
-    assert!(pfn_notify.is_none() && user_data.is_none(),
-        "ocl::core::build_program(): Callback functions not yet implemented.");
-
     let (devices_len, devices_ptr) = match devices {
         Some(dvs) => (dvs.len() as u32, dvs.as_ptr() as *const cl_device_id),
         None => (0, ptr::null() as *const cl_device_id),
     };
 
-    let user_data = match user_data {
-        Some(ud) => ud.unwrapped(),
-        None => ptr::null_mut(),
-    };
+    let user_data = user_data.unwrap_or(ptr::null_mut());
 
     let errcode = unsafe { ffi::clBuildProgram(
         program.as_ptr() as cl_program,
@@ -1797,69 +2340,171 @@ pub fn build_program<D: ClDeviceIdPtr>(
     }
 }
 
-/// [UNIMPLEMENTED: Please implement me]
+/// Compiles a program's source for all or some of the devices associated
+/// with the program, producing a compiled object which may later be linked
+/// using `::link_program`.
 ///
-/// [Version Controlled: OpenCL 1.2+] See module docs for more info.
-pub fn compile_program(device_version: Option<&OpenclVersion>) -> OclCoreResult<()> {
-    // clCompileProgram(program: cl_program,
-    //                 num_devices: cl_uint,
-    //                 device_list: *const cl_device_id,
-    //                 options: *const c_char,
-    //                 num_input_headers: cl_uint,
-    //                 input_headers: *const cl_program,
-    //                 header_include_names: *const *const c_char,
-    //                 pfn_notify: extern fn (program: cl_program, user_data: *mut c_void),
-    //                 user_data: *mut c_void) -> cl_int;
-    let _ = device_version;
-    unimplemented!();
-}
-
-/// [UNIMPLEMENTED: Please implement me]
+/// `input_headers` are programs whose source is made available to
+/// `#include` directives under the name given by the corresponding element
+/// of `header_include_names`.
+///
+/// If `pfn_notify` is specified, compilation may proceed asynchronously (see
+/// `::build_program`).
 ///
 /// [Version Controlled: OpenCL 1.2+] See module docs for more info.
-pub fn link_program(device_version: Option<&OpenclVersion>) -> OclCoreResult<()> {
-    // clLinkProgram(context: cl_context,
-    //               num_devices: cl_uint,
-    //               device_list: *const cl_device_id,
-    //               options: *const c_char,
-    //               num_input_programs: cl_uint,
-    //               input_programs: *const cl_program,
-    //               pfn_notify: extern fn (program: cl_program, user_data: *mut c_void),
-    //               user_data: *mut c_void,
-    //               errcode_ret: *mut cl_int) -> cl_program;
-    let _ = device_version;
-    unimplemented!();
-}
+pub fn compile_program<D: ClDeviceIdPtr>(
+            program: &Program,
+            devices: Option<&[D]>,
+            options: &CString,
+            input_headers: &[&Program],
+            header_include_names: &[CString],
+            pfn_notify: Option<BuildProgramCallbackFn>,
+            user_data: Option<UserDataPtr>,
+            device_versions: Option<&[OpenclVersion]>,
+        ) -> OclCoreResult<()>
+{
+    verify_device_versions(device_versions, [1, 2], program, ApiFunction::CompileProgram)?;
 
-// [DISABLED DUE TO PLATFORM INCOMPATABILITY]
-// /// [UNTESTED]
-// /// Unloads a platform compiler.
-// ///
-// /// [Version Controlled: OpenCL 1.2+] See module docs for more info.
-// pub fn unload_platform_compiler(platform: &PlatformId,
-//          device_version: Option<&OpenclVersion>) -> OclCoreResult<()> {
-//     unsafe { eval_errcode("clUnloadPlatformCompiler", "",
-//         ffi::clUnloadPlatformCompiler(platform.as_ptr())) }
-// }
+    if input_headers.len() != header_include_names.len() {
+        return Err(ApiWrapperError::CompileProgramHeadersLenMismatch.into())
+    }
 
-/// Get program info.
-pub fn get_program_info(obj: &Program, request: ProgramInfo) -> OclCoreResult<ProgramInfoResult> {
-    let mut result_size: size_t = 0;
+    let (devices_len, devices_ptr) = match devices {
+        Some(dvs) => (dvs.len() as u32, dvs.as_ptr() as *const cl_device_id),
+        None => (0, ptr::null() as *const cl_device_id),
+    };
 
-    let errcode = unsafe { ffi::clGetProgramInfo(
-        obj.as_ptr() as cl_program,
-        request as cl_program_info,
-        0 as size_t,
-        0 as *mut c_void,
-        &mut result_size as *mut size_t,
-    ) };
+    let headers: Vec<cl_program> = input_headers.iter().map(|p| p.as_ptr()).collect();
+    let include_names: Vec<*const c_char> = header_include_names.iter()
+        .map(|name| name.as_ptr()).collect();
 
-    eval_errcode(errcode, (), "clGetProgramInfo", None::<String>)?;
+    let (headers_ptr, include_names_ptr) = if headers.len() > 0 {
+        (headers.as_ptr(), include_names.as_ptr())
+    } else {
+        (ptr::null(), ptr::null())
+    };
 
-    // If result size is zero, return an empty info result directly:
-    if result_size == 0 {
-        return ProgramInfoResult::from_bytes(request, vec![]);
-    }
+    let user_data = user_data.unwrap_or(ptr::null_mut());
+
+    let errcode = unsafe { ffi::clCompileProgram(
+        program.as_ptr() as cl_program,
+        devices_len,
+        devices_ptr,
+        options.as_ptr(),
+        headers.len() as u32,
+        headers_ptr,
+        include_names_ptr,
+        pfn_notify,
+        user_data,
+    ) };
+
+    if errcode == Status::CL_COMPILE_PROGRAM_FAILURE as i32 {
+        match devices {
+            Some(ds) => program_build_err(program, ds)?,
+            None => program_build_err(program, &program.devices()?)?,
+        }
+    }
+
+    eval_errcode(errcode, (), "clCompileProgram", None::<String>)
+}
+
+/// Links a set of compiled program objects and libraries for all or some of
+/// the devices in a context, returning a new program containing either an
+/// executable or, if `options` contains `-create-library`, a library.
+///
+/// If `pfn_notify` is specified, linking may proceed asynchronously (see
+/// `::build_program`). The callback is passed the new program.
+///
+/// [Version Controlled: OpenCL 1.2+] See module docs for more info.
+pub fn link_program<C, D>(
+            context: C,
+            devices: Option<&[D]>,
+            options: &CString,
+            input_programs: &[&Program],
+            pfn_notify: Option<BuildProgramCallbackFn>,
+            user_data: Option<UserDataPtr>,
+            device_versions: Option<&[OpenclVersion]>,
+        ) -> OclCoreResult<Program>
+        where C: ClContextPtr, D: ClDeviceIdPtr
+{
+    verify_device_versions(device_versions, [1, 2], &context.as_ptr(),
+        ApiFunction::LinkProgram)?;
+
+    if input_programs.len() == 0 {
+        return Err(ApiWrapperError::LinkProgramNoInputPrograms.into())
+    }
+
+    let (devices_len, devices_ptr) = match devices {
+        Some(dvs) => (dvs.len() as u32, dvs.as_ptr() as *const cl_device_id),
+        None => (0, ptr::null() as *const cl_device_id),
+    };
+
+    let programs: Vec<cl_program> = input_programs.iter().map(|p| p.as_ptr()).collect();
+
+    let user_data = user_data.unwrap_or(ptr::null_mut());
+
+    let mut errcode: cl_int = 0;
+
+    let program_ptr = unsafe { ffi::clLinkProgram(
+        context.as_ptr(),
+        devices_len,
+        devices_ptr,
+        options.as_ptr(),
+        programs.len() as u32,
+        programs.as_ptr(),
+        pfn_notify,
+        user_data,
+        &mut errcode,
+    ) };
+
+    // A program object may be returned along with a link failure in which
+    // case its build log contains the details:
+    if errcode == Status::CL_LINK_PROGRAM_FAILURE as i32 && !program_ptr.is_null() {
+        let program = unsafe { Program::from_raw_create_ptr(program_ptr) };
+        match devices {
+            Some(ds) => program_build_err(&program, ds)?,
+            None => program_build_err(&program, &program.devices()?)?,
+        }
+    }
+
+    eval_errcode(errcode, program_ptr, "clLinkProgram", None::<String>)
+        .map(|ptr| unsafe { Program::from_raw_create_ptr(ptr) })
+}
+
+// [DISABLED DUE TO PLATFORM INCOMPATABILITY]
+// /// [UNTESTED]
+// /// Unloads a platform compiler.
+// ///
+// /// [Version Controlled: OpenCL 1.2+] See module docs for more info.
+// pub fn unload_platform_compiler(platform: &PlatformId,
+//          device_version: Option<&OpenclVersion>) -> OclCoreResult<()> {
+//     unsafe { eval_errcode("clUnloadPlatformCompiler", "",
+//         ffi::clUnloadPlatformCompiler(platform.as_ptr())) }
+// }
+
+/// Get program info.
+pub fn get_program_info(obj: &Program, request: ProgramInfo) -> OclCoreResult<ProgramInfoResult> {
+    // Binaries are copied into caller allocated buffers:
+    if let ProgramInfo::Binaries = request {
+        return get_program_binaries(obj).map(ProgramInfoResult::Binaries);
+    }
+
+    let mut result_size: size_t = 0;
+
+    let errcode = unsafe { ffi::clGetProgramInfo(
+        obj.as_ptr() as cl_program,
+        request as cl_program_info,
+        0 as size_t,
+        0 as *mut c_void,
+        &mut result_size as *mut size_t,
+    ) };
+
+    eval_errcode(errcode, (), "clGetProgramInfo", None::<String>)?;
+
+    // If result size is zero, return an empty info result directly:
+    if result_size == 0 {
+        return ProgramInfoResult::from_bytes(request, vec![]);
+    }
 
     let mut result: Vec<u8> = iter::repeat(0u8).take(result_size).collect();
 
@@ -1875,6 +2520,28 @@ pub fn get_program_info(obj: &Program, request: ProgramInfo) -> OclCoreResult<Pr
     ProgramInfoResult::from_bytes(request, result)
 }
 
+/// Returns the binary of a program for each of its devices, in the same
+/// order as the program's device list.
+fn get_program_binaries(obj: &Program) -> OclCoreResult<Vec<Vec<u8>>> {
+    let sizes = match get_program_info(obj, ProgramInfo::BinarySizes)? {
+        ProgramInfoResult::BinarySizes(sizes) => sizes,
+        _ => unreachable!(),
+    };
+
+    let mut binaries: Vec<Vec<u8>> = sizes.iter().map(|&size| vec![0u8; size]).collect();
+    let mut binary_ptrs: Vec<*mut u8> = binaries.iter_mut().map(|bin| bin.as_mut_ptr()).collect();
+
+    let errcode = unsafe { ffi::clGetProgramInfo(
+        obj.as_ptr() as cl_program,
+        ProgramInfo::Binaries as cl_program_info,
+        binary_ptrs.len() * mem::size_of::<*mut u8>(),
+        binary_ptrs.as_mut_ptr() as *mut _ as *mut c_void,
+        ptr::null_mut(),
+    ) };
+
+    eval_errcode(errcode, binaries, "clGetProgramInfo", None::<String>)
+}
+
 /// Get program build info.
 pub fn get_program_build_info<D: ClDeviceIdPtr + fmt::Debug>(obj: &Program, device_obj: D,
             request: ProgramBuildInfo) -> OclCoreResult<ProgramBuildInfoResult>
@@ -1938,13 +2605,30 @@ pub fn create_kernel<S: AsRef<str>>(program: &Program, name: S) -> OclCoreResult
     }
 }
 
-/// [UNIMPLEMENTED: Please implement me]
-pub fn create_kernels_in_program() -> OclCoreResult<()> {
-    // ffi::clCreateKernelsInProgram(program: cl_program,
-    //                             num_kernels: cl_uint,
-    //                             kernels: *mut cl_kernel,
-    //                             num_kernels_ret: *mut cl_uint) -> cl_int;
-    unimplemented!();
+/// Returns a new kernel for each kernel function in a program.
+pub fn create_kernels_in_program(program: &Program) -> OclCoreResult<Vec<Kernel>> {
+    let mut num_kernels: cl_uint = 0;
+
+    let errcode = unsafe { ffi::clCreateKernelsInProgram(
+        program.as_ptr(),
+        0,
+        ptr::null_mut(),
+        &mut num_kernels,
+    ) };
+    eval_errcode(errcode, (), "clCreateKernelsInProgram", None::<String>)?;
+
+    let mut kernel_ptrs: Vec<cl_kernel> = iter::repeat(ptr::null_mut())
+        .take(num_kernels as usize).collect();
+
+    let errcode = unsafe { ffi::clCreateKernelsInProgram(
+        program.as_ptr(),
+        num_kernels,
+        kernel_ptrs.as_mut_ptr(),
+        ptr::null_mut(),
+    ) };
+    eval_errcode(errcode, (), "clCreateKernelsInProgram", None::<String>)?;
+
+    Ok(kernel_ptrs.into_iter().map(|ptr| unsafe { Kernel::from_raw_create_ptr(ptr) }).collect())
 }
 
 /// Increments a kernel reference counter.
@@ -1957,6 +2641,26 @@ pub unsafe fn release_kernel(kernel: &Kernel) -> OclCoreResult<()> {
     eval_errcode(ffi::clReleaseKernel(kernel.as_ptr()), (), "clReleaseKernel", None::<String>)
 }
 
+/// Returns a copy of `kernel`, including all argument values currently set.
+///
+/// The copy is independent of the source kernel: setting arguments on one
+/// does not affect the other.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.1/docs/man/xhtml/clCloneKernel.html)
+///
+/// [Version Controlled: OpenCL 2.1+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_1")]
+pub fn clone_kernel(kernel: &Kernel, device_versions: Option<&[OpenclVersion]>)
+            -> OclCoreResult<Kernel> {
+    verify_device_versions(device_versions, [2, 1], kernel, ApiFunction::CloneKernel)?;
+
+    let mut errcode: cl_int = 0;
+    let kernel_ptr = unsafe { ffi::clCloneKernel(kernel.as_ptr(), &mut errcode) };
+
+    eval_errcode(errcode, kernel_ptr, "clCloneKernel", None::<String>)
+        .map(|ptr| unsafe { Kernel::from_raw_create_ptr(ptr) })
+}
+
 
 /// Sets the argument value for a specific argument of a kernel.
 ///
@@ -2021,6 +2725,75 @@ pub fn set_kernel_arg<T: OclPrm>(kernel: &Kernel, arg_index: u32, arg: KernelArg
     }
 }
 
+/// Sets a shared virtual memory pointer as the argument value for a
+/// specific argument of a kernel.
+///
+/// `arg_value` may point anywhere within an SVM buffer (or, with
+/// fine-grained system SVM, anywhere in host memory).
+///
+/// ## Safety
+///
+/// The memory pointed to must remain allocated for as long as the kernel
+/// may be enqueued with this argument.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clSetKernelArgSVMPointer.html)
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn set_kernel_arg_svm_pointer(
+            kernel: &Kernel,
+            arg_index: u32,
+            arg_value: *const c_void,
+            device_versions: Option<&[OpenclVersion]>,
+        ) -> OclCoreResult<()>
+{
+    verify_device_versions(device_versions, [2, 0], kernel,
+        ApiFunction::SetKernelArgSvmPointer)?;
+
+    let err = ffi::clSetKernelArgSVMPointer(kernel.as_ptr(), arg_index, arg_value);
+
+    if err != Status::CL_SUCCESS as i32 {
+        let name = get_kernel_name(kernel)?;
+        eval_errcode(err, (), "clSetKernelArgSVMPointer", Some(name))
+    } else {
+        Ok(())
+    }
+}
+
+/// Specifies the shared virtual memory buffers which a kernel may access
+/// indirectly (through pointers stored within other buffers or arguments)
+/// rather than through its arguments alone.
+///
+/// Required when pointer-based data structures (trees, linked lists, etc.)
+/// span more than one SVM allocation. Replaces any previously specified
+/// list.
+///
+/// ## Safety
+///
+/// Each pointer must point within a live SVM buffer.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clSetKernelExecInfo.html)
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn set_kernel_exec_info_svm_ptrs(
+            kernel: &Kernel,
+            svm_ptrs: &[*const c_void],
+            device_versions: Option<&[OpenclVersion]>,
+        ) -> OclCoreResult<()>
+{
+    verify_device_versions(device_versions, [2, 0], kernel,
+        ApiFunction::SetKernelExecInfo)?;
+
+    let errcode = ffi::clSetKernelExecInfo(
+        kernel.as_ptr(),
+        ffi::CL_KERNEL_EXEC_INFO_SVM_PTRS as cl_kernel_exec_info,
+        svm_ptrs.len() * mem::size_of::<*const c_void>(),
+        svm_ptrs.as_ptr() as *const c_void,
+    );
+    eval_errcode(errcode, (), "clSetKernelExecInfo", None::<String>)
+}
+
 /// Get kernel info.
 pub fn get_kernel_info(obj: &Kernel, request: KernelInfo) -> OclCoreResult<KernelInfoResult> {
     let mut result_size: size_t = 0;
@@ -2159,6 +2932,54 @@ pub fn get_kernel_work_group_info<D: ClDeviceIdPtr>(obj: &Kernel, device_obj: D,
     KernelWorkGroupInfoResult::from_bytes(request, result)
 }
 
+/// Get kernel sub-group info.
+///
+/// `input` must contain the local work size (one value per dimension) for
+/// `MaxSubGroupSizeForNdrange` and `SubGroupCountForNdrange`, a single
+/// sub-group count for `LocalSizeForSubGroupCount`, and is ignored
+/// otherwise.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.1/docs/man/xhtml/clGetKernelSubGroupInfo.html)
+///
+/// [Version Controlled: OpenCL 2.1+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_1")]
+pub fn get_kernel_sub_group_info<D: ClDeviceIdPtr>(obj: &Kernel, device_obj: D,
+            request: KernelSubGroupInfo, input: &[usize], device_version: Option<&OpenclVersion>)
+            -> OclCoreResult<KernelSubGroupInfoResult>
+{
+    let device = unsafe { &DeviceId::from_raw(device_obj.as_ptr()) };
+    verify_device_version(device_version, [2, 1], device, ApiFunction::GetKernelSubGroupInfo)?;
+
+    // Every result is either a single `size_t` or, for
+    // `LocalSizeForSubGroupCount`, a `size_t` for each of three dimensions
+    // (unused dimensions are zeroed):
+    let result_size = match request {
+        KernelSubGroupInfo::LocalSizeForSubGroupCount => mem::size_of::<[usize; 3]>(),
+        _ => mem::size_of::<usize>(),
+    };
+    let mut result: Vec<u8> = iter::repeat(0u8).take(result_size).collect();
+
+    let (input_size, input_ptr) = if input.is_empty() {
+        (0, ptr::null())
+    } else {
+        (mem::size_of_val(input), input.as_ptr() as *const c_void)
+    };
+
+    let errcode = unsafe { ffi::clGetKernelSubGroupInfo(
+        obj.as_ptr() as cl_kernel,
+        device_obj.as_ptr() as cl_device_id,
+        request as cl_kernel_sub_group_info,
+        input_size,
+        input_ptr,
+        result_size,
+        result.as_mut_ptr() as *mut _ as *mut c_void,
+        0 as *mut size_t,
+    ) };
+
+    let result = eval_errcode(errcode, result, "clGetKernelSubGroupInfo", None::<String>)?;
+    KernelSubGroupInfoResult::from_bytes(request, result)
+}
+
 //============================================================================
 //========================== Event Object APIs ===============================
 //============================================================================
@@ -3340,19 +4161,290 @@ pub unsafe fn enqueue_task<En: ClNullEventPtr, Ewl: ClWaitListPtr>(
     eval_errcode(errcode, (), "clEnqueueTask", kernel_name)
 }
 
-/// [UNIMPLEMENTED: Please implement me]
-pub fn enqueue_native_kernel() -> OclCoreResult<()> {
-    // ffi::clEnqueueNativeKernel(command_queue: cl_command_queue,
-    //                          user_func: extern fn (*mut c_void),
-    //                          args: *mut c_void,
-    //                          cb_args: size_t,
-    //                          num_mem_objects: cl_uint,
-    //                          mem_list: *const cl_mem,
-    //                          args_mem_loc: *const *const c_void,
-    //                          num_events_in_wait_list: cl_uint,
-    //                          event_wait_list: *const cl_event,
-    //                          event: *mut cl_event) -> cl_int;
-    unimplemented!();
+/// Enqueues a command to execute a native (host) function.
+///
+/// `user_func` is called with a pointer to a copy of `args`. Before the call,
+/// the handle of each memory object in `mem_list` is written into that copy
+/// at the byte offset given by the corresponding element of `args_mem_loc`
+/// and is then replaced by the implementation with a pointer to the memory
+/// object's contents.
+///
+/// The device associated with `command_queue` must support
+/// `CL_EXEC_NATIVE_KERNEL` (see `DeviceInfo::ExecutionCapabilities`).
+///
+/// ## Safety
+///
+/// `user_func` is responsible for interpreting the contents of `args`
+/// correctly and must not access memory objects beyond their bounds.
+///
+/// [SDK Docs](https://www.khronos.org/registry/cl/sdk/1.2/docs/man/xhtml/clEnqueueNativeKernel.html)
+///
+pub unsafe fn enqueue_native_kernel<En, Ewl>(
+            command_queue: &CommandQueue,
+            user_func: NativeKernelFn,
+            args: &[u8],
+            mem_list: &[&Mem],
+            args_mem_loc: &[usize],
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    if mem_list.len() != args_mem_loc.len() {
+        return Err(ApiWrapperError::EnqueueNativeKernelMemLenMismatch.into())
+    }
+
+    let mut args = args.to_vec();
+    let mut mem_ptrs: Vec<cl_mem> = Vec::with_capacity(mem_list.len());
+    let mut mem_locs: Vec<*const c_void> = Vec::with_capacity(mem_list.len());
+
+    for (mem_obj, &offset) in mem_list.iter().zip(args_mem_loc.iter()) {
+        if offset + mem::size_of::<cl_mem>() > args.len() {
+            return Err(ApiWrapperError::EnqueueNativeKernelMemLocOutOfRange(offset).into())
+        }
+        let loc = args.as_mut_ptr().offset(offset as isize);
+        ptr::write_unaligned(loc as *mut cl_mem, mem_obj.as_ptr());
+        mem_ptrs.push(mem_obj.as_ptr());
+        mem_locs.push(loc as *const c_void);
+    }
+
+    let (args_ptr, args_len) = if args.len() > 0 {
+        (args.as_mut_ptr() as *mut c_void, args.len())
+    } else {
+        (ptr::null_mut(), 0)
+    };
+
+    let (mem_list_ptr, mem_locs_ptr) = if mem_ptrs.len() > 0 {
+        (mem_ptrs.as_ptr(), mem_locs.as_ptr())
+    } else {
+        (ptr::null(), ptr::null())
+    };
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueNativeKernel(
+        command_queue.as_ptr(),
+        Some(user_func),
+        args_ptr,
+        args_len,
+        mem_ptrs.len() as cl_uint,
+        mem_list_ptr,
+        mem_locs_ptr,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+    eval_errcode(errcode, (), "clEnqueueNativeKernel", None::<String>)
+}
+
+/// Enqueues a command to free shared virtual memory buffers once all
+/// previously enqueued commands (in an in-order queue) have completed.
+///
+/// The buffers are freed with `svm_free`.
+///
+/// ## Safety
+///
+/// Each pointer must have been returned by `svm_alloc` and must not be used
+/// after this command completes.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clEnqueueSVMFree.html)
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn enqueue_svm_free<En, Ewl>(
+            command_queue: &CommandQueue,
+            svm_ptrs: &[*mut c_void],
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    verify_device_version(device_version, [2, 0], command_queue,
+        ApiFunction::EnqueueSvmFree)?;
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueSVMFree(
+        command_queue.as_ptr(),
+        svm_ptrs.len() as cl_uint,
+        svm_ptrs.as_ptr() as *const *const c_void,
+        None,
+        ptr::null_mut(),
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+    eval_errcode(errcode, (), "clEnqueueSVMFree", None::<String>)
+}
+
+/// Enqueues a command to copy `size` bytes between shared virtual memory
+/// buffers and/or host memory.
+///
+/// ## Safety
+///
+/// Both regions must be valid for `size` bytes, must not overlap, and (if
+/// `block` is false) must remain valid until the command completes.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clEnqueueSVMMemcpy.html)
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn enqueue_svm_memcpy<En, Ewl>(
+            command_queue: &CommandQueue,
+            block: bool,
+            dst_ptr: *mut c_void,
+            src_ptr: *const c_void,
+            size: usize,
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    verify_device_version(device_version, [2, 0], command_queue,
+        ApiFunction::EnqueueSvmMemcpy)?;
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueSVMMemcpy(
+        command_queue.as_ptr(),
+        block as cl_uint,
+        dst_ptr,
+        src_ptr,
+        size,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+    eval_errcode(errcode, (), "clEnqueueSVMMemcpy", None::<String>)
+}
+
+/// Enqueues a command to fill `len` elements of a shared virtual memory
+/// buffer with `pattern`.
+///
+/// ## Safety
+///
+/// `svm_ptr` must point to a region of an SVM buffer valid for `len`
+/// elements of `T`.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clEnqueueSVMMemFill.html)
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn enqueue_svm_mem_fill<T, En, Ewl>(
+            command_queue: &CommandQueue,
+            svm_ptr: *mut c_void,
+            pattern: T,
+            len: usize,
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<()>
+        where T: OclPrm, En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    verify_device_version(device_version, [2, 0], command_queue,
+        ApiFunction::EnqueueSvmMemFill)?;
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueSVMMemFill(
+        command_queue.as_ptr(),
+        svm_ptr,
+        &pattern as *const _ as *const c_void,
+        mem::size_of::<T>(),
+        len * mem::size_of::<T>(),
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+    eval_errcode(errcode, (), "clEnqueueSVMMemFill", None::<String>)
+}
+
+/// Enqueues a command to map a region of a coarse-grained shared virtual
+/// memory buffer for access by the host.
+///
+/// Unlike buffer maps, the host accesses the region through `svm_ptr`
+/// itself. Fine-grained buffers need not be mapped.
+///
+/// ## Safety
+///
+/// The region must lie within an SVM buffer and must not be accessed by the
+/// host before the command completes or after it is unmapped.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clEnqueueSVMMap.html)
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn enqueue_svm_map<En, Ewl>(
+            command_queue: &CommandQueue,
+            block: bool,
+            map_flags: MapFlags,
+            svm_ptr: *mut c_void,
+            size: usize,
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    verify_device_version(device_version, [2, 0], command_queue,
+        ApiFunction::EnqueueSvmMap)?;
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueSVMMap(
+        command_queue.as_ptr(),
+        block as cl_uint,
+        map_flags.bits(),
+        svm_ptr,
+        size,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+    eval_errcode(errcode, (), "clEnqueueSVMMap", None::<String>)
+}
+
+/// Enqueues a command to unmap a region of a shared virtual memory buffer
+/// previously mapped with `enqueue_svm_map`.
+///
+/// ## Safety
+///
+/// The host must not access the region after this command is enqueued.
+///
+/// [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clEnqueueSVMUnmap.html)
+///
+/// [Version Controlled: OpenCL 2.0+] See module docs for more info.
+#[cfg(feature = "opencl_version_2_0")]
+pub unsafe fn enqueue_svm_unmap<En, Ewl>(
+            command_queue: &CommandQueue,
+            svm_ptr: *mut c_void,
+            wait_list: Option<Ewl>,
+            new_event: Option<En>,
+            device_version: Option<&OpenclVersion>,
+        ) -> OclCoreResult<()>
+        where En: ClNullEventPtr, Ewl: ClWaitListPtr
+{
+    verify_device_version(device_version, [2, 0], command_queue,
+        ApiFunction::EnqueueSvmUnmap)?;
+
+    let (wait_list_len, wait_list_ptr, new_event_ptr) =
+        resolve_event_ptrs(wait_list, new_event);
+
+    let errcode = ffi::clEnqueueSVMUnmap(
+        command_queue.as_ptr(),
+        svm_ptr,
+        wait_list_len,
+        wait_list_ptr,
+        new_event_ptr,
+    );
+    eval_errcode(errcode, (), "clEnqueueSVMUnmap", None::<String>)
 }
 
 /// Enqueues a marker command which waits for either a list of events to
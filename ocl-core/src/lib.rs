@@ -125,13 +125,16 @@ pub use self::types::abs::{ClWaitListPtr, ClNullEventPtr, ClEventPtrRef, ClPlatf
     ClDeviceIdPtr, ClContextPtr, EventRefWrapper, PlatformId, DeviceId, Context, CommandQueue, Mem,
     Program, Kernel, Event, Sampler, ClVersions, AsMem, MemCmdRw, MemCmdAll, MemMap};
 
-pub use self::types::structs::{self, OpenclVersion, ContextProperties, ImageFormatParseError,
-    ImageFormatParseResult, ImageFormat, ImageDescriptor, BufferRegion, ContextPropertyValue};
+pub use self::types::structs::{self, OpenclVersion, ContextProperties, QueueProperties,
+    ImageFormatParseError,
+    ImageFormatParseResult, ImageFormat, ImageDescriptor, BufferRegion, ContextPropertyValue,
+    DevicePartition};
 
 pub use self::types::enums::{EmptyInfoResultError, KernelArg, PlatformInfoResult, DeviceInfoResult,
     ContextInfoResult, GlContextInfoResult, CommandQueueInfoResult, MemInfoResult, ImageInfoResult,
-    SamplerInfoResult, ProgramInfoResult, ProgramBuildInfoResult, KernelInfoResult,
-    KernelArgInfoResult, KernelWorkGroupInfoResult, EventInfoResult, ProfilingInfoResult};
+    SamplerInfoResult, PipeInfoResult, ProgramInfoResult, ProgramBuildInfoResult, KernelInfoResult,
+    KernelArgInfoResult, KernelWorkGroupInfoResult, KernelSubGroupInfoResult, EventInfoResult,
+    ProfilingInfoResult};
 
 pub use self::functions::{get_platform_ids, get_platform_info, get_device_ids, get_device_info,
     create_sub_devices, retain_device, release_device, create_context, create_context_from_type,
@@ -156,7 +159,8 @@ pub use self::functions::{get_platform_ids, get_platform_info, get_device_ids, g
     enqueue_barrier_with_wait_list, get_extension_function_address_for_platform, wait_for_event,
     event_status, default_platform_idx, program_build_err, verify_context, default_platform,
     default_device_type, device_versions, event_is_complete, _dummy_event_callback,
-    _complete_user_event, get_context_platform};
+    _complete_user_event, get_context_platform, ProgramBuildError, BuildDiagnostic,
+    DiagnosticSeverity};
 
 #[cfg(not(feature="opencl_vendor_mesa"))]
 pub use self::functions::{
@@ -185,8 +189,18 @@ pub use self::vector::{
 #[cfg(feature = "ocl-core-vector")]
 pub use traits::OclVec;
 
+#[cfg(feature = "opencl_version_2_0")]
+pub use self::functions::{create_command_queue_with_properties, create_pipe, get_pipe_info,
+    svm_alloc, svm_free, set_kernel_arg_svm_pointer, set_kernel_exec_info_svm_ptrs,
+    enqueue_svm_free, enqueue_svm_memcpy, enqueue_svm_mem_fill, enqueue_svm_map,
+    enqueue_svm_unmap};
+
 #[cfg(feature = "opencl_version_2_1")]
-pub use self::functions::{create_program_with_il};
+pub use self::functions::{get_device_and_host_timer, get_host_timer, create_program_with_il,
+    get_kernel_sub_group_info, clone_kernel};
+
+#[cfg(feature = "opencl_version_2_2")]
+pub use self::functions::set_program_specialization_constant;
 
 
 
@@ -205,6 +219,8 @@ pub type EventCallbackFn = extern "C" fn (ffi::cl_event, i32, *mut ffi::c_void);
 pub type CreateContextCallbackFn = extern "C" fn (*const ffi::c_char, *const ffi::c_void,
     ffi::size_t, *mut ffi::c_void);
 pub type BuildProgramCallbackFn = extern "C" fn (*mut ffi::c_void, *mut ffi::c_void);
+pub type NativeKernelFn = extern "C" fn (*mut ffi::c_void);
+pub type MemDestructorCallbackFn = extern "C" fn (ffi::cl_mem, *mut ffi::c_void);
 pub type UserDataPtr = *mut ffi::c_void;
 
 //=============================================================================
@@ -411,6 +427,29 @@ pub const EXEC_KERNEL: DeviceExecCapabilities = DeviceExecCapabilities::KERNEL;
 pub const EXEC_NATIVE_KERNEL: DeviceExecCapabilities = DeviceExecCapabilities::NATIVE_KERNEL;
 
 
+bitflags! {
+    /// cl_device_svm_capabilities - bitfield
+    ///
+    /// * `COARSE_GRAIN_BUFFER`: SVM buffers must be mapped (or accessed with
+    ///   SVM commands) to be accessed by the host. Required of all OpenCL 2.0
+    ///   devices.
+    /// * `FINE_GRAIN_BUFFER`: SVM buffers allocated with
+    ///   `MemFlags::SVM_FINE_GRAIN_BUFFER` may be accessed by the host and
+    ///   devices concurrently without mapping.
+    /// * `FINE_GRAIN_SYSTEM`: Any host memory may be shared with devices.
+    /// * `ATOMICS`: Fine-grained buffers may be allocated with
+    ///   `MemFlags::SVM_ATOMICS`, making atomic operations visible across the
+    ///   host and devices.
+    ///
+    pub struct DeviceSvmCapabilities: u64 {
+        const COARSE_GRAIN_BUFFER = 1 << 0;
+        const FINE_GRAIN_BUFFER = 1 << 1;
+        const FINE_GRAIN_SYSTEM = 1 << 2;
+        const ATOMICS = 1 << 3;
+    }
+}
+
+
 bitflags! {
     /// cl_command_queue_properties - bitfield
     pub struct CommandQueueProperties: u64 {
@@ -475,6 +514,9 @@ bitflags! {
         const HOST_WRITE_ONLY = 1 << 7;
         const HOST_READ_ONLY = 1 << 8;
         const HOST_NO_ACCESS = 1 << 9;
+        // Used by `svm_alloc` only:
+        const SVM_FINE_GRAIN_BUFFER = 1 << 10;
+        const SVM_ATOMICS = 1 << 11;
     }
 }
 
@@ -489,6 +531,8 @@ impl MemFlags {
     #[inline] pub fn host_write_only(self) -> MemFlags { self | MemFlags::HOST_WRITE_ONLY }
     #[inline] pub fn host_read_only(self) -> MemFlags { self | MemFlags::HOST_READ_ONLY }
     #[inline] pub fn host_no_access(self) -> MemFlags { self | MemFlags::HOST_NO_ACCESS }
+    #[inline] pub fn svm_fine_grain_buffer(self) -> MemFlags { self | MemFlags::SVM_FINE_GRAIN_BUFFER }
+    #[inline] pub fn svm_atomics(self) -> MemFlags { self | MemFlags::SVM_ATOMICS }
 }
 
 impl Default for MemFlags {
@@ -568,6 +612,7 @@ bitflags! {
         const CONST = 1 << 0;
         const RESTRICT = 1 << 1;
         const VOLATILE = 1 << 2;
+        const PIPE = 1 << 3;
     }
 }
 
@@ -575,6 +620,7 @@ pub const KERNEL_ARG_TYPE_NONE: KernelArgTypeQualifier = KernelArgTypeQualifier:
 pub const KERNEL_ARG_TYPE_CONST: KernelArgTypeQualifier = KernelArgTypeQualifier::CONST;
 pub const KERNEL_ARG_TYPE_RESTRICT: KernelArgTypeQualifier = KernelArgTypeQualifier::RESTRICT;
 pub const KERNEL_ARG_TYPE_VOLATILE: KernelArgTypeQualifier = KernelArgTypeQualifier::VOLATILE;
+pub const KERNEL_ARG_TYPE_PIPE: KernelArgTypeQualifier = KernelArgTypeQualifier::PIPE;
 
 //=============================================================================
 //=============================== ENUMERATORS =================================
@@ -649,6 +695,8 @@ enum_from_primitive! {
         CL_INVALID_DEVICE_PARTITION_COUNT               = -68,
         CL_INVALID_PIPE_SIZE                            = -69,
         CL_INVALID_DEVICE_QUEUE                         = -70,
+        CL_INVALID_SPEC_ID                              = -71,
+        CL_MAX_SIZE_RESTRICTION_EXCEEDED                = -72,
         CL_INVALID_GL_SHAREGROUP_REFERENCE_KHR          = -1000,
         CL_PLATFORM_NOT_FOUND_KHR                       = -1001,
         CL_NV_INVALID_MEM_ACCESS                        = -9999,
@@ -886,6 +934,12 @@ enum_from_primitive! {
         PrintfBufferSize = ffi::CL_DEVICE_PRINTF_BUFFER_SIZE as isize,
         ImagePitchAlignment = ffi::CL_DEVICE_IMAGE_PITCH_ALIGNMENT as isize,
         ImageBaseAddressAlignment = ffi::CL_DEVICE_IMAGE_BASE_ADDRESS_ALIGNMENT as isize,
+        SvmCapabilities = ffi::CL_DEVICE_SVM_CAPABILITIES as isize,
+        MaxPipeArgs = ffi::CL_DEVICE_MAX_PIPE_ARGS as isize,
+        PipeMaxActiveReservations = ffi::CL_DEVICE_PIPE_MAX_ACTIVE_RESERVATIONS as isize,
+        PipeMaxPacketSize = ffi::CL_DEVICE_PIPE_MAX_PACKET_SIZE as isize,
+        IlVersion = ffi::CL_DEVICE_IL_VERSION as isize,
+        NonUniformWorkGroupSupport = ffi::CL_DEVICE_NON_UNIFORM_WORK_GROUP_SUPPORT as isize,
     }
 }
 
@@ -979,11 +1033,10 @@ enum_from_primitive! {
 
 
 enum_from_primitive! {
-    /// [INCOMPLETE] cl_device_partition_property
+    /// cl_device_partition_property
     ///
-    /// [FIXME]: This types variants should also contain data described in:
-    /// [https://www.khronos.org/registry/cl/sdk/1.2/docs/man/xhtml/clCreateSubDevices.html]
-    /// (https://www.khronos.org/registry/cl/sdk/1.2/docs/man/xhtml/clCreateSubDevices.html)
+    /// Use `DevicePartition` to specify a partition (including its data)
+    /// when creating sub-devices.
     ///
     #[repr(C)]
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -1005,6 +1058,31 @@ enum_from_primitive! {
         Device = ffi::CL_QUEUE_DEVICE as isize,
         ReferenceCount = ffi::CL_QUEUE_REFERENCE_COUNT as isize,
         Properties = ffi::CL_QUEUE_PROPERTIES as isize,
+        Size = ffi::CL_QUEUE_SIZE as isize,
+    }
+}
+
+
+enum_from_primitive! {
+    /// cl_queue_priority_khr (`cl_khr_priority_hints`)
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum QueuePriority {
+        High = ffi::CL_QUEUE_PRIORITY_HIGH_KHR as isize,
+        Med = ffi::CL_QUEUE_PRIORITY_MED_KHR as isize,
+        Low = ffi::CL_QUEUE_PRIORITY_LOW_KHR as isize,
+    }
+}
+
+
+enum_from_primitive! {
+    /// cl_queue_throttle_khr (`cl_khr_throttle_hints`)
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum QueueThrottle {
+        High = ffi::CL_QUEUE_THROTTLE_HIGH_KHR as isize,
+        Med = ffi::CL_QUEUE_THROTTLE_MED_KHR as isize,
+        Low = ffi::CL_QUEUE_THROTTLE_LOW_KHR as isize,
     }
 }
 
@@ -1046,6 +1124,7 @@ enum_from_primitive! {
         Image1d = ffi::CL_MEM_OBJECT_IMAGE1D as isize,
         Image1dArray = ffi::CL_MEM_OBJECT_IMAGE1D_ARRAY as isize,
         Image1dBuffer = ffi::CL_MEM_OBJECT_IMAGE1D_BUFFER as isize,
+        Pipe = ffi::CL_MEM_OBJECT_PIPE as isize,
     }
 }
 
@@ -1127,6 +1206,17 @@ enum_from_primitive! {
 }
 
 
+enum_from_primitive! {
+    /// cl_pipe_info
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum PipeInfo {
+        PacketSize = ffi::CL_PIPE_PACKET_SIZE as isize,
+        MaxPackets = ffi::CL_PIPE_MAX_PACKETS as isize,
+    }
+}
+
+
 enum_from_primitive! {
     /// cl_program_info
     #[repr(C)]
@@ -1245,6 +1335,24 @@ enum_from_primitive! {
 }
 
 
+enum_from_primitive! {
+    /// cl_kernel_sub_group_info
+    ///
+    /// [NOTE] `MaxSubGroupSizeForNdrange` and `SubGroupCountForNdrange` take
+    /// a local work size as input. `LocalSizeForSubGroupCount` takes a
+    /// sub-group count. The remaining variants take no input.
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum KernelSubGroupInfo {
+        MaxSubGroupSizeForNdrange = ffi::CL_KERNEL_MAX_SUB_GROUP_SIZE_FOR_NDRANGE as isize,
+        SubGroupCountForNdrange = ffi::CL_KERNEL_SUB_GROUP_COUNT_FOR_NDRANGE as isize,
+        LocalSizeForSubGroupCount = ffi::CL_KERNEL_LOCAL_SIZE_FOR_SUB_GROUP_COUNT as isize,
+        MaxNumSubGroups = ffi::CL_KERNEL_MAX_NUM_SUB_GROUPS as isize,
+        CompileNumSubGroups = ffi::CL_KERNEL_COMPILE_NUM_SUB_GROUPS as isize,
+    }
+}
+
+
 enum_from_primitive! {
     /// cl_event_info
     #[repr(C)]
@@ -6,6 +6,7 @@ use std::ffi::CString;
 
 #[test]
 fn buffer_copy_core() {
+    ::tests::init();
     let src = r#"
         __kernel void add(__global float* buffer, float addend) {
             buffer[get_global_id(0)] += addend;
@@ -4,6 +4,7 @@ const DATASET_SIZE: usize = 1 << 20;
 
 #[test]
 fn fill() {
+    ::tests::init();
     let src = r#"
         __kernel void add(__global float* buffer, float addend) {
             buffer[get_global_id(0)] += addend;
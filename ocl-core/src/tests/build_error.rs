@@ -1,7 +1,9 @@
 use std::ffi::CString;
 
+// The mock only fails builds on '#error' directives:
 #[test]
 #[should_panic]
+#[cfg_attr(feature = "mock", ignore)]
 #[allow(unused_variables)]
 fn bad_kernel_variable_names() {
     let kernel = r#"
@@ -23,3 +25,39 @@ fn bad_kernel_variable_names() {
     ::create_build_program(&context, &[CString::new(kernel).unwrap()],
         None::<&[()]>, &CString::new("").unwrap()).unwrap();
 }
+
+#[test]
+fn parse_build_log() {
+    use ::{BuildDiagnostic, DiagnosticSeverity};
+
+    let log = "<source>:3:5: error: use of undeclared identifier 'x'\n\
+        \x20   x = 1;\n\
+        \x20   ^\n\
+        <kernel>:7:1: warning: unused variable 'y'\n\
+        \"/tmp/OCL1234T1.cl\", line 12: error: identifier \"z\" is undefined\n\
+        /usr/include/foo.h:4:2: fatal error: 'bar.h' file not found\n\
+        error: unknown argument: '-cl-foo'\n\
+        2 errors generated.\n";
+
+    let diags = BuildDiagnostic::parse_log(log);
+    assert_eq!(diags.len(), 5);
+
+    assert_eq!(diags[0].severity(), DiagnosticSeverity::Error);
+    assert_eq!((diags[0].line(), diags[0].column()), (Some(3), Some(5)));
+    assert_eq!(diags[0].message(), "use of undeclared identifier 'x'");
+    assert!(diags[0].is_program_source());
+
+    assert_eq!(diags[1].severity(), DiagnosticSeverity::Warning);
+    assert!(diags[1].is_program_source());
+
+    // EDG-based (older AMD and NVIDIA) logs have no column:
+    assert_eq!((diags[2].line(), diags[2].column()), (Some(12), None));
+    assert_eq!(diags[2].message(), "identifier \"z\" is undefined");
+    assert!(diags[2].is_program_source());
+
+    assert_eq!(diags[3].file(), Some("/usr/include/foo.h"));
+    assert!(!diags[3].is_program_source());
+
+    assert_eq!((diags[4].file(), diags[4].line()), (None, None));
+    assert_eq!(diags[4].to_string(), "error: unknown argument: '-cl-foo'");
+}
@@ -9,10 +9,15 @@
 //!
 //! TODO: Finish porting tests from ocl.
 //!
+//! Enable the `mock` feature to run these against the in-process mock
+//! platform (`ocl-mock`) rather than an OpenCL driver.
+//!
 
 #![allow(dead_code)]
 
 extern crate rand;
+#[cfg(feature = "mock")]
+extern crate ocl_mock;
 
 pub mod build_error;
 pub mod buffer_copy;
@@ -27,6 +32,42 @@ const PRINT_SLICES_MAX: usize = 16;
 const PRINT: bool = false;
 
 
+/// Installs the mock platform and registers implementations of the kernels
+/// used by these tests if the `mock` feature is enabled.
+///
+/// Must be called at the start of every test which uses OpenCL, as the mock
+/// can not be installed once the system library has been loaded. Panics if
+/// that has already happened.
+#[cfg(feature = "mock")]
+pub fn init() {
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    // Installing again is a no-op once the mock is in place. Doing so on
+    // every call (rather than once) keeps a failure here from poisoning
+    // `INIT` and obscuring the cause in every test that follows:
+    if let Err(err) = ocl_mock::install() {
+        panic!("ocl_core::tests::init: Unable to install the mock platform: {} \
+            A test which does not call `init` has most likely used OpenCL \
+            first and loaded the system library.", err);
+    }
+
+    INIT.call_once(|| {
+        ocl_mock::register_kernel("add", |wi| {
+            let idx = wi.global_id(0);
+            let val: f32 = wi.read(0, idx);
+            let addend: f32 = wi.scalar(1);
+            wi.write(0, idx, val + addend);
+        });
+    });
+}
+
+/// Does nothing (see the `mock` feature).
+#[cfg(not(feature = "mock"))]
+pub fn init() {}
+
+
 /// Returns one context for each device on each platform available.
 pub fn get_available_contexts() -> Vec<(PlatformId, DeviceId, Context)> {
     use ::{DeviceInfo, DeviceInfoResult};
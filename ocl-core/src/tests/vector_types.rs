@@ -304,7 +304,9 @@ fn add_char16(context: &Context, queue: &CommandQueue) {
     create_enqueue_verify(context, queue, src, start_val, addend);
 }
 
+// Every kernel is named 'add', which mock kernels can not tell apart:
 #[test]
+#[cfg_attr(feature = "mock", ignore)]
 fn test_vector_types() {
     for (_, device, ref context) in get_available_contexts() {
         let queue = ::create_command_queue(context, &device, None).unwrap();
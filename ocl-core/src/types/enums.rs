@@ -17,12 +17,12 @@ use ffi::{cl_image_format, cl_context_properties, size_t, c_void};
 
 use ::{OclPrm, CommandQueueProperties, PlatformId, PlatformInfo, DeviceId, DeviceInfo, ContextInfo,
     GlContextInfo, Context, CommandQueue, CommandQueueInfo, CommandType, CommandExecutionStatus,
-    Mem, MemInfo, MemObjectType, MemFlags, Sampler, SamplerInfo, AddressingMode, FilterMode,
+    Mem, MemInfo, MemObjectType, MemFlags, Sampler, SamplerInfo, AddressingMode, FilterMode, PipeInfo,
     ProgramInfo, ProgramBuildInfo, Program, ProgramBuildStatus, ProgramBinaryType, KernelInfo,
-    KernelArgInfo, KernelWorkGroupInfo, KernelArgAddressQualifier, KernelArgAccessQualifier,
+    KernelArgInfo, KernelWorkGroupInfo, KernelSubGroupInfo, KernelArgAddressQualifier, KernelArgAccessQualifier,
     KernelArgTypeQualifier, ImageInfo, ImageFormat, EventInfo, ProfilingInfo, DeviceType,
     DeviceFpConfig, DeviceMemCacheType, DeviceLocalMemType, DeviceExecCapabilities,
-    DevicePartitionProperty, DeviceAffinityDomain, OpenclVersion, ContextProperties,
+    DevicePartitionProperty, DeviceAffinityDomain, DeviceSvmCapabilities, OpenclVersion, ContextProperties,
     ImageFormatParseResult, Status};
 
 use error::{Result as OclCoreResult, Error as OclCoreError};
@@ -46,6 +46,8 @@ pub enum EmptyInfoResultError {
     Image,
     #[fail(display = "Sampler info unavailable")]
     Sampler,
+    #[fail(display = "Pipe info unavailable")]
+    Pipe,
     #[fail(display = "Program info unavailable")]
     Program,
     #[fail(display = "Program build info unavailable")]
@@ -56,6 +58,8 @@ pub enum EmptyInfoResultError {
     KernelArg,
     #[fail(display = "Kernel work-group info unavailable")]
     KernelWorkGroup,
+    #[fail(display = "Kernel sub-group info unavailable")]
+    KernelSubGroup,
     #[fail(display = "Event info unavailable")]
     Event,
     #[fail(display = "Event profiling info unavailable")]
@@ -267,6 +271,12 @@ pub enum DeviceInfoResult {
     PrintfBufferSize(usize),         // usize
     ImagePitchAlignment(u32),      // cl_uint
     ImageBaseAddressAlignment(u32),// cl_uint
+    SvmCapabilities(DeviceSvmCapabilities), // cl_device_svm_capabilities FLAGS u64
+    MaxPipeArgs(u32),              // cl_uint
+    PipeMaxActiveReservations(u32),// cl_uint
+    PipeMaxPacketSize(u32),        // cl_uint
+    IlVersion(String),             // String
+    NonUniformWorkGroupSupport(bool), // cl_bool
 }
 
 impl DeviceInfoResult {
@@ -616,16 +626,18 @@ impl DeviceInfoResult {
                 DeviceInfoResult::PartitionMaxSubDevices(r)
             },
             DeviceInfo::PartitionProperties => {
-                // [FIXME]: INCOMPLETE:
-                //
-                // let r = unsafe { util::bytes_into::<u32>(result)? };
-                // match DevicePartitionProperty::from_u32(r) {
-                //     Some(e) => DeviceInfoResult::PartitionProperties(e),
-                //     None => DeviceInfoResult::Error(Box::new(
-                //         OclCoreError::from(format!("Error converting '{:X}' to \
-                //             DevicePartitionProperty.", r)))),
-                // }
-                DeviceInfoResult::PartitionProperties(Vec::with_capacity(0))
+                // A device which can not be partitioned returns a list
+                // containing only `0`:
+                let r = unsafe { util::bytes_into_vec::<isize>(result)? };
+                let mut props = Vec::with_capacity(r.len());
+                for p in r.into_iter().filter(|&p| p != 0) {
+                    match DevicePartitionProperty::from_isize(p) {
+                        Some(e) => props.push(e),
+                        None => return Err(OclCoreError::from(format!("Error converting \
+                            '{:X}' to DevicePartitionProperty.", p))),
+                    }
+                }
+                DeviceInfoResult::PartitionProperties(props)
             },
             DeviceInfo::PartitionAffinityDomain => {
                 let r = unsafe { util::bytes_into::<DeviceAffinityDomain>(result)? };
@@ -663,6 +675,32 @@ impl DeviceInfoResult {
                 let r = unsafe { util::bytes_into::<u32>(result)? };
                 DeviceInfoResult::ImageBaseAddressAlignment(r)
             },
+            DeviceInfo::SvmCapabilities => {
+                let r = unsafe { util::bytes_into::<u64>(result)? };
+                DeviceInfoResult::SvmCapabilities(DeviceSvmCapabilities::from_bits_truncate(r))
+            },
+            DeviceInfo::MaxPipeArgs => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::MaxPipeArgs(r)
+            },
+            DeviceInfo::PipeMaxActiveReservations => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::PipeMaxActiveReservations(r)
+            },
+            DeviceInfo::PipeMaxPacketSize => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::PipeMaxPacketSize(r)
+            },
+            DeviceInfo::IlVersion => {
+                match util::bytes_into_string(result) {
+                    Ok(s) => DeviceInfoResult::IlVersion(s),
+                    Err(err) => return Err(err.into()),
+                }
+            },
+            DeviceInfo::NonUniformWorkGroupSupport => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                DeviceInfoResult::NonUniformWorkGroupSupport(r != 0)
+            },
             // _ => DeviceInfoResult::TemporaryPlaceholderVariant(result),
         };
 
@@ -766,6 +804,12 @@ impl fmt::Display for DeviceInfoResult {
             DeviceInfoResult::PrintfBufferSize(ref s) => write!(f, "{}", s),
             DeviceInfoResult::ImagePitchAlignment(ref s) => write!(f, "{}", s),
             DeviceInfoResult::ImageBaseAddressAlignment(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::SvmCapabilities(ref s) => write!(f, "{:?}", s),
+            DeviceInfoResult::MaxPipeArgs(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::PipeMaxActiveReservations(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::PipeMaxPacketSize(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::IlVersion(ref s) => write!(f, "{}", s),
+            DeviceInfoResult::NonUniformWorkGroupSupport(ref s) => write!(f, "{}", s),
         }
     }
 }
@@ -905,6 +949,7 @@ pub enum CommandQueueInfoResult {
     Device(DeviceId),
     ReferenceCount(u32),
     Properties(CommandQueueProperties),
+    Size(u32),
 }
 
 impl CommandQueueInfoResult {
@@ -932,6 +977,10 @@ impl CommandQueueInfoResult {
                 let r = unsafe { util::bytes_into::<CommandQueueProperties>(result)? };
                 CommandQueueInfoResult::Properties(r)
             }
+            CommandQueueInfo::Size => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                CommandQueueInfoResult::Size(r)
+            }
         };
         Ok(ir)
     }
@@ -950,6 +999,7 @@ impl fmt::Display for CommandQueueInfoResult {
             CommandQueueInfoResult::Device(ref s) => write!(f, "{:?}", s),
             CommandQueueInfoResult::ReferenceCount(ref s) => write!(f, "{}", s),
             CommandQueueInfoResult::Properties(ref s) => write!(f, "{:?}", s),
+            CommandQueueInfoResult::Size(ref s) => write!(f, "{}", s),
             // _ => panic!("CommandQueueInfoResult: Converting this variant to string not yet implemented."),
         }
     }
@@ -1286,6 +1336,54 @@ impl From<SamplerInfoResult> for String {
 }
 
 
+/// A pipe info result.
+pub enum PipeInfoResult {
+    PacketSize(u32),
+    MaxPackets(u32),
+}
+
+impl PipeInfoResult {
+    pub fn from_bytes(request: PipeInfo, result: Vec<u8>) -> OclCoreResult<PipeInfoResult> {
+        if result.is_empty() {
+            return Err(OclCoreError::from(
+                EmptyInfoResultError::Pipe));
+        }
+        let ir = match request {
+            PipeInfo::PacketSize => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                PipeInfoResult::PacketSize(r)
+            },
+            PipeInfo::MaxPackets => {
+                let r = unsafe { util::bytes_into::<u32>(result)? };
+                PipeInfoResult::MaxPackets(r)
+            },
+        };
+        Ok(ir)
+    }
+}
+
+impl fmt::Debug for PipeInfoResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", &self.to_string())
+    }
+}
+
+impl fmt::Display for PipeInfoResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PipeInfoResult::PacketSize(ref s) => write!(f, "{}", s),
+            PipeInfoResult::MaxPackets(ref s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<PipeInfoResult> for String {
+    fn from(ir: PipeInfoResult) -> String {
+        ir.to_string()
+    }
+}
+
+
 /// A program info result.
 pub enum ProgramInfoResult {
     ReferenceCount(u32),
@@ -1296,7 +1394,7 @@ pub enum ProgramInfoResult {
     BinarySizes(Vec<usize>),
     Binaries(Vec<Vec<u8>>),
     NumKernels(usize),
-    KernelNames(String),
+    KernelNames(Vec<String>),
 }
 
 impl ProgramInfoResult {
@@ -1334,7 +1432,8 @@ impl ProgramInfoResult {
                     unsafe { util::bytes_into_vec::<usize>(result)? }
             ) },
             ProgramInfo::Binaries => {
-                // [FIXME]: UNIMPLEMENTED
+                // Binaries can not be created from bytes and are instead
+                // retrieved directly by `functions::get_program_info`:
                 ProgramInfoResult::Binaries(Vec::with_capacity(0))
             },
             ProgramInfo::NumKernels => {
@@ -1343,7 +1442,10 @@ impl ProgramInfoResult {
             },
             ProgramInfo::KernelNames => {
                 match util::bytes_into_string(result) {
-                    Ok(s) => ProgramInfoResult::KernelNames(s),
+                    Ok(s) => ProgramInfoResult::KernelNames(s.split(';')
+                        .filter(|name| !name.is_empty())
+                        .map(|name| name.to_owned())
+                        .collect()),
                     Err(err) => return Err(err.into()),
                 }
             },
@@ -1370,7 +1472,7 @@ impl fmt::Display for ProgramInfoResult {
             ProgramInfoResult::BinarySizes(ref s) => write!(f, "{:?}", s),
             ProgramInfoResult::Binaries(_) => write!(f, "{{unprintable}}"),
             ProgramInfoResult::NumKernels(ref s) => write!(f, "{}", s),
-            ProgramInfoResult::KernelNames(ref s) => write!(f, "{}", s),
+            ProgramInfoResult::KernelNames(ref s) => write!(f, "{}", s.join(";")),
         }
     }
 }
@@ -1712,6 +1814,73 @@ impl From<KernelWorkGroupInfoResult> for String {
 }
 
 
+/// A kernel sub-group info result.
+pub enum KernelSubGroupInfoResult {
+    MaxSubGroupSizeForNdrange(usize),
+    SubGroupCountForNdrange(usize),
+    LocalSizeForSubGroupCount([usize; 3]),
+    MaxNumSubGroups(usize),
+    CompileNumSubGroups(usize),
+}
+
+impl KernelSubGroupInfoResult {
+    pub fn from_bytes(request: KernelSubGroupInfo, result: Vec<u8>)
+            -> OclCoreResult<KernelSubGroupInfoResult> {
+        if result.is_empty() {
+            return Err(OclCoreError::from(
+                EmptyInfoResultError::KernelSubGroup));
+        }
+        let ir = match request {
+            KernelSubGroupInfo::MaxSubGroupSizeForNdrange => {
+                let r = unsafe { util::bytes_into::<usize>(result)? };
+                KernelSubGroupInfoResult::MaxSubGroupSizeForNdrange(r)
+            },
+            KernelSubGroupInfo::SubGroupCountForNdrange => {
+                let r = unsafe { util::bytes_into::<usize>(result)? };
+                KernelSubGroupInfoResult::SubGroupCountForNdrange(r)
+            },
+            KernelSubGroupInfo::LocalSizeForSubGroupCount => {
+                let r = unsafe { util::bytes_into::<[usize; 3]>(result)? };
+                KernelSubGroupInfoResult::LocalSizeForSubGroupCount(r)
+            },
+            KernelSubGroupInfo::MaxNumSubGroups => {
+                let r = unsafe { util::bytes_into::<usize>(result)? };
+                KernelSubGroupInfoResult::MaxNumSubGroups(r)
+            },
+            KernelSubGroupInfo::CompileNumSubGroups => {
+                let r = unsafe { util::bytes_into::<usize>(result)? };
+                KernelSubGroupInfoResult::CompileNumSubGroups(r)
+            },
+        };
+        Ok(ir)
+    }
+}
+
+impl fmt::Debug for KernelSubGroupInfoResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", &self.to_string())
+    }
+}
+
+impl fmt::Display for KernelSubGroupInfoResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KernelSubGroupInfoResult::MaxSubGroupSizeForNdrange(s) => write!(f, "{}", s),
+            KernelSubGroupInfoResult::SubGroupCountForNdrange(s) => write!(f, "{}", s),
+            KernelSubGroupInfoResult::LocalSizeForSubGroupCount(s) => write!(f, "{:?}", s),
+            KernelSubGroupInfoResult::MaxNumSubGroups(s) => write!(f, "{}", s),
+            KernelSubGroupInfoResult::CompileNumSubGroups(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<KernelSubGroupInfoResult> for String {
+    fn from(ir: KernelSubGroupInfoResult) -> String {
+        ir.to_string()
+    }
+}
+
+
 /// An event info result.
 pub enum EventInfoResult {
     CommandQueue(CommandQueue),
@@ -6,9 +6,11 @@ use std::marker::PhantomData;
 use std::collections::HashMap;
 use num_traits::FromPrimitive;
 use error::{Error as OclCoreError, Result as OclCoreResult};
-use ffi::{self, cl_mem, cl_buffer_region, cl_context_properties, cl_platform_id, c_void};
+use ffi::{self, cl_mem, cl_buffer_region, cl_context_properties, cl_platform_id, c_void,
+    cl_device_partition_property, cl_queue_properties};
 use ::{Mem, MemObjectType, ImageChannelOrder, ImageChannelDataType, ContextProperty,
-    PlatformId, OclPrm};
+    PlatformId, OclPrm, DevicePartitionProperty, DeviceAffinityDomain, CommandQueueProperties,
+    QueuePriority, QueueThrottle};
 
 
 // Until everything can be implemented:
@@ -539,6 +541,190 @@ impl From<ContextProperties> for Vec<isize> {
 
 
 
+/// A device partitioning scheme used when creating sub-devices.
+///
+/// ### Info (from [SDK](https://www.khronos.org/registry/cl/sdk/1.2/docs/man/xhtml/clCreateSubDevices.html))
+///
+/// `Equally(n)`: Split the device into as many sub-devices as can be created
+/// with `n` compute units each.
+///
+/// `ByCounts(counts)`: Create one sub-device for each entry in `counts`,
+/// containing that many compute units.
+///
+/// `ByAffinityDomain(domain)`: Split the device along the specified affinity
+/// domain (ex.: shared NUMA node or cache level).
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DevicePartition {
+    Equally(u32),
+    ByCounts(Vec<u32>),
+    ByAffinityDomain(DeviceAffinityDomain),
+}
+
+impl DevicePartition {
+    /// Returns a zero-terminated property list suitable for passing to
+    /// `clCreateSubDevices`.
+    pub fn to_raw(&self) -> Vec<cl_device_partition_property> {
+        match *self {
+            DevicePartition::Equally(compute_units) => vec![
+                DevicePartitionProperty::Equally as cl_device_partition_property,
+                compute_units as cl_device_partition_property,
+                0,
+            ],
+            DevicePartition::ByCounts(ref counts) => {
+                let mut props = Vec::with_capacity(counts.len() + 3);
+                props.push(DevicePartitionProperty::ByCounts as cl_device_partition_property);
+                props.extend(counts.iter().map(|&c| c as cl_device_partition_property));
+                props.push(DevicePartitionProperty::ByCountsListEnd as cl_device_partition_property);
+                props.push(0);
+                props
+            },
+            DevicePartition::ByAffinityDomain(domain) => vec![
+                DevicePartitionProperty::ByAffinityDomain as cl_device_partition_property,
+                domain.bits() as cl_device_partition_property,
+                0,
+            ],
+        }
+    }
+}
+
+
+
+/// Command queue properties list, used when creating a queue with
+/// `clCreateCommandQueueWithProperties` (OpenCL 2.0+).
+///
+/// ### Info (from [SDK](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clCreateCommandQueueWithProperties.html))
+///
+/// `properties`: A `CommandQueueProperties` bitfield. On-device queues must
+/// specify `ON_DEVICE` (and `OUT_OF_ORDER_EXEC_MODE_ENABLE`).
+///
+/// `size`: The size of an on-device queue in bytes. May only be specified
+/// for on-device queues.
+///
+/// `priority` and `throttle`: Scheduling hints. Require the
+/// `cl_khr_priority_hints` and `cl_khr_throttle_hints` extensions
+/// respectively and may not be specified for on-device queues.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueueProperties {
+    properties: CommandQueueProperties,
+    size: Option<u32>,
+    priority: Option<QueuePriority>,
+    throttle: Option<QueueThrottle>,
+}
+
+impl QueueProperties {
+    /// Returns an empty new list of queue properties.
+    pub fn new() -> QueueProperties {
+        QueueProperties::default()
+    }
+
+    /// Specifies the command queue property bitfield (builder-style).
+    pub fn properties(mut self, properties: CommandQueueProperties) -> QueueProperties {
+        self.set_properties(properties);
+        self
+    }
+
+    /// Specifies the size of an on-device queue in bytes (builder-style).
+    pub fn size(mut self, size: u32) -> QueueProperties {
+        self.set_size(size);
+        self
+    }
+
+    /// Specifies a priority hint (builder-style).
+    pub fn priority(mut self, priority: QueuePriority) -> QueueProperties {
+        self.set_priority(priority);
+        self
+    }
+
+    /// Specifies a throttle hint (builder-style).
+    pub fn throttle(mut self, throttle: QueueThrottle) -> QueueProperties {
+        self.set_throttle(throttle);
+        self
+    }
+
+    /// Specifies the command queue property bitfield.
+    pub fn set_properties(&mut self, properties: CommandQueueProperties) {
+        self.properties = properties;
+    }
+
+    /// Specifies the size of an on-device queue in bytes.
+    pub fn set_size(&mut self, size: u32) {
+        self.size = Some(size);
+    }
+
+    /// Specifies a priority hint.
+    pub fn set_priority(&mut self, priority: QueuePriority) {
+        self.priority = Some(priority);
+    }
+
+    /// Specifies a throttle hint.
+    pub fn set_throttle(&mut self, throttle: QueueThrottle) {
+        self.throttle = Some(throttle);
+    }
+
+    /// Returns the command queue property bitfield.
+    pub fn get_properties(&self) -> CommandQueueProperties {
+        self.properties
+    }
+
+    /// Returns the on-device queue size, if specified.
+    pub fn get_size(&self) -> Option<u32> {
+        self.size
+    }
+
+    /// Returns the priority hint, if specified.
+    pub fn get_priority(&self) -> Option<QueuePriority> {
+        self.priority
+    }
+
+    /// Returns the throttle hint, if specified.
+    pub fn get_throttle(&self) -> Option<QueueThrottle> {
+        self.throttle
+    }
+
+    /// Returns true if only a property bitfield has been specified, in
+    /// which case a queue may also be created with the pre-2.0
+    /// `clCreateCommandQueue`.
+    pub fn is_bitfield_only(&self) -> bool {
+        self.size.is_none() && self.priority.is_none() && self.throttle.is_none()
+    }
+
+    /// Returns a zero-terminated property list suitable for passing to
+    /// `clCreateCommandQueueWithProperties`.
+    pub fn to_raw(&self) -> Vec<cl_queue_properties> {
+        let mut props = Vec::with_capacity(9);
+
+        if !self.properties.is_empty() {
+            props.push(ffi::CL_QUEUE_PROPERTIES as cl_queue_properties);
+            props.push(self.properties.bits() as cl_queue_properties);
+        }
+        if let Some(size) = self.size {
+            props.push(ffi::CL_QUEUE_SIZE as cl_queue_properties);
+            props.push(size as cl_queue_properties);
+        }
+        if let Some(priority) = self.priority {
+            props.push(ffi::CL_QUEUE_PRIORITY_KHR);
+            props.push(priority as cl_queue_properties);
+        }
+        if let Some(throttle) = self.throttle {
+            props.push(ffi::CL_QUEUE_THROTTLE_KHR);
+            props.push(throttle as cl_queue_properties);
+        }
+
+        props.push(0);
+        props
+    }
+}
+
+impl From<CommandQueueProperties> for QueueProperties {
+    fn from(properties: CommandQueueProperties) -> QueueProperties {
+        QueueProperties::new().properties(properties)
+    }
+}
+
+
+
 /// Defines a buffer region for creating a sub-buffer.
 ///
 /// ### Info (from [SDK](https://www.khronos.org/registry/cl/sdk/1.2/docs/man/xhtml/clCreateSubBuffer.html))
@@ -0,0 +1,119 @@
+//! Kernel signatures parsed from OpenCL C source (by `ocl-kernel-src`) and
+//! the Rust types used to set each argument.
+
+use ocl_kernel_src::{self, Address};
+
+
+/// The kind of a kernel argument along with the Rust type used to set it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArgKind {
+    /// A `__global` or `__constant` pointer (a `Buffer` of the contained
+    /// type).
+    Buffer(String),
+    /// A `__local` pointer (an allocation of the contained type).
+    Local(String),
+    /// A scalar or vector value.
+    Value(String),
+    /// A pipe of packets of the contained type.
+    Pipe(String),
+    Image,
+    Sampler,
+    /// A type which has no Rust equivalent (structs, `half`, `size_t`,
+    /// etc.).
+    Unsupported(String),
+}
+
+
+/// A kernel argument declaration.
+#[derive(Clone, Debug)]
+pub struct ArgSig {
+    pub name: String,
+    pub kind: ArgKind,
+}
+
+
+/// A kernel function declaration.
+#[derive(Clone, Debug)]
+pub struct KernelSig {
+    pub name: String,
+    pub args: Vec<ArgSig>,
+}
+
+
+/// Returns the Rust type of a scalar or vector OpenCL C type name.
+pub fn prm_type(type_name: &str) -> Option<String> {
+    ocl_kernel_src::vector_type(type_name).map(|(scl, card)| match card {
+        1 => scl.rust_name().to_owned(),
+        _ => format!("::ocl::prm::{}{}", scl.vector_name(), card),
+    })
+}
+
+fn arg_kind(sig: &ocl_kernel_src::ArgSig) -> ArgKind {
+    let base = sig.type_name.trim_right_matches('*');
+    let ptr_depth = sig.type_name.len() - base.len();
+
+    if sig.is_image() {
+        ArgKind::Image
+    } else if sig.is_sampler() {
+        ArgKind::Sampler
+    } else {
+        match (prm_type(base), ptr_depth) {
+            (Some(ty), 0) if sig.is_pipe() => ArgKind::Pipe(ty),
+            (Some(ty), 0) => ArgKind::Value(ty),
+            (Some(ty), 1) if sig.address == Address::Local => ArgKind::Local(ty),
+            (Some(ty), 1) => ArgKind::Buffer(ty),
+            _ => ArgKind::Unsupported(sig.type_name.clone()),
+        }
+    }
+}
+
+/// Returns the signatures of all kernel functions defined in `src`.
+pub fn parse_kernels(src: &str) -> Vec<KernelSig> {
+    ocl_kernel_src::parse_kernels(src).into_iter().map(|k| {
+        let args = k.args.iter().map(|a| ArgSig { name: a.name.clone(), kind: arg_kind(a) })
+            .collect();
+        KernelSig { name: k.name, args: args }
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_signatures() {
+        let src = r#"
+            #define SCALE 2
+            // __kernel void commented_out(int a) {}
+            __kernel void add(__global float* buf, float4 v, __local unsigned int* tmp) {
+                buf[get_global_id(0)] += SCALE;
+            }
+
+            /* A prototype: */
+            __kernel void proto(int a);
+
+            kernel __attribute__((reqd_work_group_size(64, 1, 1)))
+            void img(read_only image2d_t src, sampler_t smp, __write_only pipe uchar16 out,
+                    __global struct Foo* foo, half h) {}
+        "#;
+
+        let kernels = parse_kernels(src);
+        assert_eq!(kernels.len(), 2);
+
+        assert_eq!(kernels[0].name, "add");
+        assert_eq!(kernels[0].args[0].name, "buf");
+        assert_eq!(kernels[0].args[0].kind, ArgKind::Buffer("f32".to_owned()));
+        assert_eq!(kernels[0].args[1].kind, ArgKind::Value("::ocl::prm::Float4".to_owned()));
+        assert_eq!(kernels[0].args[2].kind, ArgKind::Local("u32".to_owned()));
+
+        let img = &kernels[1];
+        assert_eq!(img.name, "img");
+        assert_eq!(img.args[0].kind, ArgKind::Image);
+        assert_eq!(img.args[1].kind, ArgKind::Sampler);
+        assert_eq!(img.args[2].name, "out");
+        assert_eq!(img.args[2].kind, ArgKind::Pipe("::ocl::prm::Uchar16".to_owned()));
+        assert_eq!(img.args[3].kind, ArgKind::Unsupported("struct Foo*".to_owned()));
+        assert_eq!(img.args[4].kind, ArgKind::Unsupported("half".to_owned()));
+    }
+}
@@ -0,0 +1,403 @@
+//! Custom derives for the [`ocl`] crate.
+//!
+//! ## `KernelArgs`
+//!
+//! Implements `ocl::traits::KernelArgs` for a struct whose fields are the
+//! arguments of a kernel, in order, allowing them to be set with
+//! `Kernel::set_args`. Each field must implement
+//! `ocl::traits::KernelArgValue` (buffers, images, samplers, scalars,
+//! vectors, and references to any of these).
+//!
+//! Field names are checked against the names of the kernel's arguments (if
+//! reported by the platform). Use `#[kernel_arg(name = "...")]` to specify
+//! a different name.
+//!
+//! Structs with type parameters are not supported.
+//!
+//! ## `KernelBindings`
+//!
+//! Generates a struct with typed argument setters for every kernel defined
+//! in an OpenCL C source file, specified (relative to the crate's
+//! `Cargo.toml`) with a `#[kernels_src = "..."]` attribute on a unit struct:
+//!
+//! ```rust,ignore
+//! #[derive(KernelBindings)]
+//! #[kernels_src = "src/kernels.cl"]
+//! pub struct Kernels;
+//! ```
+//!
+//! For a kernel named `add_scalar` with the signature `(__global float*
+//! buffer, float scalar)`, a struct named `AddScalar` is generated, created
+//! with `AddScalar::new(&program)` and having the methods
+//! `set_buffer(&Buffer<f32>)` and `set_scalar(f32)`. Each struct
+//! dereferences to its `Kernel`. The source itself is available as
+//! `Kernels::SRC`.
+//!
+//! Arguments are mapped as follows:
+//!
+//! * `__global` or `__constant` pointers: `&Buffer<T>`
+//! * `__local` pointers: a length (in elements of `T`)
+//! * Scalars and vectors (`float`, `uint4`, etc.): `T`
+//! * Images, samplers and pipes: `&Image<_>`, `&Sampler` and `&Pipe<T>`
+//!
+//! Setters are not generated for arguments of other types (structs, `half`,
+//! etc.), which can be set using the `Kernel` directly.
+//!
+//! Because the source is read when the crate is compiled, changes to the
+//! order or types of a kernel's arguments cause a build error wherever a
+//! setter is misused.
+//!
+//! ## `OclPrm`
+//!
+//! Implements `ocl::OclPrm` for a `#[repr(C)]` struct, allowing it to be used
+//! as the element type of a `Buffer` or as a scalar kernel argument, and
+//! generates the matching OpenCL C declaration as `CL_TYPEDEF`, to be added
+//! to a program with `ProgramBuilder::src`:
+//!
+//! ```rust,ignore
+//! #[derive(Clone, Copy, Debug, Default, PartialEq, OclPrm)]
+//! #[repr(C)]
+//! struct Particle {
+//!     position: Float3,
+//!     mass: f32,
+//!     _pad: [u32; 3],
+//! }
+//!
+//! let program = Program::builder().src(Particle::CL_TYPEDEF).src(src).build(&context)?;
+//! ```
+//!
+//! Fields must be scalars (`f32`, `u8`, etc.), vectors (`ocl::prm::Float4`,
+//! etc.), or arrays of either. Because OpenCL C aligns vectors to their size
+//! (with 3-component vectors the size of 4-component vectors) but Rust
+//! aligns them to their scalar type, a build error describing the padding
+//! required is raised wherever the layouts would differ. The `OclPrm`
+//! supertraits (`Clone`, `Copy`, `Debug`, `Default`, and `PartialEq`) must
+//! be derived or implemented separately.
+//!
+//! [`ocl`]: https://github.com/cogciprocate/ocl
+
+extern crate proc_macro;
+extern crate syn;
+#[macro_use]
+extern crate quote;
+extern crate ocl_kernel_src;
+
+mod kernel_src;
+mod prm;
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use proc_macro::TokenStream;
+use syn::{Body, VariantData, Field, MetaItem, NestedMetaItem, Lit, Ty, ConstExpr};
+use kernel_src::{ArgKind, KernelSig};
+
+#[proc_macro_derive(KernelArgs, attributes(kernel_arg))]
+pub fn derive_kernel_args(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_derive_input(&input.to_string()).unwrap();
+    impl_kernel_args(&ast).parse().unwrap()
+}
+
+fn impl_kernel_args(ast: &syn::DeriveInput) -> quote::Tokens {
+    let fields = match ast.body {
+        Body::Struct(VariantData::Struct(ref fields)) => fields,
+        _ => panic!("#[derive(KernelArgs)] is only defined for structs with named fields."),
+    };
+
+    // The type name identifies a verified struct so every instantiation
+    // must have the same field types:
+    if !ast.generics.ty_params.is_empty() {
+        panic!("#[derive(KernelArgs)] is not defined for structs with type parameters.");
+    }
+
+    let ident = &ast.ident;
+    let type_name = ident.to_string();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let visits: Vec<_> = fields.iter().map(|field| {
+        let field_ident = field.ident.as_ref().unwrap();
+        let arg_name = arg_name(field);
+        quote! { visitor.visit(#arg_name, &self.#field_ident)?; }
+    }).collect();
+
+    quote! {
+        impl #impl_generics ::ocl::traits::KernelArgs for #ident #ty_generics #where_clause {
+            fn type_name() -> &'static str {
+                concat!(module_path!(), "::", #type_name)
+            }
+
+            fn visit<V: ::ocl::traits::ArgVisitor>(&self, visitor: &mut V)
+                    -> ::ocl::Result<()> {
+                #(#visits)*
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns the kernel argument name for a field: the value of its
+/// `#[kernel_arg(name = "...")]` attribute or its identifier.
+fn arg_name(field: &Field) -> String {
+    for attr in &field.attrs {
+        let items = match attr.value {
+            MetaItem::List(ref ident, ref items) if ident.as_ref() == "kernel_arg" => items,
+            _ => continue,
+        };
+
+        match items.as_slice() {
+            &[NestedMetaItem::MetaItem(MetaItem::NameValue(ref key, Lit::Str(ref name, _)))]
+                    if key.as_ref() == "name" => return name.clone(),
+            _ => panic!("Invalid 'kernel_arg' attribute. Expected: \
+                '#[kernel_arg(name = \"...\")]'."),
+        }
+    }
+
+    field.ident.as_ref().unwrap().to_string()
+}
+
+
+#[proc_macro_derive(KernelBindings, attributes(kernels_src))]
+pub fn derive_kernel_bindings(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_derive_input(&input.to_string()).unwrap();
+    impl_kernel_bindings(&ast).parse().unwrap()
+}
+
+fn impl_kernel_bindings(ast: &syn::DeriveInput) -> quote::Tokens {
+    let src_path = ast.attrs.iter().filter_map(|attr| match attr.value {
+        MetaItem::NameValue(ref ident, Lit::Str(ref path, _))
+            if ident.as_ref() == "kernels_src" => Some(path.clone()),
+        _ => None,
+    }).next().expect("#[derive(KernelBindings)] requires a '#[kernels_src = \"...\"]' \
+        attribute.");
+
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set.");
+    let path = Path::new(&manifest_dir).join(&src_path);
+    let mut src = String::new();
+    File::open(&path).and_then(|mut f| f.read_to_string(&mut src))
+        .unwrap_or_else(|err| panic!("Unable to read '{}': {}", path.display(), err));
+    let path = path.to_string_lossy().into_owned();
+
+    let ident = &ast.ident;
+    let vis = &ast.vis;
+    let kernels: Vec<_> = kernel_src::parse_kernels(&src).iter()
+        .map(|sig| kernel_binding(sig, vis)).collect();
+
+    quote! {
+        impl #ident {
+            /// The source of every kernel.
+            pub const SRC: &'static str = include_str!(#path);
+        }
+
+        #(#kernels)*
+    }
+}
+
+/// Returns the struct and setters for a kernel.
+fn kernel_binding(sig: &KernelSig, vis: &syn::Visibility) -> quote::Tokens {
+    let name = &sig.name;
+    let ident = syn::Ident::new(sig.name.split('_').filter(|w| !w.is_empty()).map(|w| {
+        let mut chars = w.chars();
+        chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>())
+            .unwrap_or_default()
+    }).collect::<String>());
+    let doc = format!("Bindings for the `{}` kernel.", name);
+    let num_args = sig.args.len() as u32;
+
+    let setters: Vec<_> = sig.args.iter().enumerate().filter_map(|(idx, arg)| {
+        let setter = syn::Ident::new(format!("set_{}", arg.name));
+        let doc = format!("Sets the `{}` argument.", arg.name);
+        let idx = idx as u32;
+        let ty = |ty: &str| syn::parse_type(ty).unwrap();
+
+        let (params, set) = match arg.kind {
+            ArgKind::Buffer(ref t) => {
+                let t = ty(t);
+                // Checks what the type cannot (ex. the size of `__constant` buffers):
+                (quote! { value: &::ocl::Buffer<#t> }, quote! {
+                    self.kernel.set_arg(#idx, value)?;
+                })
+            },
+            ArgKind::Local(ref t) => {
+                let t = ty(t);
+                (quote! { len: usize }, quote! {
+                    unsafe {
+                        self.kernel.set_arg_unchecked::<#t>(#idx,
+                            ::ocl::enums::KernelArg::Local(&len))?;
+                    }
+                })
+            },
+            ArgKind::Value(ref t) => {
+                let t = ty(t);
+                (quote! { value: #t }, quote! {
+                    self.kernel.set_arg(#idx, value)?;
+                })
+            },
+            ArgKind::Pipe(ref t) => {
+                let t = ty(t);
+                (quote! { value: &::ocl::Pipe<#t> }, quote! {
+                    self.kernel.set_arg(#idx, value)?;
+                })
+            },
+            ArgKind::Image => {
+                return Some(quote! {
+                    #[doc = #doc]
+                    pub fn #setter<T: ::ocl::OclPrm>(&mut self, value: &::ocl::Image<T>)
+                            -> ::ocl::Result<&mut Self> {
+                        self.kernel.set_arg(#idx, value)?;
+                        Ok(self)
+                    }
+                });
+            },
+            ArgKind::Sampler => {
+                (quote! { value: &::ocl::Sampler }, quote! {
+                    self.kernel.set_arg(#idx, value)?;
+                })
+            },
+            ArgKind::Unsupported(_) => return None,
+        };
+
+        Some(quote! {
+            #[doc = #doc]
+            pub fn #setter(&mut self, #params) -> ::ocl::Result<&mut Self> {
+                #set
+                Ok(self)
+            }
+        })
+    }).collect();
+
+    quote! {
+        #[doc = #doc]
+        #vis struct #ident {
+            kernel: ::ocl::Kernel,
+        }
+
+        impl #ident {
+            /// The name of the kernel function.
+            pub const NAME: &'static str = #name;
+
+            /// Creates the kernel from a program built from the source the
+            /// bindings were generated from.
+            pub fn new(program: &::ocl::Program) -> ::ocl::Result<#ident> {
+                let kernel = ::ocl::Kernel::new(#name, program)?;
+                let num_args = kernel.num_args()?;
+                if num_args != #num_args {
+                    return Err(format!(
+                        "Kernel '{}' has {} arguments but its bindings were generated for {}.",
+                        #name, num_args, #num_args).into());
+                }
+                Ok(#ident { kernel: kernel })
+            }
+
+            #(#setters)*
+
+            /// Returns the kernel.
+            pub fn into_kernel(self) -> ::ocl::Kernel {
+                self.kernel
+            }
+        }
+
+        impl ::std::ops::Deref for #ident {
+            type Target = ::ocl::Kernel;
+
+            fn deref(&self) -> &::ocl::Kernel {
+                &self.kernel
+            }
+        }
+
+        impl ::std::ops::DerefMut for #ident {
+            fn deref_mut(&mut self) -> &mut ::ocl::Kernel {
+                &mut self.kernel
+            }
+        }
+    }
+}
+
+
+#[proc_macro_derive(OclPrm)]
+pub fn derive_ocl_prm(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_derive_input(&input.to_string()).unwrap();
+    impl_ocl_prm(&ast).parse().unwrap()
+}
+
+fn impl_ocl_prm(ast: &syn::DeriveInput) -> quote::Tokens {
+    let ident = &ast.ident;
+    let fields = match ast.body {
+        Body::Struct(VariantData::Struct(ref fields)) => fields,
+        _ => panic!("#[derive(OclPrm)] is only defined for structs with named fields."),
+    };
+
+    if !ast.generics.ty_params.is_empty() {
+        panic!("#[derive(OclPrm)] is not defined for structs with type parameters.");
+    }
+
+    let repr_c = ast.attrs.iter().any(|attr| match attr.value {
+        MetaItem::List(ref ident, ref items) if ident.as_ref() == "repr" => {
+            items.iter().any(|item| match *item {
+                NestedMetaItem::MetaItem(MetaItem::Word(ref word)) => {
+                    if word.as_ref() == "packed" {
+                        panic!("#[derive(OclPrm)] is not defined for '#[repr(packed)]' structs.");
+                    }
+                    word.as_ref() == "C"
+                },
+                _ => false,
+            })
+        },
+        _ => false,
+    });
+    if !repr_c {
+        panic!("#[derive(OclPrm)] requires '#[repr(C)]' on '{}'.", ident);
+    }
+
+    let cl_fields: Vec<_> = fields.iter().map(|field| {
+        let name = field.ident.as_ref().unwrap().to_string();
+        let ty = field_type(&field.ty).unwrap_or_else(|| panic!("#[derive(OclPrm)]: The type \
+            of field '{}' of '{}' has no OpenCL C equivalent. Fields must be scalars ('f32', \
+            'u8', etc.), vectors ('ocl::prm::Float4', etc.), or arrays of either.", name, ident));
+        prm::Field { name: name, ty: ty }
+    }).collect();
+
+    let (typedef, size) = prm::cl_typedef(ident.as_ref(), &cl_fields);
+
+    // The layout was computed from type names only. Ensure that they refer
+    // to the types expected:
+    let field_checks: Vec<_> = fields.iter().zip(cl_fields.iter()).map(|(field, cl_field)| {
+        let field_ident = field.ident.as_ref().unwrap();
+        let expected = kernel_src::prm_type(&cl_field.ty.cl_name)
+            .and_then(|path| syn::parse_type(&path).ok()).unwrap();
+        match cl_field.ty.len {
+            Some(len) => quote! { let _: &[#expected; #len] = &self.#field_ident; },
+            None => quote! { let _: &#expected = &self.#field_ident; },
+        }
+    }).collect();
+
+    quote! {
+        unsafe impl ::ocl::OclPrm for #ident {}
+
+        impl #ident {
+            /// The OpenCL C declaration of this struct.
+            pub const CL_TYPEDEF: &'static str = #typedef;
+
+            #[allow(dead_code)]
+            fn __ocl_prm_layout(&self) {
+                #(#field_checks)*
+                let _: [(); #size] = [(); ::std::mem::size_of::<#ident>()];
+            }
+        }
+    }
+}
+
+/// Returns the type of a struct field, if supported.
+fn field_type(ty: &Ty) -> Option<prm::FieldType> {
+    match *ty {
+        Ty::Path(None, ref path) => path.segments.last()
+            .and_then(|seg| prm::FieldType::from_rust_name(seg.ident.as_ref())),
+        Ty::Array(ref elem, ConstExpr::Lit(Lit::Int(len, _))) => {
+            match **elem {
+                Ty::Array(..) => None,
+                ref elem => field_type(elem).map(|ty| ty.array(len as usize)),
+            }
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,166 @@
+//! OpenCL C layouts of `#[repr(C)]` structs.
+//!
+//! Scalars are aligned to their size in both Rust and OpenCL C. Vectors
+//! (`ocl::prm::Float4`, etc.) are aligned to their scalar type in Rust but to
+//! their own size in OpenCL C, with 3-component vectors having the size and
+//! alignment of 4-component vectors.
+
+use ocl_kernel_src::Scalar;
+
+/// Returns `offset` rounded up to a multiple of `align`.
+fn round_up(offset: usize, align: usize) -> usize {
+    (offset + align - 1) / align * align
+}
+
+
+/// The type of a struct field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldType {
+    /// The OpenCL C type name (ex. 'float4').
+    pub cl_name: String,
+    /// The number of elements if the field is an array.
+    pub len: Option<usize>,
+    pub size: usize,
+    pub rust_align: usize,
+    pub cl_align: usize,
+}
+
+impl FieldType {
+    /// Returns the type of a field given the last segment of its Rust type
+    /// path (ex. 'f32', 'Float4'), or `None` if it has no OpenCL C
+    /// equivalent.
+    pub fn from_rust_name(rust_name: &str) -> Option<FieldType> {
+        let base_len = rust_name.trim_right_matches(|c: char| c.is_digit(10)).len();
+        let (base, card) = rust_name.split_at(base_len);
+
+        if let Some(scl) = Scalar::from_rust_name(rust_name) {
+            let size = scl.size();
+            return Some(FieldType { cl_name: scl.cl_name().to_owned(), len: None, size: size,
+                rust_align: size, cl_align: size });
+        }
+
+        let scl = match Scalar::from_vector_name(base) {
+            Some(scl) => scl,
+            None => return None,
+        };
+        let width = match card {
+            "2" | "4" | "8" | "16" => card.parse::<usize>().unwrap(),
+            // Stored (and aligned) as four components:
+            "3" => 4,
+            _ => return None,
+        };
+
+        let scl_size = scl.size();
+        Some(FieldType { cl_name: format!("{}{}", scl.cl_name(), card), len: None,
+            size: scl_size * width, rust_align: scl_size, cl_align: scl_size * width })
+    }
+
+    /// Returns the type of an array of `len` elements of this type.
+    pub fn array(self, len: usize) -> FieldType {
+        FieldType { len: Some(len), size: self.size * len, ..self }
+    }
+}
+
+
+/// A struct field.
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub name: String,
+    pub ty: FieldType,
+}
+
+
+/// Returns the OpenCL C `typedef` of a struct along with its size in bytes.
+///
+/// Panics with a description of the padding required if the layout of any
+/// field (or the size of the struct) differs between Rust and OpenCL C.
+pub fn cl_typedef(struct_name: &str, fields: &[Field]) -> (String, usize) {
+    let mut decl = format!("typedef struct {} {{\n", struct_name);
+    let mut rust_offset = 0;
+    let mut cl_offset = 0;
+    let mut rust_align = 1;
+    let mut cl_align = 1;
+
+    for field in fields {
+        let ty = &field.ty;
+        let rust_field_offset = round_up(rust_offset, ty.rust_align);
+        let cl_field_offset = round_up(cl_offset, ty.cl_align);
+
+        if rust_field_offset != cl_field_offset {
+            let pad = cl_field_offset - rust_field_offset;
+            panic!("#[derive(OclPrm)]: Field '{}' ({}) of '{}' is at byte offset {} but is \
+                aligned to offset {} in OpenCL C. Add {} bytes of padding before it (ex. \
+                '_pad: [u8; {}]').", field.name, ty.cl_name, struct_name, rust_field_offset,
+                cl_field_offset, pad, pad);
+        }
+
+        decl.push_str(&match ty.len {
+            Some(len) => format!("    {} {}[{}];\n", ty.cl_name, field.name, len),
+            None => format!("    {} {};\n", ty.cl_name, field.name),
+        });
+
+        rust_offset = rust_field_offset + ty.size;
+        cl_offset = cl_field_offset + ty.size;
+        rust_align = rust_align.max(ty.rust_align);
+        cl_align = cl_align.max(ty.cl_align);
+    }
+
+    let rust_size = round_up(rust_offset, rust_align);
+    let cl_size = round_up(cl_offset, cl_align);
+
+    if rust_size != cl_size {
+        let pad = cl_size - rust_size;
+        panic!("#[derive(OclPrm)]: '{}' is {} bytes but {} bytes in OpenCL C, which aligns it \
+            to {} bytes. Add {} bytes of padding after its last field (ex. '_pad: [u8; {}]').",
+            struct_name, rust_size, cl_size, cl_align, pad, pad);
+    }
+
+    decl.push_str(&format!("}} {};\n", struct_name));
+    (decl, cl_size)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, rust_name: &str) -> Field {
+        Field { name: name.to_owned(), ty: FieldType::from_rust_name(rust_name).unwrap() }
+    }
+
+    #[test]
+    fn field_types() {
+        let float3 = FieldType::from_rust_name("Float3").unwrap();
+        assert_eq!((&float3.cl_name[..], float3.size, float3.rust_align, float3.cl_align),
+            ("float3", 16, 4, 16));
+        let ulong = FieldType::from_rust_name("u64").unwrap();
+        assert_eq!((&ulong.cl_name[..], ulong.size, ulong.cl_align), ("ulong", 8, 8));
+        assert_eq!(FieldType::from_rust_name("Uchar2").unwrap().array(3).size, 6);
+        assert!(FieldType::from_rust_name("usize").is_none());
+        assert!(FieldType::from_rust_name("Float5").is_none());
+        assert!(FieldType::from_rust_name("Point").is_none());
+    }
+
+    #[test]
+    fn typedef() {
+        let fields = [field("position", "Float3"), field("mass", "f32"),
+            Field { name: "_pad".to_owned(), ty: FieldType::from_rust_name("u32").unwrap()
+                .array(3) }];
+        let (decl, size) = cl_typedef("Particle", &fields);
+        assert_eq!(decl, "typedef struct Particle {\n    float3 position;\n    float mass;\n    \
+            uint _pad[3];\n} Particle;\n");
+        assert_eq!(size, 32);
+    }
+
+    #[test]
+    #[should_panic(expected = "Add 12 bytes of padding before it")]
+    fn misaligned_vector() {
+        cl_typedef("Misaligned", &[field("mass", "f32"), field("position", "Float4")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Add 12 bytes of padding after its last field")]
+    fn short_struct() {
+        cl_typedef("Short", &[field("position", "Float4"), field("mass", "f32")]);
+    }
+}
@@ -0,0 +1,596 @@
+//! Kernel signatures parsed from OpenCL C source.
+//!
+//! Shared by `ocl` (argument names and types on platforms which do not
+//! report them), `ocl-derive` (typed kernel bindings) and `ocl-mock` (kernel
+//! creation). Only comments, conditional preprocessor blocks (tracked, not
+//! evaluated; other directives are skipped and macros are not expanded) and
+//! `__kernel` function definitions are understood.
+//!
+//! Also shared by `ocl` and `ocl-mock` are the effects of program build
+//! options on how kernels may be enqueued (`allows_non_uniform`).
+
+/// `const` type qualifier bit (the value of `CL_KERNEL_ARG_TYPE_CONST`).
+pub const TYPE_CONST: u64 = 1 << 0;
+/// `restrict` type qualifier bit (the value of `CL_KERNEL_ARG_TYPE_RESTRICT`).
+pub const TYPE_RESTRICT: u64 = 1 << 1;
+/// `volatile` type qualifier bit (the value of `CL_KERNEL_ARG_TYPE_VOLATILE`).
+pub const TYPE_VOLATILE: u64 = 1 << 2;
+/// `pipe` type qualifier bit (the value of `CL_KERNEL_ARG_TYPE_PIPE`).
+pub const TYPE_PIPE: u64 = 1 << 3;
+
+
+/// An OpenCL C scalar type with a Rust equivalent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scalar {
+    Char,
+    Uchar,
+    Short,
+    Ushort,
+    Int,
+    Uint,
+    Long,
+    Ulong,
+    Float,
+    Double,
+}
+
+/// Each scalar type with its OpenCL C name, Rust name and the name of the
+/// corresponding `ocl::prm` vector types (less the cardinality).
+const SCALARS: &'static [(Scalar, &'static str, &'static str, &'static str)] = &[
+    (Scalar::Char, "char", "i8", "Char"),
+    (Scalar::Uchar, "uchar", "u8", "Uchar"),
+    (Scalar::Short, "short", "i16", "Short"),
+    (Scalar::Ushort, "ushort", "u16", "Ushort"),
+    (Scalar::Int, "int", "i32", "Int"),
+    (Scalar::Uint, "uint", "u32", "Uint"),
+    (Scalar::Long, "long", "i64", "Long"),
+    (Scalar::Ulong, "ulong", "u64", "Ulong"),
+    (Scalar::Float, "float", "f32", "Float"),
+    (Scalar::Double, "double", "f64", "Double"),
+];
+
+impl Scalar {
+    fn entry(self) -> &'static (Scalar, &'static str, &'static str, &'static str) {
+        SCALARS.iter().find(|e| e.0 == self).expect("Scalar::entry: missing table entry")
+    }
+
+    /// Returns the scalar type with the OpenCL C name `name` (ex. 'uint').
+    pub fn from_cl_name(name: &str) -> Option<Scalar> {
+        SCALARS.iter().find(|e| e.1 == name).map(|e| e.0)
+    }
+
+    /// Returns the scalar type with the Rust name `name` (ex. 'u32').
+    pub fn from_rust_name(name: &str) -> Option<Scalar> {
+        SCALARS.iter().find(|e| e.2 == name).map(|e| e.0)
+    }
+
+    /// Returns the scalar type of the `ocl::prm` vectors named `name` (less
+    /// the cardinality, ex. 'Uint').
+    pub fn from_vector_name(name: &str) -> Option<Scalar> {
+        SCALARS.iter().find(|e| e.3 == name).map(|e| e.0)
+    }
+
+    /// Returns the OpenCL C name (ex. 'uint').
+    pub fn cl_name(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// Returns the Rust name (ex. 'u32').
+    pub fn rust_name(self) -> &'static str {
+        self.entry().2
+    }
+
+    /// Returns the name of the `ocl::prm` vectors of this type, less the
+    /// cardinality (ex. 'Uint').
+    pub fn vector_name(self) -> &'static str {
+        self.entry().3
+    }
+
+    /// Returns the size in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Scalar::Char | Scalar::Uchar => 1,
+            Scalar::Short | Scalar::Ushort => 2,
+            Scalar::Int | Scalar::Uint | Scalar::Float => 4,
+            Scalar::Long | Scalar::Ulong | Scalar::Double => 8,
+        }
+    }
+}
+
+
+/// Returns the scalar type and cardinality (1, 2, 3, 4, 8 or 16) of an
+/// OpenCL C scalar or vector type name (ex. 'float4'), or `None` for other
+/// types (`half`, `size_t`, structs, typedefs, etc.).
+pub fn vector_type(type_name: &str) -> Option<(Scalar, u8)> {
+    let base_len = type_name.trim_right_matches(|c: char| c.is_digit(10)).len();
+    let (base, card) = type_name.split_at(base_len);
+
+    let card = match card {
+        "" => 1,
+        "2" => 2,
+        "3" => 3,
+        "4" => 4,
+        "8" => 8,
+        "16" => 16,
+        _ => return None,
+    };
+    Scalar::from_cl_name(base).map(|scl| (scl, card))
+}
+
+
+/// A kernel argument address space qualifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Address {
+    Global,
+    Local,
+    Constant,
+    Private,
+}
+
+
+/// A kernel argument access qualifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+    None,
+}
+
+
+/// A kernel argument declaration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArgSig {
+    /// The declared name (empty if unnamed).
+    pub name: String,
+    /// The type name in the form reported by `CL_KERNEL_ARG_TYPE_NAME` (ex.
+    /// 'float*', 'uint4', 'image2d_t'). Pipes report the type of their
+    /// packets.
+    pub type_name: String,
+    /// The address space qualifier (`Private` if unspecified, `Global` for
+    /// images and pipes).
+    pub address: Address,
+    /// The access qualifier (`None` for arguments other than images and
+    /// pipes, which default to `ReadOnly`).
+    pub access: Access,
+    /// The type qualifiers (`TYPE_CONST`, etc.), as reported by
+    /// `CL_KERNEL_ARG_TYPE_QUALIFIER`.
+    pub type_qualifier: u64,
+}
+
+impl ArgSig {
+    /// Returns true if the argument is a pointer (or array).
+    pub fn is_ptr(&self) -> bool {
+        self.type_name.ends_with('*')
+    }
+
+    /// Returns true if the argument is an image.
+    pub fn is_image(&self) -> bool {
+        self.type_name.starts_with("image") && !self.is_ptr()
+    }
+
+    /// Returns true if the argument is a pipe.
+    pub fn is_pipe(&self) -> bool {
+        self.type_qualifier & TYPE_PIPE != 0
+    }
+
+    /// Returns true if the argument is a sampler.
+    pub fn is_sampler(&self) -> bool {
+        self.type_name == "sampler_t"
+    }
+}
+
+
+/// A kernel function definition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernelSig {
+    /// The kernel function name.
+    pub name: String,
+    /// The argument declarations, in order.
+    pub args: Vec<ArgSig>,
+    /// True if the definition is within a conditional preprocessor block
+    /// (`#if`, `#ifdef`, etc.) and so may not be the one compiled.
+    pub conditional: bool,
+    /// The work-group size required by a `reqd_work_group_size` attribute.
+    pub reqd_work_group_size: Option<[usize; 3]>,
+}
+
+
+/// Replaces comments with whitespace, preserving line numbers.
+pub fn strip_comments(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut chars = src.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek().cloned()) {
+            ('/', Some('/')) => {
+                while let Some(&n) = chars.peek() {
+                    if n == '\n' { break; }
+                    chars.next();
+                }
+            },
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                while let Some(n) = chars.next() {
+                    if n == '\n' { out.push('\n'); }
+                    if prev == '*' && n == '/' { break; }
+                    prev = n;
+                }
+                out.push(' ');
+            },
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Splits source into identifier/number and single character tokens,
+/// skipping preprocessor directives. Returns the tokens along with whether
+/// each is within a conditional preprocessor block.
+fn tokenize(src: &str) -> (Vec<String>, Vec<bool>) {
+    let mut tokens = Vec::new();
+    let mut conditional = Vec::new();
+    let mut depth = 0usize;
+
+    for line in src.lines() {
+        let trimmed = line.trim_left();
+        if trimmed.starts_with('#') {
+            let directive = trimmed[1..].trim_left();
+            let word_len = directive.find(|c: char| !c.is_alphabetic()).unwrap_or(directive.len());
+            match &directive[..word_len] {
+                "if" | "ifdef" | "ifndef" => depth += 1,
+                "endif" => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            continue;
+        }
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c.is_alphanumeric() || c == '_' {
+                let mut tok = c.to_string();
+                while let Some(&n) = chars.peek() {
+                    if !(n.is_alphanumeric() || n == '_') { break; }
+                    tok.push(n);
+                    chars.next();
+                }
+                tokens.push(tok);
+                conditional.push(depth > 0);
+            } else if !c.is_whitespace() {
+                tokens.push(c.to_string());
+                conditional.push(depth > 0);
+            }
+        }
+    }
+    (tokens, conditional)
+}
+
+/// Returns the index of the token closing the bracket opened at `open`.
+fn closing(tokens: &[String], open: usize) -> Option<usize> {
+    let (o, c) = match &tokens[open][..] {
+        "(" => ("(", ")"),
+        "[" => ("[", "]"),
+        "{" => ("{", "}"),
+        _ => return None,
+    };
+    let mut depth = 0;
+
+    for (i, tok) in tokens.iter().enumerate().skip(open) {
+        if tok == o {
+            depth += 1;
+        } else if tok == c {
+            depth -= 1;
+            if depth == 0 { return Some(i); }
+        }
+    }
+    None
+}
+
+/// Splits a parameter list at top-level commas.
+fn split_params(params: &[String]) -> Vec<&[String]> {
+    let mut out = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+
+    for (i, tok) in params.iter().enumerate() {
+        match &tok[..] {
+            "(" | "[" => depth += 1,
+            ")" | "]" => depth -= 1,
+            "," if depth == 0 => {
+                out.push(&params[start..i]);
+                start = i + 1;
+            },
+            _ => (),
+        }
+    }
+    out.push(&params[start..]);
+    out
+}
+
+/// Normalizes multi-word type names (e.g. 'unsigned int' -> 'uint').
+fn normalize_type(words: &[String]) -> String {
+    let joined = words.join(" ");
+
+    match &joined[..] {
+        "unsigned char" => "uchar".to_owned(),
+        "unsigned short" => "ushort".to_owned(),
+        "unsigned" | "unsigned int" => "uint".to_owned(),
+        "unsigned long" => "ulong".to_owned(),
+        "signed char" => "char".to_owned(),
+        "signed short" => "short".to_owned(),
+        "signed" | "signed int" => "int".to_owned(),
+        "signed long" => "long".to_owned(),
+        _ => joined,
+    }
+}
+
+fn parse_arg(tokens: &[String]) -> ArgSig {
+    let mut address = None;
+    let mut access = None;
+    let mut type_qualifier = 0;
+    let mut type_words = Vec::new();
+    let mut ptr_depth = 0;
+    let mut name = String::new();
+
+    for (i, tok) in tokens.iter().enumerate() {
+        match &tok[..] {
+            "__global" | "global" => address = Some(Address::Global),
+            "__local" | "local" => address = Some(Address::Local),
+            "__constant" | "constant" => address = Some(Address::Constant),
+            "__private" | "private" => address = Some(Address::Private),
+            "__read_only" | "read_only" => access = Some(Access::ReadOnly),
+            "__write_only" | "write_only" => access = Some(Access::WriteOnly),
+            "__read_write" | "read_write" => access = Some(Access::ReadWrite),
+            "const" => type_qualifier |= TYPE_CONST,
+            "restrict" | "__restrict" => type_qualifier |= TYPE_RESTRICT,
+            "volatile" => type_qualifier |= TYPE_VOLATILE,
+            // Pipes report the type of their packets:
+            "pipe" => type_qualifier |= TYPE_PIPE,
+            "*" => ptr_depth += 1,
+            // Array declarators are passed as pointers:
+            "[" => {
+                ptr_depth += 1;
+                break;
+            },
+            _ => {
+                let is_last_word = tokens[i + 1..].iter().all(|t| t == "[" || t == "]" ||
+                    t.chars().all(|c| c.is_digit(10)));
+                if is_last_word && !type_words.is_empty() {
+                    name = tok.clone();
+                } else {
+                    type_words.push(tok.clone());
+                }
+            },
+        }
+    }
+
+    let mut type_name = normalize_type(&type_words);
+    for _ in 0..ptr_depth { type_name.push('*'); }
+
+    // As reported by `CL_KERNEL_ARG_TYPE_QUALIFIER`, `const` only applies to
+    // the referenced type of pointers and is implied by `__constant`:
+    if ptr_depth == 0 {
+        type_qualifier &= !TYPE_CONST;
+    }
+    if address == Some(Address::Constant) {
+        type_qualifier |= TYPE_CONST;
+    }
+
+    // Images and pipes are global and read only by default:
+    let is_image_or_pipe = (type_name.starts_with("image") && ptr_depth == 0) ||
+        type_qualifier & TYPE_PIPE != 0;
+    let address = address.unwrap_or(if is_image_or_pipe {
+        Address::Global
+    } else {
+        Address::Private
+    });
+    let access = access.unwrap_or(if is_image_or_pipe {
+        Access::ReadOnly
+    } else {
+        Access::None
+    });
+
+    ArgSig {
+        name: name,
+        type_name: type_name,
+        address: address,
+        access: access,
+        type_qualifier: type_qualifier,
+    }
+}
+
+/// Returns the sizes given by a `reqd_work_group_size` attribute within
+/// `attr` (the tokens of an `__attribute__` specifier).
+fn reqd_work_group_size(attr: &[String]) -> Option<[usize; 3]> {
+    let start = attr.iter().position(|tok| tok == "reqd_work_group_size")? + 1;
+    let close = closing(attr, start)?;
+    let sizes: Vec<usize> = attr[start + 1..close].iter()
+        .filter(|&tok| tok != ",")
+        .map(|tok| tok.parse().ok())
+        .collect::<Option<_>>()?;
+
+    match sizes.len() {
+        3 => Some([sizes[0], sizes[1], sizes[2]]),
+        _ => None,
+    }
+}
+
+/// Returns the signatures of all kernel functions defined in `src`, in
+/// order of definition.
+///
+/// A kernel defined more than once (under `#ifdef` and `#else`, for
+/// example) appears once for each definition.
+pub fn parse_kernels(src: &str) -> Vec<KernelSig> {
+    let (tokens, conditional) = tokenize(&strip_comments(src));
+    let mut kernels = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if tokens[i] != "__kernel" && tokens[i] != "kernel" {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut reqd_size = None;
+        i += 1;
+
+        // Skip attributes (`__attribute__((reqd_work_group_size(..)))`):
+        while i + 1 < tokens.len() && tokens[i] == "__attribute__" {
+            let end = closing(&tokens, i + 1).map(|c| c + 1).unwrap_or(tokens.len());
+            reqd_size = reqd_size.or_else(|| reqd_work_group_size(&tokens[i + 1..end]));
+            i = end;
+        }
+
+        if i + 2 >= tokens.len() || tokens[i] != "void" || tokens[i + 2] != "(" { continue; }
+
+        let name = tokens[i + 1].clone();
+        let open = i + 2;
+        let close = match closing(&tokens, open) {
+            Some(c) => c,
+            None => break,
+        };
+
+        let params = &tokens[open + 1..close];
+        let args = if params.is_empty() || (params.len() == 1 && params[0] == "void") {
+            Vec::new()
+        } else {
+            split_params(params).into_iter().map(parse_arg).collect()
+        };
+
+        // Only definitions (not prototypes) count:
+        if tokens.get(close + 1).map(|t| t == "{").unwrap_or(false) {
+            let conditional = conditional[start..close + 2].iter().any(|&c| c);
+            kernels.push(KernelSig {
+                name: name,
+                args: args,
+                conditional: conditional,
+                reqd_work_group_size: reqd_size,
+            });
+        }
+        i = close + 1;
+    }
+    kernels
+}
+/// Returns true if kernels from a program built with `build_options` may be
+/// enqueued with a global work size which is not a multiple of the local work
+/// size (OpenCL C 2.0+ without `-cl-uniform-work-group-size`).
+///
+/// The device must also support non-uniform work-groups (OpenCL 2.0+, and
+/// optional on OpenCL 3.0).
+pub fn allows_non_uniform(build_options: &str) -> bool {
+    let mut cl_std_2 = false;
+    for opt in build_options.split_whitespace() {
+        if opt == "-cl-uniform-work-group-size" { return false; }
+        if opt.starts_with("-cl-std=CL") {
+            cl_std_2 = opt["-cl-std=CL".len()..].split('.').next()
+                .and_then(|major| major.parse::<u32>().ok())
+                .map_or(false, |major| major >= 2);
+        }
+    }
+    cl_std_2
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_signatures() {
+        let src = r#"
+            #define SCALE 2
+            // __kernel void commented_out(int a) {}
+            __kernel void add(__global float* buf, const float4 v, __local unsigned int* tmp,
+                    __constant uchar lut[16]) {
+                buf[get_global_id(0)] += SCALE;
+            }
+
+            /* A prototype: */
+            __kernel void proto(int a);
+
+            kernel __attribute__((reqd_work_group_size(64, 1, 1)))
+            void img(read_only image2d_t src, sampler_t smp, __write_only pipe uchar16 out,
+                    __global struct Foo* foo) {}
+
+            __kernel void none(void) {}
+        "#;
+
+        let kernels = parse_kernels(src);
+        assert_eq!(kernels.len(), 3);
+
+        let add = &kernels[0];
+        assert_eq!(add.name, "add");
+        let types: Vec<_> = add.args.iter().map(|a| (&a.name[..], &a.type_name[..])).collect();
+        assert_eq!(types, [("buf", "float*"), ("v", "float4"), ("tmp", "uint*"),
+            ("lut", "uchar*")]);
+        assert_eq!(add.args[0].address, Address::Global);
+        assert_eq!(add.args[1].address, Address::Private);
+        assert_eq!(add.args[1].type_qualifier, 0);
+        assert_eq!(add.args[2].address, Address::Local);
+        assert_eq!(add.args[3].address, Address::Constant);
+        assert_eq!(add.args[3].type_qualifier, TYPE_CONST);
+
+        let img = &kernels[1];
+        assert_eq!(img.name, "img");
+        assert!(img.args[0].is_image());
+        assert_eq!(img.args[0].access, Access::ReadOnly);
+        assert!(img.args[1].is_sampler());
+        assert_eq!(img.args[2].name, "out");
+        assert_eq!(img.args[2].type_name, "uchar16");
+        assert!(img.args[2].is_pipe());
+        assert_eq!(img.args[2].address, Address::Global);
+        assert_eq!(img.args[2].access, Access::WriteOnly);
+        assert_eq!(img.args[3].type_name, "struct Foo*");
+
+        assert_eq!(add.reqd_work_group_size, None);
+        assert_eq!(img.reqd_work_group_size, Some([64, 1, 1]));
+        assert!(kernels[2].args.is_empty());
+        assert!(kernels.iter().all(|k| !k.conditional));
+    }
+
+    #[test]
+    fn conditional_definitions() {
+        let src = r#"
+            #ifdef USE_DOUBLE
+            __kernel void scale(__global double* buf, double s) {}
+            #else
+            __kernel void scale(__global float* buf, float s) {}
+            #endif
+
+            #if 0
+            __kernel void maybe(int a) {}
+            #endif
+
+            __kernel void twice(int a) {}
+            __kernel void twice(uint a) {}
+            __kernel void once(int a) {}
+        "#;
+
+        let kernels = parse_kernels(src);
+        let names: Vec<_> = kernels.iter().map(|k| (&k.name[..], k.conditional)).collect();
+        assert_eq!(names, [("scale", true), ("scale", true), ("maybe", true), ("twice", false),
+            ("twice", false), ("once", false)]);
+    }
+
+    #[test]
+    fn non_uniform_build_options() {
+        assert!(allows_non_uniform("-cl-std=CL2.0"));
+        assert!(allows_non_uniform("-Werror -cl-std=CL3.0 -DN=4"));
+        assert!(!allows_non_uniform(""));
+        assert!(!allows_non_uniform("-cl-std=CL1.2"));
+        assert!(!allows_non_uniform("-cl-std=CL2.0 -cl-uniform-work-group-size"));
+    }
+
+    #[test]
+    fn scalar_types() {
+        assert_eq!(vector_type("float4"), Some((Scalar::Float, 4)));
+        assert_eq!(vector_type("uint"), Some((Scalar::Uint, 1)));
+        assert_eq!(vector_type("char3"), Some((Scalar::Char, 3)));
+        assert_eq!(vector_type("int5"), None);
+        assert_eq!(vector_type("half2"), None);
+        assert_eq!(Scalar::from_rust_name("u16"), Some(Scalar::Ushort));
+        assert_eq!(Scalar::from_vector_name("Double").map(Scalar::cl_name), Some("double"));
+        assert_eq!(Scalar::Long.size(), 8);
+    }
+}
@@ -0,0 +1,2580 @@
+//! The OpenCL API functions.
+//!
+//! Each function has the same signature as its `cl-sys` counterpart and is
+//! handed to `cl-sys` by `symbol`.
+
+#![allow(non_snake_case)]
+
+use std::cmp;
+use std::ffi::CStr;
+use std::mem;
+use std::ptr;
+use std::slice;
+use std::str;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use ffi::*;
+use ocl_kernel_src;
+use image;
+use inject;
+use kernel;
+use source;
+use spirv;
+use state::{self, State, Handle, SubDevice, Context, Queue, Mem, Sampler, Program, Kernel, Event, Object,
+    Storage, ImageInfo, PipeInfo, ArgValue, Command, Loc, Place, PLATFORM, DEVICE, MEM_ALIGN,
+    PIPE_HEADER_SIZE,
+    MemCallbackFn, EventCallbackFn, ProgramCallbackFn, NativeKernelFn, SvmFreeCallbackFn};
+use {PLATFORM_NAME, DEVICE_NAME, VERSION};
+
+
+const MAX_WORK_GROUP_SIZE: usize = 256;
+const SUB_GROUP_SIZE: usize = 8;
+const SPIRV_VERSIONS: [(u8, u8); 3] = [(1, 0), (1, 1), (1, 2)];
+const PIPE_MAX_PACKET_SIZE: cl_uint = 1024;
+const QUEUE_ON_DEVICE_PREFERRED_SIZE: cl_uint = 16 << 10;
+const QUEUE_ON_DEVICE_MAX_SIZE: cl_uint = 256 << 10;
+
+
+//============================================================================
+//================================= Helpers ==================================
+//============================================================================
+
+fn h(ptr: *mut c_void) -> Handle {
+    ptr as Handle
+}
+
+fn p(handle: Handle) -> *mut c_void {
+    handle as *mut c_void
+}
+
+fn val<T: Copy>(v: T) -> Vec<u8> {
+    vals(&[v])
+}
+
+fn vals<T: Copy>(v: &[T]) -> Vec<u8> {
+    unsafe { slice::from_raw_parts(v.as_ptr() as *const u8, v.len() * mem::size_of::<T>()).to_vec() }
+}
+
+fn string(s: &str) -> Vec<u8> {
+    let mut bytes = s.as_bytes().to_vec();
+    bytes.push(0);
+    bytes
+}
+
+unsafe fn set_errcode(errcode_ret: *mut cl_int, errcode: cl_int) {
+    if !errcode_ret.is_null() { *errcode_ret = errcode; }
+}
+
+unsafe fn read3(ptr: *const size_t) -> Result<[usize; 3], cl_int> {
+    if ptr.is_null() { return Err(CL_INVALID_VALUE); }
+    Ok([*ptr, *ptr.offset(1), *ptr.offset(2)])
+}
+
+unsafe fn write_info(info: Result<Vec<u8>, cl_int>, param_value_size: size_t,
+        param_value: *mut c_void, param_value_size_ret: *mut size_t) -> cl_int {
+    let bytes = match info {
+        Ok(bytes) => bytes,
+        Err(err) => return err,
+    };
+
+    if !param_value.is_null() {
+        if param_value_size < bytes.len() { return CL_INVALID_VALUE; }
+        ptr::copy_nonoverlapping(bytes.as_ptr(), param_value as *mut u8, bytes.len());
+    }
+    if !param_value_size_ret.is_null() { *param_value_size_ret = bytes.len(); }
+    CL_SUCCESS
+}
+
+/// Calls `f` with the state locked unless a failure has been injected.
+fn call<F>(name: &str, f: F) -> cl_int where F: FnOnce(&mut State) -> Result<(), cl_int> {
+    match inject::take(name) {
+        Some(err) => err,
+        None => state::with(f).err().unwrap_or(CL_SUCCESS),
+    }
+}
+
+/// Creates an object with `f`.
+unsafe fn create<F>(name: &str, errcode_ret: *mut cl_int, f: F) -> *mut c_void
+        where F: FnOnce(&mut State) -> Result<Handle, cl_int> {
+    let result = match inject::take(name) {
+        Some(err) => Err(err),
+        None => state::with(f),
+    };
+
+    match result {
+        Ok(handle) => {
+            set_errcode(errcode_ret, CL_SUCCESS);
+            p(handle)
+        },
+        Err(err) => {
+            set_errcode(errcode_ret, err);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Writes the info returned by `f`.
+unsafe fn info<F>(name: &str, param_value_size: size_t, param_value: *mut c_void,
+        param_value_size_ret: *mut size_t, f: F) -> cl_int
+        where F: FnOnce(&State) -> Result<Vec<u8>, cl_int> {
+    match inject::take(name) {
+        Some(err) => err,
+        None => write_info(state::with(|st| f(st)), param_value_size, param_value,
+            param_value_size_ret),
+    }
+}
+
+unsafe fn wait_list(num_events: cl_uint, event_list: *const cl_event)
+        -> Result<Vec<Handle>, cl_int> {
+    if (num_events == 0) != event_list.is_null() { return Err(CL_INVALID_EVENT_WAIT_LIST); }
+    if num_events == 0 { return Ok(Vec::new()); }
+    Ok(slice::from_raw_parts(event_list, num_events as usize).iter().map(|&e| h(e)).collect())
+}
+
+/// Enqueues the command built by `f`, optionally blocking until it has
+/// completed.
+unsafe fn enqueue<F, R>(name: &str, queue: cl_command_queue, command_type: cl_command_type,
+        blocking: bool, num_events: cl_uint, event_list: *const cl_event, event: *mut cl_event,
+        f: F) -> Result<R, cl_int>
+        where F: FnOnce(&mut State) -> Result<(Command, Vec<Handle>, R), cl_int> {
+    if let Some(err) = inject::take(name) { return Err(err); }
+    let wait_list = wait_list(num_events, event_list)?;
+
+    let (new_event, result) = state::with(|st| -> Result<(Handle, R), cl_int> {
+        st.get::<Queue>(h(queue))?;
+        let (command, retained, result) = f(st)?;
+        let new_event = st.enqueue(h(queue), command_type, command, wait_list, retained)?;
+
+        if !event.is_null() {
+            st.retain_any(new_event);
+            *event = p(new_event);
+        }
+        if blocking { st.retain_any(new_event); }
+
+        st.progress();
+        Ok((new_event, result))
+    })?;
+
+    if blocking {
+        let done = state::wait_until(|st| st.is_done(new_event));
+        let status = state::with(|st| {
+            let status = st.get::<Event>(new_event).map(|ev| ev.status);
+            st.release_any(new_event);
+            status
+        });
+        done?;
+        if status? < 0 { return Err(CL_EXEC_STATUS_ERROR_FOR_EVENTS_IN_WAIT_LIST); }
+    }
+    Ok(result)
+}
+
+fn status(result: Result<(), cl_int>) -> cl_int {
+    result.err().unwrap_or(CL_SUCCESS)
+}
+
+/// Returns an error unless `device` is the root device or a sub-device.
+fn check_device(st: &State, device: cl_device_id) -> Result<(), cl_int> {
+    st.compute_units(h(device)).map(|_| ())
+}
+
+fn check_platform(platform: cl_platform_id) -> Result<(), cl_int> {
+    if platform.is_null() || h(platform) == PLATFORM { Ok(()) } else { Err(CL_INVALID_PLATFORM) }
+}
+
+fn matches_device_type(device_type: cl_device_type) -> Result<bool, cl_int> {
+    let valid = CL_DEVICE_TYPE_DEFAULT | CL_DEVICE_TYPE_CPU | CL_DEVICE_TYPE_GPU |
+        CL_DEVICE_TYPE_ACCELERATOR | CL_DEVICE_TYPE_CUSTOM;
+    if device_type != CL_DEVICE_TYPE_ALL && (device_type & !valid != 0 || device_type == 0) {
+        return Err(CL_INVALID_DEVICE_TYPE);
+    }
+    Ok(device_type & (CL_DEVICE_TYPE_DEFAULT | CL_DEVICE_TYPE_CPU) != 0)
+}
+
+/// Returns an error unless `mem` is a buffer in the same context as `queue`.
+fn check_buffer(st: &State, queue: cl_command_queue, mem: cl_mem) -> Result<(), cl_int> {
+    let mem = st.get::<Mem>(h(mem))?;
+    if mem.image.is_some() || mem.pipe.is_some() { return Err(CL_INVALID_MEM_OBJECT); }
+    if mem.context != st.get::<Queue>(h(queue))?.context { return Err(CL_INVALID_CONTEXT); }
+    Ok(())
+}
+
+/// Returns the location and byte region of an image region.
+fn image_region(st: &State, queue: cl_command_queue, mem: cl_mem, origin: [usize; 3],
+        region: [usize; 3]) -> Result<(Loc, [usize; 3]), cl_int> {
+    let m = st.get::<Mem>(h(mem))?;
+    if m.context != st.get::<Queue>(h(queue))?.context { return Err(CL_INVALID_CONTEXT); }
+    let img = m.image.as_ref().ok_or(CL_INVALID_MEM_OBJECT)?;
+    let dims = img.dims();
+
+    for i in 0..3 {
+        if region[i] == 0 || origin[i] + region[i] > dims[i] { return Err(CL_INVALID_VALUE); }
+    }
+    Ok((img.loc(h(mem), origin), img.region(region)))
+}
+
+fn image_format(st: &State, mem: cl_mem) -> Result<[cl_uint; 2], cl_int> {
+    st.get::<Mem>(h(mem))?.image.as_ref().map(|img| img.format).ok_or(CL_INVALID_MEM_OBJECT)
+}
+
+fn check_mem_flags(flags: cl_mem_flags, host_ptr: *mut c_void) -> Result<(), cl_int> {
+    let access = flags & (CL_MEM_READ_WRITE | CL_MEM_WRITE_ONLY | CL_MEM_READ_ONLY);
+    let host_access = flags & (CL_MEM_HOST_WRITE_ONLY | CL_MEM_HOST_READ_ONLY |
+        CL_MEM_HOST_NO_ACCESS);
+
+    if access.count_ones() > 1 || host_access.count_ones() > 1 ||
+            (flags & CL_MEM_USE_HOST_PTR != 0 &&
+                flags & (CL_MEM_ALLOC_HOST_PTR | CL_MEM_COPY_HOST_PTR) != 0) {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    let needs_ptr = flags & (CL_MEM_USE_HOST_PTR | CL_MEM_COPY_HOST_PTR) != 0;
+    if needs_ptr == host_ptr.is_null() { return Err(CL_INVALID_HOST_PTR); }
+    Ok(())
+}
+
+
+//============================================================================
+//========================= Platform / Device APIs ===========================
+//============================================================================
+
+pub unsafe extern "system" fn clGetPlatformIDs(num_entries: cl_uint,
+        platforms: *mut cl_platform_id, num_platforms: *mut cl_uint) -> cl_int {
+    if let Some(err) = inject::take("clGetPlatformIDs") { return err; }
+    if (num_entries == 0 && !platforms.is_null()) || (platforms.is_null() && num_platforms.is_null()) {
+        return CL_INVALID_VALUE;
+    }
+
+    if !platforms.is_null() { *platforms = p(PLATFORM); }
+    if !num_platforms.is_null() { *num_platforms = 1; }
+    CL_SUCCESS
+}
+
+pub unsafe extern "system" fn clGetPlatformInfo(platform: cl_platform_id,
+        param_name: cl_platform_info, param_value_size: size_t, param_value: *mut c_void,
+        param_value_size_ret: *mut size_t) -> cl_int {
+    info("clGetPlatformInfo", param_value_size, param_value, param_value_size_ret, |_| {
+        check_platform(platform)?;
+
+        match param_name {
+            CL_PLATFORM_PROFILE => Ok(string("FULL_PROFILE")),
+            CL_PLATFORM_VERSION => Ok(string(VERSION)),
+            CL_PLATFORM_NAME | CL_PLATFORM_VENDOR => Ok(string(PLATFORM_NAME)),
+            CL_PLATFORM_EXTENSIONS => Ok(string("")),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clGetDeviceIDs(platform: cl_platform_id,
+        device_type: cl_device_type, num_entries: cl_uint, devices: *mut cl_device_id,
+        num_devices: *mut cl_uint) -> cl_int {
+    if let Some(err) = inject::take("clGetDeviceIDs") { return err; }
+    if let Err(err) = check_platform(platform) { return err; }
+    if (num_entries == 0 && !devices.is_null()) || (devices.is_null() && num_devices.is_null()) {
+        return CL_INVALID_VALUE;
+    }
+
+    match matches_device_type(device_type) {
+        Ok(true) => (),
+        Ok(false) => return CL_DEVICE_NOT_FOUND,
+        Err(err) => return err,
+    }
+
+    if !devices.is_null() { *devices = p(DEVICE); }
+    if !num_devices.is_null() { *num_devices = 1; }
+    CL_SUCCESS
+}
+
+fn device_info(st: &State, device: Handle, param_name: cl_device_info)
+        -> Result<Vec<u8>, cl_int> {
+    let compute_units = st.compute_units(device)?;
+    let sub_device = st.get::<SubDevice>(device).ok();
+    let fp_config = CL_FP_ROUND_TO_NEAREST | CL_FP_INF_NAN | CL_FP_DENORM | CL_FP_FMA;
+
+    Ok(match param_name {
+        CL_DEVICE_TYPE => val(CL_DEVICE_TYPE_CPU),
+        CL_DEVICE_VENDOR_ID => val(0 as cl_uint),
+        CL_DEVICE_MAX_COMPUTE_UNITS => val(compute_units),
+        CL_DEVICE_MAX_WORK_ITEM_DIMENSIONS => val(3 as cl_uint),
+        CL_DEVICE_MAX_WORK_GROUP_SIZE => val(MAX_WORK_GROUP_SIZE),
+        CL_DEVICE_MAX_WORK_ITEM_SIZES => vals(&[MAX_WORK_GROUP_SIZE; 3]),
+        CL_DEVICE_PREFERRED_VECTOR_WIDTH_CHAR | CL_DEVICE_PREFERRED_VECTOR_WIDTH_SHORT |
+            CL_DEVICE_PREFERRED_VECTOR_WIDTH_INT | CL_DEVICE_PREFERRED_VECTOR_WIDTH_LONG |
+            CL_DEVICE_PREFERRED_VECTOR_WIDTH_FLOAT | CL_DEVICE_PREFERRED_VECTOR_WIDTH_DOUBLE |
+            CL_DEVICE_NATIVE_VECTOR_WIDTH_CHAR | CL_DEVICE_NATIVE_VECTOR_WIDTH_SHORT |
+            CL_DEVICE_NATIVE_VECTOR_WIDTH_INT | CL_DEVICE_NATIVE_VECTOR_WIDTH_LONG |
+            CL_DEVICE_NATIVE_VECTOR_WIDTH_FLOAT | CL_DEVICE_NATIVE_VECTOR_WIDTH_DOUBLE
+            => val(1 as cl_uint),
+        CL_DEVICE_PREFERRED_VECTOR_WIDTH_HALF | CL_DEVICE_NATIVE_VECTOR_WIDTH_HALF
+            => val(0 as cl_uint),
+        CL_DEVICE_MAX_CLOCK_FREQUENCY => val(1000 as cl_uint),
+        CL_DEVICE_ADDRESS_BITS => val((mem::size_of::<usize>() * 8) as cl_uint),
+        CL_DEVICE_MAX_READ_IMAGE_ARGS => val(128 as cl_uint),
+        CL_DEVICE_MAX_WRITE_IMAGE_ARGS => val(64 as cl_uint),
+        CL_DEVICE_MAX_MEM_ALLOC_SIZE => val(1u64 << 30),
+        CL_DEVICE_IMAGE2D_MAX_WIDTH | CL_DEVICE_IMAGE2D_MAX_HEIGHT => val(8192 as size_t),
+        CL_DEVICE_IMAGE3D_MAX_WIDTH | CL_DEVICE_IMAGE3D_MAX_HEIGHT |
+            CL_DEVICE_IMAGE3D_MAX_DEPTH => val(2048 as size_t),
+        CL_DEVICE_IMAGE_SUPPORT => val(CL_TRUE),
+        CL_DEVICE_MAX_PARAMETER_SIZE => val(1024 as size_t),
+        CL_DEVICE_MAX_SAMPLERS => val(16 as cl_uint),
+        CL_DEVICE_MEM_BASE_ADDR_ALIGN => val((MEM_ALIGN * 8) as cl_uint),
+        CL_DEVICE_MIN_DATA_TYPE_ALIGN_SIZE => val(MEM_ALIGN as cl_uint),
+        CL_DEVICE_SINGLE_FP_CONFIG | CL_DEVICE_DOUBLE_FP_CONFIG => val(fp_config),
+        CL_DEVICE_HALF_FP_CONFIG => val(0 as cl_bitfield),
+        CL_DEVICE_GLOBAL_MEM_CACHE_TYPE => val(CL_NONE),
+        CL_DEVICE_GLOBAL_MEM_CACHELINE_SIZE => val(64 as cl_uint),
+        CL_DEVICE_GLOBAL_MEM_CACHE_SIZE => val(0 as cl_ulong),
+        CL_DEVICE_GLOBAL_MEM_SIZE => val(1u64 << 32),
+        CL_DEVICE_MAX_CONSTANT_BUFFER_SIZE => val(1u64 << 16),
+        CL_DEVICE_MAX_CONSTANT_ARGS => val(8 as cl_uint),
+        CL_DEVICE_LOCAL_MEM_TYPE => val(CL_GLOBAL),
+        CL_DEVICE_LOCAL_MEM_SIZE => val(1u64 << 15),
+        CL_DEVICE_ERROR_CORRECTION_SUPPORT => val(CL_FALSE),
+        CL_DEVICE_PROFILING_TIMER_RESOLUTION => val(1 as size_t),
+        CL_DEVICE_ENDIAN_LITTLE => val(if cfg!(target_endian = "little") { CL_TRUE } else { CL_FALSE }),
+        CL_DEVICE_AVAILABLE | CL_DEVICE_COMPILER_AVAILABLE | CL_DEVICE_LINKER_AVAILABLE |
+            CL_DEVICE_HOST_UNIFIED_MEMORY | CL_DEVICE_PREFERRED_INTEROP_USER_SYNC
+            => val(CL_TRUE),
+        CL_DEVICE_EXECUTION_CAPABILITIES => val(CL_EXEC_KERNEL | CL_EXEC_NATIVE_KERNEL),
+        CL_DEVICE_QUEUE_PROPERTIES => val(CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE |
+            CL_QUEUE_PROFILING_ENABLE),
+        CL_DEVICE_NAME => string(DEVICE_NAME),
+        CL_DEVICE_VENDOR => string(PLATFORM_NAME),
+        CL_DRIVER_VERSION => string(env!("CARGO_PKG_VERSION")),
+        CL_DEVICE_PROFILE => string("FULL_PROFILE"),
+        CL_DEVICE_VERSION => string(VERSION),
+        CL_DEVICE_OPENCL_C_VERSION => string("OpenCL C 2.0 "),
+        CL_DEVICE_EXTENSIONS => string("cl_khr_priority_hints cl_khr_throttle_hints"),
+        CL_DEVICE_IL_VERSION => string(&SPIRV_VERSIONS.iter()
+            .map(|&(major, minor)| format!("SPIR-V_{}.{}", major, minor))
+            .collect::<Vec<_>>().join(" ")),
+        CL_DEVICE_BUILT_IN_KERNELS => string(""),
+        CL_DEVICE_PLATFORM => val(p(PLATFORM)),
+        CL_DEVICE_IMAGE_MAX_BUFFER_SIZE => val(1 << 16 as size_t),
+        CL_DEVICE_IMAGE_MAX_ARRAY_SIZE => val(2048 as size_t),
+        CL_DEVICE_PARENT_DEVICE => val(p(sub_device.map(|sd| sd.parent).unwrap_or(0))),
+        CL_DEVICE_PARTITION_MAX_SUB_DEVICES => val(compute_units),
+        CL_DEVICE_PARTITION_PROPERTIES => vals(&[
+            CL_DEVICE_PARTITION_EQUALLY as cl_device_partition_property,
+            CL_DEVICE_PARTITION_BY_COUNTS as cl_device_partition_property]),
+        CL_DEVICE_PARTITION_TYPE => match sub_device {
+            Some(sd) => vals(&sd.partition),
+            None => val(0 as cl_device_partition_property),
+        },
+        CL_DEVICE_PARTITION_AFFINITY_DOMAIN => val(0 as cl_bitfield),
+        CL_DEVICE_REFERENCE_COUNT => val(if sub_device.is_some() { st.refcount(device) } else { 1 }),
+        CL_DEVICE_PRINTF_BUFFER_SIZE => val(1 << 20 as size_t),
+        CL_DEVICE_IMAGE_PITCH_ALIGNMENT | CL_DEVICE_IMAGE_BASE_ADDRESS_ALIGNMENT
+            => val(1 as cl_uint),
+        CL_DEVICE_SVM_CAPABILITIES => val(CL_DEVICE_SVM_COARSE_GRAIN_BUFFER |
+            CL_DEVICE_SVM_FINE_GRAIN_BUFFER | CL_DEVICE_SVM_ATOMICS),
+        CL_DEVICE_MAX_PIPE_ARGS => val(16 as cl_uint),
+        CL_DEVICE_PIPE_MAX_ACTIVE_RESERVATIONS => val(1 as cl_uint),
+        CL_DEVICE_PIPE_MAX_PACKET_SIZE => val(PIPE_MAX_PACKET_SIZE),
+        CL_DEVICE_QUEUE_ON_DEVICE_PROPERTIES => val(CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE |
+            CL_QUEUE_PROFILING_ENABLE),
+        CL_DEVICE_QUEUE_ON_DEVICE_PREFERRED_SIZE => val(QUEUE_ON_DEVICE_PREFERRED_SIZE),
+        CL_DEVICE_QUEUE_ON_DEVICE_MAX_SIZE => val(QUEUE_ON_DEVICE_MAX_SIZE),
+        CL_DEVICE_MAX_ON_DEVICE_QUEUES => val(1 as cl_uint),
+        CL_DEVICE_MAX_ON_DEVICE_EVENTS => val(1024 as cl_uint),
+        CL_DEVICE_MAX_NUM_SUB_GROUPS => val((MAX_WORK_GROUP_SIZE / SUB_GROUP_SIZE) as cl_uint),
+        CL_DEVICE_SUB_GROUP_INDEPENDENT_FORWARD_PROGRESS => val(CL_FALSE),
+        _ => return Err(CL_INVALID_VALUE),
+    })
+}
+
+pub unsafe extern "system" fn clGetDeviceInfo(device: cl_device_id, param_name: cl_device_info,
+        param_value_size: size_t, param_value: *mut c_void, param_value_size_ret: *mut size_t)
+        -> cl_int {
+    info("clGetDeviceInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        device_info(st, h(device), param_name)
+    })
+}
+
+/// Parses a partition property list, returning the compute unit count of
+/// each sub-device to create.
+unsafe fn partition_counts(properties: *const cl_device_partition_property,
+        compute_units: cl_uint) -> Result<(Vec<cl_uint>, Vec<cl_device_partition_property>), cl_int> {
+    if properties.is_null() { return Err(CL_INVALID_VALUE); }
+    let mut raw = vec![*properties];
+    let mut counts = Vec::new();
+
+    match *properties as cl_uint {
+        CL_DEVICE_PARTITION_EQUALLY => {
+            let units = *properties.offset(1);
+            raw.push(units);
+            if units <= 0 { return Err(CL_INVALID_VALUE); }
+            if units as cl_uint > compute_units { return Err(CL_DEVICE_PARTITION_FAILED); }
+            counts = vec![units as cl_uint; (compute_units / units as cl_uint) as usize];
+        },
+        CL_DEVICE_PARTITION_BY_COUNTS => {
+            let mut idx = 1;
+            loop {
+                let count = *properties.offset(idx);
+                raw.push(count);
+                if count == CL_DEVICE_PARTITION_BY_COUNTS_LIST_END as cl_device_partition_property {
+                    break;
+                }
+                if count < 0 { return Err(CL_INVALID_DEVICE_PARTITION_COUNT); }
+                counts.push(count as cl_uint);
+                idx += 1;
+            }
+
+            if counts.is_empty() || counts.len() > compute_units as usize ||
+                    counts.iter().sum::<cl_uint>() > compute_units {
+                return Err(CL_INVALID_DEVICE_PARTITION_COUNT);
+            }
+        },
+        // Affinity domains are not reported by `CL_DEVICE_PARTITION_PROPERTIES`:
+        _ => return Err(CL_INVALID_VALUE),
+    }
+
+    raw.push(0);
+    Ok((counts, raw))
+}
+
+pub unsafe extern "system" fn clCreateSubDevices(in_device: cl_device_id,
+        properties: *const cl_device_partition_property, num_devices: cl_uint,
+        out_devices: *mut cl_device_id, num_devices_ret: *mut cl_uint) -> cl_int {
+    call("clCreateSubDevices", |st| {
+        let compute_units = st.compute_units(h(in_device))?;
+        let (counts, raw) = partition_counts(properties, compute_units)?;
+
+        if !out_devices.is_null() {
+            if (num_devices as usize) < counts.len() { return Err(CL_INVALID_VALUE); }
+            let owners = if h(in_device) == DEVICE { Vec::new() } else { vec![h(in_device)] };
+
+            for (i, &count) in counts.iter().enumerate() {
+                *out_devices.offset(i as isize) = p(st.insert(Object::SubDevice(SubDevice {
+                    parent: h(in_device),
+                    compute_units: count,
+                    partition: raw.clone(),
+                }), owners.clone()));
+            }
+        }
+        if !num_devices_ret.is_null() { *num_devices_ret = counts.len() as cl_uint; }
+        Ok(())
+    })
+}
+
+pub unsafe extern "system" fn clRetainDevice(device: cl_device_id) -> cl_int {
+    // Root devices are not reference counted:
+    call("clRetainDevice", |st| match h(device) {
+        DEVICE => Ok(()),
+        device => st.retain::<SubDevice>(device),
+    })
+}
+
+pub unsafe extern "system" fn clReleaseDevice(device: cl_device_id) -> cl_int {
+    call("clReleaseDevice", |st| match h(device) {
+        DEVICE => Ok(()),
+        device => st.release::<SubDevice>(device),
+    })
+}
+
+/// Returns the host timer, which counts nanoseconds since the Unix epoch.
+fn host_timer() -> cl_ulong {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+    elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as cl_ulong
+}
+
+pub unsafe extern "system" fn clGetDeviceAndHostTimer(device: cl_device_id,
+        device_timestamp: *mut cl_ulong, host_timestamp: *mut cl_ulong) -> cl_int {
+    call("clGetDeviceAndHostTimer", |st| {
+        check_device(st, device)?;
+        if device_timestamp.is_null() || host_timestamp.is_null() { return Err(CL_INVALID_VALUE); }
+        // The device timer shares its timebase with event profiling info:
+        *device_timestamp = st.now();
+        *host_timestamp = host_timer();
+        Ok(())
+    })
+}
+
+pub unsafe extern "system" fn clGetHostTimer(device: cl_device_id, host_timestamp: *mut cl_ulong)
+        -> cl_int {
+    call("clGetHostTimer", |st| {
+        check_device(st, device)?;
+        if host_timestamp.is_null() { return Err(CL_INVALID_VALUE); }
+        *host_timestamp = host_timer();
+        Ok(())
+    })
+}
+
+
+//============================================================================
+//============================== Context APIs ================================
+//============================================================================
+
+unsafe fn context_properties(properties: *const cl_context_properties)
+        -> Result<Vec<cl_context_properties>, cl_int> {
+    let mut props = Vec::new();
+    if properties.is_null() { return Ok(props); }
+    let mut idx = 0;
+
+    loop {
+        let key = *properties.offset(idx);
+        props.push(key);
+        if key == 0 { break; }
+        let value = *properties.offset(idx + 1);
+        props.push(value);
+
+        match key as cl_uint {
+            CL_CONTEXT_PLATFORM => check_platform(value as cl_platform_id)?,
+            CL_CONTEXT_INTEROP_USER_SYNC => (),
+            _ => return Err(CL_INVALID_PROPERTY),
+        }
+        idx += 2;
+    }
+    Ok(props)
+}
+
+pub unsafe extern "system" fn clCreateContext(properties: *const cl_context_properties,
+        num_devices: cl_uint, devices: *const cl_device_id,
+        _pfn_notify: Option<extern fn (*const c_char, *const c_void, size_t, *mut c_void)>,
+        _user_data: *mut c_void, errcode_ret: *mut cl_int) -> cl_context {
+    create("clCreateContext", errcode_ret, |st| {
+        let properties = context_properties(properties)?;
+        if num_devices == 0 || devices.is_null() { return Err(CL_INVALID_VALUE); }
+        let devices = slice::from_raw_parts(devices, num_devices as usize);
+        for &device in devices { check_device(st, device)?; }
+
+        // Contexts retain their sub-devices:
+        let devices: Vec<Handle> = devices.iter().map(|&d| h(d)).collect();
+        let owners = devices.iter().cloned().filter(|&d| d != DEVICE).collect();
+
+        Ok(st.insert(Object::Context(Context {
+            devices: devices,
+            properties: properties,
+        }), owners))
+    })
+}
+
+pub unsafe extern "system" fn clCreateContextFromType(properties: *const cl_context_properties,
+        device_type: cl_device_type,
+        _pfn_notify: Option<extern fn (*const c_char, *const c_void, size_t, *mut c_void)>,
+        _user_data: *mut c_void, errcode_ret: *mut cl_int) -> cl_context {
+    create("clCreateContextFromType", errcode_ret, |st| {
+        let properties = context_properties(properties)?;
+        if !matches_device_type(device_type)? { return Err(CL_DEVICE_NOT_FOUND); }
+
+        Ok(st.insert(Object::Context(Context {
+            devices: vec![DEVICE],
+            properties: properties,
+        }), Vec::new()))
+    })
+}
+
+pub unsafe extern "system" fn clRetainContext(context: cl_context) -> cl_int {
+    call("clRetainContext", |st| st.retain::<Context>(h(context)))
+}
+
+pub unsafe extern "system" fn clReleaseContext(context: cl_context) -> cl_int {
+    call("clReleaseContext", |st| st.release::<Context>(h(context)))
+}
+
+pub unsafe extern "system" fn clGetContextInfo(context: cl_context, param_name: cl_context_info,
+        param_value_size: size_t, param_value: *mut c_void, param_value_size_ret: *mut size_t)
+        -> cl_int {
+    info("clGetContextInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let ctx = st.get::<Context>(h(context))?;
+
+        match param_name {
+            CL_CONTEXT_REFERENCE_COUNT => Ok(val(st.refcount(h(context)))),
+            CL_CONTEXT_DEVICES => Ok(vals(&ctx.devices.iter().map(|&d| p(d)).collect::<Vec<_>>())),
+            CL_CONTEXT_PROPERTIES => Ok(vals(&ctx.properties)),
+            CL_CONTEXT_NUM_DEVICES => Ok(val(ctx.devices.len() as cl_uint)),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+
+//============================================================================
+//=========================== Command Queue APIs =============================
+//============================================================================
+
+pub unsafe extern "system" fn clCreateCommandQueue(context: cl_context, device: cl_device_id,
+        properties: cl_command_queue_properties, errcode_ret: *mut cl_int) -> cl_command_queue {
+    create("clCreateCommandQueue", errcode_ret, |st| {
+        if !st.get::<Context>(h(context))?.devices.contains(&h(device)) {
+            return Err(CL_INVALID_DEVICE);
+        }
+        if properties & !(CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE | CL_QUEUE_PROFILING_ENABLE) != 0 {
+            return Err(CL_INVALID_VALUE);
+        }
+
+        Ok(st.insert(Object::Queue(Queue {
+            context: h(context),
+            device: h(device),
+            properties: properties,
+            size: None,
+            pending: Default::default(),
+            barrier: None,
+        }), vec![h(context)]))
+    })
+}
+
+pub unsafe extern "system" fn clCreateCommandQueueWithProperties(context: cl_context,
+        device: cl_device_id, properties: *const cl_queue_properties, errcode_ret: *mut cl_int)
+        -> cl_command_queue {
+    create("clCreateCommandQueueWithProperties", errcode_ret, |st| {
+        if !st.get::<Context>(h(context))?.devices.contains(&h(device)) {
+            return Err(CL_INVALID_DEVICE);
+        }
+
+        let (mut props, mut size, mut hints) = (0, None, false);
+        let mut idx = 0;
+        while !properties.is_null() && *properties.offset(idx) != 0 {
+            let val = *properties.offset(idx + 1);
+            match *properties.offset(idx) {
+                k if k == CL_QUEUE_PROPERTIES as cl_queue_properties => props = val,
+                k if k == CL_QUEUE_SIZE as cl_queue_properties => size = Some(val as cl_uint),
+                CL_QUEUE_PRIORITY_KHR | CL_QUEUE_THROTTLE_KHR => {
+                    if val == 0 || val & !0b111 != 0 || !val.is_power_of_two() {
+                        return Err(CL_INVALID_VALUE);
+                    }
+                    hints = true;
+                },
+                _ => return Err(CL_INVALID_VALUE),
+            }
+            idx += 2;
+        }
+
+        let valid = CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE | CL_QUEUE_PROFILING_ENABLE |
+            CL_QUEUE_ON_DEVICE | CL_QUEUE_ON_DEVICE_DEFAULT;
+        if props & !valid != 0 { return Err(CL_INVALID_VALUE); }
+
+        if props & CL_QUEUE_ON_DEVICE != 0 {
+            // On-device queues must be out-of-order and cannot take hints:
+            if props & CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE == 0 || hints {
+                return Err(CL_INVALID_QUEUE_PROPERTIES);
+            }
+            if size.map(|s| s > QUEUE_ON_DEVICE_MAX_SIZE).unwrap_or(false) {
+                return Err(CL_INVALID_VALUE);
+            }
+            size = Some(size.unwrap_or(QUEUE_ON_DEVICE_PREFERRED_SIZE));
+        } else if props & CL_QUEUE_ON_DEVICE_DEFAULT != 0 || size.is_some() {
+            return Err(CL_INVALID_VALUE);
+        }
+
+        Ok(st.insert(Object::Queue(Queue {
+            context: h(context),
+            device: h(device),
+            properties: props,
+            size: size,
+            pending: Default::default(),
+            barrier: None,
+        }), vec![h(context)]))
+    })
+}
+
+pub unsafe extern "system" fn clRetainCommandQueue(command_queue: cl_command_queue) -> cl_int {
+    call("clRetainCommandQueue", |st| st.retain::<Queue>(h(command_queue)))
+}
+
+pub unsafe extern "system" fn clReleaseCommandQueue(command_queue: cl_command_queue) -> cl_int {
+    call("clReleaseCommandQueue", |st| st.release::<Queue>(h(command_queue)))
+}
+
+pub unsafe extern "system" fn clGetCommandQueueInfo(command_queue: cl_command_queue,
+        param_name: cl_command_queue_info, param_value_size: size_t, param_value: *mut c_void,
+        param_value_size_ret: *mut size_t) -> cl_int {
+    info("clGetCommandQueueInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let queue = st.get::<Queue>(h(command_queue))?;
+
+        match param_name {
+            CL_QUEUE_CONTEXT => Ok(val(p(queue.context))),
+            CL_QUEUE_DEVICE => Ok(val(p(queue.device))),
+            CL_QUEUE_REFERENCE_COUNT => Ok(val(st.refcount(h(command_queue)))),
+            CL_QUEUE_PROPERTIES => Ok(val(queue.properties)),
+            CL_QUEUE_SIZE => queue.size.map(val).ok_or(CL_INVALID_COMMAND_QUEUE),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clFlush(command_queue: cl_command_queue) -> cl_int {
+    call("clFlush", |st| st.get::<Queue>(h(command_queue)).map(|_| ()))
+}
+
+pub unsafe extern "system" fn clFinish(command_queue: cl_command_queue) -> cl_int {
+    if let Some(err) = inject::take("clFinish") { return err; }
+
+    status(state::wait_until(|st| {
+        st.get::<Queue>(h(command_queue)).map(|q| q.pending.is_empty())
+    }))
+}
+
+
+//============================================================================
+//============================ Memory Object APIs ============================
+//============================================================================
+
+pub unsafe extern "system" fn clCreateBuffer(context: cl_context, flags: cl_mem_flags,
+        size: size_t, host_ptr: *mut c_void, errcode_ret: *mut cl_int) -> cl_mem {
+    create("clCreateBuffer", errcode_ret, |st| {
+        st.get::<Context>(h(context))?;
+        if size == 0 { return Err(CL_INVALID_BUFFER_SIZE); }
+        check_mem_flags(flags, host_ptr)?;
+
+        let storage = if flags & CL_MEM_USE_HOST_PTR != 0 {
+            Storage::Host(host_ptr as usize)
+        } else {
+            let storage = Storage::alloc(size);
+            if flags & CL_MEM_COPY_HOST_PTR != 0 {
+                if let Storage::Owned(ptr, _) = storage {
+                    ptr::copy_nonoverlapping(host_ptr as *const u8, ptr as *mut u8, size);
+                }
+            }
+            storage
+        };
+
+        Ok(st.insert(Object::Mem(Mem {
+            context: h(context),
+            flags: flags,
+            size: size,
+            host_ptr: if flags & CL_MEM_USE_HOST_PTR != 0 { host_ptr as usize } else { 0 },
+            storage: storage,
+            parent: None,
+            image: None,
+            pipe: None,
+            map_count: 0,
+            destructors: Vec::new(),
+        }), vec![h(context)]))
+    })
+}
+
+pub unsafe extern "system" fn clCreateSubBuffer(buffer: cl_mem, flags: cl_mem_flags,
+        buffer_create_type: cl_buffer_create_type, buffer_create_info: *const c_void,
+        errcode_ret: *mut cl_int) -> cl_mem {
+    create("clCreateSubBuffer", errcode_ret, |st| {
+        let (context, parent_flags, parent_size, parent_host_ptr) = {
+            let parent = st.get::<Mem>(h(buffer))?;
+            if parent.parent.is_some() || parent.image.is_some() {
+                return Err(CL_INVALID_MEM_OBJECT);
+            }
+            (parent.context, parent.flags, parent.size, parent.host_ptr)
+        };
+
+        if buffer_create_type != CL_BUFFER_CREATE_TYPE_REGION || buffer_create_info.is_null() {
+            return Err(CL_INVALID_VALUE);
+        }
+        if flags & (CL_MEM_USE_HOST_PTR | CL_MEM_ALLOC_HOST_PTR | CL_MEM_COPY_HOST_PTR) != 0 {
+            return Err(CL_INVALID_VALUE);
+        }
+
+        let region = &*(buffer_create_info as *const cl_buffer_region);
+        if region.size == 0 { return Err(CL_INVALID_BUFFER_SIZE); }
+        if region.origin + region.size > parent_size { return Err(CL_INVALID_VALUE); }
+        if region.origin % MEM_ALIGN != 0 { return Err(CL_MISALIGNED_SUB_BUFFER_OFFSET); }
+
+        // Unspecified access and host pointer flags are inherited:
+        let access = CL_MEM_READ_WRITE | CL_MEM_WRITE_ONLY | CL_MEM_READ_ONLY;
+        let host = CL_MEM_USE_HOST_PTR | CL_MEM_ALLOC_HOST_PTR | CL_MEM_COPY_HOST_PTR;
+        let mut flags = flags;
+        if flags & access == 0 { flags |= parent_flags & access; }
+        flags |= parent_flags & host;
+
+        Ok(st.insert(Object::Mem(Mem {
+            context: context,
+            flags: flags,
+            size: region.size,
+            host_ptr: if parent_host_ptr != 0 { parent_host_ptr + region.origin } else { 0 },
+            storage: Storage::Sub(region.origin),
+            parent: Some(h(buffer)),
+            image: None,
+            pipe: None,
+            map_count: 0,
+            destructors: Vec::new(),
+        }), vec![context, h(buffer)]))
+    })
+}
+
+pub unsafe extern "system" fn clCreateImage(context: cl_context, flags: cl_mem_flags,
+        image_format: *const cl_image_format, image_desc: *const cl_image_desc,
+        host_ptr: *mut c_void, errcode_ret: *mut cl_int) -> cl_mem {
+    create("clCreateImage", errcode_ret, |st| {
+        st.get::<Context>(h(context))?;
+        check_mem_flags(flags, host_ptr)?;
+        if image_format.is_null() { return Err(CL_INVALID_IMAGE_FORMAT_DESCRIPTOR); }
+        if image_desc.is_null() { return Err(CL_INVALID_IMAGE_DESCRIPTOR); }
+
+        let format = [(*image_format).image_channel_order, (*image_format).image_channel_data_type];
+        let element_size = image::element_size(format[0], format[1])
+            .ok_or(CL_IMAGE_FORMAT_NOT_SUPPORTED)?;
+        let desc = &*image_desc;
+        let dim_count = image::dim_count(desc.image_type).ok_or(CL_INVALID_IMAGE_DESCRIPTOR)?;
+
+        let is_array = desc.image_type == CL_MEM_OBJECT_IMAGE1D_ARRAY ||
+            desc.image_type == CL_MEM_OBJECT_IMAGE2D_ARRAY;
+        let mut img = ImageInfo {
+            format: format,
+            image_type: desc.image_type,
+            width: desc.image_width,
+            height: if dim_count >= 2 && desc.image_type != CL_MEM_OBJECT_IMAGE1D_ARRAY {
+                desc.image_height } else { 0 },
+            depth: if desc.image_type == CL_MEM_OBJECT_IMAGE3D { desc.image_depth } else { 0 },
+            array_size: if is_array { desc.image_array_size } else { 0 },
+            element_size: element_size,
+            row_pitch: 0,
+            slice_pitch: 0,
+            buffer: None,
+        };
+
+        let dims = img.dims();
+        if dims.iter().any(|&d| d == 0) { return Err(CL_INVALID_IMAGE_SIZE); }
+
+        if host_ptr.is_null() && (desc.image_row_pitch != 0 || desc.image_slice_pitch != 0) {
+            return Err(CL_INVALID_IMAGE_DESCRIPTOR);
+        }
+
+        let tight = Loc::linear(Place::Host(host_ptr as usize), 0)
+            .pitched([dims[0] * element_size, dims[1], dims[2]]);
+        let host = Loc::new(Place::Host(host_ptr as usize), [0; 3], desc.image_row_pitch,
+            desc.image_slice_pitch).pitched([dims[0] * element_size, dims[1], dims[2]]);
+        let mut owners = vec![h(context)];
+
+        let (storage, parent, size) = if desc.image_type == CL_MEM_OBJECT_IMAGE1D_BUFFER {
+            let buffer = h(desc.buffer);
+            let buf_size = st.get::<Mem>(buffer)?.size;
+            let size = dims[0] * element_size;
+            if size > buf_size { return Err(CL_INVALID_IMAGE_SIZE); }
+            img.buffer = Some(buffer);
+            owners.push(buffer);
+            img.row_pitch = size;
+            (Storage::Sub(0), Some(buffer), size)
+        } else if flags & CL_MEM_USE_HOST_PTR != 0 {
+            img.row_pitch = host.row_pitch;
+            img.slice_pitch = host.slice_pitch;
+            (Storage::Host(host_ptr as usize), None, host.slice_pitch * dims[2])
+        } else {
+            img.row_pitch = tight.row_pitch;
+            img.slice_pitch = tight.slice_pitch;
+            let size = tight.slice_pitch * dims[2];
+            let storage = Storage::alloc(size);
+
+            if flags & CL_MEM_COPY_HOST_PTR != 0 {
+                if let Storage::Owned(ptr, _) = storage {
+                    for z in 0..dims[2] {
+                        for y in 0..dims[1] {
+                            ptr::copy_nonoverlapping(
+                                (host_ptr as usize + y * host.row_pitch + z * host.slice_pitch)
+                                    as *const u8,
+                                (ptr + y * tight.row_pitch + z * tight.slice_pitch) as *mut u8,
+                                tight.row_pitch);
+                        }
+                    }
+                }
+            }
+            (storage, None, size)
+        };
+
+        Ok(st.insert(Object::Mem(Mem {
+            context: h(context),
+            flags: flags,
+            size: size,
+            host_ptr: if flags & CL_MEM_USE_HOST_PTR != 0 { host_ptr as usize } else { 0 },
+            storage: storage,
+            parent: parent,
+            image: Some(img),
+            pipe: None,
+            map_count: 0,
+            destructors: Vec::new(),
+        }), owners))
+    })
+}
+
+pub unsafe extern "system" fn clRetainMemObject(memobj: cl_mem) -> cl_int {
+    call("clRetainMemObject", |st| st.retain::<Mem>(h(memobj)))
+}
+
+pub unsafe extern "system" fn clReleaseMemObject(memobj: cl_mem) -> cl_int {
+    call("clReleaseMemObject", |st| st.release::<Mem>(h(memobj)))
+}
+
+pub unsafe extern "system" fn clGetSupportedImageFormats(context: cl_context,
+        _flags: cl_mem_flags, image_type: cl_mem_object_type, num_entries: cl_uint,
+        image_formats: *mut cl_image_format, num_image_formats: *mut cl_uint) -> cl_int {
+    call("clGetSupportedImageFormats", |st| {
+        st.get::<Context>(h(context))?;
+        image::dim_count(image_type).ok_or(CL_INVALID_VALUE)?;
+        if num_entries == 0 && !image_formats.is_null() { return Err(CL_INVALID_VALUE); }
+
+        let formats = image::supported_formats();
+
+        if !image_formats.is_null() {
+            for (i, format) in formats.iter().take(num_entries as usize).enumerate() {
+                let dst = &mut *image_formats.offset(i as isize);
+                dst.image_channel_order = format[0];
+                dst.image_channel_data_type = format[1];
+            }
+        }
+        if !num_image_formats.is_null() { *num_image_formats = formats.len() as cl_uint; }
+        Ok(())
+    })
+}
+
+pub unsafe extern "system" fn clGetMemObjectInfo(memobj: cl_mem, param_name: cl_mem_info,
+        param_value_size: size_t, param_value: *mut c_void, param_value_size_ret: *mut size_t)
+        -> cl_int {
+    info("clGetMemObjectInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let mem = st.get::<Mem>(h(memobj))?;
+
+        match param_name {
+            CL_MEM_TYPE => Ok(val(match (&mem.image, &mem.pipe) {
+                (&Some(ref img), _) => img.image_type,
+                (_, &Some(_)) => CL_MEM_OBJECT_PIPE,
+                _ => CL_MEM_OBJECT_BUFFER,
+            })),
+            CL_MEM_FLAGS => Ok(val(mem.flags)),
+            CL_MEM_SIZE => Ok(val(mem.size)),
+            CL_MEM_HOST_PTR => Ok(val(mem.host_ptr as *mut c_void)),
+            CL_MEM_MAP_COUNT => Ok(val(mem.map_count)),
+            CL_MEM_REFERENCE_COUNT => Ok(val(st.refcount(h(memobj)))),
+            CL_MEM_CONTEXT => Ok(val(p(mem.context))),
+            CL_MEM_ASSOCIATED_MEMOBJECT => Ok(val(p(mem.parent.unwrap_or(0)))),
+            CL_MEM_OFFSET => Ok(val(match (&mem.storage, &mem.image) {
+                (&Storage::Sub(offset), &None) => offset,
+                _ => 0 as size_t,
+            })),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clGetImageInfo(image: cl_mem, param_name: cl_image_info,
+        param_value_size: size_t, param_value: *mut c_void, param_value_size_ret: *mut size_t)
+        -> cl_int {
+    info("clGetImageInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let img = st.get::<Mem>(h(image))?.image.as_ref().ok_or(CL_INVALID_MEM_OBJECT)?;
+
+        match param_name {
+            CL_IMAGE_FORMAT => Ok(vals(&img.format)),
+            CL_IMAGE_ELEMENT_SIZE => Ok(val(img.element_size)),
+            CL_IMAGE_ROW_PITCH => Ok(val(img.row_pitch)),
+            CL_IMAGE_SLICE_PITCH => Ok(val(match img.image_type {
+                CL_MEM_OBJECT_IMAGE3D | CL_MEM_OBJECT_IMAGE2D_ARRAY => img.slice_pitch,
+                CL_MEM_OBJECT_IMAGE1D_ARRAY => img.row_pitch,
+                _ => 0,
+            })),
+            CL_IMAGE_WIDTH => Ok(val(img.width)),
+            CL_IMAGE_HEIGHT => Ok(val(img.height)),
+            CL_IMAGE_DEPTH => Ok(val(img.depth)),
+            CL_IMAGE_ARRAY_SIZE => Ok(val(img.array_size)),
+            CL_IMAGE_BUFFER => Ok(val(p(img.buffer.unwrap_or(0)))),
+            CL_IMAGE_NUM_MIP_LEVELS | CL_IMAGE_NUM_SAMPLES => Ok(val(0 as cl_uint)),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clCreatePipe(context: cl_context, flags: cl_mem_flags,
+        pipe_packet_size: cl_uint, pipe_max_packets: cl_uint,
+        properties: *const cl_pipe_properties, errcode_ret: *mut cl_int) -> cl_mem {
+    create("clCreatePipe", errcode_ret, |st| {
+        st.get::<Context>(h(context))?;
+        if flags & !(CL_MEM_READ_WRITE | CL_MEM_HOST_NO_ACCESS) != 0 ||
+                (!properties.is_null() && *properties != 0) {
+            return Err(CL_INVALID_VALUE);
+        }
+        if pipe_packet_size == 0 || pipe_packet_size > PIPE_MAX_PACKET_SIZE ||
+                pipe_max_packets == 0 {
+            return Err(CL_INVALID_PIPE_SIZE);
+        }
+
+        let size = pipe_packet_size as usize * pipe_max_packets as usize;
+
+        Ok(st.insert(Object::Mem(Mem {
+            context: h(context),
+            flags: if flags == 0 { CL_MEM_READ_WRITE | CL_MEM_HOST_NO_ACCESS } else { flags },
+            size: size,
+            host_ptr: 0,
+            storage: Storage::alloc(PIPE_HEADER_SIZE + size),
+            parent: None,
+            image: None,
+            pipe: Some(PipeInfo { packet_size: pipe_packet_size, max_packets: pipe_max_packets }),
+            map_count: 0,
+            destructors: Vec::new(),
+        }), vec![h(context)]))
+    })
+}
+
+pub unsafe extern "system" fn clGetPipeInfo(pipe: cl_mem, param_name: cl_pipe_info,
+        param_value_size: size_t, param_value: *mut c_void, param_value_size_ret: *mut size_t)
+        -> cl_int {
+    info("clGetPipeInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let pipe = st.get::<Mem>(h(pipe))?.pipe.ok_or(CL_INVALID_MEM_OBJECT)?;
+
+        match param_name {
+            CL_PIPE_PACKET_SIZE => Ok(val(pipe.packet_size)),
+            CL_PIPE_MAX_PACKETS => Ok(val(pipe.max_packets)),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clSetMemObjectDestructorCallback(memobj: cl_mem,
+        pfn_notify: Option<MemCallbackFn>, user_data: *mut c_void) -> cl_int {
+    call("clSetMemObjectDestructorCallback", |st| {
+        let func = pfn_notify.ok_or(CL_INVALID_VALUE)?;
+        st.get_mut::<Mem>(h(memobj))?.destructors.push((func, user_data as usize));
+        Ok(())
+    })
+}
+
+pub unsafe extern "system" fn clSVMAlloc(context: cl_context, flags: cl_svm_mem_flags,
+        size: size_t, alignment: cl_uint) -> *mut c_void {
+    if inject::take("clSVMAlloc").is_some() { return ptr::null_mut(); }
+
+    let valid = CL_MEM_READ_WRITE | CL_MEM_WRITE_ONLY | CL_MEM_READ_ONLY |
+        CL_MEM_SVM_FINE_GRAIN_BUFFER | CL_MEM_SVM_ATOMICS;
+    if size == 0 || flags & !valid != 0 ||
+            (flags & CL_MEM_SVM_ATOMICS != 0 && flags & CL_MEM_SVM_FINE_GRAIN_BUFFER == 0) ||
+            (alignment != 0 && (!alignment.is_power_of_two() || alignment as usize > MEM_ALIGN)) {
+        return ptr::null_mut();
+    }
+
+    state::with(|st| {
+        match st.get::<Context>(h(context)) {
+            Ok(_) => st.svm_alloc(h(context), size) as *mut c_void,
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clSVMFree(_context: cl_context, svm_pointer: *mut c_void) {
+    if !svm_pointer.is_null() {
+        state::with(|st| st.svm_free(svm_pointer as usize).ok());
+    }
+}
+
+
+//============================================================================
+//============================== Sampler APIs ================================
+//============================================================================
+
+pub unsafe extern "system" fn clCreateSampler(context: cl_context, normalize_coords: cl_bool,
+        addressing_mode: cl_addressing_mode, filter_mode: cl_filter_mode,
+        errcode_ret: *mut cl_int) -> cl_sampler {
+    create("clCreateSampler", errcode_ret, |st| {
+        st.get::<Context>(h(context))?;
+
+        match addressing_mode {
+            CL_ADDRESS_NONE | CL_ADDRESS_CLAMP_TO_EDGE | CL_ADDRESS_CLAMP | CL_ADDRESS_REPEAT |
+                CL_ADDRESS_MIRRORED_REPEAT => (),
+            _ => return Err(CL_INVALID_VALUE),
+        }
+        match filter_mode {
+            CL_FILTER_NEAREST | CL_FILTER_LINEAR => (),
+            _ => return Err(CL_INVALID_VALUE),
+        }
+
+        Ok(st.insert(Object::Sampler(Sampler {
+            context: h(context),
+            normalized_coords: normalize_coords,
+            addressing_mode: addressing_mode,
+            filter_mode: filter_mode,
+        }), vec![h(context)]))
+    })
+}
+
+pub unsafe extern "system" fn clRetainSampler(sampler: cl_sampler) -> cl_int {
+    call("clRetainSampler", |st| st.retain::<Sampler>(h(sampler)))
+}
+
+pub unsafe extern "system" fn clReleaseSampler(sampler: cl_sampler) -> cl_int {
+    call("clReleaseSampler", |st| st.release::<Sampler>(h(sampler)))
+}
+
+pub unsafe extern "system" fn clGetSamplerInfo(sampler: cl_sampler, param_name: cl_sampler_info,
+        param_value_size: size_t, param_value: *mut c_void, param_value_size_ret: *mut size_t)
+        -> cl_int {
+    info("clGetSamplerInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let smp = st.get::<Sampler>(h(sampler))?;
+
+        match param_name {
+            CL_SAMPLER_REFERENCE_COUNT => Ok(val(st.refcount(h(sampler)))),
+            CL_SAMPLER_CONTEXT => Ok(val(p(smp.context))),
+            CL_SAMPLER_NORMALIZED_COORDS => Ok(val(smp.normalized_coords)),
+            CL_SAMPLER_ADDRESSING_MODE => Ok(val(smp.addressing_mode)),
+            CL_SAMPLER_FILTER_MODE => Ok(val(smp.filter_mode)),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+
+//============================================================================
+//============================== Program APIs ================================
+//============================================================================
+
+fn new_program(st: &mut State, context: cl_context, source: String) -> Handle {
+    st.insert(Object::Program(Program {
+        context: h(context),
+        source: source,
+        binary: String::new(),
+        binary_type: CL_PROGRAM_BINARY_TYPE_NONE as cl_program_binary_type,
+        build_status: CL_BUILD_NONE as cl_build_status,
+        build_options: String::new(),
+        build_log: String::new(),
+        kernels: Vec::new(),
+        kernel_count: 0,
+        il: Vec::new(),
+        spec_constants: Vec::new(),
+        built_spec_constants: Vec::new(),
+    }), vec![h(context)])
+}
+
+pub unsafe extern "system" fn clCreateProgramWithSource(context: cl_context, count: cl_uint,
+        strings: *const *const c_char, lengths: *const size_t, errcode_ret: *mut cl_int)
+        -> cl_program {
+    create("clCreateProgramWithSource", errcode_ret, |st| {
+        st.get::<Context>(h(context))?;
+        if count == 0 || strings.is_null() { return Err(CL_INVALID_VALUE); }
+        let mut source = String::new();
+
+        for i in 0..count as isize {
+            let string = *strings.offset(i);
+            if string.is_null() { return Err(CL_INVALID_VALUE); }
+
+            let len = if lengths.is_null() { 0 } else { *lengths.offset(i) };
+            let bytes = if len == 0 {
+                CStr::from_ptr(string).to_bytes()
+            } else {
+                slice::from_raw_parts(string as *const u8, len)
+            };
+            source.push_str(&String::from_utf8_lossy(bytes));
+        }
+
+        Ok(new_program(st, context, source))
+    })
+}
+
+pub unsafe extern "system" fn clCreateProgramWithBinary(context: cl_context, num_devices: cl_uint,
+        device_list: *const cl_device_id, lengths: *const size_t,
+        binaries: *const *const c_uchar, binary_status: *mut cl_int, errcode_ret: *mut cl_int)
+        -> cl_program {
+    create("clCreateProgramWithBinary", errcode_ret, |st| {
+        st.get::<Context>(h(context))?;
+        if num_devices == 0 || device_list.is_null() || lengths.is_null() || binaries.is_null() {
+            return Err(CL_INVALID_VALUE);
+        }
+        for &device in slice::from_raw_parts(device_list, num_devices as usize) {
+            check_device(st, device)?;
+        }
+
+        // Mock 'binaries' are just source:
+        let (binary, len) = (*binaries, *lengths);
+        if binary.is_null() || len == 0 { return Err(CL_INVALID_VALUE); }
+
+        let source = match str::from_utf8(slice::from_raw_parts(binary, len)) {
+            Ok(source) => source.to_owned(),
+            Err(_) => {
+                if !binary_status.is_null() { *binary_status = CL_INVALID_BINARY; }
+                return Err(CL_INVALID_BINARY);
+            },
+        };
+        if !binary_status.is_null() {
+            for i in 0..num_devices as isize { *binary_status.offset(i) = CL_SUCCESS; }
+        }
+
+        Ok(new_program(st, context, source))
+    })
+}
+
+pub unsafe extern "system" fn clCreateProgramWithIL(context: cl_context, il: *const c_void,
+        length: size_t, errcode_ret: *mut cl_int) -> cl_program {
+    create("clCreateProgramWithIL", errcode_ret, |st| {
+        st.get::<Context>(h(context))?;
+        if il.is_null() || length == 0 { return Err(CL_INVALID_VALUE); }
+
+        let il = slice::from_raw_parts(il as *const u8, length).to_vec();
+        match spirv::parse(&il) {
+            Some(ref module) if SPIRV_VERSIONS.contains(&module.version) => (),
+            _ => return Err(CL_INVALID_VALUE),
+        }
+
+        let program = new_program(st, context, String::new());
+        st.get_mut::<Program>(program).unwrap().il = il;
+        Ok(program)
+    })
+}
+
+pub unsafe extern "system" fn clSetProgramSpecializationConstant(program: cl_program,
+        spec_id: cl_uint, spec_size: size_t, spec_value: *const c_void) -> cl_int {
+    call("clSetProgramSpecializationConstant", |st| {
+        let prg = st.get_mut::<Program>(h(program))?;
+        let module = spirv::parse(&prg.il).ok_or(CL_INVALID_PROGRAM)?;
+        if !module.spec_ids.contains(&spec_id) { return Err(CL_INVALID_SPEC_ID); }
+        if spec_value.is_null() || spec_size == 0 { return Err(CL_INVALID_VALUE); }
+
+        let value = slice::from_raw_parts(spec_value as *const u8, spec_size).to_vec();
+        prg.spec_constants.retain(|&(id, _)| id != spec_id);
+        prg.spec_constants.push((spec_id, value));
+        Ok(())
+    })
+}
+
+pub unsafe extern "system" fn clRetainProgram(program: cl_program) -> cl_int {
+    call("clRetainProgram", |st| st.retain::<Program>(h(program)))
+}
+
+pub unsafe extern "system" fn clReleaseProgram(program: cl_program) -> cl_int {
+    call("clReleaseProgram", |st| st.release::<Program>(h(program)))
+}
+
+unsafe fn check_device_list(st: &State, num_devices: cl_uint, device_list: *const cl_device_id)
+        -> Result<(), cl_int> {
+    if (num_devices == 0) != device_list.is_null() { return Err(CL_INVALID_VALUE); }
+    if !device_list.is_null() {
+        for &device in slice::from_raw_parts(device_list, num_devices as usize) {
+            check_device(st, device)?;
+        }
+    }
+    Ok(())
+}
+
+unsafe fn options_string(options: *const c_char) -> String {
+    if options.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(options).to_string_lossy().into_owned()
+    }
+}
+
+pub unsafe extern "system" fn clBuildProgram(program: cl_program, num_devices: cl_uint,
+        device_list: *const cl_device_id, options: *const c_char,
+        pfn_notify: Option<ProgramCallbackFn>, user_data: *mut c_void) -> cl_int {
+    call("clBuildProgram", |st| {
+        check_device_list(st, num_devices, device_list)?;
+
+        let result = {
+            let prg = st.get_mut::<Program>(h(program))?;
+            if prg.kernel_count != 0 { return Err(CL_INVALID_OPERATION); }
+
+            prg.build_options = options_string(options);
+
+            let errors = source::error_directives(&prg.source);
+
+            if let Some(module) = spirv::parse(&prg.il) {
+                prg.build_status = CL_BUILD_SUCCESS as cl_build_status;
+                prg.build_log = String::new();
+                prg.binary_type = CL_PROGRAM_BINARY_TYPE_EXECUTABLE as cl_program_binary_type;
+                prg.kernels = module.entry_points.into_iter()
+                    .map(|name| source::KernelSig { name: name, args: Vec::new(),
+                        reqd_work_group_size: None })
+                    .collect();
+                prg.built_spec_constants = prg.spec_constants.clone();
+                Ok(())
+            } else if errors.is_empty() {
+                prg.build_status = CL_BUILD_SUCCESS as cl_build_status;
+                prg.build_log = String::new();
+                prg.binary = prg.source.clone();
+                prg.binary_type = CL_PROGRAM_BINARY_TYPE_EXECUTABLE as cl_program_binary_type;
+                prg.kernels = source::parse_kernels(&prg.source);
+                Ok(())
+            } else {
+                prg.build_status = CL_BUILD_ERROR as cl_build_status;
+                prg.build_log = errors.join("\n");
+                prg.kernels = Vec::new();
+                Err(CL_BUILD_PROGRAM_FAILURE)
+            }
+        };
+
+        if let Some(func) = pfn_notify {
+            st.add_program_callback(h(program), func, user_data as usize);
+        }
+        result
+    })
+}
+
+pub unsafe extern "system" fn clCompileProgram(program: cl_program, num_devices: cl_uint,
+        device_list: *const cl_device_id, options: *const c_char, num_input_headers: cl_uint,
+        input_headers: *const cl_program, header_include_names: *const *const c_char,
+        pfn_notify: Option<ProgramCallbackFn>, user_data: *mut c_void) -> cl_int {
+    call("clCompileProgram", |st| {
+        check_device_list(st, num_devices, device_list)?;
+        if (num_input_headers == 0) != input_headers.is_null() ||
+                (num_input_headers == 0) != header_include_names.is_null() {
+            return Err(CL_INVALID_VALUE);
+        }
+
+        let mut headers = Vec::with_capacity(num_input_headers as usize);
+        for i in 0..num_input_headers as isize {
+            let name = *header_include_names.offset(i);
+            if name.is_null() { return Err(CL_INVALID_VALUE); }
+            let header = st.get::<Program>(h(*input_headers.offset(i)))?;
+            headers.push((CStr::from_ptr(name).to_string_lossy().into_owned(),
+                header.source.clone()));
+        }
+
+        let result = {
+            let prg = st.get_mut::<Program>(h(program))?;
+            // Only programs created from source may be compiled:
+            if prg.kernel_count != 0 || prg.source.is_empty() { return Err(CL_INVALID_OPERATION); }
+
+            prg.build_options = options_string(options);
+            prg.kernels = Vec::new();
+
+            let compiled = source::resolve_includes(&prg.source, &headers).and_then(|src| {
+                let errors = source::error_directives(&src);
+                if errors.is_empty() { Ok(src) } else { Err(errors) }
+            });
+
+            match compiled {
+                Ok(src) => {
+                    prg.build_status = CL_BUILD_SUCCESS as cl_build_status;
+                    prg.build_log = String::new();
+                    prg.binary = src;
+                    prg.binary_type = CL_PROGRAM_BINARY_TYPE_COMPILED_OBJECT
+                        as cl_program_binary_type;
+                    Ok(())
+                },
+                Err(errors) => {
+                    prg.build_status = CL_BUILD_ERROR as cl_build_status;
+                    prg.build_log = errors.join("\n");
+                    prg.binary = String::new();
+                    prg.binary_type = CL_PROGRAM_BINARY_TYPE_NONE as cl_program_binary_type;
+                    Err(CL_COMPILE_PROGRAM_FAILURE)
+                },
+            }
+        };
+
+        if let Some(func) = pfn_notify {
+            st.add_program_callback(h(program), func, user_data as usize);
+        }
+        result
+    })
+}
+
+/// Links compiled objects and libraries by concatenating their preprocessed
+/// source. Kernels defined more than once cause a link failure.
+pub unsafe extern "system" fn clLinkProgram(context: cl_context, num_devices: cl_uint,
+        device_list: *const cl_device_id, options: *const c_char, num_input_programs: cl_uint,
+        input_programs: *const cl_program, pfn_notify: Option<ProgramCallbackFn>,
+        user_data: *mut c_void, errcode_ret: *mut cl_int) -> cl_program {
+    if let Some(err) = inject::take("clLinkProgram") {
+        set_errcode(errcode_ret, err);
+        return ptr::null_mut();
+    }
+
+    // A program is returned along with a link failure so that its build log
+    // may be queried:
+    let result = state::with(|st| {
+        st.get::<Context>(h(context))?;
+        check_device_list(st, num_devices, device_list)?;
+        if num_input_programs == 0 || input_programs.is_null() { return Err(CL_INVALID_VALUE); }
+
+        let options = options_string(options);
+        let create_library = options.split_whitespace().any(|opt| opt == "-create-library");
+
+        let mut binary = String::new();
+        for &input in slice::from_raw_parts(input_programs, num_input_programs as usize) {
+            let prg = st.get::<Program>(h(input))?;
+            if prg.binary_type != CL_PROGRAM_BINARY_TYPE_COMPILED_OBJECT as cl_program_binary_type
+                    && prg.binary_type != CL_PROGRAM_BINARY_TYPE_LIBRARY as cl_program_binary_type {
+                return Err(CL_INVALID_OPERATION);
+            }
+            binary.push_str(&prg.binary);
+        }
+
+        let kernels = source::parse_kernels(&binary);
+        let errors: Vec<String> = kernels.iter().enumerate()
+            .filter(|&(i, k)| kernels[..i].iter().any(|prev| prev.name == k.name))
+            .map(|(_, k)| format!("error: duplicate symbol '{}'", k.name))
+            .collect();
+
+        let program = new_program(st, context, String::new());
+        let errcode = {
+            let prg = st.get_mut::<Program>(program).unwrap();
+            prg.build_options = options;
+
+            if errors.is_empty() {
+                prg.build_status = CL_BUILD_SUCCESS as cl_build_status;
+                prg.binary = binary;
+                if create_library {
+                    prg.binary_type = CL_PROGRAM_BINARY_TYPE_LIBRARY as cl_program_binary_type;
+                } else {
+                    prg.binary_type = CL_PROGRAM_BINARY_TYPE_EXECUTABLE as cl_program_binary_type;
+                    prg.kernels = kernels;
+                }
+                CL_SUCCESS
+            } else {
+                prg.build_status = CL_BUILD_ERROR as cl_build_status;
+                prg.build_log = errors.join("\n");
+                CL_LINK_PROGRAM_FAILURE
+            }
+        };
+
+        if let Some(func) = pfn_notify {
+            st.add_program_callback(program, func, user_data as usize);
+        }
+        Ok((program, errcode))
+    });
+
+    match result {
+        Ok((program, errcode)) => {
+            set_errcode(errcode_ret, errcode);
+            p(program)
+        },
+        Err(err) => {
+            set_errcode(errcode_ret, err);
+            ptr::null_mut()
+        },
+    }
+}
+
+pub unsafe extern "system" fn clUnloadCompiler() -> cl_int {
+    CL_SUCCESS
+}
+
+pub unsafe extern "system" fn clUnloadPlatformCompiler(platform: cl_platform_id) -> cl_int {
+    status(check_platform(platform))
+}
+
+fn built_program(st: &State, program: cl_program) -> Result<&Program, cl_int> {
+    let prg = st.get::<Program>(h(program))?;
+    if prg.build_status != CL_BUILD_SUCCESS as cl_build_status ||
+            prg.binary_type != CL_PROGRAM_BINARY_TYPE_EXECUTABLE as cl_program_binary_type {
+        return Err(CL_INVALID_PROGRAM_EXECUTABLE);
+    }
+    Ok(prg)
+}
+
+pub unsafe extern "system" fn clGetProgramInfo(program: cl_program, param_name: cl_program_info,
+        param_value_size: size_t, param_value: *mut c_void, param_value_size_ret: *mut size_t)
+        -> cl_int {
+    info("clGetProgramInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let prg = st.get::<Program>(h(program))?;
+        let binary = if prg.binary.is_empty() { &prg.source } else { &prg.binary };
+        let num_devices = st.get::<Context>(prg.context)?.devices.len();
+
+        match param_name {
+            CL_PROGRAM_REFERENCE_COUNT => Ok(val(st.refcount(h(program)))),
+            CL_PROGRAM_CONTEXT => Ok(val(p(prg.context))),
+            CL_PROGRAM_NUM_DEVICES => Ok(val(st.get::<Context>(prg.context)?.devices.len()
+                as cl_uint)),
+            CL_PROGRAM_DEVICES => Ok(vals(&st.get::<Context>(prg.context)?.devices.iter()
+                .map(|&d| p(d)).collect::<Vec<_>>())),
+            CL_PROGRAM_SOURCE => Ok(string(&prg.source)),
+            CL_PROGRAM_IL => Ok(prg.il.clone()),
+            CL_PROGRAM_BINARY_SIZES => Ok(vals(&vec![binary.len(); num_devices])),
+            CL_PROGRAM_BINARIES => {
+                // An array of pointers to caller allocated buffers, one per device:
+                let mut dsts = vec![ptr::null_mut::<u8>(); num_devices];
+                if !param_value.is_null() {
+                    let count = cmp::min(param_value_size / mem::size_of::<*mut u8>(), num_devices);
+                    for (i, dst) in dsts.iter_mut().enumerate().take(count) {
+                        *dst = *(param_value as *const *mut u8).offset(i as isize);
+                        if !dst.is_null() {
+                            ptr::copy_nonoverlapping(binary.as_ptr(), *dst, binary.len());
+                        }
+                    }
+                }
+                Ok(vals(&dsts))
+            },
+            CL_PROGRAM_NUM_KERNELS => Ok(val(built_program(st, program)?.kernels.len())),
+            CL_PROGRAM_KERNEL_NAMES => Ok(string(&built_program(st, program)?.kernels.iter()
+                .map(|k| &k.name[..]).collect::<Vec<_>>().join(";"))),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clGetProgramBuildInfo(program: cl_program, device: cl_device_id,
+        param_name: cl_program_build_info, param_value_size: size_t, param_value: *mut c_void,
+        param_value_size_ret: *mut size_t) -> cl_int {
+    info("clGetProgramBuildInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let prg = st.get::<Program>(h(program))?;
+        check_device(st, device)?;
+
+        match param_name {
+            CL_PROGRAM_BUILD_STATUS => Ok(val(prg.build_status)),
+            CL_PROGRAM_BUILD_OPTIONS => Ok(string(&prg.build_options)),
+            CL_PROGRAM_BUILD_LOG => Ok(string(&prg.build_log)),
+            CL_PROGRAM_BINARY_TYPE => Ok(val(prg.binary_type)),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+
+//============================================================================
+//=============================== Kernel APIs ================================
+//============================================================================
+
+fn new_kernel(st: &mut State, program: cl_program, sig: source::KernelSig) -> Handle {
+    let context = {
+        let prg = st.get_mut::<Program>(h(program)).unwrap();
+        prg.kernel_count += 1;
+        prg.context
+    };
+
+    st.insert(Object::Kernel(Kernel {
+        context: context,
+        program: h(program),
+        args: vec![None; sig.args.len()],
+        sig: sig,
+    }), vec![h(program)])
+}
+
+pub unsafe extern "system" fn clCreateKernel(program: cl_program, kernel_name: *const c_char,
+        errcode_ret: *mut cl_int) -> cl_kernel {
+    create("clCreateKernel", errcode_ret, |st| {
+        if kernel_name.is_null() { return Err(CL_INVALID_VALUE); }
+        let name = CStr::from_ptr(kernel_name).to_string_lossy();
+
+        let sig = built_program(st, program)?.kernels.iter().find(|k| k.name == name)
+            .cloned().ok_or(CL_INVALID_KERNEL_NAME)?;
+        Ok(new_kernel(st, program, sig))
+    })
+}
+
+pub unsafe extern "system" fn clCreateKernelsInProgram(program: cl_program, num_kernels: cl_uint,
+        kernels: *mut cl_kernel, num_kernels_ret: *mut cl_uint) -> cl_int {
+    call("clCreateKernelsInProgram", |st| {
+        let sigs = built_program(st, program)?.kernels.clone();
+        if !kernels.is_null() && (num_kernels as usize) < sigs.len() { return Err(CL_INVALID_VALUE); }
+
+        if !kernels.is_null() {
+            for (i, sig) in sigs.iter().enumerate() {
+                *kernels.offset(i as isize) = p(new_kernel(st, program, sig.clone()));
+            }
+        }
+        if !num_kernels_ret.is_null() { *num_kernels_ret = sigs.len() as cl_uint; }
+        Ok(())
+    })
+}
+
+pub unsafe extern "system" fn clCloneKernel(source_kernel: cl_kernel, errcode_ret: *mut cl_int)
+        -> cl_kernel {
+    create("clCloneKernel", errcode_ret, |st| {
+        let (program, sig, args) = {
+            let kern = st.get::<Kernel>(h(source_kernel))?;
+            (kern.program, kern.sig.clone(), kern.args.clone())
+        };
+        let clone = new_kernel(st, p(program), sig);
+        st.get_mut::<Kernel>(clone).unwrap().args = args;
+        Ok(clone)
+    })
+}
+
+pub unsafe extern "system" fn clRetainKernel(kernel: cl_kernel) -> cl_int {
+    call("clRetainKernel", |st| st.retain::<Kernel>(h(kernel)))
+}
+
+pub unsafe extern "system" fn clReleaseKernel(kernel: cl_kernel) -> cl_int {
+    call("clReleaseKernel", |st| st.release::<Kernel>(h(kernel)))
+}
+
+pub unsafe extern "system" fn clSetKernelArg(kernel: cl_kernel, arg_index: cl_uint,
+        arg_size: size_t, arg_value: *const c_void) -> cl_int {
+    call("clSetKernelArg", |st| {
+        let arg = st.get::<Kernel>(h(kernel))?.sig.args.get(arg_index as usize).cloned()
+            .ok_or(CL_INVALID_ARG_INDEX)?;
+
+        let value = if arg.is_local() {
+            if !arg_value.is_null() { return Err(CL_INVALID_ARG_VALUE); }
+            if arg_size == 0 { return Err(CL_INVALID_ARG_SIZE); }
+            ArgValue::Local(arg_size)
+        } else if arg.is_mem() {
+            if arg_size != mem::size_of::<cl_mem>() { return Err(CL_INVALID_ARG_SIZE); }
+            let mem = if arg_value.is_null() { ptr::null_mut() } else { *(arg_value as *const cl_mem) };
+
+            if mem.is_null() {
+                if arg.is_image() || arg.is_pipe() { return Err(CL_INVALID_ARG_VALUE); }
+                ArgValue::Mem(None)
+            } else {
+                let m = st.get::<Mem>(h(mem))?;
+                if arg.is_image() != m.image.is_some() || arg.is_pipe() != m.pipe.is_some() {
+                    return Err(CL_INVALID_ARG_VALUE);
+                }
+                ArgValue::Mem(Some(h(mem)))
+            }
+        } else if arg.is_sampler() {
+            if arg_size != mem::size_of::<cl_sampler>() { return Err(CL_INVALID_ARG_SIZE); }
+            if arg_value.is_null() { return Err(CL_INVALID_SAMPLER); }
+            let sampler = *(arg_value as *const cl_sampler);
+            st.get::<Sampler>(h(sampler))?;
+            ArgValue::Bytes(val(sampler))
+        } else {
+            if arg_value.is_null() { return Err(CL_INVALID_ARG_VALUE); }
+            if arg.value_size().map(|size| size != arg_size).unwrap_or(false) {
+                return Err(CL_INVALID_ARG_SIZE);
+            }
+            ArgValue::Bytes(slice::from_raw_parts(arg_value as *const u8, arg_size).to_vec())
+        };
+
+        st.get_mut::<Kernel>(h(kernel))?.args[arg_index as usize] = Some(value);
+        Ok(())
+    })
+}
+
+pub unsafe extern "system" fn clSetKernelArgSVMPointer(kernel: cl_kernel, arg_index: cl_uint,
+        arg_value: *const c_void) -> cl_int {
+    call("clSetKernelArgSVMPointer", |st| {
+        let arg = st.get::<Kernel>(h(kernel))?.sig.args.get(arg_index as usize).cloned()
+            .ok_or(CL_INVALID_ARG_INDEX)?;
+        if !arg.is_mem() || arg.is_image() || arg.is_pipe() { return Err(CL_INVALID_ARG_VALUE); }
+
+        let value = if arg_value.is_null() {
+            ArgValue::Mem(None)
+        } else {
+            st.svm_region(arg_value as usize).map_err(|_| CL_INVALID_ARG_VALUE)?;
+            ArgValue::Svm(arg_value as usize)
+        };
+
+        st.get_mut::<Kernel>(h(kernel))?.args[arg_index as usize] = Some(value);
+        Ok(())
+    })
+}
+
+pub unsafe extern "system" fn clSetKernelExecInfo(kernel: cl_kernel,
+        param_name: cl_kernel_exec_info, param_value_size: size_t, param_value: *const c_void)
+        -> cl_int {
+    call("clSetKernelExecInfo", |st| {
+        st.get::<Kernel>(h(kernel))?;
+        if param_value.is_null() { return Err(CL_INVALID_VALUE); }
+
+        match param_name {
+            CL_KERNEL_EXEC_INFO_SVM_PTRS => {
+                let ptr_size = mem::size_of::<*const c_void>();
+                if param_value_size % ptr_size != 0 { return Err(CL_INVALID_VALUE); }
+                let ptrs = slice::from_raw_parts(param_value as *const *const c_void,
+                    param_value_size / ptr_size);
+                for &ptr in ptrs { st.svm_region(ptr as usize)?; }
+                Ok(())
+            },
+            CL_KERNEL_EXEC_INFO_SVM_FINE_GRAIN_SYSTEM => {
+                if param_value_size != mem::size_of::<cl_bool>() { return Err(CL_INVALID_VALUE); }
+                // Fine-grained system SVM is not supported:
+                if *(param_value as *const cl_bool) != CL_FALSE { return Err(CL_INVALID_OPERATION); }
+                Ok(())
+            },
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clGetKernelInfo(kernel: cl_kernel, param_name: cl_kernel_info,
+        param_value_size: size_t, param_value: *mut c_void, param_value_size_ret: *mut size_t)
+        -> cl_int {
+    info("clGetKernelInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let kern = st.get::<Kernel>(h(kernel))?;
+
+        match param_name {
+            CL_KERNEL_FUNCTION_NAME => Ok(string(&kern.sig.name)),
+            CL_KERNEL_NUM_ARGS => Ok(val(kern.sig.args.len() as cl_uint)),
+            CL_KERNEL_REFERENCE_COUNT => Ok(val(st.refcount(h(kernel)))),
+            CL_KERNEL_CONTEXT => Ok(val(p(kern.context))),
+            CL_KERNEL_PROGRAM => Ok(val(p(kern.program))),
+            CL_KERNEL_ATTRIBUTES => Ok(string("")),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clGetKernelArgInfo(kernel: cl_kernel, arg_indx: cl_uint,
+        param_name: cl_kernel_arg_info, param_value_size: size_t, param_value: *mut c_void,
+        param_value_size_ret: *mut size_t) -> cl_int {
+    info("clGetKernelArgInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let arg = st.get::<Kernel>(h(kernel))?.sig.args.get(arg_indx as usize)
+            .ok_or(CL_INVALID_ARG_INDEX)?;
+
+        match param_name {
+            CL_KERNEL_ARG_ADDRESS_QUALIFIER => Ok(val(arg.address)),
+            CL_KERNEL_ARG_ACCESS_QUALIFIER => Ok(val(arg.access)),
+            CL_KERNEL_ARG_TYPE_NAME => Ok(string(&arg.type_name)),
+            CL_KERNEL_ARG_TYPE_QUALIFIER => Ok(val(arg.type_qualifier)),
+            CL_KERNEL_ARG_NAME => Ok(string(&arg.name)),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clGetKernelWorkGroupInfo(kernel: cl_kernel, device: cl_device_id,
+        param_name: cl_kernel_work_group_info, param_value_size: size_t,
+        param_value: *mut c_void, param_value_size_ret: *mut size_t) -> cl_int {
+    info("clGetKernelWorkGroupInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let kern = st.get::<Kernel>(h(kernel))?;
+        check_device(st, device)?;
+
+        match param_name {
+            CL_KERNEL_WORK_GROUP_SIZE => Ok(val(MAX_WORK_GROUP_SIZE)),
+            CL_KERNEL_COMPILE_WORK_GROUP_SIZE => {
+                Ok(vals(&kern.sig.reqd_work_group_size.unwrap_or([0; 3])))
+            },
+            CL_KERNEL_LOCAL_MEM_SIZE => Ok(val(kern.args.iter().map(|arg| match *arg {
+                Some(ArgValue::Local(size)) => size as cl_ulong,
+                _ => 0,
+            }).sum::<cl_ulong>())),
+            CL_KERNEL_PREFERRED_WORK_GROUP_SIZE_MULTIPLE => Ok(val(1 as size_t)),
+            CL_KERNEL_PRIVATE_MEM_SIZE => Ok(val(0 as cl_ulong)),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clGetKernelSubGroupInfo(kernel: cl_kernel, device: cl_device_id,
+        param_name: cl_kernel_sub_group_info, input_value_size: size_t,
+        input_value: *const c_void, param_value_size: size_t, param_value: *mut c_void,
+        param_value_size_ret: *mut size_t) -> cl_int {
+    info("clGetKernelSubGroupInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        st.get::<Kernel>(h(kernel))?;
+        check_device(st, device)?;
+
+        // Work-items are packed into sub-groups of `SUB_GROUP_SIZE` in
+        // linear order, the last sub-group of a work-group possibly being
+        // partial.
+        let input = || -> Result<&[size_t], cl_int> {
+            let size_len = mem::size_of::<size_t>();
+            if input_value.is_null() || input_value_size == 0 ||
+                    input_value_size % size_len != 0 || input_value_size > size_len * 3 {
+                return Err(CL_INVALID_VALUE);
+            }
+            Ok(slice::from_raw_parts(input_value as *const size_t, input_value_size / size_len))
+        };
+        let local_size = || -> Result<usize, cl_int> {
+            let local_size = input()?.iter().product::<usize>();
+            if local_size == 0 || local_size > MAX_WORK_GROUP_SIZE { return Err(CL_INVALID_VALUE); }
+            Ok(local_size)
+        };
+
+        match param_name {
+            CL_KERNEL_MAX_SUB_GROUP_SIZE_FOR_NDRANGE =>
+                Ok(val(cmp::min(local_size()?, SUB_GROUP_SIZE))),
+            CL_KERNEL_SUB_GROUP_COUNT_FOR_NDRANGE =>
+                Ok(val((local_size()? + SUB_GROUP_SIZE - 1) / SUB_GROUP_SIZE)),
+            CL_KERNEL_LOCAL_SIZE_FOR_SUB_GROUP_COUNT => {
+                let count = match input()? {
+                    &[count] => count,
+                    _ => return Err(CL_INVALID_VALUE),
+                };
+                let dims = param_value_size / mem::size_of::<size_t>();
+                if dims == 0 || dims > 3 { return Err(CL_INVALID_VALUE); }
+                let mut local_size = vec![0 as size_t; dims];
+                if count * SUB_GROUP_SIZE <= MAX_WORK_GROUP_SIZE {
+                    local_size[0] = count * SUB_GROUP_SIZE;
+                    for dim in local_size.iter_mut().skip(1) { *dim = 1; }
+                }
+                Ok(vals(&local_size))
+            },
+            CL_KERNEL_MAX_NUM_SUB_GROUPS => Ok(val(MAX_WORK_GROUP_SIZE / SUB_GROUP_SIZE)),
+            CL_KERNEL_COMPILE_NUM_SUB_GROUPS => Ok(val(0 as size_t)),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+
+//============================================================================
+//=============================== Event APIs =================================
+//============================================================================
+
+pub unsafe extern "system" fn clWaitForEvents(num_events: cl_uint, event_list: *const cl_event)
+        -> cl_int {
+    if let Some(err) = inject::take("clWaitForEvents") { return err; }
+    if num_events == 0 || event_list.is_null() { return CL_INVALID_VALUE; }
+    let events = match wait_list(num_events, event_list) {
+        Ok(events) => events,
+        Err(err) => return err,
+    };
+
+    let done = state::wait_until(|st| {
+        for &event in &events {
+            if !st.is_done(event)? { return Ok(false); }
+        }
+        Ok(true)
+    });
+
+    status(done.and_then(|_| state::with(|st| {
+        for &event in &events {
+            if st.get::<Event>(event)?.status < 0 {
+                return Err(CL_EXEC_STATUS_ERROR_FOR_EVENTS_IN_WAIT_LIST);
+            }
+        }
+        Ok(())
+    })))
+}
+
+pub unsafe extern "system" fn clGetEventInfo(event: cl_event, param_name: cl_event_info,
+        param_value_size: size_t, param_value: *mut c_void, param_value_size_ret: *mut size_t)
+        -> cl_int {
+    info("clGetEventInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let ev = st.get::<Event>(h(event))?;
+
+        match param_name {
+            CL_EVENT_COMMAND_QUEUE => Ok(val(p(ev.queue.unwrap_or(0)))),
+            CL_EVENT_CONTEXT => Ok(val(p(ev.context))),
+            CL_EVENT_COMMAND_TYPE => Ok(val(ev.command_type)),
+            CL_EVENT_COMMAND_EXECUTION_STATUS => Ok(val(ev.status)),
+            CL_EVENT_REFERENCE_COUNT => Ok(val(st.refcount(h(event)))),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+pub unsafe extern "system" fn clCreateUserEvent(context: cl_context, errcode_ret: *mut cl_int)
+        -> cl_event {
+    create("clCreateUserEvent", errcode_ret, |st| st.create_user_event(h(context)))
+}
+
+pub unsafe extern "system" fn clRetainEvent(event: cl_event) -> cl_int {
+    call("clRetainEvent", |st| st.retain::<Event>(h(event)))
+}
+
+pub unsafe extern "system" fn clReleaseEvent(event: cl_event) -> cl_int {
+    call("clReleaseEvent", |st| st.release::<Event>(h(event)))
+}
+
+pub unsafe extern "system" fn clSetUserEventStatus(event: cl_event, execution_status: cl_int)
+        -> cl_int {
+    call("clSetUserEventStatus", |st| {
+        {
+            let ev = st.get::<Event>(h(event))?;
+            if ev.queue.is_some() { return Err(CL_INVALID_EVENT); }
+            if execution_status > CL_COMPLETE { return Err(CL_INVALID_VALUE); }
+            if ev.status != CL_SUBMITTED { return Err(CL_INVALID_OPERATION); }
+        }
+
+        st.set_status(h(event), execution_status);
+        st.progress();
+        Ok(())
+    })
+}
+
+pub unsafe extern "system" fn clSetEventCallback(event: cl_event,
+        command_exec_callback_type: cl_int, pfn_notify: Option<EventCallbackFn>,
+        user_data: *mut c_void) -> cl_int {
+    call("clSetEventCallback", |st| {
+        let func = pfn_notify.ok_or(CL_INVALID_VALUE)?;
+
+        match command_exec_callback_type {
+            CL_COMPLETE | CL_RUNNING | CL_SUBMITTED => (),
+            _ => return Err(CL_INVALID_VALUE),
+        }
+
+        st.add_event_callback(h(event), command_exec_callback_type, func, user_data as usize)
+    })
+}
+
+pub unsafe extern "system" fn clGetEventProfilingInfo(event: cl_event,
+        param_name: cl_profiling_info, param_value_size: size_t, param_value: *mut c_void,
+        param_value_size_ret: *mut size_t) -> cl_int {
+    info("clGetEventProfilingInfo", param_value_size, param_value, param_value_size_ret, |st| {
+        let ev = st.get::<Event>(h(event))?;
+        let queue = ev.queue.ok_or(CL_PROFILING_INFO_NOT_AVAILABLE)?;
+
+        if st.get::<Queue>(queue)?.properties & CL_QUEUE_PROFILING_ENABLE == 0 ||
+                ev.status != CL_COMPLETE {
+            return Err(CL_PROFILING_INFO_NOT_AVAILABLE);
+        }
+
+        match param_name {
+            CL_PROFILING_COMMAND_QUEUED => Ok(val(ev.times[0])),
+            CL_PROFILING_COMMAND_SUBMIT => Ok(val(ev.times[1])),
+            CL_PROFILING_COMMAND_START => Ok(val(ev.times[2])),
+            CL_PROFILING_COMMAND_END | CL_PROFILING_COMMAND_COMPLETE => Ok(val(ev.times[3])),
+            _ => Err(CL_INVALID_VALUE),
+        }
+    })
+}
+
+
+//============================================================================
+//============================== Enqueue APIs ================================
+//============================================================================
+
+pub unsafe extern "system" fn clEnqueueReadBuffer(command_queue: cl_command_queue, buffer: cl_mem,
+        blocking_read: cl_bool, offset: size_t, cb: size_t, ptr: *mut c_void,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueReadBuffer", command_queue, CL_COMMAND_READ_BUFFER,
+            blocking_read != CL_FALSE, num_events_in_wait_list, event_wait_list, event, |st| {
+        check_buffer(st, command_queue, buffer)?;
+        if ptr.is_null() { return Err(CL_INVALID_VALUE); }
+        let src = Loc::linear(Place::Mem(h(buffer)), offset);
+        st.check_loc(&src, [cb, 1, 1])?;
+
+        Ok((Command::Copy { src: src, dst: Loc::linear(Place::Host(ptr as usize), 0),
+            region: [cb, 1, 1] }, vec![h(buffer)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueReadBufferRect(command_queue: cl_command_queue,
+        buffer: cl_mem, blocking_read: cl_bool, buffer_origin: *const size_t,
+        host_origin: *const size_t, region: *const size_t, buffer_row_pitch: size_t,
+        buffer_slc_pitch: size_t, host_row_pitch: size_t, host_slc_pitch: size_t,
+        ptr: *mut c_void, num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event,
+        event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueReadBufferRect", command_queue, CL_COMMAND_READ_BUFFER_RECT,
+            blocking_read != CL_FALSE, num_events_in_wait_list, event_wait_list, event, |st| {
+        check_buffer(st, command_queue, buffer)?;
+        if ptr.is_null() { return Err(CL_INVALID_VALUE); }
+        let region = read3(region)?;
+        let src = Loc::new(Place::Mem(h(buffer)), read3(buffer_origin)?, buffer_row_pitch,
+            buffer_slc_pitch).pitched(region);
+        let dst = Loc::new(Place::Host(ptr as usize), read3(host_origin)?, host_row_pitch,
+            host_slc_pitch).pitched(region);
+        st.check_loc(&src, region)?;
+
+        Ok((Command::Copy { src: src, dst: dst, region: region }, vec![h(buffer)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueWriteBuffer(command_queue: cl_command_queue,
+        buffer: cl_mem, blocking_write: cl_bool, offset: size_t, cb: size_t, ptr: *const c_void,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueWriteBuffer", command_queue, CL_COMMAND_WRITE_BUFFER,
+            blocking_write != CL_FALSE, num_events_in_wait_list, event_wait_list, event, |st| {
+        check_buffer(st, command_queue, buffer)?;
+        if ptr.is_null() { return Err(CL_INVALID_VALUE); }
+        let dst = Loc::linear(Place::Mem(h(buffer)), offset);
+        st.check_loc(&dst, [cb, 1, 1])?;
+
+        Ok((Command::Copy { src: Loc::linear(Place::Host(ptr as usize), 0), dst: dst,
+            region: [cb, 1, 1] }, vec![h(buffer)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueWriteBufferRect(command_queue: cl_command_queue,
+        buffer: cl_mem, blocking_write: cl_bool, buffer_origin: *const size_t,
+        host_origin: *const size_t, region: *const size_t, buffer_row_pitch: size_t,
+        buffer_slc_pitch: size_t, host_row_pitch: size_t, host_slc_pitch: size_t,
+        ptr: *const c_void, num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event,
+        event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueWriteBufferRect", command_queue, CL_COMMAND_WRITE_BUFFER_RECT,
+            blocking_write != CL_FALSE, num_events_in_wait_list, event_wait_list, event, |st| {
+        check_buffer(st, command_queue, buffer)?;
+        if ptr.is_null() { return Err(CL_INVALID_VALUE); }
+        let region = read3(region)?;
+        let dst = Loc::new(Place::Mem(h(buffer)), read3(buffer_origin)?, buffer_row_pitch,
+            buffer_slc_pitch).pitched(region);
+        let src = Loc::new(Place::Host(ptr as usize), read3(host_origin)?, host_row_pitch,
+            host_slc_pitch).pitched(region);
+        st.check_loc(&dst, region)?;
+
+        Ok((Command::Copy { src: src, dst: dst, region: region }, vec![h(buffer)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueFillBuffer(command_queue: cl_command_queue,
+        buffer: cl_mem, pattern: *const c_void, pattern_size: size_t, offset: size_t,
+        size: size_t, num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event,
+        event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueFillBuffer", command_queue, CL_COMMAND_FILL_BUFFER, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        check_buffer(st, command_queue, buffer)?;
+        if pattern.is_null() || !pattern_size.is_power_of_two() || pattern_size > 128 ||
+                offset % pattern_size != 0 || size % pattern_size != 0 {
+            return Err(CL_INVALID_VALUE);
+        }
+        let dst = Loc::linear(Place::Mem(h(buffer)), offset);
+        st.check_loc(&dst, [size, 1, 1])?;
+
+        Ok((Command::Fill { dst: dst, region: [size, 1, 1],
+            pattern: slice::from_raw_parts(pattern as *const u8, pattern_size).to_vec() },
+            vec![h(buffer)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueCopyBuffer(command_queue: cl_command_queue,
+        src_buffer: cl_mem, dst_buffer: cl_mem, src_offset: size_t, dst_offset: size_t,
+        cb: size_t, num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event,
+        event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueCopyBuffer", command_queue, CL_COMMAND_COPY_BUFFER, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        check_buffer(st, command_queue, src_buffer)?;
+        check_buffer(st, command_queue, dst_buffer)?;
+        let src = Loc::linear(Place::Mem(h(src_buffer)), src_offset);
+        let dst = Loc::linear(Place::Mem(h(dst_buffer)), dst_offset);
+        st.check_loc(&src, [cb, 1, 1])?;
+        st.check_loc(&dst, [cb, 1, 1])?;
+
+        if src_buffer == dst_buffer && src_offset < dst_offset + cb && dst_offset < src_offset + cb {
+            return Err(CL_MEM_COPY_OVERLAP);
+        }
+
+        Ok((Command::Copy { src: src, dst: dst, region: [cb, 1, 1] },
+            vec![h(src_buffer), h(dst_buffer)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueCopyBufferRect(command_queue: cl_command_queue,
+        src_buffer: cl_mem, dst_buffer: cl_mem, src_origin: *const size_t,
+        dst_origin: *const size_t, region: *const size_t, src_row_pitch: size_t,
+        src_slc_pitch: size_t, dst_row_pitch: size_t, dst_slc_pitch: size_t,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueCopyBufferRect", command_queue, CL_COMMAND_COPY_BUFFER_RECT, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        check_buffer(st, command_queue, src_buffer)?;
+        check_buffer(st, command_queue, dst_buffer)?;
+        let region = read3(region)?;
+        let src = Loc::new(Place::Mem(h(src_buffer)), read3(src_origin)?, src_row_pitch,
+            src_slc_pitch).pitched(region);
+        let dst = Loc::new(Place::Mem(h(dst_buffer)), read3(dst_origin)?, dst_row_pitch,
+            dst_slc_pitch).pitched(region);
+        st.check_loc(&src, region)?;
+        st.check_loc(&dst, region)?;
+
+        Ok((Command::Copy { src: src, dst: dst, region: region },
+            vec![h(src_buffer), h(dst_buffer)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueReadImage(command_queue: cl_command_queue, image: cl_mem,
+        blocking_read: cl_bool, origin: *const size_t, region: *const size_t, row_pitch: size_t,
+        slc_pitch: size_t, ptr: *mut c_void, num_events_in_wait_list: cl_uint,
+        event_wait_list: *const cl_event, event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueReadImage", command_queue, CL_COMMAND_READ_IMAGE,
+            blocking_read != CL_FALSE, num_events_in_wait_list, event_wait_list, event, |st| {
+        if ptr.is_null() { return Err(CL_INVALID_VALUE); }
+        let (src, region) = image_region(st, command_queue, image, read3(origin)?,
+            read3(region)?)?;
+        let dst = Loc::new(Place::Host(ptr as usize), [0; 3], row_pitch, slc_pitch)
+            .pitched(region);
+
+        Ok((Command::Copy { src: src, dst: dst, region: region }, vec![h(image)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueWriteImage(command_queue: cl_command_queue, image: cl_mem,
+        blocking_write: cl_bool, origin: *const size_t, region: *const size_t,
+        input_row_pitch: size_t, input_slc_pitch: size_t, ptr: *const c_void,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueWriteImage", command_queue, CL_COMMAND_WRITE_IMAGE,
+            blocking_write != CL_FALSE, num_events_in_wait_list, event_wait_list, event, |st| {
+        if ptr.is_null() { return Err(CL_INVALID_VALUE); }
+        let (dst, region) = image_region(st, command_queue, image, read3(origin)?,
+            read3(region)?)?;
+        let src = Loc::new(Place::Host(ptr as usize), [0; 3], input_row_pitch, input_slc_pitch)
+            .pitched(region);
+
+        Ok((Command::Copy { src: src, dst: dst, region: region }, vec![h(image)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueFillImage(command_queue: cl_command_queue, image: cl_mem,
+        fill_color: *const c_void, origin: *const size_t, region: *const size_t,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueFillImage", command_queue, CL_COMMAND_FILL_IMAGE, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        if fill_color.is_null() { return Err(CL_INVALID_VALUE); }
+        let (dst, region) = image_region(st, command_queue, image, read3(origin)?,
+            read3(region)?)?;
+        let format = image_format(st, image)?;
+        let pixel = image::pixel_from_fill_color(format[0], format[1],
+            &*(fill_color as *const [u8; 16])).ok_or(CL_INVALID_VALUE)?;
+
+        Ok((Command::Fill { dst: dst, region: region, pattern: pixel }, vec![h(image)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueCopyImage(command_queue: cl_command_queue,
+        src_image: cl_mem, dst_image: cl_mem, src_origin: *const size_t,
+        dst_origin: *const size_t, region: *const size_t, num_events_in_wait_list: cl_uint,
+        event_wait_list: *const cl_event, event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueCopyImage", command_queue, CL_COMMAND_COPY_IMAGE, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        let region = read3(region)?;
+        let (src, byte_region) = image_region(st, command_queue, src_image, read3(src_origin)?,
+            region)?;
+        let (dst, _) = image_region(st, command_queue, dst_image, read3(dst_origin)?, region)?;
+        if image_format(st, src_image)? != image_format(st, dst_image)? {
+            return Err(CL_IMAGE_FORMAT_MISMATCH);
+        }
+
+        Ok((Command::Copy { src: src, dst: dst, region: byte_region },
+            vec![h(src_image), h(dst_image)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueCopyImageToBuffer(command_queue: cl_command_queue,
+        src_image: cl_mem, dst_buffer: cl_mem, src_origin: *const size_t,
+        region: *const size_t, dst_offset: size_t, num_events_in_wait_list: cl_uint,
+        event_wait_list: *const cl_event, event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueCopyImageToBuffer", command_queue, CL_COMMAND_COPY_IMAGE_TO_BUFFER,
+            false, num_events_in_wait_list, event_wait_list, event, |st| {
+        check_buffer(st, command_queue, dst_buffer)?;
+        let (src, region) = image_region(st, command_queue, src_image, read3(src_origin)?,
+            read3(region)?)?;
+        let dst = Loc::linear(Place::Mem(h(dst_buffer)), dst_offset).pitched(region);
+        st.check_loc(&dst, region)?;
+
+        Ok((Command::Copy { src: src, dst: dst, region: region },
+            vec![h(src_image), h(dst_buffer)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueCopyBufferToImage(command_queue: cl_command_queue,
+        src_buffer: cl_mem, dst_image: cl_mem, src_offset: size_t, dst_origin: *const size_t,
+        region: *const size_t, num_events_in_wait_list: cl_uint,
+        event_wait_list: *const cl_event, event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueCopyBufferToImage", command_queue, CL_COMMAND_COPY_BUFFER_TO_IMAGE,
+            false, num_events_in_wait_list, event_wait_list, event, |st| {
+        check_buffer(st, command_queue, src_buffer)?;
+        let (dst, region) = image_region(st, command_queue, dst_image, read3(dst_origin)?,
+            read3(region)?)?;
+        let src = Loc::linear(Place::Mem(h(src_buffer)), src_offset).pitched(region);
+        st.check_loc(&src, region)?;
+
+        Ok((Command::Copy { src: src, dst: dst, region: region },
+            vec![h(src_buffer), h(dst_image)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueMapBuffer(command_queue: cl_command_queue, buffer: cl_mem,
+        blocking_map: cl_bool, _map_flags: cl_map_flags, offset: size_t, size: size_t,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event,
+        errorcode_ret: *mut cl_int) -> *mut c_void {
+    let result = enqueue("clEnqueueMapBuffer", command_queue, CL_COMMAND_MAP_BUFFER,
+            blocking_map != CL_FALSE, num_events_in_wait_list, event_wait_list, event, |st| {
+        check_buffer(st, command_queue, buffer)?;
+        st.check_loc(&Loc::linear(Place::Mem(h(buffer)), offset), [size, 1, 1])?;
+        let ptr = st.mem_region(h(buffer))?.0 + offset;
+        st.get_mut::<Mem>(h(buffer))?.map_count += 1;
+
+        Ok((Command::Nop, vec![h(buffer)], ptr as *mut c_void))
+    });
+
+    set_errcode(errorcode_ret, result.err().unwrap_or(CL_SUCCESS));
+    result.unwrap_or(ptr::null_mut())
+}
+
+pub unsafe extern "system" fn clEnqueueMapImage(command_queue: cl_command_queue, image: cl_mem,
+        blocking_map: cl_bool, _map_flags: cl_map_flags, origin: *const size_t,
+        region: *const size_t, image_row_pitch: *mut size_t, image_slc_pitch: *mut size_t,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event,
+        errorcode_ret: *mut cl_int) -> *mut c_void {
+    let result = enqueue("clEnqueueMapImage", command_queue, CL_COMMAND_MAP_IMAGE,
+            blocking_map != CL_FALSE, num_events_in_wait_list, event_wait_list, event, |st| {
+        if image_row_pitch.is_null() { return Err(CL_INVALID_VALUE); }
+        let (loc, _) = image_region(st, command_queue, image, read3(origin)?, read3(region)?)?;
+        let ptr = st.mem_region(h(image))?.0 + loc.origin[0] + loc.origin[1] * loc.row_pitch +
+            loc.origin[2] * loc.slice_pitch;
+
+        *image_row_pitch = loc.row_pitch;
+        if !image_slc_pitch.is_null() { *image_slc_pitch = loc.slice_pitch; }
+        st.get_mut::<Mem>(h(image))?.map_count += 1;
+
+        Ok((Command::Nop, vec![h(image)], ptr as *mut c_void))
+    });
+
+    set_errcode(errorcode_ret, result.err().unwrap_or(CL_SUCCESS));
+    result.unwrap_or(ptr::null_mut())
+}
+
+pub unsafe extern "system" fn clEnqueueUnmapMemObject(command_queue: cl_command_queue,
+        memobj: cl_mem, mapped_ptr: *mut c_void, num_events_in_wait_list: cl_uint,
+        event_wait_list: *const cl_event, event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueUnmapMemObject", command_queue, CL_COMMAND_UNMAP_MEM_OBJECT, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        let mem = st.get_mut::<Mem>(h(memobj))?;
+        if mapped_ptr.is_null() || mem.map_count == 0 { return Err(CL_INVALID_VALUE); }
+        mem.map_count -= 1;
+
+        Ok((Command::Nop, vec![h(memobj)], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueMigrateMemObjects(command_queue: cl_command_queue,
+        num_mem_objects: cl_uint, mem_objects: *const cl_mem, _flags: cl_mem_migration_flags,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueMigrateMemObjects", command_queue, CL_COMMAND_MIGRATE_MEM_OBJECTS,
+            false, num_events_in_wait_list, event_wait_list, event, |st| {
+        if num_mem_objects == 0 || mem_objects.is_null() { return Err(CL_INVALID_VALUE); }
+        let mems: Vec<Handle> = slice::from_raw_parts(mem_objects, num_mem_objects as usize)
+            .iter().map(|&m| h(m)).collect();
+        for &mem in &mems { st.get::<Mem>(mem)?; }
+
+        Ok((Command::Nop, mems, ()))
+    }))
+}
+
+/// Returns an error unless `ptr` (and `size` bytes following it) lies within
+/// a shared virtual memory allocation in the context of `queue`.
+fn check_svm(st: &State, queue: cl_command_queue, ptr: *const c_void, size: usize)
+        -> Result<(), cl_int> {
+    if ptr.is_null() { return Err(CL_INVALID_VALUE); }
+    let (_, len) = st.svm_region(ptr as usize)?;
+    if size > len { return Err(CL_INVALID_VALUE); }
+    if st.svm_context(ptr as usize)? != st.get::<Queue>(h(queue))?.context {
+        return Err(CL_INVALID_CONTEXT);
+    }
+    Ok(())
+}
+
+pub unsafe extern "system" fn clEnqueueSVMFree(command_queue: cl_command_queue,
+        num_svm_pointers: cl_uint, svm_pointers: *const *const c_void,
+        pfn_free_func: Option<SvmFreeCallbackFn>, user_data: *mut c_void,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueSVMFree", command_queue, CL_COMMAND_SVM_FREE, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        if num_svm_pointers == 0 || svm_pointers.is_null() { return Err(CL_INVALID_VALUE); }
+        let ptrs: Vec<usize> = slice::from_raw_parts(svm_pointers, num_svm_pointers as usize)
+            .iter().map(|&ptr| ptr as usize).collect();
+        for &ptr in &ptrs { check_svm(st, command_queue, ptr as *const c_void, 0)?; }
+
+        Ok((Command::SvmFree { queue: h(command_queue), ptrs: ptrs,
+            callback: pfn_free_func.map(|func| (func, user_data as usize)) }, vec![], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueSVMMemcpy(command_queue: cl_command_queue,
+        blocking_copy: cl_bool, dst_ptr: *mut c_void, src_ptr: *const c_void, size: size_t,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueSVMMemcpy", command_queue, CL_COMMAND_SVM_MEMCPY,
+            blocking_copy != CL_FALSE, num_events_in_wait_list, event_wait_list, event, |_| {
+        if dst_ptr.is_null() || src_ptr.is_null() { return Err(CL_INVALID_VALUE); }
+        let (dst, src) = (dst_ptr as usize, src_ptr as usize);
+        if dst < src + size && src < dst + size { return Err(CL_MEM_COPY_OVERLAP); }
+
+        Ok((Command::Copy { src: Loc::linear(Place::Host(src), 0),
+            dst: Loc::linear(Place::Host(dst), 0), region: [size, 1, 1] }, vec![], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueSVMMemFill(command_queue: cl_command_queue,
+        svm_ptr: *mut c_void, pattern: *const c_void, pattern_size: size_t, size: size_t,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueSVMMemFill", command_queue, CL_COMMAND_SVM_MEMFILL, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        if pattern.is_null() || !pattern_size.is_power_of_two() || pattern_size > 128 ||
+                svm_ptr as usize % pattern_size != 0 || size % pattern_size != 0 {
+            return Err(CL_INVALID_VALUE);
+        }
+        check_svm(st, command_queue, svm_ptr, size)?;
+
+        Ok((Command::Fill { dst: Loc::linear(Place::Host(svm_ptr as usize), 0),
+            region: [size, 1, 1],
+            pattern: slice::from_raw_parts(pattern as *const u8, pattern_size).to_vec() },
+            vec![], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueSVMMap(command_queue: cl_command_queue,
+        blocking_map: cl_bool, _flags: cl_map_flags, svm_ptr: *mut c_void, size: size_t,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueSVMMap", command_queue, CL_COMMAND_SVM_MAP,
+            blocking_map != CL_FALSE, num_events_in_wait_list, event_wait_list, event, |st| {
+        if size == 0 { return Err(CL_INVALID_VALUE); }
+        check_svm(st, command_queue, svm_ptr, size)?;
+        Ok((Command::Nop, vec![], ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueSVMUnmap(command_queue: cl_command_queue,
+        svm_ptr: *mut c_void, num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event,
+        event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueSVMUnmap", command_queue, CL_COMMAND_SVM_UNMAP, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        check_svm(st, command_queue, svm_ptr, 0)?;
+        Ok((Command::Nop, vec![], ()))
+    }))
+}
+
+/// Builds a kernel command, validating the arguments and work sizes.
+fn kernel_command(st: &State, queue: cl_command_queue, kernel: cl_kernel, work_dim: cl_uint,
+        global_offset: [usize; 3], global_size: [usize; 3], local_size: Option<[usize; 3]>)
+        -> Result<(Command, Vec<Handle>, ()), cl_int> {
+    let kern = st.get::<Kernel>(h(kernel))?;
+    if kern.context != st.get::<Queue>(h(queue))?.context { return Err(CL_INVALID_CONTEXT); }
+    let func = kernel::lookup(&kern.sig.name).ok_or(CL_INVALID_KERNEL)?;
+    let prg = st.get::<Program>(kern.program)?;
+    let spec_constants = prg.built_spec_constants.clone();
+    let non_uniform = ocl_kernel_src::allows_non_uniform(&prg.build_options);
+
+    let mut args = Vec::with_capacity(kern.args.len());
+    let mut retained = vec![h(kernel)];
+
+    for arg in &kern.args {
+        match *arg {
+            Some(ref arg) => {
+                if let ArgValue::Mem(Some(mem)) = *arg { retained.push(mem); }
+                args.push(arg.clone());
+            },
+            None => return Err(CL_INVALID_KERNEL_ARGS),
+        }
+    }
+
+    if global_size[..work_dim as usize].iter().any(|&s| s == 0) {
+        return Err(CL_INVALID_GLOBAL_WORK_SIZE);
+    }
+
+    match (kern.sig.reqd_work_group_size, local_size) {
+        (Some(reqd), Some(local)) if reqd[..work_dim as usize] == local[..work_dim as usize] => (),
+        (Some(_), _) => return Err(CL_INVALID_WORK_GROUP_SIZE),
+        (None, _) => (),
+    }
+
+    // Without a local size, the whole range is one work group:
+    let local_size = local_size.unwrap_or(global_size);
+    for i in 0..work_dim as usize {
+        if local_size[i] == 0 || (global_size[i] % local_size[i] != 0 && !non_uniform) {
+            return Err(CL_INVALID_WORK_GROUP_SIZE);
+        }
+    }
+
+    Ok((Command::Kernel {
+        func: func,
+        args: args,
+        spec_constants: spec_constants,
+        work_dim: work_dim,
+        global_offset: global_offset,
+        global_size: global_size,
+        local_size: local_size,
+    }, retained, ()))
+}
+
+pub unsafe extern "system" fn clEnqueueNDRangeKernel(command_queue: cl_command_queue,
+        kernel: cl_kernel, work_dim: cl_uint, global_work_offset: *const size_t,
+        global_work_dims: *const size_t, local_work_dims: *const size_t,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueNDRangeKernel", command_queue, CL_COMMAND_NDRANGE_KERNEL, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        if work_dim < 1 || work_dim > 3 { return Err(CL_INVALID_WORK_DIMENSION); }
+        if global_work_dims.is_null() { return Err(CL_INVALID_GLOBAL_WORK_SIZE); }
+
+        let dims = |ptr: *const size_t, default: usize| -> [usize; 3] {
+            let mut out = [default, default, default];
+            if default == 0 { out = [0, 0, 0]; }
+            for i in 0..3 {
+                out[i] = if i < work_dim as usize { *ptr.offset(i as isize) }
+                    else if default == 0 { 0 } else { 1 };
+            }
+            out
+        };
+
+        let global_offset = if global_work_offset.is_null() {
+            [0; 3]
+        } else {
+            dims(global_work_offset, 0)
+        };
+        let local_size = if local_work_dims.is_null() {
+            None
+        } else {
+            Some(dims(local_work_dims, 1))
+        };
+
+        kernel_command(st, command_queue, kernel, work_dim, global_offset,
+            dims(global_work_dims, 1), local_size)
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueTask(command_queue: cl_command_queue, kernel: cl_kernel,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueTask", command_queue, CL_COMMAND_TASK, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        kernel_command(st, command_queue, kernel, 1, [0; 3], [1; 3], Some([1; 3]))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueNativeKernel(command_queue: cl_command_queue,
+        user_func: Option<NativeKernelFn>, args: *mut c_void, cb_args: size_t,
+        num_mem_objects: cl_uint, mem_list: *const cl_mem, args_mem_loc: *const *const c_void,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event,
+        event: *mut cl_event) -> cl_int {
+    status(enqueue("clEnqueueNativeKernel", command_queue, CL_COMMAND_NATIVE_KERNEL, false,
+            num_events_in_wait_list, event_wait_list, event, |st| {
+        let func = user_func.ok_or(CL_INVALID_VALUE)?;
+        if args.is_null() != (cb_args == 0) { return Err(CL_INVALID_VALUE); }
+        if num_mem_objects > 0 && (args.is_null() || mem_list.is_null() ||
+                args_mem_loc.is_null()) {
+            return Err(CL_INVALID_VALUE);
+        }
+        if num_mem_objects == 0 && (!mem_list.is_null() || !args_mem_loc.is_null()) {
+            return Err(CL_INVALID_VALUE);
+        }
+
+        // The arguments are copied so that the caller may reuse them:
+        let args_copy = if args.is_null() {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(args as *const u8, cb_args).to_vec()
+        };
+
+        let mut mems = Vec::with_capacity(num_mem_objects as usize);
+        for i in 0..num_mem_objects as isize {
+            let mem = *mem_list.offset(i);
+            st.get::<Mem>(h(mem))?;
+            let offset = (*args_mem_loc.offset(i) as usize).wrapping_sub(args as usize);
+            if offset + mem::size_of::<cl_mem>() > cb_args { return Err(CL_INVALID_VALUE); }
+            mems.push((offset, h(mem)));
+        }
+
+        let retained = mems.iter().map(|&(_, mem)| mem).collect();
+        Ok((Command::Native { func: func, args: args_copy, mems: mems }, retained, ()))
+    }))
+}
+
+pub unsafe extern "system" fn clEnqueueMarker(command_queue: cl_command_queue,
+        event: *mut cl_event) -> cl_int {
+    if event.is_null() { return CL_INVALID_VALUE; }
+    status(enqueue("clEnqueueMarker", command_queue, CL_COMMAND_MARKER, false, 0, ptr::null(),
+        event, |_| Ok((Command::Nop, Vec::new(), ()))))
+}
+
+pub unsafe extern "system" fn clEnqueueMarkerWithWaitList(command_queue: cl_command_queue,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueMarkerWithWaitList", command_queue, CL_COMMAND_MARKER, false,
+        num_events_in_wait_list, event_wait_list, event, |_| Ok((Command::Nop, Vec::new(), ()))))
+}
+
+pub unsafe extern "system" fn clEnqueueWaitForEvents(command_queue: cl_command_queue,
+        num_events: cl_uint, event_list: *mut cl_event) -> cl_int {
+    if num_events == 0 || event_list.is_null() { return CL_INVALID_VALUE; }
+    status(enqueue("clEnqueueWaitForEvents", command_queue, CL_COMMAND_MARKER, false, num_events,
+        event_list, ptr::null_mut(), |_| Ok((Command::Nop, Vec::new(), ()))))
+}
+
+pub unsafe extern "system" fn clEnqueueBarrierWithWaitList(command_queue: cl_command_queue,
+        num_events_in_wait_list: cl_uint, event_wait_list: *const cl_event, event: *mut cl_event)
+        -> cl_int {
+    status(enqueue("clEnqueueBarrierWithWaitList", command_queue, CL_COMMAND_BARRIER, false,
+        num_events_in_wait_list, event_wait_list, event, |_| Ok((Command::Nop, Vec::new(), ()))))
+}
+
+pub unsafe extern "system" fn clEnqueueBarrier(command_queue: cl_command_queue) -> cl_int {
+    status(enqueue("clEnqueueBarrier", command_queue, CL_COMMAND_BARRIER, false, 0, ptr::null(),
+        ptr::null_mut(), |_| Ok((Command::Nop, Vec::new(), ()))))
+}
+
+pub unsafe extern "system" fn clGetExtensionFunctionAddressForPlatform(_platform: cl_platform_id,
+        _func_name: *const c_char) -> *mut c_void {
+    ptr::null_mut()
+}
+
+
+//============================================================================
+//================================= Symbols ==================================
+//============================================================================
+
+macro_rules! symbols {
+    ($($func:ident,)*) => (
+        /// Returns the address of the mock API function named `name` or null
+        /// if it is not implemented.
+        pub fn symbol(name: &str) -> *mut c_void {
+            match name {
+                $(stringify!($func) => $func as usize as *mut c_void,)*
+                _ => ptr::null_mut(),
+            }
+        }
+    )
+}
+
+symbols! {
+    clGetPlatformIDs,
+    clGetPlatformInfo,
+    clGetDeviceIDs,
+    clGetDeviceInfo,
+    clCreateSubDevices,
+    clRetainDevice,
+    clReleaseDevice,
+    clGetDeviceAndHostTimer,
+    clGetHostTimer,
+    clCreateContext,
+    clCreateContextFromType,
+    clRetainContext,
+    clReleaseContext,
+    clGetContextInfo,
+    clCreateCommandQueue,
+    clCreateCommandQueueWithProperties,
+    clRetainCommandQueue,
+    clReleaseCommandQueue,
+    clGetCommandQueueInfo,
+    clFlush,
+    clFinish,
+    clCreateBuffer,
+    clCreateSubBuffer,
+    clCreateImage,
+    clRetainMemObject,
+    clReleaseMemObject,
+    clGetSupportedImageFormats,
+    clGetMemObjectInfo,
+    clGetImageInfo,
+    clSetMemObjectDestructorCallback,
+    clCreatePipe,
+    clGetPipeInfo,
+    clSVMAlloc,
+    clSVMFree,
+    clCreateSampler,
+    clRetainSampler,
+    clReleaseSampler,
+    clGetSamplerInfo,
+    clCreateProgramWithSource,
+    clCreateProgramWithBinary,
+    clCreateProgramWithIL,
+    clSetProgramSpecializationConstant,
+    clRetainProgram,
+    clReleaseProgram,
+    clBuildProgram,
+    clCompileProgram,
+    clLinkProgram,
+    clUnloadCompiler,
+    clUnloadPlatformCompiler,
+    clGetProgramInfo,
+    clGetProgramBuildInfo,
+    clCreateKernel,
+    clCreateKernelsInProgram,
+    clCloneKernel,
+    clRetainKernel,
+    clReleaseKernel,
+    clSetKernelArg,
+    clSetKernelArgSVMPointer,
+    clSetKernelExecInfo,
+    clGetKernelInfo,
+    clGetKernelArgInfo,
+    clGetKernelWorkGroupInfo,
+    clGetKernelSubGroupInfo,
+    clWaitForEvents,
+    clGetEventInfo,
+    clCreateUserEvent,
+    clRetainEvent,
+    clReleaseEvent,
+    clSetUserEventStatus,
+    clSetEventCallback,
+    clGetEventProfilingInfo,
+    clEnqueueReadBuffer,
+    clEnqueueReadBufferRect,
+    clEnqueueWriteBuffer,
+    clEnqueueWriteBufferRect,
+    clEnqueueFillBuffer,
+    clEnqueueCopyBuffer,
+    clEnqueueCopyBufferRect,
+    clEnqueueReadImage,
+    clEnqueueWriteImage,
+    clEnqueueFillImage,
+    clEnqueueCopyImage,
+    clEnqueueCopyImageToBuffer,
+    clEnqueueCopyBufferToImage,
+    clEnqueueMapBuffer,
+    clEnqueueMapImage,
+    clEnqueueUnmapMemObject,
+    clEnqueueMigrateMemObjects,
+    clEnqueueSVMFree,
+    clEnqueueSVMMemcpy,
+    clEnqueueSVMMemFill,
+    clEnqueueSVMMap,
+    clEnqueueSVMUnmap,
+    clEnqueueNDRangeKernel,
+    clEnqueueTask,
+    clEnqueueNativeKernel,
+    clEnqueueMarker,
+    clEnqueueMarkerWithWaitList,
+    clEnqueueWaitForEvents,
+    clEnqueueBarrierWithWaitList,
+    clEnqueueBarrier,
+    clGetExtensionFunctionAddressForPlatform,
+}
@@ -0,0 +1,141 @@
+//! Image formats.
+
+use std::mem;
+use std::slice;
+use ffi::{cl_uint, cl_int, cl_float, CL_R, CL_A, CL_RG, CL_RA, CL_RGB, CL_RGBA, CL_BGRA,
+    CL_ARGB, CL_INTENSITY, CL_LUMINANCE, CL_Rx, CL_RGx, CL_RGBx, CL_SNORM_INT8, CL_SNORM_INT16,
+    CL_UNORM_INT8, CL_UNORM_INT16, CL_SIGNED_INT8, CL_SIGNED_INT16, CL_SIGNED_INT32,
+    CL_UNSIGNED_INT8, CL_UNSIGNED_INT16, CL_UNSIGNED_INT32, CL_HALF_FLOAT, CL_FLOAT,
+    CL_MEM_OBJECT_IMAGE1D, CL_MEM_OBJECT_IMAGE1D_ARRAY, CL_MEM_OBJECT_IMAGE1D_BUFFER,
+    CL_MEM_OBJECT_IMAGE2D, CL_MEM_OBJECT_IMAGE2D_ARRAY, CL_MEM_OBJECT_IMAGE3D};
+
+
+/// The formats reported by `clGetSupportedImageFormats`.
+pub fn supported_formats() -> Vec<[cl_uint; 2]> {
+    let mut formats = Vec::new();
+
+    for &order in &[CL_R, CL_RG, CL_RGBA] {
+        for &data_type in &[CL_UNORM_INT8, CL_UNORM_INT16, CL_SNORM_INT8, CL_SNORM_INT16,
+                CL_SIGNED_INT8, CL_SIGNED_INT16, CL_SIGNED_INT32, CL_UNSIGNED_INT8,
+                CL_UNSIGNED_INT16, CL_UNSIGNED_INT32, CL_HALF_FLOAT, CL_FLOAT] {
+            formats.push([order, data_type]);
+        }
+    }
+    formats.push([CL_BGRA, CL_UNORM_INT8]);
+    formats
+}
+
+/// Returns the number of channels for a channel order.
+fn channel_count(order: cl_uint) -> Option<usize> {
+    match order {
+        CL_R | CL_A | CL_INTENSITY | CL_LUMINANCE | CL_Rx => Some(1),
+        CL_RG | CL_RA | CL_RGx => Some(2),
+        CL_RGB | CL_RGBx => Some(3),
+        CL_RGBA | CL_BGRA | CL_ARGB => Some(4),
+        _ => None,
+    }
+}
+
+/// Returns the size of a single channel.
+fn channel_size(data_type: cl_uint) -> Option<usize> {
+    match data_type {
+        CL_SNORM_INT8 | CL_UNORM_INT8 | CL_SIGNED_INT8 | CL_UNSIGNED_INT8 => Some(1),
+        CL_SNORM_INT16 | CL_UNORM_INT16 | CL_SIGNED_INT16 | CL_UNSIGNED_INT16
+            | CL_HALF_FLOAT => Some(2),
+        CL_SIGNED_INT32 | CL_UNSIGNED_INT32 | CL_FLOAT => Some(4),
+        _ => None,
+    }
+}
+
+/// Returns the size of a single pixel or `None` if the format is unsupported.
+pub fn element_size(order: cl_uint, data_type: cl_uint) -> Option<usize> {
+    match (channel_count(order), channel_size(data_type)) {
+        (Some(count), Some(size)) => Some(count * size),
+        _ => None,
+    }
+}
+
+/// Returns the number of dimensions ('width', 'height', 'depth') an image
+/// type spans, including array dimensions.
+pub fn dim_count(image_type: cl_uint) -> Option<usize> {
+    match image_type {
+        CL_MEM_OBJECT_IMAGE1D | CL_MEM_OBJECT_IMAGE1D_BUFFER => Some(1),
+        CL_MEM_OBJECT_IMAGE1D_ARRAY | CL_MEM_OBJECT_IMAGE2D => Some(2),
+        CL_MEM_OBJECT_IMAGE2D_ARRAY | CL_MEM_OBJECT_IMAGE3D => Some(3),
+        _ => None,
+    }
+}
+
+/// Converts a 32 bit float to half precision bits (truncating).
+fn f32_to_f16(val: f32) -> u16 {
+    let bits: u32 = unsafe { mem::transmute(val) };
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mant = bits & 0x007f_ffff;
+
+    if exp >= 0x1f {
+        // Overflow, infinity, or NaN:
+        sign | 0x7c00 | if (bits & 0x7fff_ffff) > 0x7f80_0000 { 0x200 } else { 0 }
+    } else if exp <= 0 {
+        // Subnormal or zero:
+        if exp < -10 { return sign; }
+        sign | (((mant | 0x0080_0000) >> (14 - exp)) as u16)
+    } else {
+        sign | ((exp as u16) << 10) | ((mant >> 13) as u16)
+    }
+}
+
+fn push_bytes<T: Copy>(out: &mut Vec<u8>, val: T) {
+    let bytes = unsafe { slice::from_raw_parts(&val as *const T as *const u8, mem::size_of::<T>()) };
+    out.extend_from_slice(bytes);
+}
+
+/// Converts a fill color (`float4`, `int4`, or `uint4` depending on the
+/// channel data type) into the bytes of a single pixel.
+pub fn pixel_from_fill_color(order: cl_uint, data_type: cl_uint, color: &[u8; 16])
+        -> Option<Vec<u8>> {
+    // Channel indexes into the RGBA color:
+    let channels: &[usize] = match order {
+        CL_R | CL_Rx | CL_INTENSITY | CL_LUMINANCE => &[0],
+        CL_A => &[3],
+        CL_RG | CL_RGx => &[0, 1],
+        CL_RA => &[0, 3],
+        CL_RGB | CL_RGBx => &[0, 1, 2],
+        CL_RGBA => &[0, 1, 2, 3],
+        CL_BGRA => &[2, 1, 0, 3],
+        CL_ARGB => &[3, 0, 1, 2],
+        _ => return None,
+    };
+
+    let word = |idx: usize| -> [u8; 4] {
+        [color[idx * 4], color[idx * 4 + 1], color[idx * 4 + 2], color[idx * 4 + 3]]
+    };
+    let float = |idx: usize| -> cl_float { unsafe { mem::transmute(word(idx)) } };
+    let int = |idx: usize| -> cl_int { unsafe { mem::transmute(word(idx)) } };
+    let uint = |idx: usize| -> cl_uint { unsafe { mem::transmute(word(idx)) } };
+
+    let mut pixel = Vec::with_capacity(16);
+
+    for &ch in channels {
+        match data_type {
+            CL_UNORM_INT8 => push_bytes(&mut pixel,
+                (float(ch).max(0.).min(1.) * 255.).round() as u8),
+            CL_UNORM_INT16 => push_bytes(&mut pixel,
+                (float(ch).max(0.).min(1.) * 65535.).round() as u16),
+            CL_SNORM_INT8 => push_bytes(&mut pixel,
+                (float(ch).max(-1.).min(1.) * 127.).round() as i8),
+            CL_SNORM_INT16 => push_bytes(&mut pixel,
+                (float(ch).max(-1.).min(1.) * 32767.).round() as i16),
+            CL_SIGNED_INT8 => push_bytes(&mut pixel, int(ch).max(-128).min(127) as i8),
+            CL_SIGNED_INT16 => push_bytes(&mut pixel, int(ch).max(-32768).min(32767) as i16),
+            CL_SIGNED_INT32 => push_bytes(&mut pixel, int(ch)),
+            CL_UNSIGNED_INT8 => push_bytes(&mut pixel, uint(ch).min(255) as u8),
+            CL_UNSIGNED_INT16 => push_bytes(&mut pixel, uint(ch).min(65535) as u16),
+            CL_UNSIGNED_INT32 => push_bytes(&mut pixel, uint(ch)),
+            CL_HALF_FLOAT => push_bytes(&mut pixel, f32_to_f16(float(ch))),
+            CL_FLOAT => push_bytes(&mut pixel, float(ch)),
+            _ => return None,
+        }
+    }
+    Some(pixel)
+}
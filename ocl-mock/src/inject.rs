@@ -0,0 +1,36 @@
+//! Error injection.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use ffi::cl_int;
+
+thread_local! {
+    static FAILURES: RefCell<HashMap<String, VecDeque<cl_int>>> = RefCell::new(HashMap::new());
+}
+
+
+/// Causes the next call to the API function named `function` (e.g.
+/// `"clCreateBuffer"`) made from the current thread to fail with `errcode`
+/// (e.g. `CL_OUT_OF_RESOURCES`).
+///
+/// The function returns the error without doing anything else. Calling this
+/// more than once for the same function queues up multiple failures.
+pub fn fail_next(function: &str, errcode: cl_int) {
+    assert!(errcode < 0, "ocl_mock::fail_next: Error codes must be negative.");
+
+    FAILURES.with(|f| {
+        f.borrow_mut().entry(function.to_owned()).or_insert_with(VecDeque::new).push_back(errcode)
+    })
+}
+
+/// Removes any failures queued with `fail_next` from the current thread.
+pub fn clear_failures() {
+    FAILURES.with(|f| f.borrow_mut().clear())
+}
+
+/// Returns the next queued failure for `function`, if any.
+pub fn take(function: &str) -> Option<cl_int> {
+    FAILURES.with(|f| {
+        f.borrow_mut().get_mut(function).and_then(|errs| errs.pop_front())
+    })
+}
@@ -0,0 +1,300 @@
+//! Kernel implementations.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::{Arc, Mutex, Once};
+use state::PIPE_HEADER_SIZE;
+
+
+/// A kernel implementation.
+pub type KernelFn = Arc<Fn(&mut WorkItem) + Send + Sync>;
+
+
+fn registry() -> &'static Mutex<HashMap<String, KernelFn>> {
+    static INIT: Once = Once::new();
+    static mut REGISTRY: *const Mutex<HashMap<String, KernelFn>> = 0 as *const _;
+
+    unsafe {
+        INIT.call_once(|| {
+            REGISTRY = Box::into_raw(Box::new(Mutex::new(HashMap::new())));
+        });
+        &*REGISTRY
+    }
+}
+
+/// Registers `func` as the implementation of every kernel function named
+/// `name`, replacing any previously registered implementation.
+///
+/// `func` is called once per work item, in order, one work group at a time.
+/// Enqueuing a kernel without a registered implementation fails with
+/// `CL_INVALID_KERNEL`. If `func` panics, the kernel command's event will
+/// have a status of `CL_OUT_OF_RESOURCES`.
+///
+/// Kernel implementations must not call any OpenCL functions.
+pub fn register_kernel<F>(name: &str, func: F)
+        where F: Fn(&mut WorkItem) + Send + Sync + 'static {
+    registry().lock().unwrap().insert(name.to_owned(), Arc::new(func));
+}
+
+/// Removes the implementation registered for kernels named `name`.
+pub fn unregister_kernel(name: &str) {
+    registry().lock().unwrap().remove(name);
+}
+
+/// Returns the implementation registered for `name`.
+pub fn lookup(name: &str) -> Option<KernelFn> {
+    registry().lock().unwrap().get(name).cloned()
+}
+
+
+/// A kernel argument value resolved for execution.
+pub enum ArgData {
+    Bytes(Vec<u8>),
+    Mem { ptr: usize, len: usize },
+    /// Pipe storage (see `state::PipeInfo`).
+    Pipe { ptr: usize, packet_size: usize, max_packets: usize },
+    Local(usize),
+    Null,
+}
+
+
+/// A single work item, passed to kernel implementations.
+///
+/// Dimension indexes beyond the work dimension count behave as they do in
+/// OpenCL C (sizes of 1, ids of 0).
+pub struct WorkItem<'a> {
+    args: &'a [ArgData],
+    spec_constants: &'a [(u32, Vec<u8>)],
+    locals: &'a mut [Vec<u8>],
+    work_dim: u32,
+    global_offset: [usize; 3],
+    global_size: [usize; 3],
+    /// The size of this work item's work group, which is smaller than
+    /// `enqueued_local_size` for the last group of a non-uniform range:
+    local_size: [usize; 3],
+    enqueued_local_size: [usize; 3],
+    group_id: [usize; 3],
+    local_id: [usize; 3],
+}
+
+impl<'a> WorkItem<'a> {
+    /// Returns the number of work dimensions in use.
+    pub fn work_dim(&self) -> u32 {
+        self.work_dim
+    }
+
+    /// Returns the global id of this work item (including the global work
+    /// offset).
+    pub fn global_id(&self, dim: usize) -> usize {
+        if dim >= 3 { return 0; }
+        self.global_offset[dim] + self.group_id[dim] * self.enqueued_local_size[dim] +
+            self.local_id[dim]
+    }
+
+    /// Returns the global work size.
+    pub fn global_size(&self, dim: usize) -> usize {
+        if dim >= 3 { 1 } else { self.global_size[dim] }
+    }
+
+    /// Returns the global work offset.
+    pub fn global_offset(&self, dim: usize) -> usize {
+        if dim >= 3 { 0 } else { self.global_offset[dim] }
+    }
+
+    /// Returns the id of this work item within its work group.
+    pub fn local_id(&self, dim: usize) -> usize {
+        if dim >= 3 { 0 } else { self.local_id[dim] }
+    }
+
+    /// Returns the size of this work item's work group.
+    pub fn local_size(&self, dim: usize) -> usize {
+        if dim >= 3 { 1 } else { self.local_size[dim] }
+    }
+
+    /// Returns the local work size specified when the kernel was enqueued.
+    ///
+    /// Differs from `::local_size` only within the last work group of a
+    /// global work size which is not a multiple of the local work size.
+    pub fn enqueued_local_size(&self, dim: usize) -> usize {
+        if dim >= 3 { 1 } else { self.enqueued_local_size[dim] }
+    }
+
+    /// Returns the id of the work group this work item belongs to.
+    pub fn group_id(&self, dim: usize) -> usize {
+        if dim >= 3 { 0 } else { self.group_id[dim] }
+    }
+
+    /// Returns the number of work groups.
+    pub fn num_groups(&self, dim: usize) -> usize {
+        if dim >= 3 { 1 } else {
+            (self.global_size[dim] + self.enqueued_local_size[dim] - 1) /
+                self.enqueued_local_size[dim]
+        }
+    }
+
+    /// Returns the value of the scalar or vector argument at index `arg`.
+    ///
+    /// Panics if `arg` is not a by-value argument of the same size as `T`.
+    pub fn scalar<T: Copy>(&self, arg: usize) -> T {
+        match self.args[arg] {
+            ArgData::Bytes(ref bytes) => {
+                assert_eq!(bytes.len(), mem::size_of::<T>(), "ocl_mock::WorkItem::scalar: \
+                    Argument {} is {} bytes, not {}.", arg, bytes.len(), mem::size_of::<T>());
+                unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) }
+            },
+            _ => panic!("ocl_mock::WorkItem::scalar: Argument {} is not a scalar.", arg),
+        }
+    }
+
+    /// Returns the value of the specialization constant `spec_id` set with
+    /// `clSetProgramSpecializationConstant` when the kernel's program was
+    /// built, or `default` (the module's default value) if it was not set.
+    ///
+    /// Panics if the value set is not the same size as `T`.
+    pub fn spec_constant<T: Copy>(&self, spec_id: u32, default: T) -> T {
+        match self.spec_constants.iter().find(|&&(id, _)| id == spec_id) {
+            Some(&(_, ref bytes)) => {
+                assert_eq!(bytes.len(), mem::size_of::<T>(), "ocl_mock::WorkItem::spec_constant: \
+                    Specialization constant {} is {} bytes, not {}.", spec_id, bytes.len(),
+                    mem::size_of::<T>());
+                unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) }
+            },
+            None => default,
+        }
+    }
+
+    /// Returns the number of `T` elements in the buffer or local argument at
+    /// index `arg`.
+    pub fn len<T>(&self, arg: usize) -> usize {
+        self.slice(arg).1 / mem::size_of::<T>()
+    }
+
+    /// Reads element `idx` of the buffer or local argument at index `arg`.
+    ///
+    /// Panics if out of range.
+    pub fn read<T: Copy>(&self, arg: usize, idx: usize) -> T {
+        let (ptr, len) = self.slice(arg);
+        assert!((idx + 1) * mem::size_of::<T>() <= len, "ocl_mock::WorkItem::read: Index {} \
+            out of range for argument {}.", idx, arg);
+        unsafe { ptr::read_unaligned((ptr as *const T).offset(idx as isize)) }
+    }
+
+    /// Writes `val` to element `idx` of the buffer or local argument at index
+    /// `arg`.
+    ///
+    /// Panics if out of range.
+    pub fn write<T: Copy>(&mut self, arg: usize, idx: usize, val: T) {
+        let (ptr, len) = self.slice(arg);
+        assert!((idx + 1) * mem::size_of::<T>() <= len, "ocl_mock::WorkItem::write: Index {} \
+            out of range for argument {}.", idx, arg);
+        unsafe { ptr::write_unaligned((ptr as *mut T).offset(idx as isize), val) }
+    }
+
+    /// Reads (removes) the oldest packet from the pipe argument at index
+    /// `arg`, returning `None` if the pipe is empty.
+    ///
+    /// Panics if `arg` is not a pipe with packets the size of `T`.
+    pub fn read_pipe<T: Copy>(&mut self, arg: usize) -> Option<T> {
+        let (header, packets, max_packets) = self.pipe::<T>(arg);
+        unsafe {
+            let (head, count) = (*header, *header.offset(1));
+            if count == 0 { return None; }
+            *header = (head + 1) % max_packets;
+            *header.offset(1) = count - 1;
+            Some(ptr::read_unaligned(packets.offset(head as isize)))
+        }
+    }
+
+    /// Writes `val` as a new packet to the pipe argument at index `arg`,
+    /// returning false if the pipe is full.
+    ///
+    /// Panics if `arg` is not a pipe with packets the size of `T`.
+    pub fn write_pipe<T: Copy>(&mut self, arg: usize, val: T) -> bool {
+        let (header, packets, max_packets) = self.pipe::<T>(arg);
+        unsafe {
+            let (head, count) = (*header, *header.offset(1));
+            if count == max_packets { return false; }
+            ptr::write_unaligned(packets.offset(((head + count) % max_packets) as isize), val);
+            *header.offset(1) = count + 1;
+            true
+        }
+    }
+
+    /// Returns the header, packets and capacity of a pipe argument.
+    fn pipe<T>(&self, arg: usize) -> (*mut usize, *mut T, usize) {
+        match self.args[arg] {
+            ArgData::Pipe { ptr, packet_size, max_packets } => {
+                assert_eq!(packet_size, mem::size_of::<T>(), "ocl_mock::WorkItem: Packets of \
+                    pipe argument {} are {} bytes, not {}.", arg, packet_size, mem::size_of::<T>());
+                (ptr as *mut usize, (ptr + PIPE_HEADER_SIZE) as *mut T, max_packets)
+            },
+            _ => panic!("ocl_mock::WorkItem: Argument {} is not a pipe.", arg),
+        }
+    }
+
+    fn slice(&self, arg: usize) -> (*mut u8, usize) {
+        match self.args[arg] {
+            ArgData::Mem { ptr, len } => (ptr as *mut u8, len),
+            ArgData::Local(_) => (self.locals[arg].as_ptr() as *mut u8, self.locals[arg].len()),
+            ArgData::Null => panic!("ocl_mock::WorkItem: Argument {} is null.", arg),
+            ArgData::Bytes(_) | ArgData::Pipe { .. } => panic!("ocl_mock::WorkItem: Argument {} \
+                is not a buffer or local.", arg),
+        }
+    }
+}
+
+
+/// Runs `func` for every work item. Returns false if it panicked.
+pub fn run(func: &KernelFn, args: &[ArgData], spec_constants: &[(u32, Vec<u8>)], work_dim: u32,
+        global_offset: [usize; 3], global_size: [usize; 3], local_size: [usize; 3]) -> bool {
+    // The last group in each dimension is smaller if the global size is
+    // not a multiple of the local size:
+    let groups = |dim: usize| (global_size[dim] + local_size[dim] - 1) / local_size[dim];
+    let num_groups = [groups(0), groups(1), groups(2)];
+    let group_size = |group_id: [usize; 3]| -> [usize; 3] {
+        let mut size = local_size;
+        for (dim, len) in size.iter_mut().enumerate() {
+            *len = cmp::min(*len, global_size[dim] - group_id[dim] * local_size[dim]);
+        }
+        size
+    };
+
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        for gz in 0..num_groups[2] {
+            for gy in 0..num_groups[1] {
+                for gx in 0..num_groups[0] {
+                    // Local memory is fresh for each work group:
+                    let mut locals: Vec<Vec<u8>> = args.iter().map(|arg| match *arg {
+                        ArgData::Local(size) => vec![0; size],
+                        _ => Vec::new(),
+                    }).collect();
+
+                    let group_id = [gx, gy, gz];
+                    let group_local_size = group_size(group_id);
+
+                    for lz in 0..group_local_size[2] {
+                        for ly in 0..group_local_size[1] {
+                            for lx in 0..group_local_size[0] {
+                                func(&mut WorkItem {
+                                    args: args,
+                                    spec_constants: spec_constants,
+                                    locals: &mut locals,
+                                    work_dim: work_dim,
+                                    global_offset: global_offset,
+                                    global_size: global_size,
+                                    local_size: group_local_size,
+                                    enqueued_local_size: local_size,
+                                    group_id: group_id,
+                                    local_id: [lx, ly, lz],
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })).is_ok()
+}
@@ -0,0 +1,84 @@
+//! An in-process mock OpenCL platform.
+//!
+//! `ocl-mock` implements enough of the OpenCL API (behind the `cl-sys` ABI)
+//! to exercise `ocl` and `ocl-core` on machines without an OpenCL driver. It
+//! provides a single platform with a single CPU device supporting:
+//!
+//! * Device fission (`CL_DEVICE_PARTITION_EQUALLY` and `..._BY_COUNTS`)
+//! * Contexts, in-order and out-of-order command queues, profiling and queue
+//!   properties (on-device queues and priority/throttle hints)
+//! * Buffers, sub-buffers and images (read/write/copy/fill/map, including
+//!   the 'rect' variants)
+//! * Events, user events, wait lists, markers, barriers and event callbacks
+//! * Programs built from source or binaries (kernel signatures are parsed and
+//!   `#error` directives cause build failures)
+//! * Programs built from SPIR-V (entry points become argument-less kernels)
+//!   and specialization constants, read with `WorkItem::spec_constant`
+//!   (OpenCL 2.1 and 2.2)
+//! * Kernels, implemented by Rust closures registered with `register_kernel`,
+//!   kernel cloning and sub-group queries (OpenCL 2.1)
+//! * Coarse and fine-grained shared virtual memory (OpenCL 2.0)
+//! * Pipes, accessed from kernels with `WorkItem::read_pipe` and
+//!   `::write_pipe` (OpenCL 2.0)
+//! * Device and host timers (OpenCL 2.1)
+//! * Error injection with `fail_next`
+//!
+//! Commands execute synchronously on the thread which enqueues them (or on
+//! the thread which completes the last event they are waiting on) making
+//! tests deterministic.
+//!
+//! ## Usage
+//!
+//! `cl-sys` must be built with the `dynamic` feature (enable `dynamic` on
+//! `ocl` or `ocl-core`). Call `install` before any other OpenCL function:
+//!
+//! ```rust,ignore
+//! extern crate ocl;
+//! extern crate ocl_mock;
+//!
+//! ocl_mock::install().unwrap();
+//!
+//! ocl_mock::register_kernel("add", |wi| {
+//!     let idx = wi.global_id(0);
+//!     let val: f32 = wi.read(0, idx);
+//!     wi.write(0, idx, val + wi.scalar::<f32>(1));
+//! });
+//!
+//! let src = "__kernel void add(__global float* buffer, float scalar) { }";
+//! let pro_que = ocl::ProQue::builder().src(src).dims(1 << 10).build().unwrap();
+//! ```
+
+extern crate cl_sys as ffi;
+extern crate ocl_kernel_src;
+
+mod api;
+mod image;
+mod inject;
+mod kernel;
+mod source;
+mod spirv;
+mod state;
+
+pub use ffi::LoadLibraryError;
+pub use self::inject::{fail_next, clear_failures};
+pub use self::kernel::{register_kernel, unregister_kernel, WorkItem};
+pub use self::state::{live_object_count, is_live};
+
+/// The name of the mock platform.
+pub const PLATFORM_NAME: &'static str = "ocl-mock";
+
+/// The name of the mock device.
+pub const DEVICE_NAME: &'static str = "ocl-mock CPU";
+
+/// The version string reported by both the platform and device.
+pub const VERSION: &'static str = "OpenCL 2.2 ocl-mock";
+
+
+/// Installs the mock platform as the OpenCL implementation used by `cl-sys`.
+///
+/// Must be called before any OpenCL function is called, otherwise the system
+/// library will already be in use and an error is returned. Calling this
+/// more than once has no effect.
+pub fn install() -> Result<(), LoadLibraryError> {
+    ffi::load_library_with(api::symbol)
+}
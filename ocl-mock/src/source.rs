@@ -0,0 +1,261 @@
+//! A (very) minimal OpenCL C 'compiler': kernel signatures, `#include`
+//! directives naming embedded headers and `#error` directives are all that is
+//! understood.
+
+use ffi::{cl_uint, cl_bitfield, CL_KERNEL_ARG_ADDRESS_GLOBAL, CL_KERNEL_ARG_ADDRESS_LOCAL,
+    CL_KERNEL_ARG_ADDRESS_CONSTANT, CL_KERNEL_ARG_ADDRESS_PRIVATE, CL_KERNEL_ARG_ACCESS_READ_ONLY,
+    CL_KERNEL_ARG_ACCESS_WRITE_ONLY, CL_KERNEL_ARG_ACCESS_READ_WRITE, CL_KERNEL_ARG_ACCESS_NONE,
+    CL_KERNEL_ARG_TYPE_PIPE};
+use ocl_kernel_src::{self, Address, Access, strip_comments};
+
+
+/// A kernel argument declaration.
+#[derive(Clone, Debug)]
+pub struct ArgSig {
+    pub name: String,
+    pub type_name: String,
+    pub address: cl_uint,
+    pub access: cl_uint,
+    pub type_qualifier: cl_bitfield,
+}
+
+impl ArgSig {
+    /// Returns true if the argument is a memory object (buffer, image or
+    /// pipe).
+    pub fn is_mem(&self) -> bool {
+        (self.is_ptr() && self.address != CL_KERNEL_ARG_ADDRESS_LOCAL) || self.is_image() ||
+            self.is_pipe()
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.address == CL_KERNEL_ARG_ADDRESS_LOCAL
+    }
+
+    pub fn is_ptr(&self) -> bool {
+        self.type_name.ends_with('*')
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.type_name.starts_with("image")
+    }
+
+    pub fn is_pipe(&self) -> bool {
+        self.type_qualifier & CL_KERNEL_ARG_TYPE_PIPE != 0
+    }
+
+    pub fn is_sampler(&self) -> bool {
+        self.type_name == "sampler_t"
+    }
+
+    /// Returns the size of a by-value argument if it is a known scalar or
+    /// vector type.
+    pub fn value_size(&self) -> Option<usize> {
+        let name = &self.type_name[..];
+        let base_len = name.trim_right_matches(|c: char| c.is_digit(10)).len();
+        let (base, card) = name.split_at(base_len);
+
+        let base_size = match base {
+            "bool" | "char" | "uchar" => 1,
+            "short" | "ushort" | "half" => 2,
+            "int" | "uint" | "float" => 4,
+            "long" | "ulong" | "double" => 8,
+            "size_t" | "ptrdiff_t" | "intptr_t" | "uintptr_t" => ::std::mem::size_of::<usize>(),
+            _ => return None,
+        };
+
+        match card {
+            "" => Some(base_size),
+            "2" | "4" | "8" | "16" => card.parse::<usize>().ok().map(|c| c * base_size),
+            // Three component vectors are the size of four:
+            "3" => Some(base_size * 4),
+            _ => None,
+        }
+    }
+}
+
+
+/// A kernel function declaration.
+#[derive(Clone, Debug)]
+pub struct KernelSig {
+    pub name: String,
+    pub args: Vec<ArgSig>,
+    pub reqd_work_group_size: Option<[usize; 3]>,
+}
+
+
+impl<'a> From<&'a ocl_kernel_src::ArgSig> for ArgSig {
+    fn from(sig: &'a ocl_kernel_src::ArgSig) -> ArgSig {
+        ArgSig {
+            name: sig.name.clone(),
+            type_name: sig.type_name.clone(),
+            address: match sig.address {
+                Address::Global => CL_KERNEL_ARG_ADDRESS_GLOBAL,
+                Address::Local => CL_KERNEL_ARG_ADDRESS_LOCAL,
+                Address::Constant => CL_KERNEL_ARG_ADDRESS_CONSTANT,
+                Address::Private => CL_KERNEL_ARG_ADDRESS_PRIVATE,
+            },
+            access: match sig.access {
+                Access::ReadOnly => CL_KERNEL_ARG_ACCESS_READ_ONLY,
+                Access::WriteOnly => CL_KERNEL_ARG_ACCESS_WRITE_ONLY,
+                Access::ReadWrite => CL_KERNEL_ARG_ACCESS_READ_WRITE,
+                Access::None => CL_KERNEL_ARG_ACCESS_NONE,
+            },
+            // The bits are those of `CL_KERNEL_ARG_TYPE_*`:
+            type_qualifier: sig.type_qualifier,
+        }
+    }
+}
+
+
+/// Returns the signatures of all kernel functions defined in `src`.
+pub fn parse_kernels(src: &str) -> Vec<KernelSig> {
+    ocl_kernel_src::parse_kernels(src).iter().map(|k| {
+        KernelSig {
+            name: k.name.clone(),
+            args: k.args.iter().map(ArgSig::from).collect(),
+            reqd_work_group_size: k.reqd_work_group_size,
+        }
+    }).collect()
+}
+
+/// Returns a build log entry for each `#error` directive in `src`.
+pub fn error_directives(src: &str) -> Vec<String> {
+    strip_comments(src).lines().enumerate().filter_map(|(idx, line)| {
+        let trimmed = line.trim_left();
+        if trimmed.starts_with('#') && trimmed[1..].trim_left().starts_with("error") {
+            let col = line.len() - trimmed.len() + 1;
+            let msg = trimmed[1..].trim_left()["error".len()..].trim();
+            Some(format!("<source>:{}:{}: error: {}", idx + 1, col, msg))
+        } else {
+            None
+        }
+    }).collect()
+}
+
+/// Includes nested deeper than this are assumed to be recursive.
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// Returns the column and header name of an `#include` directive.
+fn include_directive(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_left();
+    if !trimmed.starts_with('#') { return None; }
+    let directive = trimmed[1..].trim_left();
+    if !directive.starts_with("include") { return None; }
+
+    let path = directive["include".len()..].trim();
+    let close = match path.chars().next() {
+        Some('"') => '"',
+        Some('<') => '>',
+        _ => return None,
+    };
+    path[1..].find(close).map(|end| (line.len() - trimmed.len() + 1, &path[1..end + 1]))
+}
+
+fn expand_includes(src: &str, headers: &[(String, String)], depth: usize,
+        errors: &mut Vec<String>) -> String {
+    let mut out = String::with_capacity(src.len());
+
+    for (idx, line) in strip_comments(src).lines().enumerate() {
+        match include_directive(line) {
+            Some((col, name)) => match headers.iter().find(|&&(ref n, _)| n == name) {
+                Some(&(_, ref header)) if depth < MAX_INCLUDE_DEPTH => {
+                    out.push_str(&expand_includes(header, headers, depth + 1, errors));
+                },
+                Some(_) => errors.push(format!("<source>:{}:{}: fatal error: #include \
+                    nested too deeply", idx + 1, col)),
+                None => errors.push(format!("<source>:{}:{}: fatal error: '{}' file not found",
+                    idx + 1, col, name)),
+            },
+            None => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Replaces each `#include` directive in `src` with the source of the
+/// header of the same name, returning a build log entry for each header
+/// which can not be found.
+pub fn resolve_includes(src: &str, headers: &[(String, String)]) -> Result<String, Vec<String>> {
+    let mut errors = Vec::new();
+    let out = expand_includes(src, headers, 0, &mut errors);
+    if errors.is_empty() { Ok(out) } else { Err(errors) }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_signatures() {
+        let src = r#"
+            // __kernel void commented_out(int a) {}
+            __kernel void add(__global float* buf, float scl) {
+                buf[get_global_id(0)] += scl;
+            }
+
+            /* Multi
+               line */
+            kernel __attribute__((reqd_work_group_size(64, 1, 1)))
+            void img(read_only image2d_t src, __write_only image2d_t dst,
+                    sampler_t smp, __local unsigned int* loc, const uint4 v) {}
+
+            __kernel void none(void) {}
+
+            __kernel void pipes(__write_only pipe float4 out, pipe int in) {}
+        "#;
+
+        let kernels = parse_kernels(src);
+        assert_eq!(kernels.len(), 4);
+
+        assert_eq!(kernels[0].name, "add");
+        assert_eq!(kernels[0].args[0].name, "buf");
+        assert_eq!(kernels[0].args[0].type_name, "float*");
+        assert_eq!(kernels[0].args[0].address, CL_KERNEL_ARG_ADDRESS_GLOBAL);
+        assert!(kernels[0].args[0].is_mem());
+        assert_eq!(kernels[0].args[1].type_name, "float");
+        assert_eq!(kernels[0].args[1].value_size(), Some(4));
+
+        let img = &kernels[1];
+        assert_eq!(img.name, "img");
+        assert_eq!(img.args.len(), 5);
+        assert_eq!(img.args[0].access, CL_KERNEL_ARG_ACCESS_READ_ONLY);
+        assert_eq!(img.args[1].access, CL_KERNEL_ARG_ACCESS_WRITE_ONLY);
+        assert!(img.args[2].is_sampler());
+        assert_eq!(img.args[3].type_name, "uint*");
+        assert!(img.args[3].is_local());
+        // `const` only applies to the referenced type of pointers:
+        assert_eq!(img.args[4].type_qualifier, ::ffi::CL_KERNEL_ARG_TYPE_NONE);
+        assert_eq!(img.args[4].value_size(), Some(16));
+
+        assert!(kernels[2].args.is_empty());
+
+        let pipes = &kernels[3];
+        assert_eq!(pipes.args[0].type_name, "float4");
+        assert_eq!(pipes.args[0].access, CL_KERNEL_ARG_ACCESS_WRITE_ONLY);
+        assert!(pipes.args[0].is_pipe() && pipes.args[0].is_mem());
+        assert_eq!(pipes.args[1].name, "in");
+        assert_eq!(pipes.args[1].access, CL_KERNEL_ARG_ACCESS_READ_ONLY);
+    }
+
+    #[test]
+    fn error_directive() {
+        let log = error_directives("__kernel void k() {}\n  #error Something went wrong\n");
+        assert_eq!(log, vec!["<source>:2:3: error: Something went wrong".to_owned()]);
+    }
+
+    #[test]
+    fn includes() {
+        let headers = vec![
+            ("util.h".to_owned(), "#include <types.h>\nfloat twice(real x);".to_owned()),
+            ("types.h".to_owned(), "typedef float real;".to_owned()),
+        ];
+        let src = "#include \"util.h\"\n__kernel void k() {}";
+        let out = resolve_includes(src, &headers).unwrap();
+        assert_eq!(out, "typedef float real;\n\nfloat twice(real x);\n\n__kernel void k() {}\n");
+
+        let log = resolve_includes("  #include \"missing.h\"", &headers).unwrap_err();
+        assert_eq!(log, vec!["<source>:1:3: fatal error: 'missing.h' file not found".to_owned()]);
+    }
+}
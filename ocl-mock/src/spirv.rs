@@ -0,0 +1,95 @@
+//! A (very) minimal SPIR-V reader: the module version, kernel entry point
+//! names and specialization constant ids are all that is understood.
+
+/// The first word of every SPIR-V module.
+const MAGIC: u32 = 0x0723_0203;
+
+const OP_ENTRY_POINT: u32 = 15;
+const OP_DECORATE: u32 = 71;
+const EXECUTION_MODEL_KERNEL: u32 = 6;
+const DECORATION_SPEC_ID: u32 = 1;
+
+
+/// The parts of a SPIR-V module used by the mock.
+#[derive(Clone, Debug)]
+pub struct Module {
+    pub version: (u8, u8),
+    pub entry_points: Vec<String>,
+    pub spec_ids: Vec<u32>,
+}
+
+
+/// Decodes a literal string (nul-terminated, packed four bytes per word).
+fn literal_string(words: &[u32]) -> String {
+    let mut bytes = Vec::with_capacity(words.len() * 4);
+    for &word in words {
+        for i in 0..4 {
+            let byte = (word >> (i * 8)) as u8;
+            if byte == 0 { return String::from_utf8_lossy(&bytes).into_owned(); }
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Parses `il`, returning `None` if it is not a well-formed SPIR-V module.
+pub fn parse(il: &[u8]) -> Option<Module> {
+    if il.len() < 20 || il.len() % 4 != 0 { return None; }
+
+    let mut words: Vec<u32> = il.chunks(4)
+        .map(|b| b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+        .collect();
+    if words[0] != MAGIC {
+        if words[0].swap_bytes() != MAGIC { return None; }
+        for word in &mut words { *word = word.swap_bytes(); }
+    }
+
+    let version = ((words[1] >> 16) as u8, (words[1] >> 8) as u8);
+    let mut module = Module { version: version, entry_points: Vec::new(), spec_ids: Vec::new() };
+
+    // Instructions follow the five word header:
+    let mut idx = 5;
+    while idx < words.len() {
+        let (word_count, opcode) = ((words[idx] >> 16) as usize, words[idx] & 0xFFFF);
+        if word_count == 0 || idx + word_count > words.len() { return None; }
+        let operands = &words[idx + 1..idx + word_count];
+
+        match opcode {
+            OP_ENTRY_POINT if operands.len() >= 3 && operands[0] == EXECUTION_MODEL_KERNEL => {
+                module.entry_points.push(literal_string(&operands[2..]));
+            },
+            OP_DECORATE if operands.len() >= 3 && operands[1] == DECORATION_SPEC_ID => {
+                module.spec_ids.push(operands[2]);
+            },
+            _ => (),
+        }
+        idx += word_count;
+    }
+
+    Some(module)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|w| (0..4).map(move |i| (w >> (i * 8)) as u8)).collect()
+    }
+
+    #[test]
+    fn entry_points_and_spec_ids() {
+        let words = [MAGIC, 0x0001_0200, 0, 8, 0,
+            (5 << 16) | OP_ENTRY_POINT, EXECUTION_MODEL_KERNEL, 1, 0x6B6F_6F6C, 0x0000_7075,
+            (4 << 16) | OP_DECORATE, 2, DECORATION_SPEC_ID, 7];
+        let module = parse(&bytes(&words)).unwrap();
+        assert_eq!(module.version, (1, 2));
+        assert_eq!(module.entry_points, vec!["lookup".to_owned()]);
+        assert_eq!(module.spec_ids, vec![7]);
+
+        let swapped: Vec<u32> = words.iter().map(|w| w.swap_bytes()).collect();
+        assert_eq!(parse(&bytes(&swapped)).unwrap().entry_points, module.entry_points);
+        assert!(parse(b"__kernel void lookup() { }  ").is_none());
+    }
+}
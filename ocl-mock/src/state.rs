@@ -0,0 +1,981 @@
+//! Object storage and command execution.
+//!
+//! All objects live in a single, global `State` protected by a mutex.
+//! Handles are plain integers cast to pointers. Callbacks are collected while
+//! the state is locked and called after it has been unlocked.
+
+use std::alloc::{self, Layout};
+use std::collections::{BTreeMap, VecDeque};
+use std::mem;
+use std::ptr;
+use std::sync::{Mutex, MutexGuard, Condvar, Once};
+use std::time::Instant;
+use ffi::{c_void, cl_int, cl_uint, cl_bool, cl_event, cl_mem, cl_program, cl_mem_flags,
+    cl_command_queue,
+    cl_command_type, cl_command_queue_properties, cl_context_properties, cl_build_status,
+    cl_program_binary_type,
+    cl_device_partition_property, CL_INVALID_DEVICE,
+    CL_COMPLETE, CL_RUNNING, CL_SUBMITTED, CL_QUEUED, CL_INVALID_CONTEXT,
+    CL_INVALID_COMMAND_QUEUE, CL_INVALID_MEM_OBJECT, CL_INVALID_SAMPLER, CL_INVALID_PROGRAM,
+    CL_INVALID_KERNEL, CL_INVALID_EVENT, CL_INVALID_VALUE, CL_OUT_OF_RESOURCES,
+    CL_INVALID_EVENT_WAIT_LIST, CL_EXEC_STATUS_ERROR_FOR_EVENTS_IN_WAIT_LIST, CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE,
+    CL_QUEUE_ON_DEVICE,
+    CL_COMMAND_BARRIER, CL_COMMAND_MARKER, CL_COMMAND_USER, CL_MEM_OBJECT_IMAGE1D_ARRAY,
+    CL_MEM_OBJECT_IMAGE2D, CL_MEM_OBJECT_IMAGE2D_ARRAY, CL_MEM_OBJECT_IMAGE3D};
+use kernel::{self, KernelFn, ArgData};
+use source::KernelSig;
+
+
+/// An object handle.
+pub type Handle = usize;
+
+/// The handle of the one and only platform.
+pub const PLATFORM: Handle = 0x100;
+
+/// The handle of the one and only (root) device.
+pub const DEVICE: Handle = 0x200;
+
+/// The number of compute units of the root device.
+pub const COMPUTE_UNITS: cl_uint = 4;
+
+const FIRST_HANDLE: Handle = 0x1000;
+
+/// The alignment of buffer storage (`CL_DEVICE_MEM_BASE_ADDR_ALIGN`).
+pub const MEM_ALIGN: usize = 128;
+
+pub type EventCallbackFn = extern fn(cl_event, cl_int, *mut c_void);
+pub type MemCallbackFn = extern fn(cl_mem, *mut c_void);
+pub type ProgramCallbackFn = extern fn(cl_program, *mut c_void);
+pub type NativeKernelFn = extern fn(*mut c_void);
+pub type SvmFreeCallbackFn = extern fn(cl_command_queue, cl_uint, *const *const c_void, *mut c_void);
+
+
+/// A callback waiting to be called once the state is unlocked.
+pub enum Callback {
+    Event { func: EventCallbackFn, event: Handle, status: cl_int, user_data: usize },
+    Mem { func: MemCallbackFn, mem: Handle, user_data: usize },
+    Program { func: ProgramCallbackFn, program: Handle, user_data: usize },
+    SvmFree { func: SvmFreeCallbackFn, queue: Handle, ptrs: Vec<usize>, user_data: usize },
+}
+
+impl Callback {
+    fn call(&self) {
+        match *self {
+            Callback::Event { func, event, status, user_data } =>
+                func(event as cl_event, status, user_data as *mut c_void),
+            Callback::Mem { func, mem, user_data } =>
+                func(mem as cl_mem, user_data as *mut c_void),
+            Callback::Program { func, program, user_data } =>
+                func(program as cl_program, user_data as *mut c_void),
+            Callback::SvmFree { func, queue, ref ptrs, user_data } =>
+                func(queue as cl_command_queue, ptrs.len() as cl_uint,
+                    ptrs.as_ptr() as *const *const c_void, user_data as *mut c_void),
+        }
+    }
+
+    /// Returns the object retained on behalf of this callback.
+    fn retained(&self) -> Option<Handle> {
+        match *self {
+            Callback::Event { event, .. } => Some(event),
+            Callback::Mem { .. } => None,
+            Callback::Program { program, .. } => Some(program),
+            Callback::SvmFree { queue, .. } => Some(queue),
+        }
+    }
+}
+
+
+pub struct Context {
+    pub devices: Vec<Handle>,
+    pub properties: Vec<cl_context_properties>,
+}
+
+pub struct Queue {
+    pub context: Handle,
+    pub device: Handle,
+    pub properties: cl_command_queue_properties,
+    /// The size of an on-device queue, in bytes.
+    pub size: Option<cl_uint>,
+    pub pending: VecDeque<Handle>,
+    pub barrier: Option<Handle>,
+}
+
+impl Queue {
+    pub fn is_out_of_order(&self) -> bool {
+        self.properties & CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE != 0
+    }
+
+    /// On-device queues can only be enqueued to by kernels.
+    pub fn is_on_device(&self) -> bool {
+        self.properties & CL_QUEUE_ON_DEVICE != 0
+    }
+}
+
+/// Memory object storage.
+pub enum Storage {
+    /// Allocated by the mock (pointer, length).
+    Owned(usize, usize),
+    /// Provided by the user (`CL_MEM_USE_HOST_PTR`).
+    Host(usize),
+    /// A region of the parent memory object starting at an offset.
+    Sub(usize),
+}
+
+impl Storage {
+    /// Allocates `len` zeroed bytes.
+    pub fn alloc(len: usize) -> Storage {
+        let ptr = unsafe { alloc::alloc_zeroed(Layout::from_size_align(len, MEM_ALIGN).unwrap()) };
+        assert!(!ptr.is_null(), "ocl_mock: Allocation of {} bytes failed.", len);
+        Storage::Owned(ptr as usize, len)
+    }
+}
+
+impl Drop for Storage {
+    fn drop(&mut self) {
+        if let Storage::Owned(ptr, len) = *self {
+            unsafe { alloc::dealloc(ptr as *mut u8, Layout::from_size_align(len, MEM_ALIGN).unwrap()) }
+        }
+    }
+}
+
+pub struct ImageInfo {
+    pub format: [cl_uint; 2],
+    pub image_type: cl_uint,
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub array_size: usize,
+    pub element_size: usize,
+    pub row_pitch: usize,
+    pub slice_pitch: usize,
+    pub buffer: Option<Handle>,
+}
+
+impl ImageInfo {
+    /// Returns the storage dimensions in pixels, rows, and slices.
+    pub fn dims(&self) -> [usize; 3] {
+        match self.image_type {
+            CL_MEM_OBJECT_IMAGE1D_ARRAY => [self.width, self.array_size, 1],
+            CL_MEM_OBJECT_IMAGE2D => [self.width, self.height, 1],
+            CL_MEM_OBJECT_IMAGE2D_ARRAY => [self.width, self.height, self.array_size],
+            CL_MEM_OBJECT_IMAGE3D => [self.width, self.height, self.depth],
+            _ => [self.width, 1, 1],
+        }
+    }
+
+    /// Returns the byte-space location of the pixel at `origin`.
+    pub fn loc(&self, mem: Handle, origin: [usize; 3]) -> Loc {
+        Loc::new(Place::Mem(mem), [origin[0] * self.element_size, origin[1], origin[2]],
+            self.row_pitch, self.slice_pitch)
+    }
+
+    /// Converts a pixel region to a byte region.
+    pub fn region(&self, region: [usize; 3]) -> [usize; 3] {
+        [region[0] * self.element_size, region[1], region[2]]
+    }
+}
+
+/// Pipe properties.
+///
+/// Pipe storage begins with a header (`PIPE_HEADER_SIZE` bytes: the index
+/// of the oldest packet followed by the number of packets) after which
+/// packets are stored in a ring.
+#[derive(Clone, Copy)]
+pub struct PipeInfo {
+    pub packet_size: cl_uint,
+    pub max_packets: cl_uint,
+}
+
+/// The size of the header at the start of pipe storage.
+pub const PIPE_HEADER_SIZE: usize = 2 * mem::size_of::<usize>();
+
+pub struct Mem {
+    pub context: Handle,
+    pub flags: cl_mem_flags,
+    pub size: usize,
+    pub host_ptr: usize,
+    pub storage: Storage,
+    pub parent: Option<Handle>,
+    pub image: Option<ImageInfo>,
+    pub pipe: Option<PipeInfo>,
+    pub map_count: cl_uint,
+    pub destructors: Vec<(MemCallbackFn, usize)>,
+}
+
+pub struct SubDevice {
+    pub parent: Handle,
+    pub compute_units: cl_uint,
+    /// The properties the sub-device was partitioned with.
+    pub partition: Vec<cl_device_partition_property>,
+}
+
+pub struct Sampler {
+    pub context: Handle,
+    pub normalized_coords: cl_bool,
+    pub addressing_mode: cl_uint,
+    pub filter_mode: cl_uint,
+}
+
+pub struct Program {
+    pub context: Handle,
+    pub source: String,
+    /// Preprocessed source of a compiled object, library or linked executable.
+    pub binary: String,
+    pub binary_type: cl_program_binary_type,
+    pub build_status: cl_build_status,
+    pub build_options: String,
+    pub build_log: String,
+    pub kernels: Vec<KernelSig>,
+    pub kernel_count: usize,
+    /// The SPIR-V module of a program created from IL (empty otherwise).
+    pub il: Vec<u8>,
+    /// Specialization constants set since the last build.
+    pub spec_constants: Vec<(cl_uint, Vec<u8>)>,
+    /// Specialization constants in effect for the built executable.
+    pub built_spec_constants: Vec<(cl_uint, Vec<u8>)>,
+}
+
+#[derive(Clone)]
+pub enum ArgValue {
+    Bytes(Vec<u8>),
+    Mem(Option<Handle>),
+    Local(usize),
+    /// A pointer into a shared virtual memory allocation.
+    Svm(usize),
+}
+
+/// A shared virtual memory allocation.
+pub struct Svm {
+    pub context: Handle,
+    pub storage: Storage,
+}
+
+pub struct Kernel {
+    pub context: Handle,
+    pub program: Handle,
+    pub sig: KernelSig,
+    pub args: Vec<Option<ArgValue>>,
+}
+
+/// Where a region of memory lives.
+#[derive(Clone, Copy)]
+pub enum Place {
+    Mem(Handle),
+    Host(usize),
+}
+
+/// A byte-space location: the first element of `origin` is in bytes, the
+/// second in rows and the third in slices.
+#[derive(Clone, Copy)]
+pub struct Loc {
+    pub place: Place,
+    pub origin: [usize; 3],
+    pub row_pitch: usize,
+    pub slice_pitch: usize,
+}
+
+impl Loc {
+    pub fn new(place: Place, origin: [usize; 3], row_pitch: usize, slice_pitch: usize) -> Loc {
+        Loc { place: place, origin: origin, row_pitch: row_pitch, slice_pitch: slice_pitch }
+    }
+
+    /// A contiguous location starting at `offset`.
+    pub fn linear(place: Place, offset: usize) -> Loc {
+        Loc::new(place, [offset, 0, 0], 0, 0)
+    }
+
+    /// Fills in unspecified (zero) pitches for a region.
+    pub fn pitched(mut self, region: [usize; 3]) -> Loc {
+        if self.row_pitch == 0 { self.row_pitch = region[0]; }
+        if self.slice_pitch == 0 { self.slice_pitch = self.row_pitch * region[1]; }
+        self
+    }
+
+    /// Returns the byte offset of the start of row `y` of slice `z`.
+    fn offset(&self, y: usize, z: usize) -> usize {
+        self.origin[0] + (self.origin[1] + y) * self.row_pitch
+            + (self.origin[2] + z) * self.slice_pitch
+    }
+
+    /// Returns the offset one past the last byte of `region`.
+    fn end(&self, region: [usize; 3]) -> usize {
+        self.offset(region[1] - 1, region[2] - 1) + region[0]
+    }
+}
+
+pub enum Command {
+    Copy { src: Loc, dst: Loc, region: [usize; 3] },
+    Fill { dst: Loc, region: [usize; 3], pattern: Vec<u8> },
+    Kernel { func: KernelFn, args: Vec<ArgValue>, spec_constants: Vec<(cl_uint, Vec<u8>)>,
+        work_dim: cl_uint, global_offset: [usize; 3], global_size: [usize; 3],
+        local_size: [usize; 3] },
+    /// A host function called with a copy of `args` in which each memory
+    /// object handle (at the given byte offset) is replaced with a pointer
+    /// to its memory.
+    Native { func: NativeKernelFn, args: Vec<u8>, mems: Vec<(usize, Handle)> },
+    /// Frees shared virtual memory allocations or, if a callback is given,
+    /// hands them to it to be freed.
+    SvmFree { queue: Handle, ptrs: Vec<usize>, callback: Option<(SvmFreeCallbackFn, usize)> },
+    Nop,
+}
+
+pub struct Event {
+    pub context: Handle,
+    pub queue: Option<Handle>,
+    pub command_type: cl_command_type,
+    pub status: cl_int,
+    pub callbacks: Vec<(cl_int, EventCallbackFn, usize)>,
+    /// Queued, submitted, started, and ended times.
+    pub times: [u64; 4],
+    pub command: Option<Command>,
+    pub wait_list: Vec<Handle>,
+    pub retained: Vec<Handle>,
+}
+
+
+pub enum Object {
+    SubDevice(SubDevice),
+    Context(Context),
+    Queue(Queue),
+    Mem(Mem),
+    Sampler(Sampler),
+    Program(Program),
+    Kernel(Kernel),
+    Event(Event),
+}
+
+/// An object type.
+pub trait Kind: Sized {
+    /// The error returned when a handle is not a valid object of this type.
+    const INVALID: cl_int;
+    fn from_obj(obj: &Object) -> Option<&Self>;
+    fn from_obj_mut(obj: &mut Object) -> Option<&mut Self>;
+}
+
+macro_rules! impl_kind {
+    ($($ty:ident => $invalid:ident,)*) => ($(
+        impl Kind for $ty {
+            const INVALID: cl_int = $invalid;
+
+            fn from_obj(obj: &Object) -> Option<&$ty> {
+                match *obj { Object::$ty(ref o) => Some(o), _ => None }
+            }
+
+            fn from_obj_mut(obj: &mut Object) -> Option<&mut $ty> {
+                match *obj { Object::$ty(ref mut o) => Some(o), _ => None }
+            }
+        }
+    )*)
+}
+
+impl_kind! {
+    SubDevice => CL_INVALID_DEVICE,
+    Context => CL_INVALID_CONTEXT,
+    Queue => CL_INVALID_COMMAND_QUEUE,
+    Mem => CL_INVALID_MEM_OBJECT,
+    Sampler => CL_INVALID_SAMPLER,
+    Program => CL_INVALID_PROGRAM,
+    Kernel => CL_INVALID_KERNEL,
+    Event => CL_INVALID_EVENT,
+}
+
+
+struct Entry {
+    refcount: cl_uint,
+    object: Object,
+    /// Objects retained by (and released along with) this one.
+    owners: Vec<Handle>,
+}
+
+pub struct State {
+    objects: BTreeMap<Handle, Entry>,
+    next_handle: Handle,
+    epoch: Instant,
+    callbacks: Vec<Callback>,
+    /// Shared virtual memory allocations by address.
+    svm: BTreeMap<usize, Svm>,
+}
+
+impl State {
+    fn new() -> State {
+        State {
+            objects: BTreeMap::new(),
+            next_handle: FIRST_HANDLE,
+            epoch: Instant::now(),
+            callbacks: Vec::new(),
+            svm: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of nanoseconds elapsed since the state was created.
+    pub fn now(&self) -> u64 {
+        let elapsed = self.epoch.elapsed();
+        elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64
+    }
+
+    /// Adds an object with a reference count of one, retaining `owners`.
+    pub fn insert(&mut self, object: Object, owners: Vec<Handle>) -> Handle {
+        let handle = self.next_handle;
+        self.next_handle += 0x10;
+
+        for &owner in &owners { self.retain_any(owner); }
+        self.objects.insert(handle, Entry { refcount: 1, object: object, owners: owners });
+        handle
+    }
+
+    pub fn get<T: Kind>(&self, handle: Handle) -> Result<&T, cl_int> {
+        self.objects.get(&handle).and_then(|e| T::from_obj(&e.object)).ok_or(T::INVALID)
+    }
+
+    pub fn get_mut<T: Kind>(&mut self, handle: Handle) -> Result<&mut T, cl_int> {
+        self.objects.get_mut(&handle).and_then(|e| T::from_obj_mut(&mut e.object)).ok_or(T::INVALID)
+    }
+
+    /// Returns the number of compute units of a root or sub-device.
+    pub fn compute_units(&self, device: Handle) -> Result<cl_uint, cl_int> {
+        if device == DEVICE {
+            Ok(COMPUTE_UNITS)
+        } else {
+            self.get::<SubDevice>(device).map(|sd| sd.compute_units)
+        }
+    }
+
+    pub fn refcount(&self, handle: Handle) -> cl_uint {
+        self.objects.get(&handle).map(|e| e.refcount).unwrap_or(0)
+    }
+
+    pub fn retain<T: Kind>(&mut self, handle: Handle) -> Result<(), cl_int> {
+        self.get::<T>(handle)?;
+        self.retain_any(handle);
+        Ok(())
+    }
+
+    pub fn release<T: Kind>(&mut self, handle: Handle) -> Result<(), cl_int> {
+        self.get::<T>(handle)?;
+        self.release_any(handle);
+        Ok(())
+    }
+
+    pub fn retain_any(&mut self, handle: Handle) {
+        if let Some(entry) = self.objects.get_mut(&handle) {
+            entry.refcount += 1;
+        }
+    }
+
+    pub fn release_any(&mut self, handle: Handle) {
+        let mut releases = vec![handle];
+
+        while let Some(handle) = releases.pop() {
+            let remove = match self.objects.get_mut(&handle) {
+                Some(entry) => {
+                    entry.refcount -= 1;
+                    entry.refcount == 0
+                },
+                None => false,
+            };
+
+            if remove {
+                let entry = self.objects.remove(&handle).unwrap();
+
+                match entry.object {
+                    Object::Mem(ref mem) => {
+                        // Destructor callbacks are called in reverse order:
+                        for &(func, user_data) in mem.destructors.iter().rev() {
+                            self.callbacks.push(Callback::Mem { func: func, mem: handle,
+                                user_data: user_data });
+                        }
+                    },
+                    Object::Kernel(ref kernel) => {
+                        if let Ok(program) = self.get_mut::<Program>(kernel.program) {
+                            program.kernel_count -= 1;
+                        }
+                    },
+                    _ => (),
+                }
+
+                releases.extend(entry.owners.iter().cloned());
+            }
+        }
+    }
+
+    /// Returns the number of live objects.
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Returns the address and size of the storage of a memory object.
+    pub fn mem_region(&self, handle: Handle) -> Result<(usize, usize), cl_int> {
+        let mem = self.get::<Mem>(handle)?;
+
+        let ptr = match mem.storage {
+            Storage::Owned(ptr, _) => ptr,
+            Storage::Host(ptr) => ptr,
+            Storage::Sub(offset) => {
+                let parent = mem.parent.expect("sub-buffer without parent");
+                self.mem_region(parent)?.0 + offset
+            },
+        };
+        Ok((ptr, mem.size))
+    }
+
+    /// Allocates `size` zeroed bytes of shared virtual memory, retaining
+    /// `context` until it is freed.
+    pub fn svm_alloc(&mut self, context: Handle, size: usize) -> usize {
+        let storage = Storage::alloc(size);
+        let ptr = match storage { Storage::Owned(ptr, _) => ptr, _ => unreachable!() };
+        self.retain_any(context);
+        self.svm.insert(ptr, Svm { context: context, storage: storage });
+        ptr
+    }
+
+    /// Frees the shared virtual memory allocation starting at `ptr`.
+    pub fn svm_free(&mut self, ptr: usize) -> Result<(), cl_int> {
+        let svm = self.svm.remove(&ptr).ok_or(CL_INVALID_VALUE)?;
+        self.release_any(svm.context);
+        Ok(())
+    }
+
+    /// Returns the base address and length of the shared virtual memory
+    /// allocation containing `ptr`.
+    fn svm_alloc_of(&self, ptr: usize) -> Result<(usize, usize, &Svm), cl_int> {
+        match self.svm.range(..ptr + 1).next_back() {
+            Some((&base, svm)) => match svm.storage {
+                Storage::Owned(_, len) if ptr < base + len => Ok((base, len, svm)),
+                _ => Err(CL_INVALID_VALUE),
+            },
+            None => Err(CL_INVALID_VALUE),
+        }
+    }
+
+    /// Returns `ptr` and the number of bytes between it and the end of the
+    /// shared virtual memory allocation containing it.
+    pub fn svm_region(&self, ptr: usize) -> Result<(usize, usize), cl_int> {
+        self.svm_alloc_of(ptr).map(|(base, len, _)| (ptr, base + len - ptr))
+    }
+
+    /// Returns the context of the shared virtual memory allocation
+    /// containing `ptr`.
+    pub fn svm_context(&self, ptr: usize) -> Result<Handle, cl_int> {
+        self.svm_alloc_of(ptr).map(|(_, _, svm)| svm.context)
+    }
+
+    /// Returns an error if `region` at `loc` extends past the end of a memory
+    /// object.
+    pub fn check_loc(&self, loc: &Loc, region: [usize; 3]) -> Result<(), cl_int> {
+        if region[0] == 0 || region[1] == 0 || region[2] == 0 { return Err(CL_INVALID_VALUE); }
+
+        if let Place::Mem(mem) = loc.place {
+            if loc.end(region) > self.mem_region(mem)?.1 { return Err(CL_INVALID_VALUE); }
+        }
+        Ok(())
+    }
+
+    /// Creates a new command event and adds it to the queue without running
+    /// anything (call `::progress` afterwards).
+    ///
+    /// `retained` objects are kept alive until the command completes.
+    pub fn enqueue(&mut self, queue: Handle, command_type: cl_command_type, command: Command,
+            mut wait_list: Vec<Handle>, retained: Vec<Handle>) -> Result<Handle, cl_int> {
+        let (context, out_of_order, barrier, pending) = {
+            let q = self.get::<Queue>(queue)?;
+            if q.is_on_device() { return Err(CL_INVALID_COMMAND_QUEUE); }
+            (q.context, q.is_out_of_order(), q.barrier, q.pending.clone())
+        };
+
+        for &event in &wait_list {
+            let ev = self.get::<Event>(event).map_err(|_| CL_INVALID_EVENT_WAIT_LIST)?;
+            if ev.context != context { return Err(CL_INVALID_CONTEXT); }
+        }
+
+        if out_of_order {
+            // Barriers (and markers with no wait list) wait on all previous
+            // commands and subsequent commands wait on barriers:
+            if command_type == CL_COMMAND_BARRIER ||
+                    (command_type == CL_COMMAND_MARKER && wait_list.is_empty()) {
+                wait_list.extend(pending);
+            }
+            if let Some(barrier) = barrier { wait_list.push(barrier); }
+        }
+
+        for &handle in wait_list.iter().chain(retained.iter()) {
+            self.retain_any(handle);
+        }
+
+        let now = self.now();
+        let event = self.insert(Object::Event(Event {
+            context: context,
+            queue: Some(queue),
+            command_type: command_type,
+            status: CL_QUEUED,
+            callbacks: Vec::new(),
+            times: [now, 0, 0, 0],
+            command: Some(command),
+            wait_list: wait_list,
+            retained: retained,
+        }), vec![queue, context]);
+
+        let q = self.get_mut::<Queue>(queue)?;
+        q.pending.push_back(event);
+
+        if out_of_order && command_type == CL_COMMAND_BARRIER {
+            let prev = mem::replace(&mut q.barrier, Some(event));
+            self.retain_any(event);
+            if let Some(prev) = prev { self.release_any(prev); }
+        }
+        Ok(event)
+    }
+
+    /// Creates a user event.
+    pub fn create_user_event(&mut self, context: Handle) -> Result<Handle, cl_int> {
+        self.get::<Context>(context)?;
+
+        Ok(self.insert(Object::Event(Event {
+            context: context,
+            queue: None,
+            command_type: CL_COMMAND_USER,
+            status: CL_SUBMITTED,
+            callbacks: Vec::new(),
+            times: [0; 4],
+            command: None,
+            wait_list: Vec::new(),
+            retained: Vec::new(),
+        }), vec![context]))
+    }
+
+    /// Sets the execution status of an event, queuing any callbacks
+    /// triggered by the change.
+    pub fn set_status(&mut self, event: Handle, status: cl_int) {
+        let triggered: Vec<_> = {
+            let ev = match self.get_mut::<Event>(event) {
+                Ok(ev) => ev,
+                Err(_) => return,
+            };
+            ev.status = status;
+            let (triggered, waiting) = ev.callbacks.drain(..)
+                .partition(|&(trigger, _, _)| status <= trigger);
+            ev.callbacks = waiting;
+            triggered
+        };
+
+        for (trigger, func, user_data) in triggered {
+            self.queue_event_callback(event, trigger, func, user_data);
+        }
+    }
+
+    /// Adds an event callback, queuing it immediately if the event has
+    /// already reached `trigger`.
+    pub fn add_event_callback(&mut self, event: Handle, trigger: cl_int, func: EventCallbackFn,
+            user_data: usize) -> Result<(), cl_int> {
+        let reached = {
+            let ev = self.get_mut::<Event>(event)?;
+            if ev.status <= trigger {
+                true
+            } else {
+                ev.callbacks.push((trigger, func, user_data));
+                false
+            }
+        };
+
+        if reached { self.queue_event_callback(event, trigger, func, user_data); }
+        Ok(())
+    }
+
+    fn queue_event_callback(&mut self, event: Handle, trigger: cl_int, func: EventCallbackFn,
+            user_data: usize) {
+        let status = self.get::<Event>(event).map(|ev| ev.status).unwrap_or(CL_INVALID_EVENT);
+        self.retain_any(event);
+        self.callbacks.push(Callback::Event { func: func, event: event,
+            status: if status < 0 { status } else { trigger }, user_data: user_data });
+    }
+
+    /// Queues a program (build) callback.
+    pub fn add_program_callback(&mut self, program: Handle, func: ProgramCallbackFn,
+            user_data: usize) {
+        self.retain_any(program);
+        self.callbacks.push(Callback::Program { func: func, program: program,
+            user_data: user_data });
+    }
+
+    /// Returns true if the event has completed (successfully or not).
+    pub fn is_done(&self, event: Handle) -> Result<bool, cl_int> {
+        self.get::<Event>(event).map(|ev| ev.status <= CL_COMPLETE)
+    }
+
+    /// Runs every command which is ready to run, repeating until nothing
+    /// more can be done.
+    pub fn progress(&mut self) {
+        loop {
+            let mut ran = false;
+
+            let queues: Vec<Handle> = self.objects.iter().filter_map(|(&h, e)| match e.object {
+                Object::Queue(ref q) if !q.pending.is_empty() => Some(h),
+                _ => None,
+            }).collect();
+
+            for queue in queues {
+                let (pending, out_of_order) = match self.get::<Queue>(queue) {
+                    Ok(q) => (q.pending.clone(), q.is_out_of_order()),
+                    Err(_) => continue,
+                };
+
+                for event in pending {
+                    let (failed, ready) = {
+                        let ev = self.get::<Event>(event).expect("pending event released");
+                        let statuses: Vec<cl_int> = ev.wait_list.iter().map(|&w| {
+                            self.get::<Event>(w).map(|w| w.status).unwrap_or(CL_INVALID_EVENT)
+                        }).collect();
+                        (statuses.iter().any(|&s| s < 0), statuses.iter().all(|&s| s == CL_COMPLETE))
+                    };
+
+                    if failed || ready {
+                        self.get_mut::<Queue>(queue).unwrap().pending.retain(|&e| e != event);
+                        self.execute(event, if failed {
+                            Some(CL_EXEC_STATUS_ERROR_FOR_EVENTS_IN_WAIT_LIST)
+                        } else {
+                            None
+                        });
+                        ran = true;
+                        break;
+                    } else if !out_of_order {
+                        break;
+                    }
+                }
+            }
+
+            if !ran { break; }
+        }
+    }
+
+    /// Executes the command associated with an event (or fails it with
+    /// `error`) then releases everything the command was holding on to.
+    fn execute(&mut self, event: Handle, error: Option<cl_int>) {
+        let (command, wait_list, retained, queue) = {
+            let ev = self.get_mut::<Event>(event).unwrap();
+            (ev.command.take(), mem::replace(&mut ev.wait_list, Vec::new()),
+                mem::replace(&mut ev.retained, Vec::new()), ev.queue)
+        };
+
+        let status = match error {
+            Some(err) => err,
+            None => {
+                let now = self.now();
+                self.get_mut::<Event>(event).unwrap().times[1] = now;
+                self.set_status(event, CL_SUBMITTED);
+                self.get_mut::<Event>(event).unwrap().times[2] = now;
+                self.set_status(event, CL_RUNNING);
+
+                let result = match command {
+                    Some(command) => self.run(command),
+                    None => Ok(()),
+                };
+
+                result.err().unwrap_or(CL_COMPLETE)
+            },
+        };
+
+        let now = self.now();
+        self.get_mut::<Event>(event).unwrap().times[3] = now;
+        self.set_status(event, status);
+
+        if let Some(queue) = queue {
+            let clear_barrier = match self.get_mut::<Queue>(queue) {
+                Ok(ref mut q) if q.barrier == Some(event) => {
+                    q.barrier = None;
+                    true
+                },
+                _ => false,
+            };
+            if clear_barrier { self.release_any(event); }
+        }
+
+        for handle in wait_list.into_iter().chain(retained.into_iter()) {
+            self.release_any(handle);
+        }
+
+        // Release the queue's reference:
+        self.release_any(event);
+    }
+
+    fn run(&mut self, command: Command) -> Result<(), cl_int> {
+        match command {
+            Command::Copy { src, dst, region } => {
+                let (src_ptr, dst_ptr) = (self.place_ptr(src.place)?, self.place_ptr(dst.place)?);
+
+                for z in 0..region[2] {
+                    for y in 0..region[1] {
+                        unsafe {
+                            ptr::copy((src_ptr + src.offset(y, z)) as *const u8,
+                                (dst_ptr + dst.offset(y, z)) as *mut u8, region[0]);
+                        }
+                    }
+                }
+                Ok(())
+            },
+            Command::Fill { dst, region, pattern } => {
+                let dst_ptr = self.place_ptr(dst.place)?;
+
+                for z in 0..region[2] {
+                    for y in 0..region[1] {
+                        let row = (dst_ptr + dst.offset(y, z)) as *mut u8;
+                        for x in 0..(region[0] / pattern.len()) {
+                            unsafe {
+                                ptr::copy_nonoverlapping(pattern.as_ptr(),
+                                    row.offset((x * pattern.len()) as isize), pattern.len());
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            },
+            Command::Kernel { func, args, spec_constants, work_dim, global_offset, global_size,
+                    local_size } => {
+                let mut data = Vec::with_capacity(args.len());
+
+                for arg in args {
+                    data.push(match arg {
+                        ArgValue::Bytes(bytes) => ArgData::Bytes(bytes),
+                        ArgValue::Mem(Some(mem)) => {
+                            let (ptr, len) = self.mem_region(mem)?;
+                            match self.get::<Mem>(mem)?.pipe {
+                                Some(pipe) => ArgData::Pipe { ptr: ptr,
+                                    packet_size: pipe.packet_size as usize,
+                                    max_packets: pipe.max_packets as usize },
+                                None => ArgData::Mem { ptr: ptr, len: len },
+                            }
+                        },
+                        ArgValue::Mem(None) => ArgData::Null,
+                        ArgValue::Local(size) => ArgData::Local(size),
+                        ArgValue::Svm(ptr) => {
+                            let (ptr, len) = self.svm_region(ptr)?;
+                            ArgData::Mem { ptr: ptr, len: len }
+                        },
+                    });
+                }
+
+                if kernel::run(&func, &data, &spec_constants, work_dim, global_offset, global_size,
+                        local_size) {
+                    Ok(())
+                } else {
+                    Err(CL_OUT_OF_RESOURCES)
+                }
+            },
+            Command::Native { func, mut args, mems } => {
+                for (offset, mem) in mems {
+                    let (ptr, _) = self.mem_region(mem)?;
+                    unsafe {
+                        ptr::write_unaligned(args.as_mut_ptr().offset(offset as isize)
+                            as *mut *mut c_void, ptr as *mut c_void);
+                    }
+                }
+
+                func(args.as_mut_ptr() as *mut c_void);
+                Ok(())
+            },
+            Command::SvmFree { queue, ptrs, callback } => {
+                match callback {
+                    Some((func, user_data)) => {
+                        self.retain_any(queue);
+                        self.callbacks.push(Callback::SvmFree { func: func, queue: queue,
+                            ptrs: ptrs, user_data: user_data });
+                        Ok(())
+                    },
+                    None => ptrs.into_iter().map(|ptr| self.svm_free(ptr)).collect(),
+                }
+            },
+            Command::Nop => Ok(()),
+        }
+    }
+
+    fn place_ptr(&self, place: Place) -> Result<usize, cl_int> {
+        match place {
+            Place::Mem(mem) => self.mem_region(mem).map(|(ptr, _)| ptr),
+            Place::Host(ptr) => Ok(ptr),
+        }
+    }
+}
+
+
+struct Shared {
+    state: Mutex<State>,
+    cvar: Condvar,
+}
+
+fn shared() -> &'static Shared {
+    static INIT: Once = Once::new();
+    static mut SHARED: *const Shared = 0 as *const Shared;
+
+    unsafe {
+        INIT.call_once(|| {
+            SHARED = Box::into_raw(Box::new(Shared {
+                state: Mutex::new(State::new()),
+                cvar: Condvar::new(),
+            }));
+        });
+        &*SHARED
+    }
+}
+
+fn lock() -> MutexGuard<'static, State> {
+    shared().state.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Calls `f` with the state locked then calls any callbacks queued in the
+/// process (after unlocking).
+pub fn with<F, R>(f: F) -> R where F: FnOnce(&mut State) -> R {
+    let (result, callbacks) = {
+        let mut state = lock();
+        let result = f(&mut state);
+        (result, mem::replace(&mut state.callbacks, Vec::new()))
+    };
+
+    run_callbacks(callbacks);
+    result
+}
+
+/// Wakes any waiting threads then calls `callbacks`, releasing the objects
+/// they retained (which may queue further callbacks).
+fn run_callbacks(mut callbacks: Vec<Callback>) {
+    shared().cvar.notify_all();
+
+    while !callbacks.is_empty() {
+        for callback in mem::replace(&mut callbacks, Vec::new()) {
+            callback.call();
+
+            if let Some(handle) = callback.retained() {
+                let mut state = lock();
+                state.release_any(handle);
+                callbacks.extend(state.callbacks.drain(..));
+            }
+        }
+    }
+}
+
+/// Blocks until `done` returns true (or an error).
+pub fn wait_until<F>(mut done: F) -> Result<(), cl_int>
+        where F: FnMut(&State) -> Result<bool, cl_int> {
+    let mut state = lock();
+
+    while !done(&state)? {
+        state = shared().cvar.wait(state).unwrap_or_else(|err| err.into_inner());
+    }
+    Ok(())
+}
+
+/// Returns the number of live OpenCL objects (excluding the platform and
+/// device).
+///
+/// Useful for detecting leaks: the count should return to its previous value
+/// once everything created by a test has been dropped and all commands have
+/// completed.
+pub fn live_object_count() -> usize {
+    lock().len()
+}
+
+/// Returns true if `object` (a context, queue, memory object, etc.) has not
+/// yet been released.
+///
+/// Handles are never reused, so unlike `live_object_count`, this is
+/// unaffected by objects created and released concurrently.
+pub fn is_live<T>(object: *mut T) -> bool {
+    lock().refcount(object as Handle) > 0
+}
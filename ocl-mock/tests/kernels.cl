@@ -0,0 +1,8 @@
+__kernel void mock_add(__global float* buffer, float scalar) {
+    buffer[get_global_id(0)] += scalar;
+}
+
+__kernel void mock_group_sum(__global const int* src, __local int* tmp,
+        __global int* sums) {
+    // Implemented by a registered closure.
+}
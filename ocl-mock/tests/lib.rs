@@ -0,0 +1,1363 @@
+extern crate futures;
+extern crate ocl;
+extern crate ocl_mock;
+#[macro_use]
+extern crate ocl_derive;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::thread;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime};
+use std::ffi::CString;
+use std::io::Write;
+use futures::Future;
+use ocl::{Platform, Device, Context, Queue, Program, Kernel, Buffer, Image, Event, EventList, RwVec,
+    ProQue, MemFlags, SvmKind, SvmVec, SvmBox, Pipe, SourceOrigin, KernelError,
+    KernelArgProblem, ArgInfoSource, ArgType, SpatialDims, LwsCache};
+use ocl::async::BufferSink;
+use ocl::enums::{PlatformInfo, DeviceInfo, DeviceInfoResult, DevicePartitionProperty, MemInfo,
+    MemInfoResult, MemObjectType, PipeInfo, PipeInfoResult, CommandQueueInfo,
+    CommandQueueInfoResult, QueuePriority, QueueThrottle, ProfilingInfo, KernelSubGroupInfo,
+    KernelSubGroupInfoResult, ImageChannelOrder, ImageChannelDataType};
+use ocl::core::Status;
+use ocl::prm::Float4;
+use ocl::error::ErrorKind;
+use ocl::ffi::{CL_OUT_OF_RESOURCES, CL_KERNEL_ARG_INFO_NOT_AVAILABLE};
+use ocl::flags::QUEUE_PROFILING_ENABLE;
+
+const LEN: usize = 1 << 10;
+
+const SRC: &'static str = r#"
+    __kernel void mock_add(__global float* buffer, float scalar) {
+        buffer[get_global_id(0)] += scalar;
+    }
+
+    __kernel void mock_group_sum(__global const int* src, __local int* tmp,
+            __global int* sums) {
+        // Implemented by a registered closure.
+    }
+"#;
+
+#[derive(KernelArgs)]
+struct MockAddArgs<'a> {
+    buffer: &'a Buffer<f32>,
+    scalar: f32,
+}
+
+#[derive(KernelArgs)]
+struct MismatchedArgs {
+    buffer: Buffer<i32>,
+    #[kernel_arg(name = "amount")]
+    scalar: f32,
+    extra: u32,
+}
+
+fn pro_que() -> ProQue {
+    ocl_mock::install().unwrap();
+
+    ocl_mock::register_kernel("mock_add", |wi| {
+        let idx = wi.global_id(0);
+        let val: f32 = wi.read(0, idx);
+        let scalar: f32 = wi.scalar(1);
+        wi.write(0, idx, val + scalar);
+    });
+
+    ProQue::builder().src(SRC).dims(LEN).build().unwrap()
+}
+
+
+#[test]
+fn platform_and_device() {
+    ocl_mock::install().unwrap();
+
+    let platform = Platform::default();
+    assert_eq!(platform.info(PlatformInfo::Name).unwrap().to_string(), ocl_mock::PLATFORM_NAME);
+
+    let device = Device::first(platform).unwrap();
+    assert_eq!(device.info(DeviceInfo::Name).unwrap().to_string(), ocl_mock::DEVICE_NAME);
+}
+
+#[test]
+fn device_partition() {
+    ocl_mock::install().unwrap();
+    let device = Device::first(Platform::default()).unwrap();
+
+    match device.info(DeviceInfo::PartitionProperties).unwrap() {
+        DeviceInfoResult::PartitionProperties(props) => {
+            assert!(props.contains(&DevicePartitionProperty::Equally));
+        },
+        _ => panic!("Unexpected 'DeviceInfoResult' variant."),
+    }
+
+    let sub_devices = device.partition_equally(2).unwrap();
+    assert_eq!(sub_devices.len(), 2);
+    assert_eq!(sub_devices[0].parent_device().unwrap(), Some(device));
+
+    let counts = device.partition_by_counts(&[1, 3]).unwrap();
+    match counts[1].info(DeviceInfo::MaxComputeUnits).unwrap() {
+        DeviceInfoResult::MaxComputeUnits(units) => assert_eq!(units, 3),
+        _ => panic!("Unexpected 'DeviceInfoResult' variant."),
+    }
+    assert!(device.partition_by_counts(&[4, 4]).is_err());
+
+    // Sub-devices work with contexts and `ProQue`:
+    let context = Context::builder().devices(&sub_devices).build().unwrap();
+    assert_eq!(context.devices(), vec![sub_devices[0].device(), sub_devices[1].device()]);
+
+    let pro_que = ProQue::builder().device(&counts[0]).src(SRC).dims(LEN).build().unwrap();
+    assert_eq!(pro_que.queue().device(), counts[0].device());
+
+    // The context retains its sub-devices:
+    let sub_device = sub_devices[1].device();
+    drop(sub_devices);
+    assert!(sub_device.name().is_ok());
+    drop(context);
+    assert!(sub_device.name().is_err());
+}
+
+#[test]
+fn buffer_round_trip() {
+    let pro_que = pro_que();
+    let vec_src: Vec<u32> = (0..LEN as u32).collect();
+
+    let buffer = Buffer::builder()
+        .queue(pro_que.queue().clone())
+        .flags(MemFlags::new().read_write().copy_host_ptr())
+        .len(LEN)
+        .host_data(&vec_src)
+        .build().unwrap();
+
+    let mut vec_dst = vec![0u32; LEN];
+    buffer.read(&mut vec_dst).enq().unwrap();
+    assert_eq!(vec_src, vec_dst);
+
+    buffer.cmd().fill(7, Some(LEN / 2)).enq().unwrap();
+    buffer.read(&mut vec_dst).enq().unwrap();
+    assert!(vec_dst[..LEN / 2].iter().all(|&v| v == 7));
+    assert_eq!(vec_dst[LEN / 2..], vec_src[LEN / 2..]);
+}
+
+#[test]
+fn kernel_closure() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+
+    let kernel = pro_que.create_kernel("mock_add").unwrap()
+        .arg_buf(&buffer)
+        .arg_scl(10.0f32);
+
+    unsafe { kernel.enq().unwrap(); }
+    unsafe { kernel.cmd().gwo(LEN / 2).gws(LEN / 2).enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec[..LEN / 2].iter().all(|&v| v == 10.0));
+    assert!(vec[LEN / 2..].iter().all(|&v| v == 20.0));
+}
+
+#[test]
+fn kernel_builder() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+
+    let kernel = pro_que.kernel_builder("mock_add")
+        .arg_buf_named("buffer", Some(&buffer))
+        .arg_scl(10.0f32)
+        .build().unwrap();
+
+    assert_eq!(kernel.named_arg_idx("buffer"), Some(0));
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 10.0));
+
+    // Every problem is reported at once:
+    let err = pro_que.kernel_builder("mock_group_sum")
+        .arg_buf(&buffer)
+        .arg_loc::<i32>(16)
+        .build().unwrap_err();
+
+    match *err.kind() {
+        ErrorKind::Kernel(KernelError::BuilderArgs { ref kernel, ref problems }) => {
+            assert_eq!(kernel, "mock_group_sum");
+            assert_eq!(problems, &[
+                KernelArgProblem::TypeMismatch { idx: 0, name: Some("src".to_owned()),
+                    specified: "buffer", declared: "int*".to_owned() },
+                KernelArgProblem::Unset { idx: 2, name: Some("sums".to_owned()) },
+            ]);
+        },
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+    assert!(err.to_string().contains("argument [2] ('sums') is unset"));
+
+    let err = pro_que.kernel_builder("mock_add")
+        .arg_buf(&buffer)
+        .arg_scl_named("scalar", Some(1.0f32))
+        .arg_scl_named("scalar", Some(2.0f32))
+        .build().unwrap_err();
+
+    match *err.kind() {
+        ErrorKind::Kernel(KernelError::BuilderArgs { ref problems, .. }) => {
+            assert_eq!(problems, &[
+                KernelArgProblem::Count { specified: 3, declared: 2 },
+                KernelArgProblem::DuplicateName { name: "scalar" },
+            ]);
+        },
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+
+    match *Kernel::builder().name("mock_add").build().unwrap_err().kind() {
+        ErrorKind::Kernel(KernelError::BuilderNoProgram) => (),
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+}
+
+#[test]
+fn kernel_args_derive() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+
+    let mut kernel = pro_que.create_kernel("mock_add").unwrap();
+    kernel.set_args(&MockAddArgs { buffer: &buffer, scalar: 2.0 }).unwrap();
+    unsafe { kernel.enq().unwrap(); }
+    kernel.set_args(&MockAddArgs { buffer: &buffer, scalar: 3.0 }).unwrap();
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 5.0));
+
+    let args = MismatchedArgs { buffer: pro_que.create_buffer::<i32>().unwrap(), scalar: 1.0,
+        extra: 0 };
+    let mut kernel = pro_que.create_kernel("mock_add").unwrap();
+    let err = kernel.set_args(&args).unwrap_err();
+
+    match *err.kind() {
+        ErrorKind::Kernel(KernelError::Args { ref kernel, args, ref problems }) => {
+            assert_eq!(kernel, "mock_add");
+            assert!(args.ends_with("::MismatchedArgs"));
+            assert_eq!(problems, &[
+                KernelArgProblem::TypeMismatch { idx: 0, name: Some("buffer".to_owned()),
+                    specified: "buffer", declared: "float*".to_owned() },
+                KernelArgProblem::NameMismatch { idx: 1, specified: "amount",
+                    declared: "scalar".to_owned() },
+                KernelArgProblem::Count { specified: 3, declared: 2 },
+            ]);
+        },
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+}
+
+#[test]
+fn kernel_dynamic_named_args() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+
+    // Names come from the kernel itself:
+    let mut kernel = pro_que.create_kernel("mock_add").unwrap();
+    assert_eq!(kernel.arg_name(0), Some("buffer"));
+    assert_eq!(kernel.arg_name(1), Some("scalar"));
+    assert_eq!(kernel.arg_name(2), None);
+
+    let scalar_name = String::from("scalar");
+    kernel.set_arg("buffer", &buffer).unwrap().set_arg(scalar_name, 2.0f32).unwrap();
+    unsafe { kernel.enq().unwrap(); }
+    kernel.set_arg(1, 3.0f32).unwrap();
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 5.0));
+
+    // Declared names are used when the kernel reports none by that name:
+    let mut kernel = pro_que.create_kernel("mock_add").unwrap()
+        .arg_buf(&buffer)
+        .arg_scl_named("amount", Some(1.0f32));
+    assert_eq!(kernel.named_arg_idx("amount"), Some(1));
+    kernel.set_arg("amount", 4.0f32).unwrap();
+
+    match *kernel.set_arg("nonexistent", 1.0f32).unwrap_err().kind() {
+        ErrorKind::Kernel(KernelError::UnknownArgName { ref kernel, ref name }) => {
+            assert_eq!(kernel, "mock_add");
+            assert_eq!(name, "nonexistent");
+        },
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+    assert!(kernel.set_arg("scalar", 1i32).is_err());
+    assert!(kernel.set_arg(2, 1.0f32).is_err());
+
+    // Declared names take precedence over those reported:
+    let kernel = pro_que.create_kernel("mock_add").unwrap()
+        .arg_buf_named("scalar", Some(&buffer))
+        .arg_scl(1.0f32);
+    assert_eq!(kernel.named_arg_idx("scalar"), Some(0));
+    assert_eq!(kernel.named_arg_idx("buffer"), Some(0));
+}
+
+#[test]
+fn kernel_arg_info_from_source() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+
+    let sig = pro_que.program().kernel_sig("mock_add").unwrap();
+    let args: Vec<_> = sig.args().iter().map(|a| (a.name(), a.type_name())).collect();
+    assert_eq!(args, [("buffer", "float*"), ("scalar", "float")]);
+    assert_eq!(pro_que.program().kernel_sigs().len(), 2);
+
+    // Argument info unavailable from the platform (type name and name for
+    // each argument) is taken from the source:
+    for _ in 0..4 {
+        ocl_mock::fail_next("clGetKernelArgInfo", CL_KERNEL_ARG_INFO_NOT_AVAILABLE);
+    }
+    let mut kernel = pro_que.create_kernel("mock_add").unwrap();
+    ocl_mock::clear_failures();
+
+    assert_eq!(kernel.arg_name(1), Some("scalar"));
+    assert!(kernel.set_arg("buffer", 1i32).is_err());
+    kernel.set_arg("buffer", &buffer).unwrap().set_arg("scalar", 1.0f32).unwrap();
+
+    let program = Program::builder()
+        .src(SRC)
+        .arg_info_source(ArgInfoSource::Source)
+        .build(pro_que.context()).unwrap();
+    assert_eq!(program.arg_info_source(), ArgInfoSource::Source);
+
+    let kernels = program.kernels().unwrap();
+    assert_eq!(kernels["mock_group_sum"].arg_name(2), Some("sums"));
+    assert!(Kernel::new("mock_add", &program).unwrap().set_arg(0, 1i32).is_err());
+}
+
+#[test]
+fn kernel_sig_conditional() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<i32>().unwrap();
+
+    let src = r#"
+        #ifdef USE_FLOAT
+        __kernel void scale(__global float* buffer) {}
+        #else
+        __kernel void scale(__global int* buffer) {}
+        #endif
+        __kernel void twice(__global float* buffer) {}
+        __kernel void twice(__global int* buffer) {}
+    "#;
+    let program = Program::builder().src(src).build(pro_que.context()).unwrap();
+    assert_eq!(program.kernel_sigs().len(), 4);
+    assert!(program.kernel_sigs()[0].is_conditional());
+    assert!(program.kernel_sig("scale").is_none());
+    assert!(program.kernel_sig("twice").is_none());
+
+    // Which definition was compiled is unknown so, when the platform does not
+    // report argument info, any argument type is accepted:
+    for _ in 0..2 {
+        ocl_mock::fail_next("clGetKernelArgInfo", CL_KERNEL_ARG_INFO_NOT_AVAILABLE);
+    }
+    let mut kernel = Kernel::new("scale", &program).unwrap();
+    ocl_mock::clear_failures();
+
+    assert_eq!(kernel.arg_name(0), None);
+    kernel.set_arg(0, &buffer).unwrap();
+}
+
+#[test]
+fn kernel_arg_qualifiers() {
+    let src = r#"
+        __kernel void mock_qualified(__constant float* lut, __local float* tmp,
+                read_only image2d_t img, __global float* out) {}
+    "#;
+    ocl_mock::install().unwrap();
+    let pro_que = ProQue::builder().src(src).dims(LEN).build().unwrap();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+
+    let image_builder = |image_type, flags| Image::<u8>::builder()
+        .channel_order(ImageChannelOrder::Rgba)
+        .channel_data_type(ImageChannelDataType::UnormInt8)
+        .image_type(image_type)
+        .dims((4, 4, 4))
+        .flags(flags)
+        .queue(pro_que.queue().clone())
+        .build().unwrap();
+    let image = image_builder(MemObjectType::Image2d, MemFlags::READ_ONLY);
+    let write_only = image_builder(MemObjectType::Image2d, MemFlags::WRITE_ONLY);
+    let image_3d = image_builder(MemObjectType::Image3d, MemFlags::READ_ONLY);
+
+    // One float more than the 64 KiB the mock allows for constant buffers:
+    let big = Buffer::<f32>::builder()
+        .queue(pro_que.queue().clone())
+        .len((1 << 14) + 1)
+        .build().unwrap();
+
+    let mut kernel = pro_que.create_kernel("mock_qualified").unwrap();
+    kernel.set_arg("lut", &buffer).unwrap()
+        .set_arg("img", &image).unwrap()
+        .set_arg("out", &buffer).unwrap();
+
+    let err = kernel.set_arg("tmp", &buffer).unwrap_err();
+    assert!(err.to_string().contains("'__local float*'"));
+    assert!(kernel.set_arg("out", &image).is_err());
+    assert!(kernel.set_arg("out", 1.0f32).is_err());
+    assert!(kernel.set_arg("img", &image_3d).is_err());
+
+    match *kernel.set_arg("img", &write_only).unwrap_err().kind() {
+        ErrorKind::Kernel(KernelError::InvalidArg { ref problem, .. }) => match *problem {
+            KernelArgProblem::AccessMismatch { idx: 2, ref declared, .. } => {
+                assert_eq!(declared, "read_only image2d_t");
+            },
+            ref problem => panic!("unexpected problem: {}", problem),
+        },
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+
+    match *kernel.set_arg("lut", &big).unwrap_err().kind() {
+        ErrorKind::Kernel(KernelError::InvalidArg { ref problem, .. }) => {
+            assert_eq!(problem, &KernelArgProblem::ConstantBufferTooLarge { idx: 0,
+                name: Some("lut".to_owned()), size: (1 << 16) + 4, max: 1 << 16 });
+        },
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+
+    // The builder reports every problem at once:
+    let err = pro_que.kernel_builder("mock_qualified")
+        .arg_buf(&big)
+        .arg_buf(&buffer)
+        .arg_img(&write_only)
+        .arg_img(&image)
+        .build().unwrap_err();
+
+    match *err.kind() {
+        ErrorKind::Kernel(KernelError::BuilderArgs { ref problems, .. }) => {
+            assert_eq!(problems.len(), 4);
+            match problems[0] {
+                KernelArgProblem::ConstantBufferTooLarge { idx: 0, .. } => (),
+                ref problem => panic!("unexpected problem: {}", problem),
+            }
+            assert_eq!(problems[1], KernelArgProblem::TypeMismatch { idx: 1,
+                name: Some("tmp".to_owned()), specified: "buffer",
+                declared: "__local float*".to_owned() });
+            match problems[2] {
+                KernelArgProblem::AccessMismatch { idx: 2, .. } => (),
+                ref problem => panic!("unexpected problem: {}", problem),
+            }
+            assert_eq!(problems[3], KernelArgProblem::TypeMismatch { idx: 3,
+                name: Some("out".to_owned()), specified: "image",
+                declared: "float*".to_owned() });
+        },
+        ref kind => panic!("unexpected error: {}", kind),
+    }
+
+    pro_que.kernel_builder("mock_qualified")
+        .arg_buf(&buffer)
+        .arg_loc::<f32>(16)
+        .arg_img(&image)
+        .arg_buf(&buffer)
+        .build().unwrap();
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, OclPrm)]
+#[repr(C)]
+struct Particle {
+    position: Float4,
+    mass: f32,
+    _pad: [u32; 3],
+}
+
+#[test]
+fn ocl_prm_derive() {
+    assert_eq!(Particle::CL_TYPEDEF, "typedef struct Particle {\n    float4 position;\n    \
+        float mass;\n    uint _pad[3];\n} Particle;\n");
+    assert_eq!(std::mem::size_of::<Particle>(), 32);
+
+    let src = format!("{}{}", Particle::CL_TYPEDEF,
+        "__kernel void mock_particles(__global Particle* particles, Particle p) {}");
+    ocl_mock::install().unwrap();
+    let pro_que = ProQue::builder().src(src).dims(LEN).build().unwrap();
+    let particles = pro_que.create_buffer::<Particle>().unwrap();
+
+    let particle = Particle { position: Float4::new(1.0, 2.0, 3.0, 0.0), mass: 4.0,
+        ..Default::default() };
+    particles.write(&vec![particle; LEN]).enq().unwrap();
+    let mut vec = vec![Particle::default(); LEN];
+    particles.read(&mut vec).enq().unwrap();
+    assert_eq!(vec[LEN - 1], particle);
+
+    pro_que.kernel_builder("mock_particles")
+        .arg_buf(&particles)
+        .arg_scl(particle)
+        .build().unwrap();
+}
+
+#[derive(KernelBindings)]
+#[kernels_src = "tests/kernels.cl"]
+struct MockKernels;
+
+#[test]
+fn kernel_bindings() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+
+    let mut mock_add = MockAdd::new(pro_que.program()).unwrap();
+    mock_add.set_buffer(&buffer).unwrap().set_scalar(4.0).unwrap();
+    unsafe { mock_add.cmd().queue(pro_que.queue()).gws(LEN).enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 4.0));
+
+    let mut group_sum = MockGroupSum::new(pro_que.program()).unwrap();
+    group_sum.set_tmp(16).unwrap();
+    assert_eq!(MockGroupSum::NAME, "mock_group_sum");
+    assert_eq!(group_sum.name().unwrap(), "mock_group_sum");
+    assert!(MockKernels::SRC.contains("__kernel void mock_group_sum"));
+}
+
+#[test]
+fn kernel_local_memory() {
+    ocl_mock::install().unwrap();
+    const GROUP_SIZE: usize = 16;
+
+    ocl_mock::register_kernel("mock_group_sum", |wi| {
+        let (lid, gid) = (wi.local_id(0), wi.global_id(0));
+        let val: i32 = wi.read(0, gid);
+        wi.write(1, lid, val);
+
+        // Work items within a group run in order:
+        if lid == wi.local_size(0) - 1 {
+            let sum = (0..wi.local_size(0)).map(|i| wi.read::<i32>(1, i)).sum::<i32>();
+            let group = wi.group_id(0);
+            wi.write(2, group, sum);
+        }
+    });
+
+    let pro_que = ProQue::builder().src(SRC).dims(LEN).build().unwrap();
+    let src = Buffer::builder().queue(pro_que.queue().clone()).len(LEN)
+        .flags(MemFlags::new().read_only().copy_host_ptr())
+        .host_data(&vec![1i32; LEN]).build().unwrap();
+    let sums = Buffer::<i32>::builder().queue(pro_que.queue().clone())
+        .len(LEN / GROUP_SIZE).build().unwrap();
+
+    let kernel = pro_que.create_kernel("mock_group_sum").unwrap()
+        .lws(GROUP_SIZE)
+        .arg_buf(&src)
+        .arg_loc::<i32>(GROUP_SIZE)
+        .arg_buf(&sums);
+
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut vec = vec![0i32; LEN / GROUP_SIZE];
+    sums.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == GROUP_SIZE as i32));
+}
+
+#[test]
+fn user_event_gating() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+    let kernel = pro_que.create_kernel("mock_add").unwrap()
+        .arg_buf(&buffer)
+        .arg_scl(1.0f32);
+
+    let user_event = Event::user(pro_que.context()).unwrap();
+    let mut wait_list = EventList::new();
+    wait_list.push(user_event.clone());
+
+    let mut kernel_event = Event::empty();
+    unsafe { kernel.cmd().ewait(&wait_list).enew(&mut kernel_event).enq().unwrap(); }
+    assert!(!kernel_event.is_complete().unwrap());
+
+    user_event.set_complete().unwrap();
+    assert!(kernel_event.is_complete().unwrap());
+
+    let mut vec = vec![0.0f32; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 1.0));
+}
+
+#[test]
+fn event_future_from_another_thread() {
+    let pro_que = pro_que();
+    let user_event = Event::user(pro_que.context()).unwrap();
+
+    let setter = user_event.clone();
+    let thread = thread::spawn(move || setter.set_complete().unwrap());
+
+    user_event.clone().wait().unwrap();
+    thread.join().unwrap();
+    assert!(user_event.is_complete().unwrap());
+}
+
+#[test]
+fn rw_vec_async() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+    let rw_vec = RwVec::from(vec![3.0f32; LEN]);
+
+    let write = buffer.write(&rw_vec).enq_async().unwrap();
+    write.wait().unwrap();
+
+    let kernel = pro_que.create_kernel("mock_add").unwrap()
+        .arg_buf(&buffer)
+        .arg_scl(1.0f32);
+    unsafe { kernel.enq().unwrap(); }
+
+    let read = buffer.read(&rw_vec).enq_async().unwrap();
+    let guard = read.wait().unwrap();
+    assert!(guard.iter().all(|&v| v == 4.0));
+}
+
+#[test]
+fn buffer_sink() {
+    let pro_que = pro_que();
+    let sink: BufferSink<f32> = BufferSink::new(pro_que.queue().clone(), LEN).unwrap();
+
+    {
+        let mut guard = sink.clone().write().wait().unwrap();
+        for (i, val) in guard.iter_mut().enumerate() { *val = i as f32; }
+    }
+    sink.clone().flush().enq().unwrap().wait().unwrap();
+
+    let mut vec = vec![0.0f32; LEN];
+    sink.buffer().read(&mut vec).queue(pro_que.queue()).enq().unwrap();
+    assert!(vec.iter().enumerate().all(|(i, &v)| v == i as f32));
+}
+
+#[test]
+fn error_injection() {
+    let pro_que = pro_que();
+
+    ocl_mock::fail_next("clCreateBuffer", CL_OUT_OF_RESOURCES);
+    let err = Buffer::<f32>::builder()
+        .queue(pro_que.queue().clone())
+        .flags(MemFlags::new().read_write())
+        .len(LEN)
+        .build().unwrap_err();
+    assert_eq!(err.api_status(), Some(Status::CL_OUT_OF_RESOURCES));
+
+    // Only the next call fails:
+    pro_que.create_buffer::<f32>().unwrap();
+}
+
+#[test]
+fn kernel_panic() {
+    ocl_mock::install().unwrap();
+    ocl_mock::register_kernel("mock_panic", |_| panic!("mock kernel failure"));
+
+    let src = "__kernel void mock_panic(__global int* buffer) { }";
+    let pro_que = ProQue::builder().src(src).dims(LEN).build().unwrap();
+    let buffer = pro_que.create_buffer::<i32>().unwrap();
+    let kernel = pro_que.create_kernel("mock_panic").unwrap().arg_buf(&buffer);
+
+    let mut event = Event::empty();
+    unsafe { kernel.cmd().enew(&mut event).enq().unwrap(); }
+    assert!(event.wait_for().is_err());
+}
+
+#[test]
+fn build_error() {
+    ocl_mock::install().unwrap();
+
+    let context = Context::builder().build().unwrap();
+    let src = "#error \"intentional\"\n__kernel void mock_broken() { }";
+    let err = Program::builder().src(src).build(&context).unwrap_err();
+    assert!(err.to_string().contains("intentional"));
+}
+
+#[test]
+fn build_diagnostics() {
+    ocl_mock::install().unwrap();
+
+    let context = Context::builder().build().unwrap();
+    let src_path = env::temp_dir().join(format!("ocl-mock-diagnostics-{}.cl", process::id()));
+    fs::File::create(&src_path).unwrap()
+        .write_all(b"__kernel void mock_a() { }\n  #error \"in file\"\n").unwrap();
+
+    let err = Program::builder()
+        .src_file(&src_path)
+        .src("__kernel void mock_b() { }\n#error \"in text\"")
+        .build(&context)
+        .unwrap_err();
+    fs::remove_file(&src_path).unwrap();
+
+    let diagnostics = match *err.kind() {
+        ErrorKind::ProgramBuild(ref err) => err.diagnostics().to_vec(),
+        _ => panic!("Unexpected error: {}", err),
+    };
+    assert_eq!(diagnostics.len(), 2);
+
+    // Locations are relative to the file or text each diagnostic came from:
+    assert_eq!(diagnostics[0].origin(), Some(&SourceOrigin::File(src_path.clone())));
+    assert_eq!((diagnostics[0].line(), diagnostics[0].column()), (Some(2), Some(3)));
+    assert_eq!(diagnostics[0].source_line(), Some("  #error \"in file\""));
+    assert_eq!(diagnostics[1].origin(), Some(&SourceOrigin::IncludeRawEof(0)));
+    assert_eq!((diagnostics[1].line(), diagnostics[1].column()), (Some(2), Some(1)));
+
+    assert!(err.to_string().contains(&format!("--> {}:2:3", src_path.display())));
+}
+
+#[test]
+fn build_async() {
+    ocl_mock::install().unwrap();
+
+    let context = Context::builder().build().unwrap();
+    let builds: Vec<_> = (0..4)
+        .map(|_| Program::builder().src(SRC).build_async(&context).unwrap())
+        .collect();
+
+    for program in futures::future::join_all(builds).wait().unwrap() {
+        assert_eq!(program.kernel_names().unwrap().len(), 2);
+    }
+
+    let src = "#error \"intentional\"\n__kernel void mock_broken() { }";
+    let build = Program::builder().src(src).build_async(&context).unwrap();
+    assert!(build.wait().unwrap_err().to_string().contains("intentional"));
+}
+
+#[test]
+fn compile_and_link() {
+    ocl_mock::install().unwrap();
+    ocl_mock::register_kernel("mock_scale", |wi| {
+        let idx = wi.global_id(0);
+        let val: f32 = wi.read(0, idx);
+        wi.write(0, idx, val * 2.0);
+    });
+
+    let context = Context::builder().build().unwrap();
+    let header = Program::with_source(&context,
+        &[CString::new("float scale(float x);").unwrap()]).unwrap();
+
+    let util = Program::builder()
+        .src("#include \"util.h\"\nfloat scale(float x) { return x * 2.0f; }")
+        .header("util.h", &header)
+        .compile(&context).unwrap();
+    let library = Program::link_library(&context, &[&util], None, CString::new("").unwrap())
+        .unwrap();
+    assert!(Kernel::new("mock_scale", &library).is_err());
+
+    let main = Program::builder()
+        .src("#include \"util.h\"\n__kernel void mock_scale(__global float* buffer) { }")
+        .header("util.h", &header)
+        .compile(&context).unwrap();
+    let program = Program::link(&context, &[&main, &library], None, CString::new("").unwrap())
+        .unwrap();
+
+    let queue = Queue::new(&context, context.devices()[0], None).unwrap();
+    let buffer = Buffer::builder().queue(queue.clone()).len(LEN)
+        .flags(MemFlags::new().read_write().copy_host_ptr())
+        .host_data(&vec![1.5f32; LEN]).build().unwrap();
+    let kernel = Kernel::new("mock_scale", &program).unwrap()
+        .queue(queue).gws(LEN).arg_buf(&buffer);
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 3.0));
+
+    // Missing headers and duplicate kernels are reported in the build log:
+    let err = Program::builder().src("#include \"missing.h\"").compile(&context).unwrap_err();
+    assert!(err.to_string().contains("'missing.h' file not found"));
+    let err = Program::link(&context, &[&main, &main], None, CString::new("").unwrap())
+        .unwrap_err();
+    assert!(err.to_string().contains("duplicate symbol 'mock_scale'"));
+}
+
+#[test]
+fn program_binaries() {
+    ocl_mock::install().unwrap();
+
+    let context = Context::builder().build().unwrap();
+    let program = Program::builder().src(SRC).build(&context).unwrap();
+    let binaries = program.binaries().unwrap();
+    assert_eq!(binaries.len(), program.devices().unwrap().len());
+    assert!(binaries.iter().all(|bin| bin.len() > 0));
+
+    let program = Program::builder().binaries(binaries).build(&context).unwrap();
+    assert_eq!(program.kernel_names().unwrap().len(), 2);
+
+    // The first build populates the cache and the second skips compilation:
+    let cache_dir = env::temp_dir().join(format!("ocl-mock-cache-{}", process::id()));
+    let builder = Program::builder().src(SRC).binary_cache(&cache_dir);
+    builder.clone().build(&context).unwrap();
+    assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), context.devices().len());
+
+    ocl_mock::fail_next("clCreateProgramWithSource", CL_OUT_OF_RESOURCES);
+    let program = builder.build(&context);
+    ocl_mock::clear_failures();
+    assert_eq!(program.unwrap().kernel_names().unwrap().len(), 2);
+
+    // Editing a header found through '-I' misses the cache:
+    let incl_dir = cache_dir.join("include");
+    fs::create_dir_all(&incl_dir).unwrap();
+    fs::write(incl_dir.join("cache.h"), "#define SCALE 1").unwrap();
+    let builder = Program::builder().src(format!("#include \"cache.h\"\n{}", SRC))
+        .cmplr_opt(format!("-I {}", incl_dir.display())).binary_cache(&cache_dir);
+    builder.clone().build(&context).unwrap();
+    builder.clone().build(&context).unwrap();
+    fs::write(incl_dir.join("cache.h"), "#define SCALE 2").unwrap();
+    builder.build(&context).unwrap();
+    let entries = fs::read_dir(&cache_dir).unwrap().filter(|e| e.as_ref().unwrap().path()
+        .is_file()).count();
+    fs::remove_dir_all(&cache_dir).unwrap();
+    assert_eq!(entries, context.devices().len() * 3);
+}
+
+/// Returns a SPIR-V module containing only what the mock reads: a kernel
+/// entry point named `name` and a specialization constant with `SpecId` 0.
+fn spirv_module(version: (u8, u8), name: &str) -> Vec<u8> {
+    let mut name_words: Vec<u32> = name.as_bytes().chunks(4).map(|c| c.iter().enumerate()
+        .fold(0, |w, (i, &b)| w | (b as u32) << (i * 8))).collect();
+    if name.len() % 4 == 0 { name_words.push(0); }
+
+    let mut words = vec![0x0723_0203, (version.0 as u32) << 16 | (version.1 as u32) << 8, 0, 4, 0];
+    words.extend(&[(3 + name_words.len() as u32) << 16 | 15, 6, 1]);
+    words.extend(name_words);
+    words.extend(&[4 << 16 | 71, 2, 1, 0]);
+    words.iter().flat_map(|w| (0..4).map(move |i| (w >> (i * 8)) as u8)).collect()
+}
+
+#[test]
+fn spirv_spec_constants() {
+    ocl_mock::install().unwrap();
+    ocl_mock::register_kernel("mock_spec", |wi| {
+        let scale: u32 = wi.spec_constant(0, 1);
+        assert_eq!(scale, 64);
+    });
+
+    let context = Context::builder().build().unwrap();
+    let queue = Queue::new(&context, context.devices()[0], None).unwrap();
+
+    // Loaded from a file and specialized:
+    let il_path = env::temp_dir().join(format!("ocl-mock-{}.spv", process::id()));
+    fs::write(&il_path, spirv_module((1, 2), "mock_spec")).unwrap();
+    let program = Program::builder().il_file(&il_path).spec_constant(0, 64u32)
+        .build(&context).unwrap();
+    fs::remove_file(&il_path).unwrap();
+    assert_eq!(program.kernel_names().unwrap(), vec!["mock_spec".to_owned()]);
+
+    let kernel = Kernel::new("mock_spec", &program).unwrap().queue(queue).gws(4);
+    let mut event = Event::empty();
+    unsafe { kernel.cmd().enew(&mut event).enq().unwrap(); }
+    event.wait_for().unwrap();
+
+    // Unknown specialization constant ids are rejected:
+    let err = Program::builder().il(spirv_module((1, 2), "mock_spec")).spec_constant(3, 1u8)
+        .build(&context).unwrap_err();
+    assert!(err.to_string().contains("CL_INVALID_SPEC_ID"));
+
+    // The device only advertises SPIR-V 1.0 through 1.2:
+    let err = Program::builder().il(spirv_module((1, 5), "mock_spec")).build(&context)
+        .unwrap_err();
+    assert!(err.to_string().contains("does not support SPIR-V 1.5"));
+
+    // Specialization constants require IL:
+    assert!(Program::builder().src(SRC).spec_constant(0, 1u32).build(&context).is_err());
+}
+
+#[test]
+fn program_kernels() {
+    let mut pro_que = pro_que();
+
+    let mut names = pro_que.kernel_names().unwrap();
+    names.sort();
+    assert_eq!(names, vec!["mock_add".to_owned(), "mock_group_sum".to_owned()]);
+
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+    {
+        let kernels = pro_que.kernels().unwrap();
+        assert_eq!(kernels.len(), 2);
+        assert_eq!(kernels["mock_group_sum"].num_args().unwrap(), 3);
+        kernels.get_mut("mock_add").unwrap().set_arg(0, &buffer).unwrap()
+            .set_arg(1, 5.0f32).unwrap();
+    }
+
+    // Kernels are created once, keeping their arguments:
+    let kernel = &pro_que.kernels().unwrap()["mock_add"];
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 5.0));
+
+    // Clones can be made while arguments are unset and create their own
+    // kernels:
+    let mut clone = pro_que.clone();
+    {
+        let kernels = clone.kernels().unwrap();
+        assert_eq!(kernels.len(), 2);
+        kernels.get_mut("mock_add").unwrap().set_arg(0, &buffer).unwrap()
+            .set_arg(1, 1.0f32).unwrap();
+        unsafe { kernels["mock_add"].enq().unwrap(); }
+    }
+    unsafe { pro_que.kernels().unwrap()["mock_add"].enq().unwrap(); }
+
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 11.0));
+}
+
+#[test]
+fn native_kernel() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+    let kernel = pro_que.create_kernel("mock_add").unwrap()
+        .arg_buf(&buffer)
+        .arg_scl(1.0f32);
+
+    // The native kernel waits on a user event and the kernel waits on it:
+    let user_event = Event::user(pro_que.context()).unwrap();
+    let mut native_event = Event::empty();
+    unsafe {
+        pro_que.queue().native_kernel(|mem| {
+            assert_eq!(mem.len(), 1);
+            for val in mem.get_mut::<f32>(0) { *val += 2.0; }
+        }).mem(&buffer).ewait(&user_event).enew(&mut native_event).enq().unwrap();
+
+        kernel.cmd().ewait(&native_event).enq().unwrap();
+    }
+    assert!(!native_event.is_complete().unwrap());
+
+    user_event.set_complete().unwrap();
+    let mut vec = vec![0.0f32; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 3.0));
+
+    // Panics are caught and reported:
+    let mut event = Event::empty();
+    let status = unsafe {
+        pro_que.queue().native_kernel(|_| panic!("native kernel"))
+            .enew(&mut event).enq().unwrap()
+    };
+    event.wait_for().unwrap();
+    assert!(status.panicked());
+    assert!(status.result().is_err());
+}
+
+#[test]
+fn buffer_on_release() {
+    let pro_que = pro_que();
+    let (tx, rx) = mpsc::channel();
+
+    // The allocation is handed back once the driver is done with it:
+    let host_data = vec![1.0f32; LEN];
+    let buffer = Buffer::builder().queue(pro_que.queue().clone()).len(LEN)
+        .flags(MemFlags::new().read_write().use_host_ptr())
+        .host_data(&host_data).build().unwrap();
+    buffer.on_release(move || tx.send(host_data).unwrap()).unwrap();
+
+    // Kernels hold on to their buffer arguments:
+    let kernel = pro_que.create_kernel("mock_add").unwrap().arg_buf(&buffer).arg_scl(1.0f32);
+    unsafe { kernel.enq().unwrap(); }
+    drop(buffer);
+    assert!(rx.try_recv().is_err());
+
+    drop(kernel);
+    let host_data = rx.try_recv().unwrap();
+    assert!(host_data.iter().all(|&v| v == 2.0));
+}
+
+#[test]
+fn svm() {
+    let pro_que = pro_que();
+    let queue = pro_que.queue();
+    assert_eq!(SvmKind::best_supported(&pro_que.context().devices()).unwrap(),
+        Some(SvmKind::FineGrainAtomics));
+
+    // Fine-grained memory is accessed directly (while no kernel is running):
+    let mut fine = SvmVec::<f32>::new(queue, LEN).unwrap();
+    unsafe {
+        assert!(fine.as_slice().unwrap().iter().all(|&v| v == 0.0));
+        fine.as_mut_slice().unwrap()[0] = 1.0;
+    }
+
+    let mut kernel = pro_que.create_kernel("mock_add").unwrap()
+        .arg_svm_named("buffer", Some(&fine))
+        .arg_scl(2.0f32);
+    unsafe {
+        kernel.enq().unwrap();
+        assert_eq!(fine.as_slice().unwrap()[0], 3.0);
+        assert!(fine.as_slice().unwrap()[1..].iter().all(|&v| v == 2.0));
+    }
+
+    // Coarse-grained memory must be mapped:
+    let mut coarse = SvmVec::<f32>::with_kind(queue, LEN, SvmKind::CoarseGrain).unwrap();
+    assert!(unsafe { coarse.as_slice() }.is_err());
+    coarse.write(&[5.0; LEN]).enq().unwrap();
+    kernel.set_arg_svm_named("buffer", Some(&coarse)).unwrap();
+    kernel.set_svm_indirect(vec![fine.svm_ref()]).unwrap();
+    unsafe { kernel.enq().unwrap(); }
+    {
+        let mut map = unsafe { coarse.map().enq().unwrap() };
+        assert!(map.iter().all(|&v| v == 7.0));
+        map[1] = 0.0;
+        map.unmap().enq().unwrap();
+    }
+
+    // Copies, fills, reads and writes:
+    let mut vec = vec![0.0f32; LEN];
+    coarse.read(&mut vec).enq().unwrap();
+    assert_eq!(&vec[..2], &[7.0, 0.0]);
+    coarse.copy(&mut fine).enq().unwrap();
+    assert_eq!(unsafe { &fine.as_slice().unwrap()[..2] }, &[7.0, 0.0]);
+    fine.fill(4.0).enq().unwrap();
+    fine.write(&[1.0, 2.0]).enq().unwrap();
+    assert_eq!(unsafe { &fine.as_slice().unwrap()[..3] }, &[1.0, 2.0, 4.0]);
+
+    // Copies between arrays of different lengths must specify a length:
+    let mut short = SvmVec::<f32>::new(queue, 2).unwrap();
+    assert!(fine.copy(&mut short).enq().is_err());
+    assert!(fine.copy(&mut short).len(3).enq().is_err());
+    fine.copy(&mut short).len(2).enq().unwrap();
+    assert!(coarse.read(&mut vec![0.0; LEN + 1]).enq().is_err());
+
+    let boxed = SvmBox::with_kind(queue, 9u32, SvmKind::CoarseGrain).unwrap();
+    assert_eq!(boxed.value().unwrap(), 9);
+    assert!(unsafe { boxed.get() }.is_err());
+}
+
+#[test]
+fn pipe() {
+    let src = r#"
+        __kernel void mock_produce(__global const float* src, __write_only pipe float out) {
+            write_pipe(out, &src[get_global_id(0)]);
+        }
+
+        __kernel void mock_consume(__read_only pipe float in, __global float* dst) {
+            read_pipe(in, &dst[get_global_id(0)]);
+        }
+    "#;
+    ocl_mock::install().unwrap();
+    let pro_que = ProQue::builder().src(src).dims(LEN).build().unwrap();
+
+    ocl_mock::register_kernel("mock_produce", |wi| {
+        let idx = wi.global_id(0);
+        let val: f32 = wi.read(0, idx);
+        assert!(wi.write_pipe(1, val));
+    });
+
+    ocl_mock::register_kernel("mock_consume", |wi| {
+        let idx = wi.global_id(0);
+        let val: f32 = wi.read_pipe(0).unwrap();
+        wi.write(1, idx, val);
+    });
+
+    let pipe = Pipe::<f32>::new(pro_que.context(), LEN as u32).unwrap();
+    assert_eq!(pipe.max_packets(), LEN as u32);
+    match pipe.info(PipeInfo::PacketSize).unwrap() {
+        PipeInfoResult::PacketSize(size) => assert_eq!(size, 4),
+        _ => panic!("Unexpected 'PipeInfoResult' variant."),
+    }
+    match pipe.mem_info(MemInfo::Type).unwrap() {
+        MemInfoResult::Type(ty) => assert_eq!(ty, MemObjectType::Pipe),
+        _ => panic!("Unexpected 'MemInfoResult' variant."),
+    }
+
+    let vals: Vec<f32> = (0..LEN).map(|i| i as f32).collect();
+    let src_buf = pro_que.create_buffer::<f32>().unwrap();
+    src_buf.write(&vals).enq().unwrap();
+    let dst_buf = pro_que.create_buffer::<f32>().unwrap();
+
+    // The producer and consumer are chained through the pipe:
+    let producer = pro_que.create_kernel("mock_produce").unwrap()
+        .arg_buf(&src_buf)
+        .arg_pipe(&pipe);
+    let consumer = pro_que.create_kernel("mock_consume").unwrap()
+        .arg_pipe_named("in", Some(&pipe))
+        .arg_buf(&dst_buf);
+
+    unsafe {
+        producer.enq().unwrap();
+        consumer.enq().unwrap();
+    }
+
+    let mut dst = vec![0.0f32; LEN];
+    dst_buf.read(&mut dst).enq().unwrap();
+    assert_eq!(dst, vals);
+
+    // Pipes are rejected where the packet type does not match:
+    let int_pipe = Pipe::<i32>::new(pro_que.context(), 4).unwrap();
+    let mut consumer = consumer;
+    assert!(consumer.set_arg_pipe_named("in", Some(&int_pipe)).is_err());
+    consumer.set_arg_pipe_named("in", Some(&pipe)).unwrap();
+
+    // Pipes and buffers are not interchangeable:
+    assert!(consumer.set_arg("in", &dst_buf).is_err());
+    assert!(consumer.set_arg(1, &pipe).is_err());
+
+    // Only the 'pipe' keyword itself marks a pipe:
+    assert!(ArgType::from_str("pipe float").unwrap().is_pipe());
+    assert!(!ArgType::from_str("pipeline_t").unwrap().is_pipe());
+}
+
+#[test]
+fn queue_builder() {
+    ocl_mock::install().unwrap();
+    let context = Context::builder().build().unwrap();
+
+    // Scheduling hints use the properties list:
+    let interactive = Queue::builder()
+        .priority(QueuePriority::High)
+        .throttle(QueueThrottle::Low)
+        .profiling()
+        .build(&context).unwrap();
+    match interactive.info(CommandQueueInfo::Properties).unwrap() {
+        CommandQueueInfoResult::Properties(props) => assert!(props.contains(QUEUE_PROFILING_ENABLE)),
+        _ => panic!("Unexpected 'CommandQueueInfoResult' variant."),
+    }
+    let buffer = Buffer::<u32>::builder().queue(interactive.clone()).len(LEN).fill_val(3)
+        .build().unwrap();
+    let mut vec = vec![0; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 3));
+
+    // On-device queues have a size and do not accept host commands:
+    let on_device = Queue::builder().on_device(true).size(1 << 16).build(&context).unwrap();
+    match on_device.info(CommandQueueInfo::Size).unwrap() {
+        CommandQueueInfoResult::Size(size) => assert_eq!(size, 1 << 16),
+        _ => panic!("Unexpected 'CommandQueueInfoResult' variant."),
+    }
+    assert!(buffer.read(&mut vec).queue(&on_device).enq().is_err());
+
+    // Hints cannot be given to on-device queues:
+    assert!(Queue::builder().on_device(false).priority(QueuePriority::Low)
+        .build(&context).is_err());
+
+    // Plain queues are unaffected:
+    let queue = Queue::builder().out_of_order().build(&context).unwrap();
+    assert!(queue.info(CommandQueueInfo::Size).is_err());
+}
+
+#[test]
+fn host_timers() {
+    ocl_mock::install().unwrap();
+    let context = Context::builder().build().unwrap();
+    let device = context.devices()[0];
+
+    let (device_ts, host_ts) = device.device_and_host_timer().unwrap();
+    assert!(device.host_timer().unwrap() >= host_ts);
+
+    // Profiling timestamps are placed on the host timeline:
+    let queue = Queue::new(&context, device, Some(QUEUE_PROFILING_ENABLE)).unwrap();
+    let before = Instant::now();
+    let mut event = Event::empty();
+    let buffer = Buffer::<u8>::builder().queue(queue.clone()).len(LEN).build().unwrap();
+    buffer.write(&vec![1; LEN]).enew(&mut event).enq().unwrap();
+    event.wait_for().unwrap();
+    let after = Instant::now();
+
+    let correlation = device.timer_correlation().unwrap();
+    assert!(correlation.device_timestamp() >= device_ts);
+    let end = event.profiling_info(ProfilingInfo::End).unwrap().time().unwrap();
+    let slop = Duration::from_millis(5);
+    let end_instant = correlation.instant(end);
+    assert!(end_instant + slop >= before && end_instant <= after + slop);
+    let end_time = correlation.system_time(end);
+    assert!(end_time <= SystemTime::now() + slop);
+}
+
+#[test]
+fn kernel_autotune() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+    let mut kernel = pro_que.kernel_builder("mock_add")
+        .arg_buf(&buffer)
+        .arg_scl(1.0f32)
+        .build().unwrap();
+
+    // Powers of two up to the mock's maximum work-group size:
+    let candidates = kernel.lws_candidates(pro_que.queue().device(), kernel.get_gws()).unwrap();
+    assert_eq!(candidates.len(), 9);
+    assert_eq!(candidates[0], SpatialDims::One(1));
+    assert_eq!(candidates[8], SpatialDims::One(256));
+
+    let lws = unsafe { kernel.autotune(&[]).unwrap() };
+    assert!(candidates.contains(&lws));
+    assert_eq!(kernel.get_lws(), lws);
+
+    // Sizes which do not divide the global work size fail to enqueue:
+    let lws = unsafe { kernel.autotune(&[SpatialDims::One(3), SpatialDims::One(64)]).unwrap() };
+    assert_eq!(lws, SpatialDims::One(64));
+    assert!(unsafe { kernel.autotune(&[SpatialDims::One(3)]) }.is_err());
+    assert!(unsafe { kernel.autotune(&[SpatialDims::Two(8, 8)]) }.is_err());
+
+    // ...even if the kernel would otherwise be split into several dispatches:
+    let mut kernel = kernel.non_uniform(true);
+    let lws = unsafe { kernel.autotune(&[SpatialDims::One(3), SpatialDims::One(64)]).unwrap() };
+    assert_eq!(lws, SpatialDims::One(64));
+    assert!(unsafe { kernel.autotune(&[SpatialDims::One(3)]) }.is_err());
+
+    let path = env::temp_dir().join(format!("ocl-mock-lws-{}.txt", process::id()));
+    let mut cache = LwsCache::load(&path).unwrap();
+    assert!(cache.is_empty());
+    cache.insert(&kernel, SpatialDims::One(32)).unwrap();
+    assert_eq!(unsafe { kernel.autotune_cached(&mut cache, &[]).unwrap() },
+        SpatialDims::One(32));
+    assert_eq!(kernel.get_lws(), SpatialDims::One(32));
+
+    // Entries are keyed by global work size:
+    let mut kernel = kernel.gws(LEN / 2);
+    assert_eq!(cache.get(&kernel).unwrap(), None);
+    let lws = unsafe { kernel.autotune_cached(&mut cache, &candidates[..4]).unwrap() };
+    assert_eq!(cache.get(&kernel).unwrap(), Some(lws));
+    assert_eq!(cache.len(), 2);
+
+    cache.save(&path).unwrap();
+    let loaded = LwsCache::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, cache);
+    assert_eq!(loaded.to_string().lines().count(), 2);
+}
+
+#[test]
+fn kernel_non_uniform() {
+    let src = "__kernel void mock_sizes(__global uint* global_sizes, \
+        __global uint* local_sizes) {}";
+    ocl_mock::install().unwrap();
+    ocl_mock::register_kernel("mock_sizes", |wi| {
+        let idx = wi.global_id(0);
+        let global_size = wi.global_size(0) as u32;
+        let local_size = wi.local_size(0) as u32;
+        wi.write(0, idx, global_size);
+        wi.write(1, idx, local_size);
+    });
+
+    // Programs compiled for OpenCL C 2.0 support non-uniform work-groups:
+    for &native in [false, true].iter() {
+        let opts = if native { "-cl-std=CL2.0" } else { "-cl-std=CL1.2" };
+        let pro_que = ProQue::builder()
+            .prog_bldr(Program::builder().src(src).cmplr_opt(opts))
+            .dims(LEN)
+            .build().unwrap();
+        let global_sizes = pro_que.create_buffer::<u32>().unwrap();
+        let local_sizes = pro_que.create_buffer::<u32>().unwrap();
+        let kernel = pro_que.kernel_builder("mock_sizes")
+            .arg_buf(&global_sizes)
+            .arg_buf(&local_sizes)
+            .gws(1000)
+            .lws(64)
+            .build().unwrap();
+
+        assert!(!kernel.is_non_uniform());
+        match unsafe { kernel.enq() } {
+            Ok(()) => assert!(native),
+            Err(err) => {
+                assert!(!native);
+                assert_eq!(err.api_status(), Some(Status::CL_INVALID_WORK_GROUP_SIZE));
+            },
+        }
+
+        let kernel = kernel.non_uniform(true);
+        let mut event = Event::empty();
+        unsafe { kernel.cmd().enew(&mut event).enq().unwrap(); }
+        event.wait_for().unwrap();
+
+        let mut global_vec = vec![0u32; LEN];
+        let mut local_vec = vec![0u32; LEN];
+        global_sizes.read(&mut global_vec).enq().unwrap();
+        local_sizes.read(&mut local_vec).enq().unwrap();
+
+        // Otherwise, the last 40 work items are enqueued separately:
+        let (main_global_size, rem_global_size) = if native { (1000, 1000) } else { (960, 40) };
+        assert!(global_vec[..960].iter().all(|&size| size == main_global_size));
+        assert!(global_vec[960..1000].iter().all(|&size| size == rem_global_size));
+        assert!(local_vec[..960].iter().all(|&size| size == 64));
+        assert!(local_vec[960..1000].iter().all(|&size| size == 40));
+        assert!(global_vec[1000..].iter().all(|&size| size == 0));
+
+        // Sizes which divide evenly are unaffected:
+        unsafe { kernel.cmd().gws(LEN).enq().unwrap(); }
+        global_sizes.read(&mut global_vec).enq().unwrap();
+        assert!(global_vec.iter().all(|&size| size == LEN as u32));
+
+        // A local work size of zero is an error rather than a panic (note
+        // that `SpatialDims::from(0)` itself panics):
+        let err = unsafe { kernel.cmd().lws(SpatialDims::One(0)).enq() }.unwrap_err();
+        assert_eq!(err.api_status(), Some(Status::CL_INVALID_WORK_GROUP_SIZE));
+    }
+
+    // Dispatches are checked before any are enqueued. The remainder of a
+    // kernel requiring a work-group size can not be enqueued so the main
+    // dispatch is not either:
+    let src = "__kernel __attribute__((reqd_work_group_size(64, 1, 1))) \
+        void mock_sizes(__global uint* global_sizes, __global uint* local_sizes) {}";
+    let pro_que = ProQue::builder().src(src).dims(LEN).build().unwrap();
+    let global_sizes = pro_que.create_buffer::<u32>().unwrap();
+    let local_sizes = pro_que.create_buffer::<u32>().unwrap();
+    let kernel = pro_que.kernel_builder("mock_sizes")
+        .arg_buf(&global_sizes)
+        .arg_buf(&local_sizes)
+        .gws(1000)
+        .lws(64)
+        .non_uniform(true)
+        .build().unwrap();
+
+    let mut event = Event::empty();
+    assert!(unsafe { kernel.cmd().enew(&mut event).enq() }.is_err());
+    assert!(event.is_empty());
+
+    let mut global_vec = vec![0u32; LEN];
+    global_sizes.read(&mut global_vec).enq().unwrap();
+    assert!(global_vec.iter().all(|&size| size == 0));
+}
+
+#[test]
+fn kernel_try_clone() {
+    let pro_que = pro_que();
+    let buffer = pro_que.create_buffer::<f32>().unwrap();
+    let other = pro_que.create_buffer::<f32>().unwrap();
+
+    let mut kernel = pro_que.create_kernel("mock_add").unwrap()
+        .arg_buf_named("buffer", Some(&buffer))
+        .arg_scl_named("scalar", Some(1.0f32));
+
+    // The copy keeps its arguments while the original is modified:
+    let copy = kernel.try_clone().unwrap();
+    assert!(copy.as_core().as_ptr() != kernel.as_core().as_ptr());
+    kernel.set_arg_buf_named("buffer", Some(&other)).unwrap()
+        .set_arg_scl_named("scalar", 5.0f32).unwrap();
+
+    unsafe { copy.enq().unwrap(); }
+    unsafe { kernel.enq().unwrap(); }
+
+    let mut vec = vec![0.0f32; LEN];
+    buffer.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 1.0));
+    other.read(&mut vec).enq().unwrap();
+    assert!(vec.iter().all(|&v| v == 5.0));
+
+    let device = pro_que.queue().device();
+    match kernel.sub_group_info(device, KernelSubGroupInfo::SubGroupCountForNdrange, &[20, 2])
+            .unwrap() {
+        KernelSubGroupInfoResult::SubGroupCountForNdrange(count) => assert_eq!(count, 5),
+        r => panic!("unexpected result: {}", r),
+    }
+    match kernel.sub_group_info(device, KernelSubGroupInfo::LocalSizeForSubGroupCount, &[4])
+            .unwrap() {
+        KernelSubGroupInfoResult::LocalSizeForSubGroupCount(size) => {
+            assert_eq!(size, [32, 1, 1])
+        },
+        r => panic!("unexpected result: {}", r),
+    }
+    assert!(kernel.sub_group_info(device, KernelSubGroupInfo::MaxSubGroupSizeForNdrange, &[])
+        .is_err());
+}
+
+#[test]
+fn objects_released() {
+    ocl_mock::install().unwrap();
+
+    let context = Context::builder().build().unwrap();
+    let queue = Queue::new(&context, context.devices()[0], None).unwrap();
+    let buffer = Buffer::<u8>::builder().queue(queue.clone()).len(LEN).build().unwrap();
+    let program = Program::builder().src(SRC).build(&context).unwrap();
+    let kernel = Kernel::new("mock_add", &program).unwrap();
+
+    // Other tests run concurrently so only this test's objects are checked:
+    let handles = [context.as_core().as_ptr(), queue.as_core().as_ptr(),
+        buffer.as_core().as_ptr(), program.as_core().as_ptr(), kernel.as_core().as_ptr()];
+    assert!(handles.iter().all(|&h| ocl_mock::is_live(h)));
+
+    drop(program);
+    assert!(ocl_mock::is_live(handles[3]), "program released before its kernel");
+    drop((kernel, buffer, queue, context));
+    assert!(handles.iter().all(|&h| !ocl_mock::is_live(h)));
+}
@@ -8,7 +8,7 @@ use futures::sync::oneshot::Canceled as OneshotCanceled;
 use futures::sync::mpsc::SendError;
 use core::error::{Error as OclCoreError};
 use core::Status;
-use standard::DeviceError;
+use standard::{DeviceError, ProgramBuildError, KernelError};
 use ::BufferCmdError;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -33,6 +33,10 @@ pub enum ErrorKind {
     BufferCmd(BufferCmdError),
     #[fail(display = "{}", _0)]
     Device(DeviceError),
+    #[fail(display = "{}", _0)]
+    ProgramBuild(ProgramBuildError),
+    #[fail(display = "{}", _0)]
+    Kernel(KernelError),
 }
 
 
@@ -158,6 +162,18 @@ impl From<DeviceError> for Error {
     }
 }
 
+impl From<ProgramBuildError> for Error {
+    fn from(err: ProgramBuildError) -> Error {
+        Error { inner: Context::new(ErrorKind::ProgramBuild(err)) }
+    }
+}
+
+impl From<KernelError> for Error {
+    fn from(err: KernelError) -> Error {
+        Error { inner: Context::new(ErrorKind::Kernel(err)) }
+    }
+}
+
 impl From<Error> for String {
     fn from(err: Error) -> String {
         err.to_string()
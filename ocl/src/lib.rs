@@ -53,6 +53,7 @@ extern crate futures;
 #[macro_use]
 extern crate failure;
 pub extern crate ocl_core as core;
+extern crate ocl_kernel_src;
 
 
 #[cfg(test)]
@@ -61,8 +62,15 @@ mod standard;
 pub mod error;
 pub mod async;
 
-pub use self::standard::{Platform, Device, Context, Program, Queue, Kernel, Buffer, Image, Event,
-    EventList, EventArray, Sampler, SpatialDims, ProQue, BufferCmdError};
+pub use self::standard::{Platform, Device, SubDevice, Context, Program, Queue, Kernel, Buffer,
+    Image, Event, EventList, EventArray, Sampler, SpatialDims, ProQue, BufferCmdError,
+    FutureProgram, ProgramBuildError, SourceDiagnostic, SourceOrigin, KernelError,
+    KernelArgProblem, ArgType, ArgKind, ArgIdxSpecifier, ArgInfoSource, KernelSig, ArgSig,
+    LwsCache, NativeKernelStatus};
+#[cfg(feature = "opencl_version_2_0")]
+pub use self::standard::{SvmKind, SvmRef, SvmVec, SvmBox, SvmMap, Pipe};
+#[cfg(feature = "opencl_version_2_1")]
+pub use self::standard::TimerCorrelation;
 pub use self::async::{MemMap, FutureMemMap, RwVec, ReadGuard, WriteGuard,
     FutureReadGuard, FutureWriteGuard};
 pub use error::{Error, Result};
@@ -124,7 +132,8 @@ pub mod prm {
 pub mod traits {
     //! Commonly used traits.
 
-    pub use standard::{WorkDims, MemLen, IntoMarker, IntoRawEventArray};
+    pub use standard::{WorkDims, MemLen, IntoMarker, IntoRawEventArray, KernelArgs, ArgVisitor,
+        KernelArgValue};
     pub use core::{OclPrm, OclScl, OclVec};
 }
 
@@ -133,9 +142,12 @@ pub mod builders {
 
     pub use standard::{ContextBuilder, BuildOpt, ProgramBuilder, ImageBuilder, ProQueBuilder,
         DeviceSpecifier, BufferCmdKind, BufferCmdDataShape, BufferCmd, BufferReadCmd,
-        BufferWriteCmd, BufferMapCmd, ImageCmdKind, ImageCmd, KernelCmd, BufferBuilder};
+        BufferWriteCmd, BufferMapCmd, ImageCmdKind, ImageCmd, KernelCmd, BufferBuilder,
+        NativeKernelCmd, NativeKernelMem, QueueBuilder, KernelBuilder};
+    #[cfg(feature = "opencl_version_2_0")]
+    pub use standard::{SvmMapCmd, SvmUnmapCmd, SvmCopyCmd, SvmFillCmd};
     pub use standard::{ClNullEventPtrEnum, ClWaitListPtrEnum};
-    pub use core::{ImageFormat, ImageDescriptor, ContextProperties};
+    pub use core::{ImageFormat, ImageDescriptor, ContextProperties, QueueProperties};
     // #[cfg(not(release))] pub use standard::BufferTest;
 }
 
@@ -151,8 +163,11 @@ pub mod flags {
             FP_ROUND_TO_INF, FP_FMA, FP_SOFT_FLOAT, FP_CORRECTLY_ROUNDED_DIVIDE_SQRT,
         // cl_device_exec_capabilities - bitfield
         DeviceExecCapabilities, EXEC_KERNEL, EXEC_NATIVE_KERNEL,
+        // cl_device_svm_capabilities - bitfield
+        DeviceSvmCapabilities,
         // cl_command_queue_properties - bitfield
         CommandQueueProperties, QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE, QUEUE_PROFILING_ENABLE,
+            QUEUE_ON_DEVICE, QUEUE_ON_DEVICE_DEFAULT,
         // cl_device_affinity_domain
         DeviceAffinityDomain, DEVICE_AFFINITY_DOMAIN_NUMA, DEVICE_AFFINITY_DOMAIN_L4_CACHE,
             DEVICE_AFFINITY_DOMAIN_L3_CACHE, DEVICE_AFFINITY_DOMAIN_L2_CACHE,
@@ -170,7 +185,7 @@ pub mod flags {
             PROGRAM_BINARY_TYPE_LIBRARY, PROGRAM_BINARY_TYPE_EXECUTABLE,
         // cl_kernel_arg_type_qualifer
         KernelArgTypeQualifier, KERNEL_ARG_TYPE_NONE, KERNEL_ARG_TYPE_CONST,
-            KERNEL_ARG_TYPE_RESTRICT, KERNEL_ARG_TYPE_VOLATILE,
+            KERNEL_ARG_TYPE_RESTRICT, KERNEL_ARG_TYPE_VOLATILE, KERNEL_ARG_TYPE_PIPE,
     };
 }
 
@@ -182,17 +197,20 @@ pub mod enums {
     // API enums.
     pub use core::{ImageChannelOrder, ImageChannelDataType, Cbool, Polling, PlatformInfo,
         DeviceInfo, DeviceMemCacheType, DeviceLocalMemType, ContextInfo, ContextProperty,
-        ContextInfoOrPropertiesPointerType, DevicePartitionProperty, CommandQueueInfo, ChannelType,
-        MemObjectType, MemInfo, ImageInfo, AddressingMode, FilterMode, SamplerInfo, ProgramInfo,
+        ContextInfoOrPropertiesPointerType, DevicePartitionProperty, CommandQueueInfo,
+        QueuePriority, QueueThrottle, ChannelType,
+        MemObjectType, MemInfo, ImageInfo, AddressingMode, FilterMode, SamplerInfo, PipeInfo,
+        ProgramInfo,
         ProgramBuildInfo, ProgramBuildStatus, KernelInfo, KernelArgInfo, KernelArgAddressQualifier,
-        KernelArgAccessQualifier, KernelWorkGroupInfo, EventInfo, CommandType,
+        KernelArgAccessQualifier, KernelWorkGroupInfo, KernelSubGroupInfo, EventInfo, CommandType,
         CommandExecutionStatus, BufferCreateType, ProfilingInfo};
 
     // Custom enums.
-    pub use core::{KernelArg, ContextPropertyValue, PlatformInfoResult, DeviceInfoResult,
+    pub use core::{KernelArg, ContextPropertyValue, DevicePartition, PlatformInfoResult, DeviceInfoResult,
         ContextInfoResult, CommandQueueInfoResult, MemInfoResult, ImageInfoResult,
-        SamplerInfoResult, ProgramInfoResult, ProgramBuildInfoResult, KernelInfoResult,
-        KernelArgInfoResult, KernelWorkGroupInfoResult, EventInfoResult, ProfilingInfoResult};
+        SamplerInfoResult, PipeInfoResult, ProgramInfoResult, ProgramBuildInfoResult,
+        KernelInfoResult, KernelArgInfoResult, KernelWorkGroupInfoResult,
+        KernelSubGroupInfoResult, EventInfoResult, ProfilingInfoResult, DiagnosticSeverity};
 
     // Error status.
     pub use core::Status;
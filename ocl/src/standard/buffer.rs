@@ -1732,6 +1732,22 @@ impl<T: OclPrm> Buffer<T> {
         &self.obj_core
     }
 
+    /// Registers a callback to be called once the underlying memory object
+    /// has been released by the driver.
+    ///
+    /// The memory object is released only after this buffer, all of its
+    /// clones and sub-buffers, and any kernels or commands using it have
+    /// released it. Host memory passed with `MemFlags::USE_HOST_PTR` may be
+    /// freed or reused from within the callback.
+    ///
+    /// The callback may be called from a driver thread and must not call
+    /// any OpenCL functions. A panic within it is caught and ignored.
+    pub fn on_release<F>(&self, callback: F) -> OclResult<()>
+            where F: FnOnce() + Send + 'static
+    {
+        super::set_mem_destructor(&self.obj_core, callback)
+    }
+
     /// Returns the memory flags used during the creation of this buffer.
     ///
     #[inline]
@@ -3,8 +3,11 @@
 use std;
 use std::ops::{Deref, DerefMut};
 use std::borrow::Borrow;
+#[cfg(feature = "opencl_version_2_1")]
+use std::time::{Duration, Instant, SystemTime};
 use ffi::cl_device_id;
-use core::{self, util, DeviceId as DeviceIdCore, DeviceType, DeviceInfo, DeviceInfoResult, ClDeviceIdPtr};
+use core::{self, util, DeviceId as DeviceIdCore, DeviceType, DeviceInfo, DeviceInfoResult, ClDeviceIdPtr,
+    DevicePartition, DeviceAffinityDomain};
 use core::error::{Error as OclCoreError, Result as OclCoreResult};
 use error::{Error as OclError, Result as OclResult};
 use standard::Platform;
@@ -239,6 +242,24 @@ impl<'a> From<&'a DeviceIdCore> for DeviceSpecifier {
     }
 }
 
+impl<'a> From<&'a SubDevice> for DeviceSpecifier {
+    fn from(sub_device: &'a SubDevice) -> DeviceSpecifier {
+        DeviceSpecifier::Single(sub_device.0)
+    }
+}
+
+impl<'a> From<&'a [SubDevice]> for DeviceSpecifier {
+    fn from(sub_devices: &'a [SubDevice]) -> DeviceSpecifier {
+        DeviceSpecifier::List(sub_devices.iter().map(|sd| sd.0).collect())
+    }
+}
+
+impl<'a> From<&'a Vec<SubDevice>> for DeviceSpecifier {
+    fn from(sub_devices: &'a Vec<SubDevice>) -> DeviceSpecifier {
+        DeviceSpecifier::from(sub_devices.as_slice())
+    }
+}
+
 impl From<DeviceType> for DeviceSpecifier {
     fn from(flags: DeviceType) -> DeviceSpecifier {
         DeviceSpecifier::TypeFlags(flags)
@@ -387,6 +408,44 @@ impl Device {
         }
     }
 
+    /// Partitions this device into as many sub-devices as possible, each
+    /// containing `compute_units` compute units.
+    pub fn partition_equally(&self, compute_units: u32) -> OclResult<Vec<SubDevice>> {
+        self.partition(&DevicePartition::Equally(compute_units))
+    }
+
+    /// Partitions this device into one sub-device for each entry in
+    /// `compute_unit_counts`, each containing the specified number of
+    /// compute units.
+    pub fn partition_by_counts(&self, compute_unit_counts: &[u32]) -> OclResult<Vec<SubDevice>> {
+        self.partition(&DevicePartition::ByCounts(compute_unit_counts.to_owned()))
+    }
+
+    /// Partitions this device into sub-devices which share the specified
+    /// level of the cache hierarchy or NUMA node.
+    pub fn partition_by_affinity(&self, domain: DeviceAffinityDomain)
+            -> OclResult<Vec<SubDevice>> {
+        self.partition(&DevicePartition::ByAffinityDomain(domain))
+    }
+
+    /// Partitions this device into sub-devices according to `partition`.
+    ///
+    /// [Version Controlled: OpenCL 1.2+]
+    pub fn partition(&self, partition: &DevicePartition) -> OclResult<Vec<SubDevice>> {
+        core::create_sub_devices(&self.0, partition, None)
+            .map(|sub_devices| sub_devices.into_iter().map(|d| SubDevice(Device(d))).collect())
+            .map_err(OclError::from)
+    }
+
+    /// Returns the device this device was partitioned from, if any.
+    pub fn parent_device(&self) -> OclCoreResult<Option<Device>> {
+        match self.info(DeviceInfo::ParentDevice) {
+            Ok(DeviceInfoResult::ParentDevice(r)) => Ok(r.map(Device)),
+            Err(err) => Err(OclCoreError::from(err)),
+            _ => panic!("Device::parent_device: Unexpected 'DeviceInfoResult' variant."),
+        }
+    }
+
     /// Returns the device name.
     pub fn name(&self) -> OclCoreResult<String> {
         core::get_device_info(&self.0, DeviceInfo::Name).map(|r| r.to_string())
@@ -424,6 +483,39 @@ impl Device {
         }
     }
 
+    /// Returns the current value of the host timer, as seen by this device,
+    /// in nanoseconds.
+    ///
+    /// [Version Controlled: OpenCL 2.1+]
+    #[cfg(feature = "opencl_version_2_1")]
+    pub fn host_timer(&self) -> OclCoreResult<u64> {
+        core::get_host_timer(&self.0, None)
+    }
+
+    /// Returns a reasonably synchronized pair of device and host timer
+    /// values, `(device, host)`, in nanoseconds.
+    ///
+    /// The device timer shares a timebase with `Event::profiling_info`.
+    ///
+    /// [Version Controlled: OpenCL 2.1+]
+    #[cfg(feature = "opencl_version_2_1")]
+    pub fn device_and_host_timer(&self) -> OclCoreResult<(u64, u64)> {
+        core::get_device_and_host_timer(&self.0, None)
+    }
+
+    /// Samples the device timer alongside the host clocks, returning a
+    /// `TimerCorrelation` which converts event profiling timestamps from
+    /// this device into `Instant`s and `SystemTime`s.
+    ///
+    /// Device and host clocks drift apart over time. Take a new correlation
+    /// periodically when converting timestamps from long running programs.
+    ///
+    /// [Version Controlled: OpenCL 2.1+]
+    #[cfg(feature = "opencl_version_2_1")]
+    pub fn timer_correlation(&self) -> OclCoreResult<TimerCorrelation> {
+        TimerCorrelation::new(self)
+    }
+
     /// Returns info about the device.
     pub fn info(&self, info_kind: DeviceInfo) -> OclCoreResult<DeviceInfoResult> {
         core::get_device_info(&self.0, info_kind)
@@ -576,3 +668,132 @@ impl DerefMut for Device {
         &mut self.0
     }
 }
+
+
+
+/// A sub-device, created by partitioning a `Device`.
+///
+/// Dereferences to `Device` and can be used anywhere a `Device` can. The
+/// underlying sub-device is retained when cloned and released when dropped.
+/// Contexts retain their devices, so a `Context` (or `ProQue`) created using
+/// a sub-device remains valid after the `SubDevice` has been dropped.
+///
+#[derive(Debug)]
+pub struct SubDevice(Device);
+
+impl SubDevice {
+    /// Returns the sub-device as a `Device`.
+    ///
+    /// The returned `Device` is not reference counted and must not be used
+    /// after this `SubDevice` (and any context using it) has been dropped.
+    pub fn device(&self) -> Device {
+        self.0
+    }
+}
+
+impl Clone for SubDevice {
+    fn clone(&self) -> SubDevice {
+        unsafe { core::retain_device(&(self.0).0, None).unwrap(); }
+        SubDevice(self.0)
+    }
+}
+
+impl Drop for SubDevice {
+    fn drop(&mut self) {
+        unsafe { core::release_device(&(self.0).0, None).unwrap(); }
+    }
+}
+
+impl Deref for SubDevice {
+    type Target = Device;
+
+    fn deref(&self) -> &Device {
+        &self.0
+    }
+}
+
+impl AsRef<Device> for SubDevice {
+    fn as_ref(&self) -> &Device {
+        &self.0
+    }
+}
+
+unsafe impl<'a> ClDeviceIdPtr for &'a SubDevice {
+    fn as_ptr(&self) -> cl_device_id {
+        (self.0).0.as_raw()
+    }
+}
+
+
+/// A correlation between a device timer and the host clocks, used to place
+/// event profiling timestamps on the host timeline.
+///
+/// Created with `Device::timer_correlation`.
+///
+/// ## Example
+///
+/// ```rust,ignore
+/// let correlation = queue.device().timer_correlation()?;
+/// let start = event.profiling_info(ProfilingInfo::Start)?.time()?;
+/// let started_at: Instant = correlation.instant(start);
+/// ```
+///
+/// [Version Controlled: OpenCL 2.1+]
+#[cfg(feature = "opencl_version_2_1")]
+#[derive(Clone, Copy, Debug)]
+pub struct TimerCorrelation {
+    device_timestamp: u64,
+    host_timestamp: u64,
+    instant: Instant,
+    system_time: SystemTime,
+}
+
+#[cfg(feature = "opencl_version_2_1")]
+impl TimerCorrelation {
+    /// Samples the timers of `device`.
+    pub fn new(device: &Device) -> OclCoreResult<TimerCorrelation> {
+        // Sample the host clocks on either side of the device timer and use
+        // the midpoint to minimize skew:
+        let before = Instant::now();
+        let system_time = SystemTime::now();
+        let (device_timestamp, host_timestamp) = device.device_and_host_timer()?;
+        let after = Instant::now();
+
+        Ok(TimerCorrelation {
+            device_timestamp: device_timestamp,
+            host_timestamp: host_timestamp,
+            instant: before + (after - before) / 2,
+            system_time: system_time + (after - before) / 2,
+        })
+    }
+
+    /// Returns the device timer value sampled, in nanoseconds.
+    pub fn device_timestamp(&self) -> u64 {
+        self.device_timestamp
+    }
+
+    /// Returns the host timer value sampled, in nanoseconds.
+    pub fn host_timestamp(&self) -> u64 {
+        self.host_timestamp
+    }
+
+    /// Converts a device timestamp in nanoseconds (such as those returned by
+    /// `Event::profiling_info`) into an `Instant`.
+    pub fn instant(&self, device_timestamp: u64) -> Instant {
+        if device_timestamp >= self.device_timestamp {
+            self.instant + Duration::from_nanos(device_timestamp - self.device_timestamp)
+        } else {
+            self.instant - Duration::from_nanos(self.device_timestamp - device_timestamp)
+        }
+    }
+
+    /// Converts a device timestamp in nanoseconds (such as those returned by
+    /// `Event::profiling_info`) into a `SystemTime`.
+    pub fn system_time(&self, device_timestamp: u64) -> SystemTime {
+        if device_timestamp >= self.device_timestamp {
+            self.system_time + Duration::from_nanos(device_timestamp - self.device_timestamp)
+        } else {
+            self.system_time - Duration::from_nanos(self.device_timestamp - device_timestamp)
+        }
+    }
+}
@@ -10,6 +10,7 @@ use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::marker::PhantomData;
 use core::error::{Result as OclCoreResult};
+use error::{Result as OclResult};
 use core::{self, OclPrm, Mem as MemCore, MemFlags, MemObjectType, ImageFormatParseResult,
     ImageFormat, ImageDescriptor, ImageInfo, ImageInfoResult, MemInfo, MemInfoResult,
     ImageChannelOrder, ImageChannelDataType, AsMem, MemCmdRw, MemCmdAll,
@@ -821,6 +822,22 @@ impl<T: OclPrm> Image<T> {
         &self.obj_core
     }
 
+    /// Registers a callback to be called once the underlying memory object
+    /// has been released by the driver.
+    ///
+    /// The memory object is released only after this image, all of its
+    /// clones, and any kernels or commands using it have released it. Host
+    /// memory passed with `MemFlags::USE_HOST_PTR` may be freed or reused
+    /// from within the callback.
+    ///
+    /// The callback may be called from a driver thread and must not call
+    /// any OpenCL functions. A panic within it is caught and ignored.
+    pub fn on_release<F>(&self, callback: F) -> OclResult<()>
+            where F: FnOnce() + Send + 'static
+    {
+        super::set_mem_destructor(&self.obj_core, callback)
+    }
+
 
     /// Format image info.
     fn fmt_info(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
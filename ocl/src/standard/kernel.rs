@@ -3,19 +3,72 @@
 use std;
 use std::ops::{Deref, DerefMut};
 use std::any::Any;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::fs::{self, File};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::collections::{HashMap, BTreeMap};
+use failure::Fail;
 use core::{self, OclPrm, Kernel as KernelCore, CommandQueue as CommandQueueCore, Mem as MemCore,
     KernelArg, KernelInfo, KernelInfoResult, KernelArgInfo, KernelArgInfoResult,
-    KernelWorkGroupInfo, KernelWorkGroupInfoResult, AsMem, MemCmdAll, ClVersions};
+    KernelWorkGroupInfo, KernelWorkGroupInfoResult, AsMem, MemCmdAll, ClVersions, MemInfo,
+    MemInfoResult, MemFlags, DeviceInfo, DeviceInfoResult, KernelArgAddressQualifier,
+    ProfilingInfo, ProgramBuildInfo, ProgramBuildInfoResult, OpenclVersion};
 use core::error::{Result as OclCoreResult, ErrorKind as OclCoreErrorKind};
 use error::{Error as OclError, Result as OclResult};
+use ocl_kernel_src;
 use standard::{SpatialDims, Program, Queue, WorkDims, Sampler, Device, ClNullEventPtrEnum,
-    ClWaitListPtrEnum};
-pub use self::arg_type::{BaseType, Cardinality, ArgType};
+    ClWaitListPtrEnum, Buffer, Image, KernelSig, ArgInfoSource, Event};
+#[cfg(feature = "opencl_version_2_0")]
+use standard::{SvmVec, SvmRef, Pipe};
+#[cfg(feature = "opencl_version_2_1")]
+use core::{KernelSubGroupInfo, KernelSubGroupInfoResult};
+pub use self::arg_type::{BaseType, Cardinality, ArgType, ArgKind};
+use core::{Char2, Char3, Char4, Char8, Char16, Uchar2, Uchar3, Uchar4, Uchar8, Uchar16,
+    Short2, Short3, Short4, Short8, Short16, Ushort2, Ushort3, Ushort4, Ushort8, Ushort16,
+    Int2, Int3, Int4, Int8, Int16, Uint2, Uint3, Uint4, Uint8, Uint16,
+    Long2, Long3, Long4, Long8, Long16, Ulong2, Ulong3, Ulong4, Ulong8, Ulong16,
+    Float2, Float3, Float4, Float8, Float16, Double2, Double3, Double4, Double8, Double16};
 
 const PRINT_DEBUG: bool = false;
 
+/// The number of timed runs of each candidate local work size during
+/// `Kernel::autotune`:
+const AUTOTUNE_RUNS: usize = 3;
+
+/// An argument index or name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArgIdxSpecifier {
+    Index(u32),
+    Name(String),
+}
+
+impl From<u32> for ArgIdxSpecifier {
+    fn from(idx: u32) -> ArgIdxSpecifier {
+        ArgIdxSpecifier::Index(idx)
+    }
+}
+
+impl<'a> From<&'a str> for ArgIdxSpecifier {
+    fn from(name: &'a str) -> ArgIdxSpecifier {
+        ArgIdxSpecifier::Name(name.to_owned())
+    }
+}
+
+impl From<String> for ArgIdxSpecifier {
+    fn from(name: String) -> ArgIdxSpecifier {
+        ArgIdxSpecifier::Name(name)
+    }
+}
+
+impl<'a> From<&'a String> for ArgIdxSpecifier {
+    fn from(name: &'a String) -> ArgIdxSpecifier {
+        ArgIdxSpecifier::Name(name.clone())
+    }
+}
+
+
 /// A kernel command builder used to queue a kernel with a mix of default
 /// and optionally specified arguments.
 #[must_use = "commands do nothing unless enqueued"]
@@ -25,6 +78,8 @@ pub struct KernelCmd<'k> {
     gwo: SpatialDims,
     gws: SpatialDims,
     lws: SpatialDims,
+    non_uniform: bool,
+    non_uniform_support: &'k Mutex<Option<bool>>,
     wait_events: Option<ClWaitListPtrEnum<'k>>,
     new_event: Option<ClNullEventPtrEnum<'k>>,
 }
@@ -62,6 +117,14 @@ impl<'k> KernelCmd<'k> {
         self
     }
 
+    /// Specifies whether or not the global work size may be other than a
+    /// multiple of the local work size for this call only (see
+    /// `Kernel::non_uniform`).
+    pub fn non_uniform(mut self, non_uniform: bool) -> KernelCmd<'k> {
+        self.non_uniform = non_uniform;
+        self
+    }
+
     /// Specifies a list of events to wait on before the command will run.
     pub fn ewait<'e, Ewl>(mut self, ewait: Ewl) -> KernelCmd<'k>
             where 'e: 'k, Ewl: Into<ClWaitListPtrEnum<'e>> {
@@ -105,8 +168,23 @@ impl<'k> KernelCmd<'k> {
                 core::get_kernel_info(self.kernel, KernelInfo::FunctionName)?);
         }
 
+        let lws = self.lws.to_work_size();
+
+        if let (true, Some(lws)) = (self.non_uniform, lws) {
+            // A local work size of zero is left for `enqueue_kernel` to
+            // reject (`CL_INVALID_WORK_GROUP_SIZE`):
+            let dims = &lws[..dim_count as usize];
+            let uneven = !dims.contains(&0) &&
+                dims.iter().zip(gws.iter()).any(|(&lws, &gws)| gws % lws != 0);
+
+            if uneven && !cached_non_uniform_support(self.non_uniform_support, self.kernel) {
+                return enqueue_split(queue, self.kernel, dim_count, self.gwo.to_work_offset(),
+                    gws, lws, self.wait_events, self.new_event);
+            }
+        }
+
         core::enqueue_kernel(queue, self.kernel, dim_count, self.gwo.to_work_offset(),
-            &gws, self.lws.to_work_size(), self.wait_events, self.new_event)
+            &gws, lws, self.wait_events, self.new_event)
             .map_err(OclError::from)
     }
 }
@@ -164,49 +242,132 @@ pub struct Kernel {
     obj_core: KernelCore,
     named_args: Option<HashMap<&'static str, u32>>,
     mem_args: Arc<Mutex<Vec<Option<MemCore>>>>,
+    #[cfg(feature = "opencl_version_2_0")]
+    svm_args: Arc<Mutex<Vec<Option<SvmRef>>>>,
+    #[cfg(feature = "opencl_version_2_0")]
+    svm_indirect: Arc<Mutex<Vec<SvmRef>>>,
     new_arg_count: u32,
     queue: Option<Queue>,
     gwo: SpatialDims,
     gws: SpatialDims,
     lws: SpatialDims,
+    /// Allows global work sizes which are not multiples of `lws`:
+    non_uniform: bool,
+    /// Whether non-uniform work-groups are supported natively, determined
+    /// when first needed:
+    non_uniform_support: Arc<Mutex<Option<bool>>>,
     num_args: u32,
     arg_types: Vec<ArgType>,
+    /// Argument names reported by the platform (OpenCL 1.2+):
+    arg_names: Vec<Option<String>>,
     /// Bypasses argument type check if true:
     bypass_arg_check: bool,
+    /// The smallest `MaxConstantBufferSize` of the program's devices if any
+    /// argument is `__constant`:
+    max_constant_buffer_size: Option<u64>,
+    /// `KernelArgs::type_name`s of argument structs already verified:
+    verified_args: Vec<&'static str>,
 }
 
 
 impl Kernel {
+    /// Returns a new `KernelBuilder`.
+    ///
+    /// This is the preferred way to create a kernel with arguments as all
+    /// argument problems are reported by `KernelBuilder::build` rather than
+    /// by a panic.
+    pub fn builder<'b>() -> KernelBuilder<'b> {
+        KernelBuilder::new()
+    }
+
     /// Returns a new kernel.
+    ///
+    /// Argument names and types are taken from the platform or from the
+    /// program source according to the program's `ArgInfoSource` (see
+    /// `ProgramBuilder::arg_info_source`).
     pub fn new<S: AsRef<str>>(name: S, program: &Program) -> OclResult<Kernel> {
-        // let name = name.into();
+        let sig = program.kernel_sig(name.as_ref());
         let obj_core = core::create_kernel(program, name)?;
+        Kernel::from_core_and_sig(obj_core, sig, program.arg_info_source())
+    }
+
+    /// Returns a new kernel from a pre-created core kernel.
+    ///
+    /// Argument names and types are taken from the platform only. Prefer
+    /// `::new` or `Program::kernels` to create a new `Kernel`.
+    pub fn from_core(obj_core: KernelCore) -> OclResult<Kernel> {
+        Kernel::from_core_and_sig(obj_core, None, ArgInfoSource::Metadata)
+    }
 
+    /// Returns a new kernel from a pre-created core kernel, taking argument
+    /// names and types from `sig` (parsed from the program source) when
+    /// preferred by `source` or when the platform does not report them.
+    pub(crate) fn from_core_and_sig(obj_core: KernelCore, sig: Option<&KernelSig>,
+            source: ArgInfoSource) -> OclResult<Kernel> {
         let num_args = match core::get_kernel_info(&obj_core, KernelInfo::NumArgs) {
             Ok(KernelInfoResult::NumArgs(num)) => num,
             Err(err) => return Err(OclError::from(err)),
             _=> unreachable!(),
         };
 
+        // Ignore signatures which could not have been parsed correctly:
+        let sig = match sig {
+            Some(sig) if sig.args().len() == num_args as usize => Some(sig),
+            _ => None,
+        };
+
         let mut arg_types = Vec::with_capacity(num_args as usize);
+        let mut arg_names = Vec::with_capacity(num_args as usize);
         let mut bypass_arg_check = false;
 
-        // Cache argument types for later use, bypassing if the OpenCL version
-        // is too low (v1.1).
+        // Cache argument types and names for later use, bypassing checks
+        // (with types of `ArgType::unknown()`) if the OpenCL version is too
+        // low (v1.1) and no signature is available.
         for arg_idx in 0..num_args {
+            let arg_sig = sig.map(|sig| &sig.args()[arg_idx as usize]);
+
+            if let (Some(arg_sig), ArgInfoSource::Source) = (arg_sig, source) {
+                arg_types.push(ArgType::from_sig(arg_sig)?);
+                arg_names.push(Some(arg_sig.name().to_owned()));
+                continue;
+            }
+
             let arg_type = match ArgType::from_kern_and_idx(&obj_core, arg_idx) {
+                Ok(ref at) if at.is_unknown() && arg_sig.is_some() => {
+                    ArgType::from_sig(arg_sig.unwrap())?
+                },
                 Ok(at) => at,
                 Err(e) => {
                     if let OclCoreErrorKind::VersionLow { .. } = *e.kind() {
-                        bypass_arg_check = true;
-                        break;
+                        match arg_sig {
+                            Some(arg_sig) => ArgType::from_sig(arg_sig)?,
+                            None => {
+                                bypass_arg_check = true;
+                                ArgType::unknown()?
+                            },
+                        }
+                    } else {
+                        return Err(OclError::from(e));
                     }
-                    return Err(OclError::from(e));
                 },
             };
             arg_types.push(arg_type);
+
+            // Names are unavailable unless the program was built with
+            // `-cl-kernel-arg-info` on some platforms:
+            arg_names.push(match arg_info(&obj_core, arg_idx, KernelArgInfo::Name) {
+                Ok(KernelArgInfoResult::Name(ref name)) if !name.is_empty() => Some(name.clone()),
+                _ => arg_sig.map(|arg_sig| arg_sig.name().to_owned()),
+            });
         }
 
+        let max_constant_buffer_size = if arg_types.iter()
+                .any(|at| at.address() == Some(KernelArgAddressQualifier::Constant)) {
+            max_constant_buffer_size(&obj_core)?
+        } else {
+            None
+        };
+
         let mem_args = vec![None; num_args as usize];
 
         Ok(Kernel {
@@ -214,13 +375,22 @@ impl Kernel {
             named_args: None,
             new_arg_count: 0,
             mem_args: Arc::new(Mutex::new(mem_args)),
+            #[cfg(feature = "opencl_version_2_0")]
+            svm_args: Arc::new(Mutex::new(vec![None; num_args as usize])),
+            #[cfg(feature = "opencl_version_2_0")]
+            svm_indirect: Arc::new(Mutex::new(Vec::new())),
             queue: None,
             gwo: SpatialDims::Unspecified,
             gws: SpatialDims::Unspecified,
             lws: SpatialDims::Unspecified,
+            non_uniform: false,
+            non_uniform_support: Arc::new(Mutex::new(None)),
             num_args: num_args,
             arg_types: arg_types,
+            arg_names: arg_names,
             bypass_arg_check,
+            max_constant_buffer_size: max_constant_buffer_size,
+            verified_args: Vec::new(),
         })
     }
 
@@ -262,6 +432,37 @@ impl Kernel {
         self
     }
 
+    /// Allows the global work size to be other than a multiple of the local
+    /// work size (builder-style).
+    ///
+    /// OpenCL 1.x requires each dimension of the global work size to be a
+    /// multiple of the local work size. When enabled, kernels with such a
+    /// global work size are enqueued as a main dispatch covering the largest
+    /// multiple of the local work size in each dimension followed by
+    /// dispatches for the remaining work items, placed using global work
+    /// offsets. Each dispatch waits on the one before it and the event
+    /// returned through `enew` is that of the last dispatch. It completes
+    /// after all of them but its profiling information (see
+    /// `Event::profiling_info`) covers the last dispatch alone.
+    ///
+    /// Kernels for which `get_global_id` is used are unaffected but
+    /// `get_global_size`, `get_local_size` and `get_num_groups` reflect the
+    /// dispatch each work item is part of.
+    ///
+    /// On OpenCL 2.0+ devices running programs compiled for OpenCL C 2.0+
+    /// (`-cl-std=CL2.0`, without `-cl-uniform-work-group-size`), which
+    /// support non-uniform work-groups natively, a single dispatch is used.
+    /// Support is optional on OpenCL 3.0 devices
+    /// (`DeviceInfo::NonUniformWorkGroupSupport`). Whether every device of
+    /// the kernel's program supports them is determined once, the first time
+    /// a global work size is uneven.
+    ///
+    /// Superseded if specified while building a queue command with `::cmd`.
+    pub fn non_uniform(mut self, non_uniform: bool) -> Kernel {
+        self.non_uniform = non_uniform;
+        self
+    }
+
     /// Adds a new argument to the kernel specifying the buffer object represented
     /// by 'buffer' (builder-style). Argument is added to the bottom of the argument
     /// order.
@@ -370,11 +571,62 @@ impl Kernel {
         self
     }
 
+    /// Adds a new argument to the kernel specifying the shared virtual
+    /// memory array represented by 'svm_vec' (builder-style). Argument is
+    /// added to the bottom of the argument order.
+    ///
+    /// The array is kept alive for the life of this kernel.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn arg_svm<T>(mut self, svm_vec: &SvmVec<T>) -> Kernel
+            where T: OclPrm + 'static {
+        self.new_arg_svm(Some(svm_vec));
+        self
+    }
+
+    /// Adds a new named argument specifying the shared virtual memory array
+    /// represented by 'svm_vec' (builder-style). Argument is added to the
+    /// bottom of the argument order.
+    ///
+    /// Named arguments can be easily modified later using `::set_arg_svm_named()`.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn arg_svm_named<T>(mut self, name: &'static str, svm_vec_opt: Option<&SvmVec<T>>)
+            -> Kernel
+            where T: OclPrm + 'static {
+        let arg_idx = self.new_arg_svm(svm_vec_opt);
+        self.insert_named_arg(name, arg_idx);
+        self
+    }
+
+    /// Adds a new argument to the kernel specifying the pipe object
+    /// represented by 'pipe' (builder-style). Argument is added to the bottom
+    /// of the argument order.
+    ///
+    /// The kernel parameter must be declared as a `pipe` of `T`.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn arg_pipe<T>(mut self, pipe: &Pipe<T>) -> Kernel
+            where T: OclPrm + 'static {
+        self.new_arg_pipe(Some(pipe));
+        self
+    }
+
+    /// Adds a new named argument specifying the pipe object represented by
+    /// 'pipe' (builder-style). Argument is added to the bottom of the
+    /// argument order.
+    ///
+    /// Named arguments can be easily modified later using `::set_arg_pipe_named()`.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn arg_pipe_named<T>(mut self, name: &'static str, pipe_opt: Option<&Pipe<T>>) -> Kernel
+            where T: OclPrm + 'static {
+        let arg_idx = self.new_arg_pipe(pipe_opt);
+        self.insert_named_arg(name, arg_idx);
+        self
+    }
+
     /// Modifies the kernel argument named: `name`.
     ///
     /// ## Panics [FIXME]
     // [FIXME]: CHECK THAT NAME EXISTS AND GIVE A BETTER ERROR MESSAGE
-    pub fn set_arg_scl_named<'a, T>(&'a mut self, name: &'static str, scalar: T)
+    pub fn set_arg_scl_named<'a, T>(&'a mut self, name: &str, scalar: T)
             -> OclResult<&'a mut Kernel>
             where T: OclPrm + 'static {
         let arg_idx = self.resolve_named_arg_idx(name)?;
@@ -386,7 +638,7 @@ impl Kernel {
     ///
     /// ## Panics [FIXME]
     // [FIXME]: CHECK THAT NAME EXISTS AND GIVE A BETTER ERROR MESSAGE
-    pub fn set_arg_vec_named<'a, T>(&'a mut self, name: &'static str, vector: T)
+    pub fn set_arg_vec_named<'a, T>(&'a mut self, name: &str, vector: T)
             -> OclResult<&'a mut Kernel>
             where T: OclPrm + 'static {
         let arg_idx = self.resolve_named_arg_idx(name)?;
@@ -398,7 +650,7 @@ impl Kernel {
     ///
     /// ## Panics [FIXME]
     // * [FIXME] TODO: CHECK THAT NAME EXISTS AND GIVE A BETTER ERROR MESSAGE
-    pub fn set_arg_buf_named<'a, T, M>(&'a mut self, name: &'static str,
+    pub fn set_arg_buf_named<'a, T, M>(&'a mut self, name: &str,
             buffer_opt: Option<M>)
             -> OclResult<&'a mut Kernel>
             where T: OclPrm + 'static, M: AsMem<T> + MemCmdAll {
@@ -418,7 +670,7 @@ impl Kernel {
     ///
     /// ## Panics [FIXME]
     // * [FIXME] TODO: CHECK THAT NAME EXISTS AND GIVE A BETTER ERROR MESSAGE
-    pub fn set_arg_img_named<'a, T, M>(&'a mut self, name: &'static str,
+    pub fn set_arg_img_named<'a, T, M>(&'a mut self, name: &str,
             image_opt: Option<M>)
             -> OclResult<&'a mut Kernel>
             where T: OclPrm + 'static, M: AsMem<T> + MemCmdAll {
@@ -426,10 +678,11 @@ impl Kernel {
         let arg_idx = self.resolve_named_arg_idx(name)?;
         match image_opt {
             Some(img) => {
-                self._set_arg::<T>(arg_idx, KernelArg::Mem(img.as_mem()))
+                // Type is ignored:
+                self._set_arg::<u64>(arg_idx, KernelArg::Mem(img.as_mem()))
             },
             None => {
-                self._set_arg::<T>(arg_idx, KernelArg::MemNull)
+                self._set_arg::<u64>(arg_idx, KernelArg::MemNull)
             },
         }.and(Ok(self))
     }
@@ -439,7 +692,7 @@ impl Kernel {
     /// ## Panics [FIXME]
     // [PLACEHOLDER] Set a named sampler argument
     #[allow(unused_variables)]
-    pub fn set_arg_smp_named<'a, T: OclPrm>(&'a mut self, name: &'static str,
+    pub fn set_arg_smp_named<'a, T: OclPrm>(&'a mut self, name: &str,
             sampler_opt: Option<&Sampler>) -> OclResult<&'a mut Kernel> {
         unimplemented!();
     }
@@ -448,7 +701,8 @@ impl Kernel {
     /// 'enqueue' command together.
     pub fn cmd(&self) -> KernelCmd {
         KernelCmd { queue: self.queue.as_ref().map(|q| q.as_ref()), kernel: &self.obj_core,
-            gwo: self.gwo, gws: self.gws, lws: self.lws,
+            gwo: self.gwo, gws: self.gws, lws: self.lws, non_uniform: self.non_uniform,
+            non_uniform_support: &self.non_uniform_support,
             wait_events: None, new_event: None }
     }
 
@@ -507,6 +761,12 @@ impl Kernel {
         self.lws
     }
 
+    /// Returns true if the global work size may be other than a multiple of
+    /// the local work size (see `::non_uniform`).
+    pub fn is_non_uniform(&self) -> bool {
+        self.non_uniform
+    }
+
     /// Returns the number of arguments specified for this kernel.
     #[inline]
     pub fn new_arg_count(&self) -> u32 {
@@ -537,6 +797,56 @@ impl Kernel {
         core::get_kernel_work_group_info(&self.obj_core, device, info_kind)
     }
 
+    /// Returns sub-group information for this kernel.
+    ///
+    /// `input` must contain a local work size (one value per dimension) for
+    /// `MaxSubGroupSizeForNdrange` and `SubGroupCountForNdrange` or a single
+    /// sub-group count for `LocalSizeForSubGroupCount`. It is ignored for
+    /// all other variants.
+    #[cfg(feature = "opencl_version_2_1")]
+    pub fn sub_group_info(&self, device: Device, info_kind: KernelSubGroupInfo, input: &[usize])
+            -> OclCoreResult<KernelSubGroupInfoResult> {
+        core::get_kernel_sub_group_info(&self.obj_core, device, info_kind, input, None)
+    }
+
+    /// Returns a new kernel which is a copy of this one, including all
+    /// argument values currently set, without rebuilding it from its
+    /// program.
+    ///
+    /// Unlike `::clone`, which shares the underlying OpenCL kernel object
+    /// (and therefore its arguments), the returned kernel is independent:
+    /// setting an argument on one does not affect the other. Argument
+    /// names, default queue, and work sizes are also copied.
+    ///
+    /// Uses `clCloneKernel` and requires OpenCL 2.1+.
+    #[cfg(feature = "opencl_version_2_1")]
+    pub fn try_clone(&self) -> OclResult<Kernel> {
+        let obj_core = core::clone_kernel(&self.obj_core, None)?;
+
+        Ok(Kernel {
+            obj_core: obj_core,
+            named_args: self.named_args.clone(),
+            new_arg_count: self.new_arg_count,
+            mem_args: Arc::new(Mutex::new(self.mem_args.lock().unwrap().clone())),
+            #[cfg(feature = "opencl_version_2_0")]
+            svm_args: Arc::new(Mutex::new(self.svm_args.lock().unwrap().clone())),
+            #[cfg(feature = "opencl_version_2_0")]
+            svm_indirect: Arc::new(Mutex::new(self.svm_indirect.lock().unwrap().clone())),
+            queue: self.queue.clone(),
+            gwo: self.gwo,
+            gws: self.gws,
+            lws: self.lws,
+            non_uniform: self.non_uniform,
+            non_uniform_support: self.non_uniform_support.clone(),
+            num_args: self.num_args,
+            arg_types: self.arg_types.clone(),
+            arg_names: self.arg_names.clone(),
+            bypass_arg_check: self.bypass_arg_check,
+            max_constant_buffer_size: self.max_constant_buffer_size,
+            verified_args: self.verified_args.clone(),
+        })
+    }
+
     /// Returns the name of this kernel.
     pub fn name(&self) -> OclCoreResult<String> {
         core::get_kernel_info(&self.obj_core, KernelInfo::FunctionName).map(|r| r.into())
@@ -551,9 +861,141 @@ impl Kernel {
         }
     }
 
-    /// Returns the argument index of a named argument if it exists.
-    pub fn named_arg_idx(&self, name: &'static str) -> Option<u32> {
-        self.resolve_named_arg_idx(name).ok()
+    /// Modifies the shared virtual memory kernel argument named: `name`.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn set_arg_svm_named<'a, T>(&'a mut self, name: &str,
+            svm_vec_opt: Option<&SvmVec<T>>)
+            -> OclResult<&'a mut Kernel>
+            where T: OclPrm + 'static {
+        let arg_idx = self.resolve_named_arg_idx(name)?;
+        self._set_arg_svm(arg_idx, svm_vec_opt).and(Ok(self))
+    }
+
+    /// Modifies the pipe kernel argument named: `name`.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn set_arg_pipe_named<'a, T>(&'a mut self, name: &str,
+            pipe_opt: Option<&Pipe<T>>)
+            -> OclResult<&'a mut Kernel>
+            where T: OclPrm + 'static {
+        let arg_idx = self.resolve_named_arg_idx(name)?;
+        match pipe_opt {
+            Some(pipe) => {
+                self._set_arg::<T>(arg_idx, KernelArg::Mem(pipe.as_core()))
+            },
+            None => {
+                self._set_arg::<T>(arg_idx, KernelArg::MemNull)
+            },
+        }.and(Ok(self))
+    }
+
+    /// Specifies the shared virtual memory allocations this kernel accesses
+    /// indirectly, through pointers stored within its arguments rather than
+    /// the arguments themselves, replacing any previously specified.
+    ///
+    /// Required when a pointer-based data structure (a tree, linked list,
+    /// etc.) spans more than one allocation. The allocations are kept alive
+    /// for the life of this kernel.
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn set_svm_indirect(&mut self, allocs: Vec<SvmRef>) -> OclResult<&mut Kernel> {
+        let ptrs: Vec<_> = allocs.iter().map(|a| a.as_ptr() as *const _).collect();
+        unsafe { core::set_kernel_exec_info_svm_ptrs(&self.obj_core, &ptrs, None)?; }
+        *self.svm_indirect.lock().unwrap() = allocs;
+        Ok(self)
+    }
+
+    /// Sets the argument specified by `arg` (an index or a name) to `value`
+    /// (a buffer, image, sampler, scalar or vector).
+    ///
+    /// Names are resolved from those declared with the `::arg_..._named`
+    /// methods or, failing that, those reported by the platform (OpenCL
+    /// 1.2+).
+    /// This allows arguments of kernels which are not known at compile time
+    /// to be set by name:
+    ///
+    /// ```rust,ignore
+    /// kernel.set_arg("buffer", &buffer)?.set_arg("scalar", 10.0f32)?;
+    /// ```
+    pub fn set_arg<'a, I, A>(&'a mut self, arg: I, value: A) -> OclResult<&'a mut Kernel>
+            where I: Into<ArgIdxSpecifier>, A: KernelArgValue {
+        let arg_idx = match arg.into() {
+            ArgIdxSpecifier::Index(idx) => idx,
+            ArgIdxSpecifier::Name(name) => self.resolve_named_arg_idx(&name)?,
+        };
+
+        if arg_idx >= self.num_args {
+            return Err(format!("Kernel arg index out of range. (kernel: {}, index: {})",
+                self.name()?, arg_idx).into());
+        }
+
+        if !self.bypass_arg_check && !value.is_match(&self.arg_types[arg_idx as usize]) {
+            return Err(format!("Kernel argument type mismatch. The argument at index [{}] \
+                is a '{}' ({:?}) but a {} was specified.", arg_idx, self.arg_type_name(arg_idx),
+                self.arg_types[arg_idx as usize], value.kind()).into());
+        }
+        if let Some(mem) = value.as_mem() {
+            let problem = self.mem_arg_problem(arg_idx, mem)?;
+            self.check_arg_problem(problem)?;
+        }
+
+        value.set_arg(self, arg_idx)?;
+        Ok(self)
+    }
+
+    /// Returns the name of the argument at `arg_idx` as reported by the
+    /// platform (OpenCL 1.2+).
+    ///
+    /// Some platforms only report names for programs built with the
+    /// `-cl-kernel-arg-info` compiler option.
+    pub fn arg_name(&self, arg_idx: u32) -> Option<&str> {
+        self.arg_names.get(arg_idx as usize).and_then(|n| n.as_ref()).map(|n| n.as_str())
+    }
+
+    /// Sets every argument from the fields of `args`, usually a struct
+    /// implementing `KernelArgs` using `#[derive(KernelArgs)]` from the
+    /// `ocl-derive` crate.
+    ///
+    /// The first time a given type is used with this kernel, the number,
+    /// names (if reported by the platform), and types of its fields are
+    /// checked against the kernel's arguments and every problem found is
+    /// returned in a single `KernelError`. Later calls only set the values.
+    ///
+    /// Buffers and images are kept alive for the life of this kernel (or
+    /// until replaced) just as with the `::arg_...` methods.
+    pub fn set_args<A: KernelArgs>(&mut self, args: &A) -> OclResult<&mut Kernel> {
+        let type_name = A::type_name();
+
+        let verified = !self.verified_args.contains(&type_name);
+        if verified {
+            let problems = {
+                let mut verifier = ArgVerifier { kernel: &*self, arg_idx: 0,
+                    problems: Vec::new() };
+                args.visit(&mut verifier)?;
+                verifier.finish()
+            };
+
+            if !problems.is_empty() {
+                return Err(KernelError::Args { kernel: self.name()?, args: type_name,
+                    problems: problems }.into());
+            }
+            self.verified_args.push(type_name);
+        }
+
+        args.visit(&mut ArgSetter { kernel: &mut *self, arg_idx: 0, verified: verified })?;
+        self.new_arg_count = self.num_args;
+        Ok(self)
+    }
+
+    /// Returns the index of the argument named `name` if it exists.
+    ///
+    /// Names declared with the `::arg_..._named` methods are searched
+    /// first, followed by names reported by the platform (OpenCL 1.2+).
+    pub fn named_arg_idx(&self, name: &str) -> Option<u32> {
+        let declared = self.named_args.as_ref().and_then(|map| map.get(name).cloned());
+        declared.or_else(|| {
+            self.arg_names.iter()
+                .position(|n| n.as_ref().map(|n| n == name).unwrap_or(false))
+                .map(|idx| idx as u32)
+        })
     }
 
     /// Verifies that a type matches the kernel arg info:
@@ -571,10 +1013,173 @@ impl Kernel {
         if arg_type.is_match::<T>() {
             Ok(())
         } else {
-            let type_name = arg_type_name(&self.obj_core, arg_index)?;
             Err(format!("Kernel argument type mismatch. The argument at index [{}] \
-                is a '{}' ({:?}).", arg_index, type_name, arg_type).into())
+                is a '{}' ({:?}).", arg_index, self.arg_type_name(arg_index), arg_type).into())
+        }
+    }
+
+    /// Returns candidate local work sizes for `::autotune` given a global
+    /// work size, `gws`, on `device`.
+    ///
+    /// Each dimension of a candidate is a power of two (or a power of two
+    /// times the kernel's `PreferredWorkGroupSizeMultiple`) which divides
+    /// the same dimension of `gws` and is within the device's
+    /// `MaxWorkItemSizes`. Candidates are no larger than the kernel's
+    /// `WorkGroupSize` and, unless none would remain, are multiples of its
+    /// preferred multiple. Candidates are ordered from smallest to largest.
+    ///
+    /// A kernel declared with `reqd_work_group_size` has only that
+    /// candidate.
+    pub fn lws_candidates(&self, device: Device, gws: SpatialDims)
+            -> OclResult<Vec<SpatialDims>> {
+        let gws_lens = match gws.to_lens() {
+            Ok(lens) => lens,
+            Err(_) => return Err("Kernel::lws_candidates: The global work size must be \
+                specified.".into()),
+        };
+        let dim_count = gws.dim_count() as usize;
+
+        if let Ok(KernelWorkGroupInfoResult::CompileWorkGroupSize(reqd)) =
+                self.wg_info(device, KernelWorkGroupInfo::CompileWorkGroupSize) {
+            if reqd[0] != 0 { return Ok(vec![dims_from_lens(&reqd[..dim_count])]); }
+        }
+
+        let device_max = device.max_wg_size()?;
+        let max_size = match self.wg_info(device, KernelWorkGroupInfo::WorkGroupSize)? {
+            KernelWorkGroupInfoResult::WorkGroupSize(size) if size > 0 => size.min(device_max),
+            _ => device_max,
+        };
+        let multiple = match self.wg_info(device,
+                KernelWorkGroupInfo::PreferredWorkGroupSizeMultiple) {
+            Ok(KernelWorkGroupInfoResult::PreferredWorkGroupSizeMultiple(m)) if m > 0 => m,
+            _ => 1,
+        };
+        let max_item_sizes = match device.info(DeviceInfo::MaxWorkItemSizes)? {
+            DeviceInfoResult::MaxWorkItemSizes(sizes) => sizes,
+            _ => unreachable!(),
+        };
+
+        let mut candidates = vec![[1usize; 3]];
+
+        for dim in 0..dim_count {
+            let limit = max_item_sizes.get(dim).map_or(max_size, |&size| size.min(max_size));
+            let powers = |base: usize| (0..).map(move |pow: u32| base << pow)
+                .take_while(move |&size| size <= limit);
+            let mut sizes: Vec<usize> = powers(1).chain(powers(multiple))
+                .filter(|&size| gws_lens[dim] % size == 0)
+                .collect();
+            sizes.sort();
+            sizes.dedup();
+
+            candidates = candidates.iter()
+                .flat_map(|lens| sizes.iter().map(move |&size| {
+                    let mut lens = *lens;
+                    lens[dim] = size;
+                    lens
+                }))
+                .filter(|lens| lens.iter().product::<usize>() <= max_size)
+                .collect();
+        }
+
+        if candidates.iter().any(|lens| lens.iter().product::<usize>() % multiple == 0) {
+            candidates.retain(|lens| lens.iter().product::<usize>() % multiple == 0);
+        }
+        candidates.sort_by_key(|lens| lens.iter().product::<usize>());
+
+        Ok(candidates.iter().map(|lens| dims_from_lens(&lens[..dim_count])).collect())
+    }
+
+    /// Times each of `candidates` as the local work size and sets the
+    /// fastest as the default local work size, returning it.
+    ///
+    /// The kernel is enqueued with its current arguments and default global
+    /// work size and offset on a new profiling-enabled queue, on the device
+    /// of its default queue, once all commands previously enqueued on its
+    /// default queue have completed. Each candidate is run once then timed over
+    /// several runs with `Event::profiling_info`, keeping the shortest.
+    /// Candidates which fail to enqueue (ex. those too large for the
+    /// kernel's resource usage) are skipped. So are candidates which do not
+    /// divide the global work size, even if `::non_uniform` is enabled, as
+    /// only the last of the dispatches it splits the kernel into would be
+    /// timed.
+    ///
+    /// If `candidates` is empty, those returned by `::lws_candidates` are
+    /// used. Use `::autotune_cached` to reuse the result in later runs.
+    ///
+    /// # Safety
+    ///
+    /// The kernel is run several times with the same arguments, which it
+    /// must tolerate. It runs on a second queue, so commands enqueued on any
+    /// queue other than its default queue which use its arguments must have
+    /// completed before calling, and none may be enqueued until this returns.
+    /// See `::enq` for other concerns.
+    pub unsafe fn autotune(&mut self, candidates: &[SpatialDims]) -> OclResult<SpatialDims> {
+        let (context, device) = match self.queue {
+            Some(ref queue) => {
+                // Commands using the kernel's arguments may still be pending:
+                queue.finish()?;
+                (queue.context(), queue.device())
+            },
+            None => return Err("Kernel::autotune: No default queue set.".into()),
+        };
+        if self.gws.is_unspecified() {
+            return Err("Kernel::autotune: No default global work size set.".into());
+        }
+
+        let candidates = if candidates.is_empty() {
+            self.lws_candidates(device, self.gws)?
+        } else {
+            candidates.to_vec()
+        };
+
+        if let Some(lws) = candidates.iter().find(|lws| lws.dim_count() != self.gws.dim_count()) {
+            return Err(format!("Kernel::autotune: The candidate local work size, {:?}, does not \
+                have the same number of dimensions as the global work size, {:?}.", lws,
+                self.gws).into());
+        }
+
+        let queue = Queue::builder().device(device).profiling().build(&context)?;
+        let mut best: Option<(u64, SpatialDims)> = None;
+        let mut last_err = None;
+
+        for &lws in candidates.iter() {
+            match self.time_lws(&queue, lws) {
+                Ok(time) => if best.map_or(true, |(best_time, _)| time < best_time) {
+                    best = Some((time, lws));
+                },
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        match best {
+            Some((_, lws)) => {
+                self.lws = lws;
+                Ok(lws)
+            },
+            None => Err(last_err.unwrap_or_else(|| "Kernel::autotune: No candidate local work \
+                sizes.".into())),
+        }
+    }
+
+    /// Sets the default local work size to the one stored in `cache` for
+    /// this kernel's name, device and global work size, running
+    /// `::autotune` and storing its result if none is stored.
+    ///
+    /// # Safety
+    ///
+    /// See `::autotune`.
+    pub unsafe fn autotune_cached(&mut self, cache: &mut LwsCache, candidates: &[SpatialDims])
+            -> OclResult<SpatialDims> {
+        let key = LwsKey::new(self)?;
+
+        if let Some(&lws) = cache.entries.get(&key) {
+            self.lws = lws;
+            return Ok(lws);
         }
+
+        let lws = self.autotune(candidates)?;
+        cache.entries.insert(key, lws);
+        Ok(lws)
     }
 
     /// Sets an argument by index without checks of any kind.
@@ -591,10 +1196,139 @@ impl Kernel {
         core::set_kernel_arg::<T>(&self.obj_core, arg_idx, arg).map_err(OclError::from)
     }
 
+    /// Returns the shortest of several timed runs of this kernel on
+    /// `queue`, which must have profiling enabled, with a local work size
+    /// of `lws`, in nanoseconds.
+    ///
+    /// The kernel is never split into several dispatches (see
+    /// `::non_uniform`) as the event of the last would be timed alone.
+    unsafe fn time_lws(&self, queue: &Queue, lws: SpatialDims) -> OclResult<u64> {
+        self.cmd().queue(queue).lws(lws).non_uniform(false).enq()?;
+        let mut shortest = u64::max_value();
+
+        for _ in 0..AUTOTUNE_RUNS {
+            let mut event = Event::empty();
+            self.cmd().queue(queue).lws(lws).non_uniform(false).enew(&mut event).enq()?;
+            event.wait_for()?;
+            let start = event.profiling_info(ProfilingInfo::Start)?.time()?;
+            let end = event.profiling_info(ProfilingInfo::End)?.time()?;
+            shortest = shortest.min(end.saturating_sub(start));
+        }
+        Ok(shortest)
+    }
+
+    /// Returns a `KernelArgProblem::TypeMismatch` if a value of kind `kind`
+    /// cannot be used as the argument at `arg_idx` (ex. a buffer for a
+    /// `__local` argument or a scalar for a pointer).
+    fn arg_kind_problem(&self, arg_idx: u32, kind: ArgKind) -> Option<KernelArgProblem> {
+        match self.arg_types.get(arg_idx as usize) {
+            Some(arg_type) if !self.bypass_arg_check && !arg_type.is_kind_match(kind) => {
+                Some(KernelArgProblem::TypeMismatch {
+                    idx: arg_idx,
+                    name: self.arg_name(arg_idx).map(|n| n.to_owned()),
+                    specified: kind.description(),
+                    declared: self.arg_type_name(arg_idx),
+                })
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the problem, if any, with using the buffer, image, or pipe
+    /// `mem` as the argument at `arg_idx`.
+    ///
+    /// In addition to its kind (see `::arg_kind_problem`), the type of an
+    /// image, the access flags of the memory object, and the size of
+    /// buffers used as `__constant` arguments are checked.
+    ///
+    /// A memory object already set as the argument at `arg_idx` has been
+    /// checked and is not queried again.
+    fn mem_arg_problem(&self, arg_idx: u32, mem: &MemCore)
+            -> OclResult<Option<KernelArgProblem>> {
+        let arg_type = match self.arg_types.get(arg_idx as usize) {
+            Some(arg_type) if !self.bypass_arg_check && !arg_type.is_unknown() => arg_type,
+            _ => return Ok(None),
+        };
+
+        if let Some(&Some(ref set)) = self.mem_args.lock().unwrap().get(arg_idx as usize) {
+            if set.as_ptr() == mem.as_ptr() { return Ok(None); }
+        }
+
+        let kind = match core::get_mem_object_info(mem, MemInfo::Type)? {
+            MemInfoResult::Type(mem_type) => ArgKind::from_mem_type(mem_type),
+            _ => unreachable!(),
+        };
+        if let Some(problem) = self.arg_kind_problem(arg_idx, kind) {
+            return Ok(Some(problem));
+        }
+
+        let flags = match core::get_mem_object_info(mem, MemInfo::Flags)? {
+            MemInfoResult::Flags(flags) => flags,
+            _ => unreachable!(),
+        };
+        if !arg_type.is_access_match(flags) {
+            return Ok(Some(KernelArgProblem::AccessMismatch {
+                idx: arg_idx,
+                name: self.arg_name(arg_idx).map(|n| n.to_owned()),
+                flags: flags,
+                declared: self.arg_type_name(arg_idx),
+            }));
+        }
+
+        if let (Some(KernelArgAddressQualifier::Constant), Some(max)) =
+                (arg_type.address(), self.max_constant_buffer_size) {
+            let size = match core::get_mem_object_info(mem, MemInfo::Size)? {
+                MemInfoResult::Size(size) => size,
+                _ => unreachable!(),
+            };
+            if size as u64 > max {
+                return Ok(Some(KernelArgProblem::ConstantBufferTooLarge {
+                    idx: arg_idx,
+                    name: self.arg_name(arg_idx).map(|n| n.to_owned()),
+                    size: size,
+                    max: max,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns a `KernelError::InvalidArg` if `problem` is `Some`.
+    fn check_arg_problem(&self, problem: Option<KernelArgProblem>) -> OclResult<()> {
+        match problem {
+            Some(problem) => Err(KernelError::InvalidArg { kernel: self.name()?,
+                problem: problem }.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Verifies that the kind of `arg` (and the buffer, image, or pipe it
+    /// refers to) can be used as the argument at `arg_idx`.
+    fn verify_arg_kind<T: OclPrm>(&self, arg_idx: u32, arg: &KernelArg<T>) -> OclResult<()> {
+        let problem = match *arg {
+            KernelArg::Mem(mem) => self.mem_arg_problem(arg_idx, mem)?,
+            KernelArg::Sampler(_) | KernelArg::SamplerNull => {
+                self.arg_kind_problem(arg_idx, ArgKind::Sampler)
+            },
+            KernelArg::Scalar(_) | KernelArg::Vector(_) => {
+                self.arg_kind_problem(arg_idx, ArgKind::Value)
+            },
+            KernelArg::Local(_) => self.arg_kind_problem(arg_idx, ArgKind::Local),
+            KernelArg::MemNull | KernelArg::UnsafePointer { .. } => None,
+        };
+        self.check_arg_problem(problem)
+    }
+
     /// Sets an argument by index.
     fn _set_arg<T: OclPrm + 'static>(&mut self, arg_idx: u32, arg: KernelArg<T>) -> OclResult<()> {
         self.verify_arg_type::<T>(arg_idx)?;
+        self.verify_arg_kind(arg_idx, &arg)?;
+        self._set_arg_unverified(arg_idx, arg)
+    }
 
+    /// Sets an argument by index without verifying its type.
+    fn _set_arg_unverified<T: OclPrm>(&mut self, arg_idx: u32, arg: KernelArg<T>)
+            -> OclResult<()> {
         // If the `KernelArg` is a `Mem` variant, clone the `MemCore` it
         // refers to, store it in `self.mem_args`, and create a new
         // `KernelArg::Mem` referring to the locally stored copy. This prevents
@@ -613,6 +1347,32 @@ impl Kernel {
         core::set_kernel_arg::<T>(&self.obj_core, arg_idx, arg).map_err(OclError::from)
     }
 
+    /// Sets a shared virtual memory argument by index, storing a reference
+    /// to keep the allocation alive (see `::_set_arg`).
+    #[cfg(feature = "opencl_version_2_0")]
+    fn _set_arg_svm<T>(&mut self, arg_idx: u32, svm_vec_opt: Option<&SvmVec<T>>)
+            -> OclResult<()>
+            where T: OclPrm + 'static {
+        self.verify_arg_type::<T>(arg_idx)?;
+        let problem = self.arg_kind_problem(arg_idx, ArgKind::Buffer);
+        self.check_arg_problem(problem)?;
+        self._set_arg_svm_unverified(arg_idx, svm_vec_opt)
+    }
+
+    /// Sets a shared virtual memory argument by index without verifying its
+    /// type.
+    #[cfg(feature = "opencl_version_2_0")]
+    fn _set_arg_svm_unverified<T>(&mut self, arg_idx: u32, svm_vec_opt: Option<&SvmVec<T>>)
+            -> OclResult<()>
+            where T: OclPrm {
+        let ptr = svm_vec_opt.map(|v| v.as_ptr()).unwrap_or(std::ptr::null());
+        unsafe {
+            core::set_kernel_arg_svm_pointer(&self.obj_core, arg_idx, ptr as *const _, None)?;
+        }
+        self.svm_args.lock().unwrap()[arg_idx as usize] = svm_vec_opt.map(|v| v.svm_ref());
+        Ok(())
+    }
+
     fn fmt_info(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("Kernel")
             .field("FunctionName", &self.info(KernelInfo::FunctionName))
@@ -668,17 +1428,23 @@ impl Kernel {
         self.named_args.as_mut().unwrap().insert(name, arg_idx);
     }
 
+    /// Returns the declared type of the argument at `arg_idx` for use in
+    /// error messages (ex. '__local float*'), as cached or, if unavailable,
+    /// as reported by the platform.
+    fn arg_type_name(&self, arg_idx: u32) -> String {
+        match self.arg_types.get(arg_idx as usize) {
+            Some(arg_type) if !arg_type.is_unknown() => arg_type.to_string(),
+            _ => arg_type_name(&self.obj_core, arg_idx)
+                .unwrap_or_else(|_| "<unknown>".to_owned()),
+        }
+    }
+
     /// Resolves the index of a named argument with a friendly error message.
-    fn resolve_named_arg_idx(&self, name: &'static str) -> OclResult<u32> {
-        match self.named_args {
-            Some(ref map) => {
-                match map.get(name) {
-                    Some(&ai) => Ok(ai),
-                    None => Err(format!("Kernel::set_arg_scl_named(): Invalid argument \
-                        name: '{}'.", name).into()),
-                }
-            },
-            None => Err("Kernel::resolve_named_arg_idx: No named arguments declared.".into()),
+    fn resolve_named_arg_idx(&self, name: &str) -> OclResult<u32> {
+        match self.named_arg_idx(name) {
+            Some(arg_idx) => Ok(arg_idx),
+            None => Err(KernelError::UnknownArgName { kernel: self.name()?,
+                name: name.to_owned() }.into()),
         }
     }
 
@@ -722,6 +1488,36 @@ impl Kernel {
         }
     }
 
+    /// Non-builder-style version of `::arg_pipe()`.
+    #[cfg(feature = "opencl_version_2_0")]
+    fn new_arg_pipe<T>(&mut self, pipe_opt: Option<&Pipe<T>>) -> u32
+            where T: OclPrm + 'static {
+        match pipe_opt {
+            Some(pipe) => {
+                self.new_arg::<T>(KernelArg::Mem(pipe.as_core()))
+            },
+            None => {
+                self.new_arg::<T>(KernelArg::MemNull)
+            },
+        }
+    }
+
+    /// Non-builder-style version of `::arg_svm()`.
+    #[cfg(feature = "opencl_version_2_0")]
+    fn new_arg_svm<T>(&mut self, svm_vec_opt: Option<&SvmVec<T>>) -> u32
+            where T: OclPrm + 'static {
+        self.assert_unlocked();
+        let arg_idx = self.new_arg_count;
+
+        if let Err(err) = self._set_arg_svm(arg_idx, svm_vec_opt) {
+            panic!("Kernel::new_arg_svm(kernel name: '{}' arg index: '{}'): {}",
+                self.name().unwrap(), arg_idx, err);
+        }
+
+        self.new_arg_count += 1;
+        arg_idx
+    }
+
     /// Non-builder-style version of `::arg_scl()`.
     fn new_arg_scl<T>(&mut self, scalar_opt: Option<T>) -> u32
             where T: OclPrm + 'static {
@@ -782,13 +1578,22 @@ impl Clone for Kernel {
             named_args: self.named_args.clone(),
             new_arg_count: self.new_arg_count.clone(),
             mem_args: self.mem_args.clone(),
+            #[cfg(feature = "opencl_version_2_0")]
+            svm_args: self.svm_args.clone(),
+            #[cfg(feature = "opencl_version_2_0")]
+            svm_indirect: self.svm_indirect.clone(),
             queue: self.queue.clone(),
             gwo: self.gwo.clone(),
             gws: self.gws.clone(),
             lws: self.lws.clone(),
+            non_uniform: self.non_uniform,
+            non_uniform_support: self.non_uniform_support.clone(),
             num_args: self.num_args.clone(),
             arg_types: self.arg_types.clone(),
+            arg_names: self.arg_names.clone(),
             bypass_arg_check: self.bypass_arg_check.clone(),
+            max_constant_buffer_size: self.max_constant_buffer_size,
+            verified_args: self.verified_args.clone(),
         }
     }
 }
@@ -816,36 +1621,1070 @@ impl DerefMut for Kernel {
 }
 
 
-/// Returns argument information for a kernel.
-pub fn arg_info(core: &KernelCore, arg_index: u32, info_kind: KernelArgInfo)
-        -> OclCoreResult<KernelArgInfoResult> {
-    let device_versions = match core.device_versions() {
-        Ok(vers) => vers,
-        Err(e) => return Err(e.into()),
-    };
-
-    core::get_kernel_arg_info(core, arg_index, info_kind,
-        Some(&device_versions))
+/// Local work sizes found by `Kernel::autotune_cached`, keyed by kernel
+/// name, device and global work size.
+///
+/// The fastest local work size varies between devices and between global
+/// work sizes, so an entry is only used for the same combination of all
+/// three. A cache is saved as plain text with one entry per line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LwsCache {
+    entries: BTreeMap<LwsKey, SpatialDims>,
 }
 
-/// Returns the type name for a kernel argument at the specified index.
-pub fn arg_type_name(core: &KernelCore, arg_index: u32) -> OclCoreResult<String> {
-    match arg_info(core, arg_index, KernelArgInfo::TypeName) {
-        Ok(KernelArgInfoResult::TypeName(type_name)) => Ok(type_name),
-        Err(err) => Err(err.into()),
-        _ => unreachable!(),
+impl LwsCache {
+    /// Returns a new, empty cache.
+    pub fn new() -> LwsCache {
+        LwsCache::default()
     }
-}
-
+
+    /// Loads a cache previously saved with `::save`.
+    ///
+    /// Returns an empty cache if `path` does not exist. Malformed entries
+    /// are ignored.
+    pub fn load<P: AsRef<Path>>(path: P) -> OclResult<LwsCache> {
+        let mut text = String::new();
+        match File::open(path) {
+            Ok(mut file) => { file.read_to_string(&mut text)?; },
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(LwsCache::new()),
+            Err(err) => return Err(err.into()),
+        }
+
+        let entries = text.lines().filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 4 { return None; }
+            let gws = dims_from_str(fields[2])?;
+            let lws = dims_from_str(fields[3])?;
+            if gws.dim_count() != lws.dim_count() { return None; }
+            Some((LwsKey { kernel: fields[0].to_owned(), device: fields[1].to_owned(),
+                gws: dims_to_string(gws) }, lws))
+        }).collect();
+
+        Ok(LwsCache { entries: entries })
+    }
+
+    /// Saves this cache to `path`, creating its directory if necessary.
+    ///
+    /// The file is written in full before being moved into place so that
+    /// concurrent processes never observe a partial cache.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> OclResult<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+
+        let tmp_path = path.with_extension(format!("tmp{}", std::process::id()));
+        File::create(&tmp_path)?.write_all(self.to_string().as_bytes())?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Returns the local work size stored for `kernel` on the device of its
+    /// default queue with its default global work size.
+    pub fn get(&self, kernel: &Kernel) -> OclResult<Option<SpatialDims>> {
+        Ok(self.entries.get(&LwsKey::new(kernel)?).cloned())
+    }
+
+    /// Stores `lws` as the local work size for `kernel` on the device of its
+    /// default queue with its default global work size, returning the
+    /// previously stored local work size, if any.
+    pub fn insert(&mut self, kernel: &Kernel, lws: SpatialDims) -> OclResult<Option<SpatialDims>> {
+        if lws.dim_count() != kernel.gws.dim_count() {
+            return Err(format!("LwsCache::insert: The local work size, {:?}, does not have the \
+                same number of dimensions as the global work size, {:?}.", lws, kernel.gws).into());
+        }
+        Ok(self.entries.insert(LwsKey::new(kernel)?, lws))
+    }
+
+    /// Returns the number of stored local work sizes.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no local work sizes are stored.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl fmt::Display for LwsCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (key, lws) in self.entries.iter() {
+            writeln!(f, "{}\t{}\t{}\t{}", key.kernel, key.device, key.gws, dims_to_string(*lws))?;
+        }
+        Ok(())
+    }
+}
+
+
+/// An `LwsCache` key.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct LwsKey {
+    kernel: String,
+    /// The device name and driver version:
+    device: String,
+    /// The global work size (ex. '1024x16'):
+    gws: String,
+}
+
+impl LwsKey {
+    /// Returns the key for a kernel on the device of its default queue with
+    /// its default global work size.
+    fn new(kernel: &Kernel) -> OclResult<LwsKey> {
+        let device = match kernel.queue {
+            Some(ref queue) => queue.device(),
+            None => return Err("LwsCache: The kernel has no default queue.".into()),
+        };
+        if kernel.gws.is_unspecified() {
+            return Err("LwsCache: The kernel has no default global work size.".into());
+        }
+
+        // Tabs and newlines would corrupt a saved cache:
+        let device_name = format!("{} ({})", device.name()?,
+            device.info(DeviceInfo::DriverVersion)?).replace(|c: char| c == '\t' || c == '\n', " ");
+
+        Ok(LwsKey {
+            kernel: kernel.name()?,
+            device: device_name,
+            gws: dims_to_string(kernel.gws),
+        })
+    }
+}
+
+
+/// A kernel related error.
+#[derive(Debug)]
+pub enum KernelError {
+    /// No program was specified with `KernelBuilder::program`.
+    BuilderNoProgram,
+    /// No kernel function name was specified with `KernelBuilder::name`.
+    BuilderNoKernelName,
+    /// The arguments specified with a `KernelBuilder` are invalid.
+    BuilderArgs { kernel: String, problems: Vec<KernelArgProblem> },
+    /// The fields of the `KernelArgs` type named `args` do not match the
+    /// arguments of the kernel.
+    Args { kernel: String, args: &'static str, problems: Vec<KernelArgProblem> },
+    /// The kernel has no argument named `name`.
+    UnknownArgName { kernel: String, name: String },
+    /// A value could not be used as an argument.
+    InvalidArg { kernel: String, problem: KernelArgProblem },
+}
+
+impl std::fmt::Display for KernelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            KernelError::BuilderNoProgram => write!(f, "KernelBuilder: No program specified."),
+            KernelError::BuilderNoKernelName => {
+                write!(f, "KernelBuilder: No kernel name specified.")
+            },
+            KernelError::BuilderArgs { ref kernel, ref problems } => {
+                write!(f, "KernelBuilder: Invalid arguments for kernel '{}':", kernel)?;
+                fmt_problems(f, problems)
+            },
+            KernelError::Args { ref kernel, args, ref problems } => {
+                write!(f, "Kernel::set_args: The fields of '{}' do not match the arguments of \
+                    kernel '{}':", args, kernel)?;
+                fmt_problems(f, problems)
+            },
+            KernelError::UnknownArgName { ref kernel, ref name } => {
+                write!(f, "Kernel '{}' has no argument named '{}'.", kernel, name)
+            },
+            KernelError::InvalidArg { ref kernel, ref problem } => {
+                write!(f, "Invalid argument for kernel '{}': {}.", kernel, problem)
+            },
+        }
+    }
+}
+
+impl Fail for KernelError {}
+
+fn fmt_problems(f: &mut std::fmt::Formatter, problems: &[KernelArgProblem]) -> std::fmt::Result {
+    for problem in problems {
+        write!(f, "\n    - {}", problem)?;
+    }
+    Ok(())
+}
+
+
+/// A problem with the arguments specified with a `KernelBuilder`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KernelArgProblem {
+    /// More arguments were specified than the kernel declares.
+    Count { specified: u32, declared: u32 },
+    /// The argument at `idx` does not match the type the kernel declares.
+    ///
+    /// `name` is the declared name if the platform reports it.
+    TypeMismatch { idx: u32, name: Option<String>, specified: &'static str, declared: String },
+    /// The argument at `idx` was not specified.
+    Unset { idx: u32, name: Option<String> },
+    /// The argument at `idx` was specified with a different name than the
+    /// kernel declares.
+    NameMismatch { idx: u32, specified: &'static str, declared: String },
+    /// More than one argument was given the same name.
+    DuplicateName { name: &'static str },
+    /// The memory object specified for the argument at `idx` was created
+    /// with `flags` which do not permit the declared access (ex. a
+    /// `WRITE_ONLY` image for a `read_only` argument).
+    AccessMismatch { idx: u32, name: Option<String>, flags: MemFlags, declared: String },
+    /// The buffer specified for the `__constant` argument at `idx` is larger
+    /// than the `DeviceInfo::MaxConstantBufferSize` of a device.
+    ConstantBufferTooLarge { idx: u32, name: Option<String>, size: usize, max: u64 },
+}
+
+impl std::fmt::Display for KernelArgProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fn fmt_arg(f: &mut std::fmt::Formatter, idx: u32, name: &Option<String>)
+                -> std::fmt::Result {
+            match *name {
+                Some(ref name) => write!(f, "argument [{}] ('{}')", idx, name),
+                None => write!(f, "argument [{}]", idx),
+            }
+        }
+
+        match *self {
+            KernelArgProblem::Count { specified, declared } => write!(f, "{} arguments \
+                specified but the kernel declares {}", specified, declared),
+            KernelArgProblem::TypeMismatch { idx, ref name, specified, ref declared } => {
+                fmt_arg(f, idx, name)?;
+                write!(f, ": a {} was specified but the kernel declares a '{}'", specified,
+                    declared)
+            },
+            KernelArgProblem::Unset { idx, ref name } => {
+                fmt_arg(f, idx, name)?;
+                write!(f, " is unset")
+            },
+            KernelArgProblem::NameMismatch { idx, specified, ref declared } => write!(f,
+                "argument [{}] was specified as '{}' but the kernel declares '{}'", idx,
+                specified, declared),
+            KernelArgProblem::DuplicateName { name } => write!(f, "the argument name '{}' is \
+                used more than once", name),
+            KernelArgProblem::AccessMismatch { idx, ref name, flags, ref declared } => {
+                fmt_arg(f, idx, name)?;
+                write!(f, ": a memory object created with {:?} was specified but the kernel \
+                    declares a '{}'", flags, declared)
+            },
+            KernelArgProblem::ConstantBufferTooLarge { idx, ref name, size, max } => {
+                fmt_arg(f, idx, name)?;
+                write!(f, ": a buffer of {} bytes was specified but constant buffers are \
+                    limited to {} bytes", size, max)
+            },
+        }
+    }
+}
+
+
+/// An argument held by a `KernelBuilder` until the kernel is built.
+struct BuilderArg<'b> {
+    name: Option<&'static str>,
+    kind: &'static str,
+    is_match: fn(&ArgType) -> bool,
+    arg_kind: ArgKind,
+    /// The buffer, image, or pipe, checked further (see
+    /// `Kernel::mem_arg_problem`):
+    mem: Option<MemCore>,
+    set: Box<Fn(&mut Kernel, u32) -> OclResult<()> + 'b>,
+}
+
+
+/// A kernel builder.
+///
+/// Unlike the builder-style `Kernel::arg_...` methods, which panic on an
+/// invalid argument, arguments are only checked when `::build` is called
+/// and every problem found is reported in a single `KernelError`.
+#[must_use = "builders do nothing unless '::build' is called"]
+pub struct KernelBuilder<'b> {
+    program: Option<&'b Program>,
+    name: Option<String>,
+    queue: Option<Queue>,
+    gwo: SpatialDims,
+    gws: SpatialDims,
+    lws: SpatialDims,
+    non_uniform: bool,
+    args: Vec<BuilderArg<'b>>,
+}
+
+impl<'b> KernelBuilder<'b> {
+    /// Returns a new kernel builder.
+    pub fn new() -> KernelBuilder<'b> {
+        KernelBuilder {
+            program: None,
+            name: None,
+            queue: None,
+            gwo: SpatialDims::Unspecified,
+            gws: SpatialDims::Unspecified,
+            lws: SpatialDims::Unspecified,
+            non_uniform: false,
+            args: Vec::with_capacity(16),
+        }
+    }
+
+    /// Specifies the program containing the kernel function.
+    pub fn program(mut self, program: &'b Program) -> KernelBuilder<'b> {
+        self.program = Some(program);
+        self
+    }
+
+    /// Specifies the name of the kernel function.
+    pub fn name<S: Into<String>>(mut self, name: S) -> KernelBuilder<'b> {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Specifies the default queue (see `Kernel::set_default_queue`).
+    pub fn queue(mut self, queue: Queue) -> KernelBuilder<'b> {
+        self.queue = Some(queue);
+        self
+    }
+
+    /// Specifies the default global work offset.
+    pub fn gwo<D: Into<SpatialDims>>(mut self, gwo: D) -> KernelBuilder<'b> {
+        self.gwo = gwo.into();
+        self
+    }
+
+    /// Specifies the default global work size.
+    pub fn gws<D: Into<SpatialDims>>(mut self, gws: D) -> KernelBuilder<'b> {
+        self.gws = gws.into();
+        self
+    }
+
+    /// Specifies the default local work size.
+    pub fn lws<D: Into<SpatialDims>>(mut self, lws: D) -> KernelBuilder<'b> {
+        self.lws = lws.into();
+        self
+    }
+
+    /// Allows the global work size to be other than a multiple of the local
+    /// work size (see `Kernel::non_uniform`).
+    pub fn non_uniform(mut self, non_uniform: bool) -> KernelBuilder<'b> {
+        self.non_uniform = non_uniform;
+        self
+    }
+
+    /// Adds a new argument specifying a buffer (see `Kernel::arg_buf`).
+    pub fn arg_buf<T, M>(self, buffer: M) -> KernelBuilder<'b>
+            where T: OclPrm + 'static, M: AsMem<T> + MemCmdAll {
+        self.arg_mem::<T>(None, "buffer", ArgKind::Buffer, Some(buffer.as_mem().clone()))
+    }
+
+    /// Adds a new named argument specifying a buffer (see
+    /// `Kernel::arg_buf_named`).
+    pub fn arg_buf_named<T, M>(self, name: &'static str, buffer_opt: Option<M>)
+            -> KernelBuilder<'b>
+            where T: OclPrm + 'static, M: AsMem<T> + MemCmdAll {
+        self.arg_mem::<T>(Some(name), "buffer", ArgKind::Buffer,
+            buffer_opt.map(|b| b.as_mem().clone()))
+    }
+
+    /// Adds a new argument specifying an image (see `Kernel::arg_img`).
+    pub fn arg_img<T, M>(self, image: M) -> KernelBuilder<'b>
+            where T: OclPrm, M: AsMem<T> + MemCmdAll {
+        // Type is ignored:
+        self.arg_mem::<u64>(None, "image", ArgKind::Image(None), Some(image.as_mem().clone()))
+    }
+
+    /// Adds a new named argument specifying an image (see
+    /// `Kernel::arg_img_named`).
+    pub fn arg_img_named<T, M>(self, name: &'static str, image_opt: Option<M>)
+            -> KernelBuilder<'b>
+            where T: OclPrm, M: AsMem<T> + MemCmdAll {
+        self.arg_mem::<u64>(Some(name), "image", ArgKind::Image(None),
+            image_opt.map(|i| i.as_mem().clone()))
+    }
+
+    /// Adds a new argument specifying a sampler (see `Kernel::arg_smp`).
+    pub fn arg_smp(self, sampler: &'b Sampler) -> KernelBuilder<'b> {
+        self.arg_smp_named_opt(None, Some(sampler))
+    }
+
+    /// Adds a new named argument specifying a sampler (see
+    /// `Kernel::arg_smp_named`).
+    pub fn arg_smp_named(self, name: &'static str, sampler_opt: Option<&'b Sampler>)
+            -> KernelBuilder<'b> {
+        self.arg_smp_named_opt(Some(name), sampler_opt)
+    }
+
+    /// Adds a new argument specifying a scalar value (see `Kernel::arg_scl`).
+    pub fn arg_scl<T>(self, scalar: T) -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        self.push_arg::<T, _>(None, "scalar", ArgKind::Value,
+            move |k, i| k._set_arg(i, KernelArg::Scalar(scalar)))
+    }
+
+    /// Adds a new named argument specifying a scalar value, the default
+    /// value if `None` (see `Kernel::arg_scl_named`).
+    pub fn arg_scl_named<T>(self, name: &'static str, scalar_opt: Option<T>) -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        let scalar = scalar_opt.unwrap_or_default();
+        self.push_arg::<T, _>(Some(name), "scalar", ArgKind::Value,
+            move |k, i| k._set_arg(i, KernelArg::Scalar(scalar)))
+    }
+
+    /// Adds a new argument specifying a vector value (see `Kernel::arg_vec`).
+    pub fn arg_vec<T>(self, vector: T) -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        self.push_arg::<T, _>(None, "vector", ArgKind::Value,
+            move |k, i| k._set_arg(i, KernelArg::Vector(vector)))
+    }
+
+    /// Adds a new named argument specifying a vector value, the default
+    /// value if `None` (see `Kernel::arg_vec_named`).
+    pub fn arg_vec_named<T>(self, name: &'static str, vector_opt: Option<T>) -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        let vector = vector_opt.unwrap_or_default();
+        self.push_arg::<T, _>(Some(name), "vector", ArgKind::Value,
+            move |k, i| k._set_arg(i, KernelArg::Vector(vector)))
+    }
+
+    /// Adds a new argument specifying the allocation of a local variable of
+    /// `length * sizeof(T)` bytes (see `Kernel::arg_loc`).
+    pub fn arg_loc<T>(self, length: usize) -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        self.push_arg::<T, _>(None, "local allocation", ArgKind::Local,
+            move |k, i| k._set_arg::<T>(i, KernelArg::Local(&length)))
+    }
+
+    /// Adds a new argument specifying a shared virtual memory array (see
+    /// `Kernel::arg_svm`).
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn arg_svm<T>(self, svm_vec: &'b SvmVec<T>) -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        self.arg_svm_named_opt(None, Some(svm_vec))
+    }
+
+    /// Adds a new named argument specifying a shared virtual memory array
+    /// (see `Kernel::arg_svm_named`).
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn arg_svm_named<T>(self, name: &'static str, svm_vec_opt: Option<&'b SvmVec<T>>)
+            -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        self.arg_svm_named_opt(Some(name), svm_vec_opt)
+    }
+
+    /// Adds a new argument specifying a pipe (see `Kernel::arg_pipe`).
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn arg_pipe<T>(self, pipe: &'b Pipe<T>) -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        self.arg_mem::<T>(None, "pipe", ArgKind::Pipe, Some(pipe.as_core().clone()))
+    }
+
+    /// Adds a new named argument specifying a pipe (see
+    /// `Kernel::arg_pipe_named`).
+    #[cfg(feature = "opencl_version_2_0")]
+    pub fn arg_pipe_named<T>(self, name: &'static str, pipe_opt: Option<&'b Pipe<T>>)
+            -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        self.arg_mem::<T>(Some(name), "pipe", ArgKind::Pipe,
+            pipe_opt.map(|p| p.as_core().clone()))
+    }
+
+    /// Creates the kernel, then verifies and sets every argument.
+    ///
+    /// Returns a `KernelError::BuilderArgs` listing every problem found if
+    /// the number of arguments does not match the number the kernel
+    /// declares, if any argument is of the wrong type or kind (ex. a buffer
+    /// for a `__local` argument), or if a buffer or image cannot be used
+    /// because of its access flags or size. Argument types are not checked
+    /// on platforms which do not report them (see `Kernel::verify_arg_type`).
+    pub fn build(self) -> OclResult<Kernel> {
+        let program = self.program.ok_or(KernelError::BuilderNoProgram)?;
+        let name = self.name.ok_or(KernelError::BuilderNoKernelName)?;
+
+        let mut kernel = Kernel::new(name.as_str(), program)?;
+        kernel.queue = self.queue;
+        kernel.gwo = self.gwo;
+        kernel.gws = self.gws;
+        kernel.lws = self.lws;
+        kernel.non_uniform = self.non_uniform;
+
+        let mut problems = Vec::new();
+        let specified = self.args.len() as u32;
+
+        if specified > kernel.num_args {
+            problems.push(KernelArgProblem::Count { specified: specified,
+                declared: kernel.num_args });
+        }
+
+        for (arg_idx, arg) in self.args.iter().enumerate().take(kernel.num_args as usize) {
+            let arg_idx = arg_idx as u32;
+            if kernel.bypass_arg_check { break; }
+
+            let arg_type = &kernel.arg_types[arg_idx as usize];
+            if !(arg.is_match)(arg_type) || !arg_type.is_kind_match(arg.arg_kind) {
+                problems.push(KernelArgProblem::TypeMismatch {
+                    idx: arg_idx,
+                    name: kernel.arg_name(arg_idx).or(arg.name).map(|n| n.to_owned()),
+                    specified: arg.kind,
+                    declared: kernel.arg_type_name(arg_idx),
+                });
+            } else if let Some(ref mem) = arg.mem {
+                if let Some(problem) = kernel.mem_arg_problem(arg_idx, mem)? {
+                    problems.push(problem);
+                }
+            }
+        }
+
+        for arg_idx in specified..kernel.num_args {
+            problems.push(KernelArgProblem::Unset { idx: arg_idx,
+                name: kernel.arg_name(arg_idx).map(|n| n.to_owned()) });
+        }
+
+        let mut names = HashMap::with_capacity(self.args.len());
+        for (arg_idx, arg) in self.args.iter().enumerate() {
+            if let Some(name) = arg.name {
+                if names.insert(name, arg_idx as u32).is_some() {
+                    problems.push(KernelArgProblem::DuplicateName { name: name });
+                }
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(KernelError::BuilderArgs { kernel: name, problems: problems }.into());
+        }
+
+        for (arg_idx, arg) in self.args.iter().enumerate() {
+            (arg.set)(&mut kernel, arg_idx as u32)?;
+        }
+        if !names.is_empty() {
+            kernel.named_args = Some(names);
+        }
+        kernel.new_arg_count = specified;
+
+        Ok(kernel)
+    }
+
+    /// Adds an argument of kind `arg_kind` set by `set`, of type `T` for the
+    /// purposes of type checking.
+    fn push_arg<T, F>(mut self, name: Option<&'static str>, kind: &'static str,
+            arg_kind: ArgKind, set: F) -> KernelBuilder<'b>
+            where T: OclPrm + 'static, F: Fn(&mut Kernel, u32) -> OclResult<()> + 'b {
+        self.args.push(BuilderArg {
+            name: name,
+            kind: kind,
+            is_match: ArgType::is_match::<T>,
+            arg_kind: arg_kind,
+            mem: None,
+            set: Box::new(set),
+        });
+        self
+    }
+
+    /// Adds a buffer, image, or pipe argument.
+    fn arg_mem<T>(self, name: Option<&'static str>, kind: &'static str, arg_kind: ArgKind,
+            mem_opt: Option<MemCore>) -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        let mem = mem_opt.clone();
+        // Verified by `::build`:
+        let mut builder = self.push_arg::<T, _>(name, kind, arg_kind, move |k, i| match mem_opt {
+            Some(ref mem) => k._set_arg_unverified::<T>(i, KernelArg::Mem(mem)),
+            None => k._set_arg_unverified::<T>(i, KernelArg::MemNull),
+        });
+        builder.args.last_mut().unwrap().mem = mem;
+        builder
+    }
+
+    /// Adds a sampler argument.
+    fn arg_smp_named_opt(self, name: Option<&'static str>, sampler_opt: Option<&'b Sampler>)
+            -> KernelBuilder<'b> {
+        // Type is ignored:
+        self.push_arg::<u64, _>(name, "sampler", ArgKind::Sampler, move |k, i| match sampler_opt {
+            Some(sampler) => k._set_arg::<u64>(i, KernelArg::Sampler(sampler)),
+            None => k._set_arg::<u64>(i, KernelArg::SamplerNull),
+        })
+    }
+
+    /// Adds a shared virtual memory argument.
+    #[cfg(feature = "opencl_version_2_0")]
+    fn arg_svm_named_opt<T>(self, name: Option<&'static str>, svm_vec_opt: Option<&'b SvmVec<T>>)
+            -> KernelBuilder<'b>
+            where T: OclPrm + 'static {
+        self.push_arg::<T, _>(name, "shared virtual memory array", ArgKind::Buffer,
+            move |k, i| k._set_arg_svm(i, svm_vec_opt))
+    }
+}
+
+
+/// Types whose fields are the arguments of a kernel, in order.
+///
+/// Implement using `#[derive(KernelArgs)]` from the `ocl-derive` crate and
+/// set all arguments at once with `Kernel::set_args`.
+pub trait KernelArgs {
+    /// Returns a name unique to the implementing type.
+    ///
+    /// Used to verify a type against a kernel only the first time it is
+    /// used.
+    fn type_name() -> &'static str;
+
+    /// Calls `visitor.visit` with the name and value of every argument, in
+    /// order.
+    fn visit<V: ArgVisitor>(&self, visitor: &mut V) -> OclResult<()>;
+}
+
+
+/// Visits the arguments of a `KernelArgs` type.
+pub trait ArgVisitor {
+    /// Visits the next argument.
+    fn visit<A: KernelArgValue + ?Sized>(&mut self, name: &'static str, arg: &A)
+        -> OclResult<()>;
+}
+
+
+/// Values which can be used as kernel arguments by `KernelArgs` types.
+pub trait KernelArgValue {
+    /// Returns the kind of argument (ex. "buffer"), used in error messages.
+    fn kind(&self) -> &'static str;
+
+    /// Returns true if this value can be used as an argument of type
+    /// `arg_type`.
+    fn is_match(&self, arg_type: &ArgType) -> bool;
+
+    /// Sets this value as the argument at `arg_idx` without any checks.
+    ///
+    /// Not intended to be called directly. Use `Kernel::set_arg`, which
+    /// verifies the value using `::is_match` and `::as_mem` first.
+    #[doc(hidden)]
+    fn set_arg(&self, kernel: &mut Kernel, arg_idx: u32) -> OclResult<()>;
+
+    /// Returns the buffer, image, or pipe this value refers to, if any.
+    ///
+    /// Used to check what cannot be determined from the type alone (ex. the
+    /// size of a buffer used as a `__constant` argument).
+    fn as_mem(&self) -> Option<&MemCore> {
+        None
+    }
+}
+
+impl<'a, A: KernelArgValue + ?Sized> KernelArgValue for &'a A {
+    fn kind(&self) -> &'static str {
+        (**self).kind()
+    }
+
+    fn is_match(&self, arg_type: &ArgType) -> bool {
+        (**self).is_match(arg_type)
+    }
+
+    fn set_arg(&self, kernel: &mut Kernel, arg_idx: u32) -> OclResult<()> {
+        (**self).set_arg(kernel, arg_idx)
+    }
+
+    fn as_mem(&self) -> Option<&MemCore> {
+        (**self).as_mem()
+    }
+}
+
+impl<T: OclPrm + 'static> KernelArgValue for Buffer<T> {
+    fn kind(&self) -> &'static str {
+        "buffer"
+    }
+
+    fn is_match(&self, arg_type: &ArgType) -> bool {
+        arg_type.is_kind_match(ArgKind::Buffer) && arg_type.is_match::<T>()
+    }
+
+    fn set_arg(&self, kernel: &mut Kernel, arg_idx: u32) -> OclResult<()> {
+        kernel._set_arg_unverified::<T>(arg_idx, KernelArg::Mem(self.as_core()))
+    }
+
+    fn as_mem(&self) -> Option<&MemCore> {
+        Some(self.as_core())
+    }
+}
+
+impl<T: OclPrm> KernelArgValue for Image<T> {
+    fn kind(&self) -> &'static str {
+        "image"
+    }
+
+    fn is_match(&self, arg_type: &ArgType) -> bool {
+        // Type is ignored:
+        arg_type.is_kind_match(ArgKind::Image(None)) && arg_type.is_match::<u64>()
+    }
+
+    fn set_arg(&self, kernel: &mut Kernel, arg_idx: u32) -> OclResult<()> {
+        kernel._set_arg_unverified::<u64>(arg_idx, KernelArg::Mem(self.as_core()))
+    }
+
+    fn as_mem(&self) -> Option<&MemCore> {
+        Some(self.as_core())
+    }
+}
+
+impl KernelArgValue for Sampler {
+    fn kind(&self) -> &'static str {
+        "sampler"
+    }
+
+    fn is_match(&self, arg_type: &ArgType) -> bool {
+        // Type is ignored:
+        arg_type.is_kind_match(ArgKind::Sampler) && arg_type.is_match::<u64>()
+    }
+
+    fn set_arg(&self, kernel: &mut Kernel, arg_idx: u32) -> OclResult<()> {
+        kernel._set_arg_unverified::<u64>(arg_idx, KernelArg::Sampler(self))
+    }
+}
+
+#[cfg(feature = "opencl_version_2_0")]
+impl<T: OclPrm + 'static> KernelArgValue for SvmVec<T> {
+    fn kind(&self) -> &'static str {
+        "shared virtual memory array"
+    }
+
+    fn is_match(&self, arg_type: &ArgType) -> bool {
+        arg_type.is_kind_match(ArgKind::Buffer) && arg_type.is_match::<T>()
+    }
+
+    fn set_arg(&self, kernel: &mut Kernel, arg_idx: u32) -> OclResult<()> {
+        kernel._set_arg_svm_unverified(arg_idx, Some(self))
+    }
+}
+
+#[cfg(feature = "opencl_version_2_0")]
+impl<T: OclPrm + 'static> KernelArgValue for Pipe<T> {
+    fn kind(&self) -> &'static str {
+        "pipe"
+    }
+
+    fn is_match(&self, arg_type: &ArgType) -> bool {
+        arg_type.is_kind_match(ArgKind::Pipe) && arg_type.is_match::<T>()
+    }
+
+    fn set_arg(&self, kernel: &mut Kernel, arg_idx: u32) -> OclResult<()> {
+        kernel._set_arg_unverified::<T>(arg_idx, KernelArg::Mem(self.as_core()))
+    }
+
+    fn as_mem(&self) -> Option<&MemCore> {
+        Some(self.as_core())
+    }
+}
+
+macro_rules! impl_kernel_arg_value {
+    ($variant:ident, $kind:expr, $($ty:ty),+) => {
+        $(impl KernelArgValue for $ty {
+            fn kind(&self) -> &'static str {
+                $kind
+            }
+
+            fn is_match(&self, arg_type: &ArgType) -> bool {
+                arg_type.is_kind_match(ArgKind::Value) && arg_type.is_match::<$ty>()
+            }
+
+            fn set_arg(&self, kernel: &mut Kernel, arg_idx: u32) -> OclResult<()> {
+                kernel._set_arg_unverified::<$ty>(arg_idx, KernelArg::$variant(*self))
+            }
+        })+
+    };
+}
+
+impl_kernel_arg_value!(Scalar, "scalar", i8, u8, i16, u16, i32, u32, i64, u64, f32, f64);
+impl_kernel_arg_value!(Vector, "vector",
+    Char2, Char3, Char4, Char8, Char16,
+    Uchar2, Uchar3, Uchar4, Uchar8, Uchar16,
+    Short2, Short3, Short4, Short8, Short16,
+    Ushort2, Ushort3, Ushort4, Ushort8, Ushort16,
+    Int2, Int3, Int4, Int8, Int16,
+    Uint2, Uint3, Uint4, Uint8, Uint16,
+    Long2, Long3, Long4, Long8, Long16,
+    Ulong2, Ulong3, Ulong4, Ulong8, Ulong16,
+    Float2, Float3, Float4, Float8, Float16,
+    Double2, Double3, Double4, Double8, Double16);
+
+
+/// Checks the arguments of a `KernelArgs` type against a kernel.
+struct ArgVerifier<'k> {
+    kernel: &'k Kernel,
+    arg_idx: u32,
+    problems: Vec<KernelArgProblem>,
+}
+
+impl<'k> ArgVerifier<'k> {
+    /// Returns every problem found, including a count mismatch.
+    fn finish(mut self) -> Vec<KernelArgProblem> {
+        if self.arg_idx > self.kernel.num_args {
+            self.problems.push(KernelArgProblem::Count { specified: self.arg_idx,
+                declared: self.kernel.num_args });
+        }
+        for arg_idx in self.arg_idx..self.kernel.num_args {
+            self.problems.push(KernelArgProblem::Unset { idx: arg_idx,
+                name: self.kernel.arg_name(arg_idx).map(|n| n.to_owned()) });
+        }
+        self.problems
+    }
+}
+
+impl<'k> ArgVisitor for ArgVerifier<'k> {
+    fn visit<A: KernelArgValue + ?Sized>(&mut self, name: &'static str, arg: &A)
+            -> OclResult<()> {
+        let arg_idx = self.arg_idx;
+        self.arg_idx += 1;
+        if arg_idx >= self.kernel.num_args { return Ok(()); }
+
+        if let Some(declared) = self.kernel.arg_name(arg_idx) {
+            if declared != name {
+                self.problems.push(KernelArgProblem::NameMismatch { idx: arg_idx,
+                    specified: name, declared: declared.to_owned() });
+            }
+        }
+
+        if !self.kernel.bypass_arg_check &&
+                !arg.is_match(&self.kernel.arg_types[arg_idx as usize]) {
+            self.problems.push(KernelArgProblem::TypeMismatch {
+                idx: arg_idx,
+                name: Some(name.to_owned()),
+                specified: arg.kind(),
+                declared: self.kernel.arg_type_name(arg_idx),
+            });
+        } else if let Some(mem) = arg.as_mem() {
+            if let Some(problem) = self.kernel.mem_arg_problem(arg_idx, mem)? {
+                self.problems.push(problem);
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Sets the arguments of a (verified) `KernelArgs` type.
+struct ArgSetter<'k> {
+    kernel: &'k mut Kernel,
+    arg_idx: u32,
+    /// Whether these values were just verified by an `ArgVerifier`:
+    verified: bool,
+}
+
+impl<'k> ArgVisitor for ArgSetter<'k> {
+    fn visit<A: KernelArgValue + ?Sized>(&mut self, _name: &'static str, arg: &A)
+            -> OclResult<()> {
+        // Memory objects may differ from those verified by an earlier call:
+        if !self.verified {
+            if let Some(mem) = arg.as_mem() {
+                let problem = self.kernel.mem_arg_problem(self.arg_idx, mem)?;
+                self.kernel.check_arg_problem(problem)?;
+            }
+        }
+        arg.set_arg(self.kernel, self.arg_idx)?;
+        self.arg_idx += 1;
+        Ok(())
+    }
+}
+
+
+/// Returns the smallest `MaxConstantBufferSize` of the devices associated
+/// with a kernel.
+fn max_constant_buffer_size(core: &KernelCore) -> OclResult<Option<u64>> {
+    let mut max_size: Option<u64> = None;
+    for device in core.devices()? {
+        match core::get_device_info(device, DeviceInfo::MaxConstantBufferSize)? {
+            DeviceInfoResult::MaxConstantBufferSize(size) => {
+                max_size = Some(max_size.map_or(size, |max| max.min(size)));
+            },
+            _ => unreachable!(),
+        }
+    }
+    Ok(max_size)
+}
+
+/// Returns true if `kernel` may be enqueued with work-groups of differing
+/// sizes on every device associated with its program.
+///
+/// Non-uniform work-groups require an OpenCL 2.0+ device (which, on OpenCL
+/// 3.0, reports `NonUniformWorkGroupSupport`) and a program compiled for
+/// OpenCL C 2.0+ without `-cl-uniform-work-group-size`.
+fn supports_non_uniform(kernel: &KernelCore) -> OclCoreResult<bool> {
+    let program = kernel.program()?;
+
+    for device in program.devices()? {
+        let version = device.version()?;
+        if version < OpenclVersion::new(2, 0) { return Ok(false); }
+
+        if version >= OpenclVersion::new(3, 0) {
+            match core::get_device_info(&device, DeviceInfo::NonUniformWorkGroupSupport)? {
+                DeviceInfoResult::NonUniformWorkGroupSupport(true) => (),
+                _ => return Ok(false),
+            }
+        }
+
+        match core::get_program_build_info(&program, &device, ProgramBuildInfo::BuildOptions)? {
+            ProgramBuildInfoResult::BuildOptions(ref options)
+                if ocl_kernel_src::allows_non_uniform(options) => (),
+            _ => return Ok(false),
+        }
+    }
+    Ok(true)
+}
+
+/// Returns the result of `supports_non_uniform`, determining and storing it
+/// in `cache` if necessary. Errors are treated as a lack of support.
+fn cached_non_uniform_support(cache: &Mutex<Option<bool>>, kernel: &KernelCore) -> bool {
+    let mut cache = cache.lock().unwrap();
+    if let Some(supported) = *cache { return supported; }
+    let supported = supports_non_uniform(kernel).unwrap_or(false);
+    *cache = Some(supported);
+    supported
+}
+
+/// Enqueues `kernel` as a main dispatch covering the largest multiple of
+/// `lws` within `gws` in each dimension plus a dispatch for each
+/// combination of remainders, placed using global work offsets.
+///
+/// Each dispatch waits on the one before it so that the event of the last
+/// (`new_event`) completes after all of them.
+///
+/// Every dispatch is checked against the kernel's work-group size (and any
+/// `reqd_work_group_size`) and the device's maximum work-item sizes before
+/// any are enqueued. If enqueuing a dispatch nevertheless fails, those
+/// already enqueued will still run and `new_event` is left unset.
+unsafe fn enqueue_split<'e>(queue: &CommandQueueCore, kernel: &KernelCore, dim_count: u32,
+        gwo: Option<[usize; 3]>, gws: [usize; 3], lws: [usize; 3],
+        wait_events: Option<ClWaitListPtrEnum<'e>>, new_event: Option<ClNullEventPtrEnum<'e>>)
+        -> OclResult<()> {
+    // The global work offset, global work size, and local work size of
+    // each dispatch:
+    let mut dispatches = vec![(gwo.unwrap_or([0; 3]), [1usize; 3], [1usize; 3])];
+
+    for dim in 0..dim_count as usize {
+        let main_len = gws[dim] / lws[dim] * lws[dim];
+        let rem_len = gws[dim] - main_len;
+        let mut parts = Vec::with_capacity(2);
+        if main_len > 0 { parts.push((0, main_len, lws[dim])); }
+        if rem_len > 0 { parts.push((main_len, rem_len, rem_len)); }
+
+        dispatches = dispatches.iter()
+            .flat_map(|&dispatch| parts.iter().map(move |&(offset, len, local_len)| {
+                let (mut gwo, mut gws, mut lws) = dispatch;
+                gwo[dim] += offset;
+                gws[dim] = len;
+                lws[dim] = local_len;
+                (gwo, gws, lws)
+            }))
+            .collect();
+    }
+
+    check_dispatches(queue, kernel, dim_count, &dispatches)?;
+
+    let mut wait_events = wait_events;
+    let mut new_event = new_event;
+    let mut prev_event: Option<Event> = None;
+    let last_idx = dispatches.len() - 1;
+
+    for (idx, &(gwo, gws, lws)) in dispatches.iter().enumerate() {
+        let mut event = Event::empty();
+        {
+            let ewait = match prev_event {
+                Some(ref prev_event) => Some(ClWaitListPtrEnum::from(prev_event)),
+                None => wait_events.take(),
+            };
+            let enew = if idx == last_idx {
+                new_event.take()
+            } else {
+                Some(ClNullEventPtrEnum::from(&mut event))
+            };
+            core::enqueue_kernel(queue, kernel, dim_count, Some(gwo), &gws, Some(lws), ewait,
+                enew)?;
+        }
+        prev_event = Some(event);
+    }
+    Ok(())
+}
+
+/// Returns an error if the local work size of any of `dispatches` would be
+/// rejected by `enqueue_kernel`.
+fn check_dispatches(queue: &CommandQueueCore, kernel: &KernelCore, dim_count: u32,
+        dispatches: &[([usize; 3], [usize; 3], [usize; 3])]) -> OclResult<()> {
+    let device = queue.device()?;
+
+    let reqd = core::get_kernel_work_group_info(kernel, &device,
+        KernelWorkGroupInfo::CompileWorkGroupSize);
+    if let Ok(KernelWorkGroupInfoResult::CompileWorkGroupSize(reqd)) = reqd {
+        if reqd[0] != 0 {
+            return Err("KernelCmd::enq: Kernels declared with 'reqd_work_group_size' can not \
+                be split into dispatches of differing local work sizes. Use a global work \
+                size which is a multiple of the required work-group size.".into());
+        }
+    }
+
+    let max_size = match core::get_kernel_work_group_info(kernel, &device,
+            KernelWorkGroupInfo::WorkGroupSize)? {
+        KernelWorkGroupInfoResult::WorkGroupSize(size) => size,
+        _ => unreachable!(),
+    };
+    let max_item_sizes = match core::get_device_info(&device, DeviceInfo::MaxWorkItemSizes)? {
+        DeviceInfoResult::MaxWorkItemSizes(sizes) => sizes,
+        _ => unreachable!(),
+    };
+
+    for &(_, _, lws) in dispatches {
+        let lws = &lws[..dim_count as usize];
+        if lws.iter().product::<usize>() > max_size ||
+                lws.iter().zip(max_item_sizes.iter()).any(|(&len, &max)| len > max) {
+            return Err(format!("KernelCmd::enq: The local work size of a dispatch ({:?}) \
+                exceeds the kernel's work-group size ({}) or the device's maximum work-item \
+                sizes ({:?}).", lws, max_size, max_item_sizes).into());
+        }
+    }
+    Ok(())
+}
+
+/// Returns dimensions with the lengths in `lens` (one to three).
+fn dims_from_lens(lens: &[usize]) -> SpatialDims {
+    match lens.len() {
+        1 => SpatialDims::One(lens[0]),
+        2 => SpatialDims::Two(lens[0], lens[1]),
+        3 => SpatialDims::Three(lens[0], lens[1], lens[2]),
+        _ => SpatialDims::Unspecified,
+    }
+}
+
+/// Formats dimensions as their lengths separated by 'x' (ex. '64x4').
+fn dims_to_string(dims: SpatialDims) -> String {
+    let lens = dims.to_lens().unwrap_or([0; 3]);
+    lens[..dims.dim_count() as usize].iter().map(|len| len.to_string())
+        .collect::<Vec<_>>().join("x")
+}
+
+/// Parses dimensions formatted by `dims_to_string`.
+fn dims_from_str(s: &str) -> Option<SpatialDims> {
+    let lens: Vec<usize> = s.split('x').map(|len| len.parse().ok())
+        .collect::<Option<_>>()?;
+    if lens.contains(&0) { return None; }
+    match dims_from_lens(&lens) {
+        SpatialDims::Unspecified => None,
+        dims => Some(dims),
+    }
+}
+
+/// Returns argument information for a kernel.
+pub fn arg_info(core: &KernelCore, arg_index: u32, info_kind: KernelArgInfo)
+        -> OclCoreResult<KernelArgInfoResult> {
+    let device_versions = match core.device_versions() {
+        Ok(vers) => vers,
+        Err(e) => return Err(e.into()),
+    };
+
+    core::get_kernel_arg_info(core, arg_index, info_kind,
+        Some(&device_versions))
+}
+
+/// Returns the type name for a kernel argument at the specified index.
+pub fn arg_type_name(core: &KernelCore, arg_index: u32) -> OclCoreResult<String> {
+    match arg_info(core, arg_index, KernelArgInfo::TypeName) {
+        Ok(KernelArgInfoResult::TypeName(type_name)) => Ok(type_name),
+        Err(err) => Err(err.into()),
+        _ => unreachable!(),
+    }
+}
+
 
 pub mod arg_type {
     #![allow(unused_imports)]
     use std::any::{Any, TypeId};
+    use std::fmt;
     use ffi::{cl_char, cl_uchar, cl_short, cl_ushort, cl_int, cl_uint, cl_long, cl_ulong,
         cl_half, cl_float, cl_double, cl_bool, cl_bitfield};
-    use core::{Error as OclCoreError, Result as OclCoreResult, Status, OclPrm, Kernel as KernelCore};
+    use core::{Error as OclCoreError, Result as OclCoreResult, Status, OclPrm, Kernel as KernelCore,
+        KernelArgAddressQualifier, KernelArgAccessQualifier, KernelArgTypeQualifier, MemObjectType,
+        MemFlags};
     use error::{Error as OclError, Result as OclResult};
-    use standard::Sampler;
+    use ocl_kernel_src::{self, Scalar};
+    use standard::{Sampler, ArgSig};
     use super::{arg_info, arg_type_name};
 
     pub use core::{
@@ -891,13 +2730,110 @@ pub mod arg_type {
         Sixteen,
     }
 
-    /// The type of a kernel argument derived from its string representation.
-    #[allow(dead_code)]
+    /// The kind of value an argument accepts.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ArgKind {
+        /// A scalar or vector value.
+        Value,
+        /// A `__global` or `__constant` pointer (a buffer or shared virtual
+        /// memory).
+        Buffer,
+        /// A `__local` pointer (a local allocation).
+        Local,
+        /// An image of the contained type, if known.
+        Image(Option<MemObjectType>),
+        Sampler,
+        Pipe,
+    }
+
+    impl ArgKind {
+        /// Returns a description of the kind used in error messages (ex. "2D
+        /// image").
+        pub fn description(&self) -> &'static str {
+            match *self {
+                ArgKind::Value => "scalar or vector",
+                ArgKind::Buffer => "buffer",
+                ArgKind::Local => "local allocation",
+                ArgKind::Image(Some(MemObjectType::Image1d)) => "1D image",
+                ArgKind::Image(Some(MemObjectType::Image1dBuffer)) => "1D image buffer",
+                ArgKind::Image(Some(MemObjectType::Image1dArray)) => "1D image array",
+                ArgKind::Image(Some(MemObjectType::Image2d)) => "2D image",
+                ArgKind::Image(Some(MemObjectType::Image2dArray)) => "2D image array",
+                ArgKind::Image(Some(MemObjectType::Image3d)) => "3D image",
+                ArgKind::Image(_) => "image",
+                ArgKind::Sampler => "sampler",
+                ArgKind::Pipe => "pipe",
+            }
+        }
+
+        /// Returns the kind of a memory object of type `mem_type`.
+        pub fn from_mem_type(mem_type: MemObjectType) -> ArgKind {
+            match mem_type {
+                MemObjectType::Buffer => ArgKind::Buffer,
+                MemObjectType::Pipe => ArgKind::Pipe,
+                image_type => ArgKind::Image(Some(image_type)),
+            }
+        }
+    }
+
+    /// Returns the base type corresponding to a scalar type.
+    fn base_type(scalar: Scalar) -> BaseType {
+        match scalar {
+            Scalar::Char => BaseType::Char,
+            Scalar::Uchar => BaseType::Uchar,
+            Scalar::Short => BaseType::Short,
+            Scalar::Ushort => BaseType::Ushort,
+            Scalar::Int => BaseType::Int,
+            Scalar::Uint => BaseType::Uint,
+            Scalar::Long => BaseType::Long,
+            Scalar::Ulong => BaseType::Ulong,
+            Scalar::Float => BaseType::Float,
+            Scalar::Double => BaseType::Double,
+        }
+    }
+
+    /// Returns the cardinality corresponding to a vector width, as returned
+    /// by `ocl_kernel_src::vector_type`.
+    fn cardinality(card: u8) -> Cardinality {
+        match card {
+            1 => Cardinality::One,
+            2 => Cardinality::Two,
+            3 => Cardinality::Three,
+            4 => Cardinality::Four,
+            8 => Cardinality::Eight,
+            16 => Cardinality::Sixteen,
+            _ => unreachable!("invalid vector width: {}", card),
+        }
+    }
+
+    /// Returns the image type corresponding to an image type name (ex.
+    /// 'image2d_t').
+    fn image_type(type_name: &str) -> Option<MemObjectType> {
+        match type_name {
+            "image1d_t" => Some(MemObjectType::Image1d),
+            "image1d_buffer_t" => Some(MemObjectType::Image1dBuffer),
+            "image1d_array_t" => Some(MemObjectType::Image1dArray),
+            "image2d_t" => Some(MemObjectType::Image2d),
+            "image2d_array_t" => Some(MemObjectType::Image2dArray),
+            "image3d_t" => Some(MemObjectType::Image3d),
+            // Depth and multi-sample images:
+            _ => None,
+        }
+    }
+
+    /// The type of a kernel argument derived from its string representation
+    /// and qualifiers.
     #[derive(Clone, Debug)]
     pub struct ArgType {
         base_type: BaseType,
         cardinality: Cardinality,
         is_ptr: bool,
+        address: Option<KernelArgAddressQualifier>,
+        access: Option<KernelArgAccessQualifier>,
+        type_qualifier: KernelArgTypeQualifier,
+        image_type: Option<MemObjectType>,
+        struct_name: Option<String>,
+        type_name: Option<String>,
     }
 
     impl ArgType {
@@ -908,65 +2844,64 @@ pub mod arg_type {
                 base_type: BaseType::Unknown,
                 cardinality: Cardinality::One,
                 is_ptr: false,
+                address: None,
+                access: None,
+                type_qualifier: KernelArgTypeQualifier::NONE,
+                image_type: None,
+                struct_name: None,
+                type_name: None,
             })
         }
 
         /// Ascertains a `KernelArgType` from the contents of a
         /// `KernelArgInfoResult::TypeName`.
         ///
-        /// * TODO: Optimize or outsource this if possible. Is `::contains`
-        /// the fastest way to parse these in this situation? Should
-        /// `::starts_with` be used for base type names instead?
+        /// Qualifiers included in the type name (ex. 'pipe', 'read_only')
+        /// are recognized but platforms report address, access, and type
+        /// qualifiers separately (see `::from_kern_and_idx`).
         pub fn from_str(type_name: &str) -> OclCoreResult<ArgType> {
-            let is_ptr = type_name.contains("*");
-
-            let card = if type_name.contains("16") {
-                Cardinality::Sixteen
-            } else if type_name.contains("8") {
-                Cardinality::Eight
-            } else if type_name.contains("4") {
-                Cardinality::Four
-            } else if type_name.contains("3") {
-                Cardinality::Three
-            } else if type_name.contains("2") {
-                Cardinality::Two
-            } else {
-                Cardinality::One
-            };
-
-            let base = if type_name.contains("uchar") {
-                BaseType::Uchar
-            } else if type_name.contains("char") {
-                BaseType::Char
-            } else if type_name.contains("ushort") {
-                BaseType::Ushort
-            } else if type_name.contains("short") {
-                BaseType::Short
-            } else if type_name.contains("uint") {
-                BaseType::Uint
-            } else if type_name.contains("int") {
-                BaseType::Int
-            } else if type_name.contains("ulong") {
-                BaseType::Ulong
-            } else if type_name.contains("long") {
-                BaseType::Long
-            } else if type_name.contains("float") {
-                BaseType::Float
-            } else if type_name.contains("double") {
-                BaseType::Double
-            } else if type_name.contains("sampler") {
-                BaseType::Sampler
-            } else if type_name.contains("image") {
-                BaseType::Image
-            } else {
-                BaseType::Unknown
-            };
+            let type_name = type_name.trim();
+            let is_ptr = type_name.ends_with('*');
+            let mut type_qualifier = KernelArgTypeQualifier::NONE;
+
+            let words: Vec<&str> = type_name.trim_right_matches('*').split_whitespace()
+                .filter(|&w| match w {
+                    "pipe" => {
+                        type_qualifier |= KernelArgTypeQualifier::PIPE;
+                        false
+                    },
+                    "const" | "volatile" | "restrict" | "read_only" | "__read_only" |
+                        "write_only" | "__write_only" | "read_write" | "__read_write" => false,
+                    _ => true,
+                })
+                .collect();
+
+            let mut arg_type = ArgType::unknown()?;
+            arg_type.is_ptr = is_ptr;
+            arg_type.type_qualifier = type_qualifier;
+            arg_type.type_name = Some(type_name.to_owned());
+
+            match words.first().cloned() {
+                Some("struct") | Some("union") => {
+                    arg_type.struct_name = Some(words[1..].join(" "));
+                },
+                Some("sampler_t") => arg_type.base_type = BaseType::Sampler,
+                Some(name) if name.starts_with("image") => {
+                    arg_type.base_type = BaseType::Image;
+                    arg_type.image_type = image_type(name);
+                },
+                Some(name) => {
+                    // Other types (`half`, `size_t`, typedefs, etc.) match
+                    // any primitive:
+                    if let Some((scalar, card)) = ocl_kernel_src::vector_type(name) {
+                        arg_type.base_type = base_type(scalar);
+                        arg_type.cardinality = cardinality(card);
+                    }
+                },
+                None => (),
+            }
 
-            Ok(ArgType {
-                base_type: base,
-                cardinality: card,
-                is_ptr: is_ptr,
-            })
+            Ok(arg_type)
         }
 
         /// Returns a new argument type specifier.
@@ -978,18 +2913,36 @@ pub mod arg_type {
         /// `ArgType::unknown()` (which matches any argument type) is returned
         /// if any are found.
         pub fn from_kern_and_idx(core: &KernelCore, arg_index: u32) -> OclCoreResult<ArgType> {
-            use core::EmptyInfoResultError;
+            use core::{EmptyInfoResultError, KernelArgInfo, KernelArgInfoResult};
             use core::ErrorKind as OclCoreErrorKind;
 
             match arg_type_name(core, arg_index) {
-                Ok(type_name) => ArgType::from_str(type_name.as_str()),
+                Ok(type_name) => {
+                    let mut arg_type = ArgType::from_str(type_name.as_str())?;
+
+                    // Pipes report the type name of their packets. Only the
+                    // type qualifier distinguishes them:
+                    if let Ok(KernelArgInfoResult::TypeQualifier(qualifier)) =
+                            arg_info(core, arg_index, KernelArgInfo::TypeQualifier) {
+                        arg_type.type_qualifier |= qualifier;
+                    }
+                    if let Ok(KernelArgInfoResult::AddressQualifier(address)) =
+                            arg_info(core, arg_index, KernelArgInfo::AddressQualifier) {
+                        arg_type.address = Some(address);
+                    }
+                    if let Ok(KernelArgInfoResult::AccessQualifier(access)) =
+                            arg_info(core, arg_index, KernelArgInfo::AccessQualifier) {
+                        arg_type.access = Some(access);
+                    }
+
+                    Ok(arg_type)
+                },
                 Err(err) => {
                     // Escape hatches for known, platform-specific errors.
                     match *err.kind() {
                         OclCoreErrorKind::Api(ref api_err) => {
                             if api_err.status() == Status::CL_KERNEL_ARG_INFO_NOT_AVAILABLE {
-                                return Ok(ArgType { base_type: BaseType::Unknown,
-                                    cardinality: Cardinality::One, is_ptr: false })
+                                return ArgType::unknown();
                             }
                         }
                         OclCoreErrorKind::EmptyInfoResult(EmptyInfoResultError::KernelArg) => {
@@ -1003,6 +2956,22 @@ pub mod arg_type {
             }
         }
 
+        /// Returns a new argument type specifier from a signature parsed
+        /// from program source.
+        pub fn from_sig(sig: &ArgSig) -> OclCoreResult<ArgType> {
+            let mut arg_type = ArgType::from_str(sig.type_name())?;
+            arg_type.type_qualifier |= sig.type_qualifier();
+            arg_type.address = Some(sig.address());
+            arg_type.access = Some(sig.access());
+            Ok(arg_type)
+        }
+
+        /// Returns true if the type could not be determined, in which case
+        /// every type matches.
+        pub fn is_unknown(&self) -> bool {
+            self.type_name.is_none()
+        }
+
         /// Returns true if the type of `T` matches the base type of this `ArgType`.
         pub fn is_match<T: OclPrm + Any + 'static>(&self) -> bool {
             match self.base_type {
@@ -1173,10 +3142,139 @@ pub mod arg_type {
             }
         }
 
-        #[allow(dead_code)]
+        /// Returns true if a value of kind `kind` can be used as this
+        /// argument.
+        ///
+        /// Image types are only compared if both are known. Every kind
+        /// matches if the type was undetermined.
+        pub fn is_kind_match(&self, kind: ArgKind) -> bool {
+            if self.is_unknown() { return true; }
+
+            match kind {
+                ArgKind::Value => !self.is_ptr && !self.is_pipe() &&
+                    self.base_type != BaseType::Image && self.base_type != BaseType::Sampler,
+                ArgKind::Buffer => self.is_ptr &&
+                    self.address != Some(KernelArgAddressQualifier::Local),
+                ArgKind::Local => self.is_ptr && (self.address.is_none() ||
+                    self.address == Some(KernelArgAddressQualifier::Local)),
+                ArgKind::Image(image_type) => self.base_type == BaseType::Image &&
+                    (image_type.is_none() || self.image_type.is_none() ||
+                        image_type == self.image_type),
+                ArgKind::Sampler => self.base_type == BaseType::Sampler,
+                ArgKind::Pipe => self.is_pipe(),
+            }
+        }
+
+        /// Returns true if a memory object created with `flags` can be used
+        /// as this argument.
+        ///
+        /// Images must be readable by the kernel if declared `read_only` or
+        /// `read_write` and writable if declared `write_only` or
+        /// `read_write`. Buffers declared `const` or `__constant` cannot be
+        /// `WRITE_ONLY`.
+        pub fn is_access_match(&self, flags: MemFlags) -> bool {
+            let write_only = flags.contains(MemFlags::WRITE_ONLY);
+            let read_only = flags.contains(MemFlags::READ_ONLY);
+
+            if self.base_type == BaseType::Image {
+                match self.access {
+                    Some(KernelArgAccessQualifier::ReadOnly) => !write_only,
+                    Some(KernelArgAccessQualifier::WriteOnly) => !read_only,
+                    Some(KernelArgAccessQualifier::ReadWrite) => !write_only && !read_only,
+                    _ => true,
+                }
+            } else if self.is_ptr {
+                !(write_only && (self.type_qualifier.contains(KernelArgTypeQualifier::CONST) ||
+                    self.address == Some(KernelArgAddressQualifier::Constant)))
+            } else {
+                true
+            }
+        }
+
+        /// Returns the base type.
+        pub fn base_type(&self) -> BaseType {
+            self.base_type
+        }
+
+        /// Returns the cardinality.
+        pub fn cardinality(&self) -> Cardinality {
+            self.cardinality
+        }
+
         pub fn is_ptr(&self) -> bool {
             self.is_ptr
         }
+
+        /// Returns true if this argument is a pipe. The base type and
+        /// cardinality of a pipe are those of its packets.
+        pub fn is_pipe(&self) -> bool {
+            self.type_qualifier.contains(KernelArgTypeQualifier::PIPE)
+        }
+
+        /// Returns the address space qualifier, if reported.
+        pub fn address(&self) -> Option<KernelArgAddressQualifier> {
+            self.address
+        }
+
+        /// Returns the access qualifier, if reported.
+        pub fn access(&self) -> Option<KernelArgAccessQualifier> {
+            self.access
+        }
+
+        /// Returns the type qualifiers.
+        pub fn type_qualifier(&self) -> KernelArgTypeQualifier {
+            self.type_qualifier
+        }
+
+        /// Returns the type of image if this argument is an image of a known
+        /// type (depth and multi-sample images are not).
+        pub fn image_type(&self) -> Option<MemObjectType> {
+            self.image_type
+        }
+
+        /// Returns the struct (or union) name if this argument is, or points
+        /// to, a struct.
+        pub fn struct_name(&self) -> Option<&str> {
+            self.struct_name.as_ref().map(|n| n.as_str())
+        }
+
+        /// Returns the type name as reported by the platform or parsed from
+        /// the program source.
+        pub fn type_name(&self) -> Option<&str> {
+            self.type_name.as_ref().map(|n| n.as_str())
+        }
+    }
+
+    impl fmt::Display for ArgType {
+        /// Formats the type name along with the address space of local and
+        /// constant pointers and the access qualifier of images and pipes
+        /// (ex. '__local float*', 'write_only image2d_t').
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let type_name = match self.type_name {
+                Some(ref type_name) => type_name,
+                None => return write!(f, "<unknown>"),
+            };
+
+            if self.is_ptr {
+                match self.address {
+                    Some(KernelArgAddressQualifier::Local) => write!(f, "__local ")?,
+                    Some(KernelArgAddressQualifier::Constant) => write!(f, "__constant ")?,
+                    _ => (),
+                }
+            }
+            if self.base_type == BaseType::Image || self.is_pipe() {
+                match self.access {
+                    Some(KernelArgAccessQualifier::ReadOnly) => write!(f, "read_only ")?,
+                    Some(KernelArgAccessQualifier::WriteOnly) => write!(f, "write_only ")?,
+                    Some(KernelArgAccessQualifier::ReadWrite) => write!(f, "read_write ")?,
+                    _ => (),
+                }
+            }
+            if self.is_pipe() && type_name.split_whitespace().next() != Some("pipe") {
+                write!(f, "pipe ")?;
+            }
+            write!(f, "{}", type_name)
+        }
     }
 
     impl<'a> From<&'a str> for ArgType {
@@ -0,0 +1,198 @@
+//! Kernel signatures parsed from OpenCL C source.
+//!
+//! Used to provide argument names and types on platforms which do not
+//! report them (OpenCL 1.1) or which report them incorrectly. Parsing is done
+//! by the `ocl-kernel-src` crate (shared with `ocl-derive` and `ocl-mock`),
+//! which understands only comments, preprocessor conditionals (macros are not
+//! expanded) and `__kernel` function definitions.
+
+use std::ffi::CString;
+use core::{KernelArgAddressQualifier, KernelArgAccessQualifier, KernelArgTypeQualifier};
+use ocl_kernel_src::{self, Address, Access};
+
+
+/// Where kernel argument names and types are taken from when a kernel is
+/// created.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArgInfoSource {
+    /// Prefer the argument info reported by the platform
+    /// (`KernelArgInfo`), using the program source only when the platform
+    /// does not report it (OpenCL 1.1).
+    Metadata,
+    /// Prefer the argument info parsed from the program source, using the
+    /// platform's argument info only when the source is unavailable (for
+    /// programs built from binaries or IL) or a kernel could not be parsed.
+    Source,
+}
+
+impl Default for ArgInfoSource {
+    fn default() -> ArgInfoSource {
+        ArgInfoSource::Metadata
+    }
+}
+
+
+/// A kernel argument declaration parsed from source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArgSig {
+    name: String,
+    type_name: String,
+    address: KernelArgAddressQualifier,
+    access: KernelArgAccessQualifier,
+    type_qualifier: KernelArgTypeQualifier,
+}
+
+impl ArgSig {
+    /// Returns the declared argument name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type name in the form reported by
+    /// `KernelArgInfo::TypeName` (e.g. 'float*', 'uint4', 'image2d_t').
+    ///
+    /// Pipes report the type of their packets.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns the address space qualifier (`__private` if unspecified,
+    /// `__global` for images and pipes).
+    pub fn address(&self) -> KernelArgAddressQualifier {
+        self.address
+    }
+
+    /// Returns the access qualifier (`None` for arguments other than images
+    /// and pipes, which default to `read_only`).
+    pub fn access(&self) -> KernelArgAccessQualifier {
+        self.access
+    }
+
+    /// Returns the type qualifiers.
+    pub fn type_qualifier(&self) -> KernelArgTypeQualifier {
+        self.type_qualifier
+    }
+
+    /// Returns true if the argument is a pipe.
+    pub fn is_pipe(&self) -> bool {
+        self.type_qualifier.contains(KernelArgTypeQualifier::PIPE)
+    }
+}
+
+
+/// A kernel function declaration parsed from source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernelSig {
+    name: String,
+    args: Vec<ArgSig>,
+    conditional: bool,
+}
+
+impl KernelSig {
+    /// Returns the kernel function name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the argument declarations, in order.
+    pub fn args(&self) -> &[ArgSig] {
+        &self.args
+    }
+
+    /// Returns true if this definition is within a conditional preprocessor
+    /// block (`#if`, `#ifdef`, etc.). Directives are not evaluated so such a
+    /// definition may not be the one compiled.
+    pub fn is_conditional(&self) -> bool {
+        self.conditional
+    }
+}
+
+
+fn arg_sig(sig: &ocl_kernel_src::ArgSig) -> ArgSig {
+    ArgSig {
+        name: sig.name.clone(),
+        type_name: sig.type_name.clone(),
+        address: match sig.address {
+            Address::Global => KernelArgAddressQualifier::Global,
+            Address::Local => KernelArgAddressQualifier::Local,
+            Address::Constant => KernelArgAddressQualifier::Constant,
+            Address::Private => KernelArgAddressQualifier::Private,
+        },
+        access: match sig.access {
+            Access::ReadOnly => KernelArgAccessQualifier::ReadOnly,
+            Access::WriteOnly => KernelArgAccessQualifier::WriteOnly,
+            Access::ReadWrite => KernelArgAccessQualifier::ReadWrite,
+            Access::None => KernelArgAccessQualifier::None,
+        },
+        type_qualifier: KernelArgTypeQualifier::from_bits_truncate(sig.type_qualifier),
+    }
+}
+
+/// Returns the signatures of all kernel functions defined in `src`.
+fn parse_kernels(src: &str) -> Vec<KernelSig> {
+    ocl_kernel_src::parse_kernels(src).into_iter()
+        .map(|k| KernelSig {
+            name: k.name,
+            args: k.args.iter().map(arg_sig).collect(),
+            conditional: k.conditional,
+        })
+        .collect()
+}
+
+/// Returns the signatures of all kernel functions defined in a program's
+/// source strings.
+pub(crate) fn parse_src_strings(src_strings: &[CString]) -> Vec<KernelSig> {
+    let src: Vec<_> = src_strings.iter().map(|s| String::from_utf8_lossy(s.as_bytes())).collect();
+    parse_kernels(&src.concat())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_signatures() {
+        let src = r#"
+            #define SCALE 2
+            // __kernel void commented_out(int a) {}
+            __kernel void add(__global float* buf, const float4 v, __local unsigned int* tmp,
+                    __constant uchar lut[16]) {
+                buf[get_global_id(0)] += SCALE;
+            }
+
+            /* A prototype: */
+            __kernel void proto(int a);
+
+            kernel __attribute__((reqd_work_group_size(64, 1, 1)))
+            void img(read_only image2d_t src, sampler_t smp, __write_only pipe uchar16 out,
+                    __global struct Foo* foo) {}
+        "#;
+
+        let kernels = parse_kernels(src);
+        assert_eq!(kernels.len(), 2);
+
+        let add = &kernels[0];
+        assert_eq!(add.name(), "add");
+        let types: Vec<_> = add.args().iter().map(|a| (a.name(), a.type_name())).collect();
+        assert_eq!(types, [("buf", "float*"), ("v", "float4"), ("tmp", "uint*"),
+            ("lut", "uchar*")]);
+        assert_eq!(add.args()[0].address(), KernelArgAddressQualifier::Global);
+        assert_eq!(add.args()[1].address(), KernelArgAddressQualifier::Private);
+        assert_eq!(add.args()[1].type_qualifier(), KernelArgTypeQualifier::NONE);
+        assert_eq!(add.args()[2].address(), KernelArgAddressQualifier::Local);
+        assert_eq!(add.args()[3].address(), KernelArgAddressQualifier::Constant);
+        assert_eq!(add.args()[3].type_qualifier(), KernelArgTypeQualifier::CONST);
+
+        let img = &kernels[1];
+        assert_eq!(img.name(), "img");
+        assert_eq!(img.args()[0].type_name(), "image2d_t");
+        assert_eq!(img.args()[0].access(), KernelArgAccessQualifier::ReadOnly);
+        assert_eq!(img.args()[1].type_name(), "sampler_t");
+        assert_eq!(img.args()[2].name(), "out");
+        assert_eq!(img.args()[2].type_name(), "uchar16");
+        assert!(img.args()[2].is_pipe());
+        assert_eq!(img.args()[2].access(), KernelArgAccessQualifier::WriteOnly);
+        assert_eq!(img.args()[3].type_name(), "struct Foo*");
+    }
+}
@@ -7,6 +7,7 @@ mod device;
 mod context;
 mod program;
 mod kernel;
+mod kernel_src;
 mod queue;
 mod buffer;
 mod image;
@@ -14,13 +15,23 @@ mod sampler;
 mod pro_que;
 mod event;
 mod spatial_dims;
+#[cfg(feature = "opencl_version_2_0")]
+mod svm;
+#[cfg(feature = "opencl_version_2_0")]
+mod pipe;
 
 pub use self::platform::Platform;
-pub use self::device::{DeviceError, Device, DeviceSpecifier};
+pub use self::device::{DeviceError, Device, SubDevice, DeviceSpecifier};
+#[cfg(feature = "opencl_version_2_1")]
+pub use self::device::TimerCorrelation;
 pub use self::context::{Context, ContextBuilder};
-pub use self::program::{Program, ProgramBuilder, BuildOpt};
-pub use self::queue::Queue;
-pub use self::kernel::{Kernel, KernelCmd};
+pub use self::program::{Program, ProgramBuilder, BuildOpt, FutureProgram, ProgramBuildError,
+    SourceDiagnostic, SourceOrigin};
+pub use self::kernel_src::{ArgInfoSource, KernelSig, ArgSig};
+pub use self::queue::{Queue, QueueBuilder, NativeKernelCmd, NativeKernelMem,
+    NativeKernelStatus};
+pub use self::kernel::{Kernel, KernelCmd, KernelBuilder, KernelError, KernelArgProblem,
+    KernelArgs, ArgVisitor, KernelArgValue, ArgType, ArgKind, ArgIdxSpecifier, LwsCache};
 pub use self::buffer::{BufferCmdKind, BufferCmdDataShape, BufferCmd, Buffer, QueCtx,
     BufferBuilder, BufferReadCmd, BufferWriteCmd, BufferMapCmd, BufferCmdError};
 pub use self::image::{Image, ImageCmd, ImageCmdKind, ImageBuilder};
@@ -28,6 +39,11 @@ pub use self::sampler::Sampler;
 pub use self::pro_que::{ProQue, ProQueBuilder};
 pub use self::event::{Event, EventArray, EventList, IntoMarker, RawEventArray, IntoRawEventArray};
 pub use self::spatial_dims::SpatialDims;
+#[cfg(feature = "opencl_version_2_0")]
+pub use self::svm::{SvmKind, SvmRef, SvmVec, SvmBox, SvmMap, SvmMapCmd, SvmUnmapCmd, SvmCopyCmd,
+    SvmFillCmd};
+#[cfg(feature = "opencl_version_2_0")]
+pub use self::pipe::Pipe;
 #[cfg(not(feature = "async_block"))]
 pub use self::cb::{_unpark_task, box_raw_void};
 pub use self::traits::{MemLen, WorkDims};
@@ -84,6 +100,44 @@ mod cb {
 
 
 
+/// Calls `func` from within a callback invoked by the OpenCL runtime,
+/// returning `false` if it panicked.
+///
+/// Unwinding into the OpenCL runtime is undefined behavior so every such
+/// callback must call user code through this.
+fn call_from_runtime<F: FnOnce()>(func: F) -> bool {
+    use std::panic::{self, AssertUnwindSafe};
+
+    panic::catch_unwind(AssertUnwindSafe(func)).is_ok()
+}
+
+/// Registers `callback` to be called once the memory object has been
+/// released by the driver.
+fn set_mem_destructor<F>(mem: &::core::Mem, callback: F) -> ::error::Result<()>
+        where F: FnOnce() + Send + 'static
+{
+    use core::ffi::{c_void, cl_mem};
+
+    extern "C" fn _call_destructor<F: FnOnce()>(_mem: cl_mem, user_data: *mut c_void) {
+        let callback = unsafe { Box::from_raw(user_data as *mut F) };
+
+        // There is no one to report a panic to:
+        call_from_runtime(move || callback());
+    }
+
+    let user_data = Box::into_raw(Box::new(callback));
+
+    unsafe {
+        ::core::set_mem_object_destructor_callback(mem, _call_destructor::<F>,
+                user_data as *mut c_void)
+            .map_err(|err| {
+                drop(Box::from_raw(user_data));
+                ::error::Error::from(err)
+            })
+    }
+}
+
+
 //=============================================================================
 //================================== TYPES ====================================
 //=============================================================================
@@ -0,0 +1,138 @@
+//! Pipe memory objects (OpenCL 2.0+).
+//!
+//! A pipe is a FIFO of packets which may only be accessed from within
+//! kernels, using the `read_pipe` and `write_pipe` built-ins. Pipes allow the
+//! output of one kernel to be streamed directly into another without any
+//! host involvement.
+
+use std;
+use std::mem;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use core::{self, Result as OclCoreResult, OclPrm, Mem as MemCore, MemFlags, MemInfo,
+    MemInfoResult, PipeInfo, PipeInfoResult, AsMem};
+use error::{Error as OclError, Result as OclResult};
+use standard::Context;
+
+
+/// A pipe memory object holding up to `max_packets` packets of type `T`.
+///
+/// Pipes are not accessible by the host. Pass them to a producer kernel
+/// (declared with a `__write_only pipe T` parameter) and a consumer kernel
+/// (declared with a `__read_only pipe T` parameter) using `Kernel::arg_pipe`,
+/// `KernelBuilder::arg_pipe` or `Kernel::set_arg_pipe_named`.
+///
+#[derive(Clone, Debug)]
+pub struct Pipe<T: OclPrm> {
+    obj_core: MemCore,
+    max_packets: u32,
+    _data: PhantomData<T>,
+}
+
+impl<T: OclPrm> Pipe<T> {
+    /// Creates a new pipe able to hold `max_packets` packets of type `T`.
+    ///
+    /// The pipe is created with the default flags (`MEM_READ_WRITE |
+    /// MEM_HOST_NO_ACCESS`).
+    ///
+    /// See [SDK Docs](https://www.khronos.org/registry/OpenCL/sdk/2.0/docs/man/xhtml/clCreatePipe.html)
+    /// for more information.
+    ///
+    pub fn new(context: &Context, max_packets: u32) -> OclResult<Pipe<T>> {
+        Pipe::with_flags(context, MemFlags::new(), max_packets)
+    }
+
+    /// Creates a new pipe using the specified flags.
+    ///
+    /// Only `MEM_READ_WRITE` and `MEM_HOST_NO_ACCESS` may be specified. An
+    /// empty set of flags is equivalent to specifying both.
+    ///
+    pub fn with_flags(context: &Context, flags: MemFlags, max_packets: u32)
+            -> OclResult<Pipe<T>> {
+        let device_versions = context.device_versions()?;
+        let obj_core = core::create_pipe(context, flags, mem::size_of::<T>() as u32, max_packets,
+            Some(&device_versions)).map_err(OclError::from)?;
+
+        Ok(Pipe {
+            obj_core: obj_core,
+            max_packets: max_packets,
+            _data: PhantomData,
+        })
+    }
+
+    /// Returns the size of each packet in bytes.
+    #[inline]
+    pub fn packet_size(&self) -> usize {
+        mem::size_of::<T>()
+    }
+
+    /// Returns the maximum number of packets the pipe can hold.
+    #[inline]
+    pub fn max_packets(&self) -> u32 {
+        self.max_packets
+    }
+
+    /// Returns a reference to the core pointer wrapper, usable by functions in
+    /// the `core` module.
+    #[inline]
+    pub fn as_core(&self) -> &MemCore {
+        &self.obj_core
+    }
+
+    /// Returns info about the underlying memory object.
+    #[inline]
+    pub fn mem_info(&self, info_kind: MemInfo) -> OclCoreResult<MemInfoResult> {
+        core::get_mem_object_info(&self.obj_core, info_kind)
+    }
+
+    /// Returns info about the pipe.
+    #[inline]
+    pub fn info(&self, info_kind: PipeInfo) -> OclCoreResult<PipeInfoResult> {
+        core::get_pipe_info(&self.obj_core, info_kind)
+    }
+
+    /// Formats pipe and memory info.
+    fn fmt_info(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Pipe")
+            .field("PacketSize", &self.info(PipeInfo::PacketSize))
+            .field("MaxPackets", &self.info(PipeInfo::MaxPackets))
+            .field("Type", &self.mem_info(MemInfo::Type))
+            .field("Flags", &self.mem_info(MemInfo::Flags))
+            .field("Size", &self.mem_info(MemInfo::Size))
+            .field("ReferenceCount", &self.mem_info(MemInfo::ReferenceCount))
+            .field("Context", &self.mem_info(MemInfo::Context))
+            .finish()
+    }
+}
+
+impl<T: OclPrm> Deref for Pipe<T> {
+    type Target = MemCore;
+
+    fn deref(&self) -> &MemCore {
+        &self.obj_core
+    }
+}
+
+impl<T: OclPrm> DerefMut for Pipe<T> {
+    fn deref_mut(&mut self) -> &mut MemCore {
+        &mut self.obj_core
+    }
+}
+
+impl<T: OclPrm> AsRef<MemCore> for Pipe<T> {
+    fn as_ref(&self) -> &MemCore {
+        &self.obj_core
+    }
+}
+
+impl<T: OclPrm> AsMem<T> for Pipe<T> {
+    fn as_mem(&self) -> &MemCore {
+        &self.obj_core
+    }
+}
+
+impl<T: OclPrm> std::fmt::Display for Pipe<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.fmt_info(f)
+    }
+}
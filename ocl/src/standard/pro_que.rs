@@ -1,10 +1,11 @@
 //! A convenient wrapper for `Program` and `Queue`.
 
 use std::ops::Deref;
+use std::collections::HashMap;
 use error::{Error as OclError, Result as OclResult};
 use core::{OclPrm, CommandQueueProperties};
 use standard::{Platform, Device, Context, ProgramBuilder, Program, Queue, Kernel, Buffer,
-    MemLen, SpatialDims, WorkDims, DeviceSpecifier};
+    MemLen, SpatialDims, WorkDims, DeviceSpecifier, KernelBuilder};
 
 static DIMS_ERR_MSG: &'static str = "This 'ProQue' has not had any dimensions specified. Use
     'ProQueBuilder::dims' during creation or 'ProQue::set_dims' after creation to specify.";
@@ -35,12 +36,14 @@ const DEBUG_PRINT: bool = false;
 /// Now handled automatically. Freely use, store, clone, discard, share among
 /// threads... put some on your toast... whatever.
 ///
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct ProQue {
     context: Context,
     queue: Queue,
     program: Program,
     dims: Option<SpatialDims>,
+    /// Every kernel in the program, created by the first call to `::kernels`:
+    kernels: Option<HashMap<String, Kernel>>,
 }
 
 impl ProQue {
@@ -67,6 +70,7 @@ impl ProQue {
             queue: queue,
             program: program,
             dims: dims.map(|d| d.into()),
+            kernels: None,
         }
     }
 
@@ -81,6 +85,45 @@ impl ProQue {
         }
     }
 
+    /// Returns a new `KernelBuilder` for the kernel function named `name`
+    /// with the program, default queue, and dimensions (as the global work
+    /// size) of this `ProQue` pre-assigned.
+    pub fn kernel_builder<S: Into<String>>(&self, name: S) -> KernelBuilder {
+        let builder = Kernel::builder().program(&self.program).name(name)
+            .queue(self.queue.clone());
+
+        match self.dims {
+            Some(d) => builder.gws(d),
+            None => builder,
+        }
+    }
+
+    /// Returns a kernel with pre-assigned dimensions for every kernel
+    /// function in the program, keyed by kernel name.
+    ///
+    /// Kernels are created by the first call and kept by this `ProQue`.
+    /// Later calls return the same kernels, along with any arguments set on
+    /// them and the dimensions they were created with. Clones of this
+    /// `ProQue` do not share them and create their own when needed.
+    pub fn kernels(&mut self) -> OclResult<&mut HashMap<String, Kernel>> {
+        if self.kernels.is_none() {
+            let kernels = self.program.kernels()?.into_iter().map(|(name, kernel)| {
+                let kernel = kernel.queue(self.queue.clone());
+                match self.dims {
+                    Some(d) => (name, kernel.gws(d)),
+                    None => (name, kernel),
+                }
+            }).collect();
+            self.kernels = Some(kernels);
+        }
+        Ok(self.kernels.as_mut().expect("ProQue::kernels: Kernels not created."))
+    }
+
+    /// Returns the names of every kernel function in the program.
+    pub fn kernel_names(&self) -> OclResult<Vec<String>> {
+        self.program.kernel_names()
+    }
+
     /// Returns a new buffer.
     ///
     /// The default dimensions and queue from this `ProQue` will be used.
@@ -154,6 +197,22 @@ impl ProQue {
     }
 }
 
+impl Clone for ProQue {
+    /// Returns a clone without any kernels created by `::kernels`.
+    ///
+    /// Those kernels may have unset arguments, which makes them impossible
+    /// to clone (see `Kernel::clone`).
+    fn clone(&self) -> ProQue {
+        ProQue {
+            context: self.context.clone(),
+            queue: self.queue.clone(),
+            program: self.program.clone(),
+            dims: self.dims,
+            kernels: None,
+        }
+    }
+}
+
 impl MemLen for ProQue {
     fn to_len(&self) -> usize {
         self.dims().to_len()
@@ -405,7 +464,7 @@ impl ProQueBuilder {
         let queue = Queue::new(&context, device, self.queue_properties)?;
 
         // println!("PROQUEBUILDER: About to load SRC_STRINGS.");
-        let src_strings = program_builder.get_src_strings().map_err(|e| e.to_string())?;
+        let (src_strings, source_map) = program_builder.get_src_strings_mapped()?;
         // println!("PROQUEBUILDER: About to load CMPLR_OPTS.");
         let cmplr_opts = program_builder.get_compiler_options().map_err(|e| e.to_string())?;
         // println!("PROQUEBUILDER: All done.");
@@ -415,9 +474,7 @@ impl ProQueBuilder {
             src_strings,
             Some(&[device]),
             cmplr_opts,
-
-
-        )?;
+        ).map_err(|err| source_map.map_err(err))?;
 
         Ok(ProQue::new(context, queue, program, self.dims))
     }
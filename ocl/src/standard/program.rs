@@ -2,19 +2,35 @@
 use std;
 use std::ops::{Deref, DerefMut};
 use std::ffi::CString;
-use std::io::Read;
-use std::fs::File;
-use std::path::PathBuf;
-use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
 use std::convert::Into;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "opencl_version_2_2")]
+use std::{mem, slice};
 
 
+use futures::{Future, Poll, Async};
+use futures::task::{self, Task};
 use core::{self, Result as OclCoreResult, Program as ProgramCore, Context as ContextCore,
-    ProgramInfo, ProgramInfoResult, ProgramBuildInfo, ProgramBuildInfoResult};
+    ProgramInfo, ProgramInfoResult, ProgramBuildInfo, ProgramBuildInfoResult, ProgramBuildStatus,
+    KernelInfo, DeviceInfo, DeviceInfoResult};
+#[cfg(not(feature = "async_block"))]
+use core::ffi::c_void;
+use core::error::{Error as OclCoreError, ErrorKind as OclCoreErrorKind};
+use core::{BuildDiagnostic, DiagnosticSeverity};
 #[cfg(feature = "opencl_version_2_1")]
 use core::ClVersions;
-use error::{Result as OclResult, Error as OclError};
-use standard::{Context, Device, DeviceSpecifier};
+#[cfg(feature = "opencl_version_2_2")]
+use core::OclPrm;
+use failure::Fail;
+use error::{Result as OclResult, Error as OclError, ErrorKind as OclErrorKind};
+use standard::{Context, Device, DeviceSpecifier, Kernel, Platform, KernelSig, ArgInfoSource};
+use standard::kernel_src;
+use ocl_kernel_src;
 
 
 /// A program from which kernels can be created from.
@@ -27,7 +43,11 @@ use standard::{Context, Device, DeviceSpecifier};
 /// as you please.
 ///
 #[derive(Clone, Debug)]
-pub struct Program(ProgramCore);
+pub struct Program {
+    obj_core: ProgramCore,
+    kernel_sigs: Option<Arc<Vec<KernelSig>>>,
+    arg_info_source: ArgInfoSource,
+}
 
 impl Program {
     /// Returns a new `ProgramBuilder`.
@@ -35,6 +55,29 @@ impl Program {
         ProgramBuilder::new()
     }
 
+    /// Returns a new program wrapping a pre-created core program, without
+    /// kernel signatures.
+    fn from_core(obj_core: ProgramCore) -> Program {
+        Program {
+            obj_core: obj_core,
+            kernel_sigs: None,
+            arg_info_source: ArgInfoSource::default(),
+        }
+    }
+
+    /// Retains the kernel signatures parsed from `src_strings`.
+    fn with_src_strings(mut self, src_strings: &[CString]) -> Program {
+        self.kernel_sigs = Some(Arc::new(kernel_src::parse_src_strings(src_strings)));
+        self
+    }
+
+    /// Sets where kernels created from this program take their argument
+    /// names and types from.
+    fn with_arg_info_source(mut self, arg_info_source: ArgInfoSource) -> Program {
+        self.arg_info_source = arg_info_source;
+        self
+    }
+
     /// Returns a new program built from pre-created build components and device
     /// list.
     ///
@@ -45,7 +88,7 @@ impl Program {
         let obj_core = core::create_build_program(context_obj_core, &src_strings, device_ids,
             &cmplr_opts)?;
 
-        Ok(Program(obj_core))
+        Ok(Program::from_core(obj_core).with_src_strings(&src_strings))
     }
 
     /// Returns a new program built from pre-created build components and device
@@ -53,25 +96,194 @@ impl Program {
     #[cfg(feature = "opencl_version_2_1")]
     pub fn with_il(il: Vec<u8>, device_ids: Option<&[Device]>, cmplr_opts: CString,
             context_obj_core: &ContextCore) -> OclResult<Program> {
+        match device_ids {
+            Some(devices) => verify_il_support(&il, devices)?,
+            None => verify_il_support(&il, &context_obj_core.devices()?.into_iter()
+                .map(Device::from).collect::<Vec<_>>())?,
+        }
+
         let device_versions = context_obj_core.device_versions()?;
 
         let obj_core = core::create_program_with_il(context_obj_core, &il, Some(&device_versions))?;
 
         core::build_program(&obj_core, device_ids, &cmplr_opts, None, None)?;
 
-        Ok(Program(obj_core))
+        Ok(Program::from_core(obj_core))
+    }
+
+    /// Returns a new program built from binaries, one for each device in
+    /// `device_ids`.
+    ///
+    /// Binaries are usually retrieved from a previously built program using
+    /// `::binaries`. They are specific to a device and driver version.
+    ///
+    /// Prefer `ProgramBuilder::binaries` to create a new `Program` from
+    /// binaries.
+    ///
+    pub fn with_binaries(context_obj_core: &ContextCore, device_ids: &[Device],
+            binaries: &[&[u8]], cmplr_opts: CString) -> OclResult<Program> {
+        let obj_core = core::create_program_with_binary(context_obj_core, device_ids, binaries)?;
+
+        core::build_program(&obj_core, Some(device_ids), &cmplr_opts, None, None)?;
+
+        Ok(Program::from_core(obj_core))
+    }
+
+    /// Returns a new program created from source without building it.
+    ///
+    /// Programs created this way may be used as embedded headers when
+    /// compiling other programs (see `ProgramBuilder::header`).
+    pub fn with_source(context_obj_core: &ContextCore, src_strings: &[CString])
+            -> OclResult<Program> {
+        let obj_core = core::create_program_with_source(context_obj_core, src_strings)?;
+        Ok(Program::from_core(obj_core).with_src_strings(src_strings))
+    }
+
+    /// Returns a new program compiled, but not linked, from pre-created
+    /// build components, device list and embedded headers.
+    ///
+    /// Each header is made available to `#include` directives under the
+    /// name it is paired with. Compiled programs must be linked using
+    /// `::link` before kernels can be created from them.
+    ///
+    /// Prefer `ProgramBuilder::compile` to compile a new `Program`.
+    ///
+    pub fn compile(context_obj_core: &ContextCore, src_strings: Vec<CString>,
+            device_ids: Option<&[Device]>, cmplr_opts: CString, headers: &[(CString, Program)])
+            -> OclResult<Program> {
+        let obj_core = core::create_program_with_source(context_obj_core, &src_strings)?;
+
+        let (header_names, header_programs): (Vec<CString>, Vec<&ProgramCore>) = headers.iter()
+            .map(|&(ref name, ref program)| (name.clone(), program.as_core())).unzip();
+
+        core::compile_program(&obj_core, device_ids, &cmplr_opts, &header_programs,
+            &header_names, None, None, None)?;
+
+        Ok(Program::from_core(obj_core).with_src_strings(&src_strings))
+    }
+
+    /// Returns a new executable program linked from a list of compiled
+    /// programs and libraries.
+    ///
+    /// Linker options such as `-cl-denorms-are-zero` may be passed using
+    /// `link_opts`.
+    ///
+    /// The kernel signatures of the linked programs are retained.
+    pub fn link(context_obj_core: &ContextCore, programs: &[&Program],
+            device_ids: Option<&[Device]>, link_opts: CString) -> OclResult<Program> {
+        let program_cores: Vec<&ProgramCore> = programs.iter().map(|p| p.as_core()).collect();
+
+        let obj_core = core::link_program(context_obj_core, device_ids, &link_opts,
+            &program_cores, None, None, None)?;
+
+        let kernel_sigs = programs.iter()
+            .filter_map(|p| p.kernel_sigs.as_ref())
+            .flat_map(|sigs| sigs.iter().cloned())
+            .collect();
+
+        Ok(Program {
+            obj_core: obj_core,
+            kernel_sigs: Some(Arc::new(kernel_sigs)),
+            arg_info_source: programs.first().map(|p| p.arg_info_source).unwrap_or_default(),
+        })
+    }
+
+    /// Returns a new library linked from a list of compiled programs and
+    /// other libraries.
+    ///
+    /// Libraries can not be used to create kernels but may themselves be
+    /// linked into any number of executable programs using `::link`.
+    pub fn link_library(context_obj_core: &ContextCore, programs: &[&Program],
+            device_ids: Option<&[Device]>, link_opts: CString) -> OclResult<Program> {
+        let mut opts = b"-create-library ".to_vec();
+        opts.extend_from_slice(link_opts.as_bytes());
+        Program::link(context_obj_core, programs, device_ids, CString::new(opts)?)
     }
 
     /// Returns a reference to the core pointer wrapper, usable by functions in
     /// the `core` module.
     #[inline]
     pub fn as_core(&self) -> &ProgramCore {
-        &self.0
+        &self.obj_core
     }
 
     /// Returns info about this program.
     pub fn info(&self, info_kind: ProgramInfo) -> OclCoreResult<ProgramInfoResult> {
-        core::get_program_info(&self.0, info_kind)
+        core::get_program_info(&self.obj_core, info_kind)
+    }
+
+    /// Returns a new kernel for every kernel function in this program, keyed
+    /// by kernel name.
+    ///
+    /// Useful for discovering the entry points of programs built from
+    /// arbitrary source.
+    pub fn kernels(&self) -> OclResult<HashMap<String, Kernel>> {
+        let mut kernels = HashMap::new();
+
+        for obj_core in core::create_kernels_in_program(&self.obj_core)? {
+            let name = core::get_kernel_info(&obj_core, KernelInfo::FunctionName)?.to_string();
+            let kernel = Kernel::from_core_and_sig(obj_core, self.kernel_sig(&name),
+                self.arg_info_source)?;
+            kernels.insert(name, kernel);
+        }
+        Ok(kernels)
+    }
+
+    /// Returns the signature, parsed from source, of the kernel function
+    /// named `name`.
+    ///
+    /// Returns `None` if no such kernel function was found, if this program
+    /// was not created from source, or if the kernel function is defined more
+    /// than once or within a conditional preprocessor block (see
+    /// `KernelSig::is_conditional`), in which case which definition was
+    /// compiled can not be known.
+    pub fn kernel_sig(&self, name: &str) -> Option<&KernelSig> {
+        let mut sigs = self.kernel_sigs().iter().filter(|sig| sig.name() == name);
+        match (sigs.next(), sigs.next()) {
+            (Some(sig), None) if !sig.is_conditional() => Some(sig),
+            _ => None,
+        }
+    }
+
+    /// Returns the signatures, parsed from source, of every kernel function
+    /// definition in this program, in order (empty if this program was not
+    /// created from source).
+    pub fn kernel_sigs(&self) -> &[KernelSig] {
+        self.kernel_sigs.as_ref().map(|sigs| &sigs[..]).unwrap_or(&[])
+    }
+
+    /// Returns where kernels created from this program take their argument
+    /// names and types from.
+    pub fn arg_info_source(&self) -> ArgInfoSource {
+        self.arg_info_source
+    }
+
+    /// Returns the devices associated with this program.
+    pub fn devices(&self) -> OclResult<Vec<Device>> {
+        match self.info(ProgramInfo::Devices)? {
+            ProgramInfoResult::Devices(devices) => Ok(Device::list_from_core(devices)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the binary for each device associated with this program, in
+    /// the same order as `::devices`.
+    ///
+    /// The binary for a device on which this program has not been built
+    /// will be empty.
+    pub fn binaries(&self) -> OclResult<Vec<Vec<u8>>> {
+        match self.info(ProgramInfo::Binaries)? {
+            ProgramInfoResult::Binaries(binaries) => Ok(binaries),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the names of all kernel functions in this program.
+    pub fn kernel_names(&self) -> OclResult<Vec<String>> {
+        match self.info(ProgramInfo::KernelNames)? {
+            ProgramInfoResult::KernelNames(names) => Ok(names),
+            _ => unreachable!("ProgramInfo::KernelNames returned a different info result"),
+        }
     }
 
     /// Returns info about this program's build.
@@ -79,7 +291,7 @@ impl Program {
     /// * TODO: Check that device is valid.
     pub fn build_info(&self, device: Device, info_kind: ProgramBuildInfo)
             -> OclCoreResult<ProgramBuildInfoResult> {
-        core::get_program_build_info(&self.0, &device, info_kind)
+        core::get_program_build_info(&self.obj_core, &device, info_kind)
     }
 
     fn fmt_info(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -108,13 +320,13 @@ impl Deref for Program {
     type Target = ProgramCore;
 
     fn deref(&self) -> &ProgramCore {
-        &self.0
+        &self.obj_core
     }
 }
 
 impl DerefMut for Program {
     fn deref_mut(&mut self) -> &mut ProgramCore {
-        &mut self.0
+        &mut self.obj_core
     }
 }
 
@@ -158,18 +370,560 @@ impl BuildOpt {
 }
 
 
-/// A builder for `Program`.
+/// An on-disk cache of program binaries.
+///
+/// Each device's binary is stored in its own file, named for a hash of the
+/// program source, the contents of the files it includes, compiler options,
+/// device name, driver version and platform name and version.
+#[derive(Clone, Debug)]
+struct BinaryCache {
+    paths: Vec<PathBuf>,
+}
+
+impl BinaryCache {
+    /// Returns the cache entries for a program built from `src_strings` and
+    /// `cmplr_opts` on each device in `devices`.
+    fn new(dir: &Path, src_strings: &[CString], cmplr_opts: &CString, devices: &[Device])
+            -> OclResult<BinaryCache> {
+        let mut paths = Vec::with_capacity(devices.len());
+        let includes = included_files(src_strings, cmplr_opts);
+
+        for device in devices {
+            let platform = match device.info(DeviceInfo::Platform)? {
+                DeviceInfoResult::Platform(platform) => Platform::new(platform),
+                _ => unreachable!(),
+            };
+
+            let mut hasher = Fnv1a::new();
+            for src in src_strings { hasher.write(src.as_bytes()); }
+            for &(ref path, ref contents) in includes.iter() {
+                hasher.write(path.to_string_lossy().as_bytes());
+                hasher.write(contents);
+            }
+            hasher.write(cmplr_opts.as_bytes());
+            hasher.write(device.name()?.as_bytes());
+            hasher.write(device.info(DeviceInfo::DriverVersion)?.to_string().as_bytes());
+            hasher.write(platform.name()?.as_bytes());
+            hasher.write(platform.version()?.as_bytes());
+
+            paths.push(dir.join(format!("{:016x}.bin", hasher.finish())));
+        }
+
+        Ok(BinaryCache { paths: paths })
+    }
+
+    /// Returns the cached binary for every device or `None` if any are
+    /// missing.
+    fn load(&self) -> Option<Vec<Vec<u8>>> {
+        self.paths.iter().map(|path| {
+            let mut binary = Vec::new();
+            File::open(path).and_then(|mut file| file.read_to_end(&mut binary)).ok()?;
+            if binary.len() == 0 { None } else { Some(binary) }
+        }).collect()
+    }
+
+    /// Returns a program built from the cached binaries or `None` if any are
+    /// missing.
+    ///
+    /// A stale or corrupt entry also returns `None`, falling through to a
+    /// normal build.
+    fn load_program(&self, context: &Context, devices: &[Device], cmplr_opts: &CString)
+            -> Option<Program> {
+        let binaries = self.load()?;
+        let binaries: Vec<&[u8]> = binaries.iter().map(|bin| &bin[..]).collect();
+        Program::with_binaries(context, devices, &binaries, cmplr_opts.clone()).ok()
+    }
+
+    /// Stores the binaries of `program` for each device.
+    ///
+    /// Each file is written in full before being moved into place so that
+    /// concurrent builds never observe a partial binary.
+    fn store(&self, program: &Program, devices: &[Device]) -> OclResult<()> {
+        let program_devices = program.devices()?;
+        let binaries = program.binaries()?;
+        if let Some(dir) = self.paths.first().and_then(|path| path.parent()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        for (device, path) in devices.iter().zip(self.paths.iter()) {
+            let binary = match program_devices.iter().position(|d| d == device) {
+                Some(idx) if binaries[idx].len() > 0 => &binaries[idx],
+                _ => continue,
+            };
+
+            let tmp_path = path.with_extension(format!("tmp{}", ::std::process::id()));
+            File::create(&tmp_path)?.write_all(binary)?;
+            fs::rename(&tmp_path, path)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Returns the path and contents of each file reachable through the
+/// `#include` directives of `src_strings`, in the order first included.
+///
+/// Headers are looked for in the directory of the including file (for nested
+/// includes), in each `-I` directory of `cmplr_opts` then relative to the
+/// current directory. Directives within preprocessor conditionals are
+/// followed regardless, and headers which can not be found (such as those
+/// within the compiler's own search paths) are ignored.
+fn included_files(src_strings: &[CString], cmplr_opts: &CString) -> Vec<(PathBuf, Vec<u8>)> {
+    let opts = cmplr_opts.to_string_lossy();
+    let mut tokens = opts.split_whitespace();
+    let mut incl_dirs = Vec::new();
+    while let Some(token) = tokens.next() {
+        if token == "-I" {
+            incl_dirs.extend(tokens.next().map(PathBuf::from));
+        } else if token.starts_with("-I") {
+            incl_dirs.push(PathBuf::from(&token[2..]));
+        }
+    }
+
+    let mut files: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+    let mut pending: Vec<(Option<PathBuf>, String)> = src_strings.iter()
+        .map(|src| (None, src.to_string_lossy().into_owned()))
+        .collect();
+    pending.reverse();
+
+    while let Some((parent_dir, src)) = pending.pop() {
+        let mut found = Vec::new();
+
+        for name in ocl_kernel_src::strip_comments(&src).lines().filter_map(include_name) {
+            let path = parent_dir.iter().chain(incl_dirs.iter()).map(|dir| dir.join(name))
+                .chain(Some(PathBuf::from(name)))
+                .find(|path| path.is_file());
+
+            if let Some(path) = path {
+                if files.iter().any(|&(ref p, _)| *p == path) { continue; }
+                if let Ok(contents) = fs::read(&path) {
+                    found.push((path.parent().map(Path::to_path_buf),
+                        String::from_utf8_lossy(&contents).into_owned()));
+                    files.push((path, contents));
+                }
+            }
+        }
+
+        found.reverse();
+        pending.extend(found);
+    }
+    files
+}
+
+/// Returns the header name of an `#include` directive.
+fn include_name(line: &str) -> Option<&str> {
+    let line = line.trim_left();
+    if !line.starts_with('#') { return None; }
+    let directive = line[1..].trim_left();
+    if !directive.starts_with("include") { return None; }
+
+    let path = directive["include".len()..].trim();
+    let close = match path.chars().next() {
+        Some('"') => '"',
+        Some('<') => '>',
+        _ => return None,
+    };
+    path[1..].find(close).map(|end| &path[1..end + 1])
+}
+
+
+/// A 64-bit FNV-1a hasher.
+///
+/// Used for binary cache keys, which must remain stable across processes
+/// and compiler versions (unlike those produced by `DefaultHasher`).
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Fnv1a {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    /// Hashes `bytes`, prefixed by their length so that consecutive writes
+    /// can not be confused with one another.
+    fn write(&mut self, bytes: &[u8]) {
+        let len = bytes.len() as u64;
+        let len_bytes: Vec<u8> = (0..8).map(|i| (len >> (i * 8)) as u8).collect();
+
+        for &byte in len_bytes.iter().chain(bytes.iter()) {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+
+/// The origin of a portion of a program's source.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SourceOrigin {
+    /// A file added with `ProgramBuilder::src_file`.
+    File(PathBuf),
+    /// A `#define` added with `BuildOpt::IncludeDefine`, by identifier.
+    IncludeDefine(String),
+    /// Text added with `BuildOpt::IncludeRaw`, by order of addition.
+    IncludeRaw(usize),
+    /// Text added with `ProgramBuilder::src` or `BuildOpt::IncludeRawEof`, by
+    /// order of addition.
+    IncludeRawEof(usize),
+}
+
+impl std::fmt::Display for SourceOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            SourceOrigin::File(ref path) => write!(f, "{}", path.display()),
+            SourceOrigin::IncludeDefine(ref ident) => write!(f, "<IncludeDefine {}>", ident),
+            SourceOrigin::IncludeRaw(idx) => write!(f, "<IncludeRaw #{}>", idx),
+            SourceOrigin::IncludeRawEof(idx) => write!(f, "<IncludeRawEof #{}>", idx),
+        }
+    }
+}
+
+
+/// A program build diagnostic mapped back to the portion of source it came
+/// from.
 ///
-// * [SOMEDAY TODO]: Keep track of line number range for each string and print
-// out during build failure.
-//
+/// Displays in the style of `rustc`, including the offending source line.
+#[derive(Clone, Debug)]
+pub struct SourceDiagnostic {
+    diagnostic: BuildDiagnostic,
+    origin: Option<SourceOrigin>,
+    line: Option<u32>,
+    column: Option<u32>,
+    source_line: Option<String>,
+}
+
+impl SourceDiagnostic {
+    /// Returns a diagnostic which could not be mapped to the program source.
+    fn unmapped(diagnostic: BuildDiagnostic) -> SourceDiagnostic {
+        SourceDiagnostic {
+            line: diagnostic.line(),
+            column: diagnostic.column(),
+            diagnostic: diagnostic,
+            origin: None,
+            source_line: None,
+        }
+    }
+
+    /// Returns the severity of this diagnostic.
+    pub fn severity(&self) -> DiagnosticSeverity {
+        self.diagnostic.severity()
+    }
+
+    /// Returns the diagnostic message.
+    pub fn message(&self) -> &str {
+        self.diagnostic.message()
+    }
+
+    /// Returns the origin of the source this diagnostic refers to or `None`
+    /// if it refers to a header included from disk or to no location at all.
+    pub fn origin(&self) -> Option<&SourceOrigin> {
+        self.origin.as_ref()
+    }
+
+    /// Returns the line number within the origin (or within the file
+    /// reported by the compiler if the origin is unknown).
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// Returns the column number within the line.
+    pub fn column(&self) -> Option<u32> {
+        self.column
+    }
+
+    /// Returns the text of the offending source line.
+    pub fn source_line(&self) -> Option<&str> {
+        self.source_line.as_ref().map(|l| l.as_str())
+    }
+
+    /// Returns the diagnostic as parsed from the build log, with its
+    /// original location.
+    pub fn as_build_diagnostic(&self) -> &BuildDiagnostic {
+        &self.diagnostic
+    }
+}
+
+impl std::fmt::Display for SourceDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity(), self.message())?;
+
+        let file = match self.origin {
+            Some(ref origin) => origin.to_string(),
+            None => match self.diagnostic.file() {
+                Some(file) => file.to_owned(),
+                None => return Ok(()),
+            },
+        };
+
+        let line = match self.line {
+            Some(line) => line,
+            None => return write!(f, "\n --> {}", file),
+        };
+
+        let gutter = " ".repeat(line.to_string().len());
+        match self.column {
+            Some(column) => write!(f, "\n{}--> {}:{}:{}", gutter, file, line, column)?,
+            None => write!(f, "\n{}--> {}:{}", gutter, file, line)?,
+        }
+
+        if let Some(ref source_line) = self.source_line {
+            write!(f, "\n{} |\n{} | {}", gutter, line, source_line)?;
+            if let Some(column) = self.column {
+                // Keep tabs so that the caret lines up:
+                let indent: String = source_line.chars()
+                    .take((column as usize).saturating_sub(1))
+                    .map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+                write!(f, "\n{} | {}^", gutter, indent)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// A failed program build, with diagnostics mapped back to the source files
+/// and text they came from.
+///
+/// Displays each diagnostic in the style of `rustc` or, if the build log
+/// could not be parsed, the raw build log.
+#[derive(Debug)]
+pub struct ProgramBuildError {
+    log: String,
+    diagnostics: Vec<SourceDiagnostic>,
+}
+
+impl ProgramBuildError {
+    /// Returns the raw build log.
+    pub fn log(&self) -> &str {
+        &self.log
+    }
+
+    /// Returns the diagnostics parsed from the build log.
+    pub fn diagnostics(&self) -> &[SourceDiagnostic] {
+        &self.diagnostics
+    }
+}
+
+impl std::fmt::Display for ProgramBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.diagnostics.is_empty() {
+            return write!(f, "Program build failed. Build log:\n\n{}", self.log);
+        }
+
+        for diagnostic in &self.diagnostics {
+            write!(f, "{}\n\n", diagnostic)?;
+        }
+
+        let error_count = self.diagnostics.iter()
+            .filter(|d| d.severity() == DiagnosticSeverity::Error).count();
+
+        match error_count {
+            0 => write!(f, "error: program build failed"),
+            1 => write!(f, "error: program build failed due to previous error"),
+            n => write!(f, "error: program build failed due to {} previous errors", n),
+        }
+    }
+}
+
+impl Fail for ProgramBuildError {}
+
+
+/// A portion of a program's source, positioned within the concatenation of
+/// all portions.
+#[derive(Clone, Debug)]
+struct SourceChunk {
+    origin: Option<SourceOrigin>,
+    line: u32,
+    column: u32,
+    text: String,
+}
+
+/// The position and origin of each portion of a program's source, used to
+/// map build log locations (which refer to the concatenated source) back to
+/// where they came from.
+#[derive(Clone, Debug)]
+pub(crate) struct SourceMap {
+    chunks: Vec<SourceChunk>,
+}
+
+impl SourceMap {
+    fn new(src_chunks: &[(Option<SourceOrigin>, CString)]) -> SourceMap {
+        let (mut line, mut column) = (1, 1);
+        let mut chunks = Vec::with_capacity(src_chunks.len());
+
+        for &(ref origin, ref src) in src_chunks {
+            chunks.push(SourceChunk {
+                origin: origin.clone(),
+                line: line,
+                column: column,
+                text: String::from_utf8_lossy(src.as_bytes()).into_owned(),
+            });
+
+            // Compilers report byte columns:
+            for &byte in src.as_bytes() {
+                if byte == b'\n' {
+                    line += 1;
+                    column = 1;
+                } else {
+                    column += 1;
+                }
+            }
+        }
+
+        SourceMap { chunks: chunks }
+    }
+
+    /// Maps a diagnostic to the chunk containing its location.
+    fn map_diagnostic(&self, diagnostic: BuildDiagnostic) -> SourceDiagnostic {
+        let line = match diagnostic.line() {
+            Some(line) if diagnostic.is_program_source() => line,
+            _ => return SourceDiagnostic::unmapped(diagnostic),
+        };
+        let column = diagnostic.column();
+        let pos = (line, column.unwrap_or(1));
+
+        let chunk = match self.chunks.iter()
+                .filter(|c| !c.text.is_empty() && (c.line, c.column) <= pos).last() {
+            Some(chunk) => chunk,
+            None => return SourceDiagnostic::unmapped(diagnostic),
+        };
+
+        let local_line = line - chunk.line + 1;
+        let local_column = column
+            .map(|c| if line == chunk.line { c - chunk.column + 1 } else { c });
+        let source_line = chunk.text.split('\n').nth(local_line as usize - 1)
+            .map(|l| l.trim_right_matches('\r').to_owned());
+
+        SourceDiagnostic {
+            diagnostic: diagnostic,
+            origin: chunk.origin.clone(),
+            line: Some(local_line),
+            column: local_column,
+            source_line: source_line,
+        }
+    }
+
+    /// Converts a program build error containing a build log into a
+    /// `ProgramBuildError`, returning any other error unchanged.
+    pub(crate) fn map_err(&self, err: OclError) -> OclError {
+        let log = match *err.kind() {
+            OclErrorKind::OclCore(ref err) => match *err.kind() {
+                OclCoreErrorKind::ProgramBuild(ref err) => err.build_log().map(|l| l.to_owned()),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match log {
+            Some(log) => ProgramBuildError {
+                diagnostics: BuildDiagnostic::parse_log(&log).into_iter()
+                    .map(|d| self.map_diagnostic(d)).collect(),
+                log: log,
+            }.into(),
+            None => err,
+        }
+    }
+}
+
+
+/// Intermediate language bytes or the path of a file containing them.
+#[cfg_attr(not(feature = "opencl_version_2_1"), allow(dead_code))]
+#[derive(Clone, Debug)]
+enum IlSource {
+    Bytes(Vec<u8>),
+    File(PathBuf),
+}
+
+impl IlSource {
+    #[cfg(feature = "opencl_version_2_1")]
+    fn load(self) -> OclResult<Vec<u8>> {
+        match self {
+            IlSource::Bytes(il) => Ok(il),
+            IlSource::File(path) => {
+                let mut il = Vec::new();
+                File::open(&path)?.read_to_end(&mut il)?;
+                Ok(il)
+            },
+        }
+    }
+}
+
+
+/// The SPIR-V magic number (the first word of every module).
+#[cfg(feature = "opencl_version_2_1")]
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+/// Returns the `(major, minor)` version of a SPIR-V module or `None` if `il`
+/// is not SPIR-V.
+#[cfg(feature = "opencl_version_2_1")]
+fn spirv_version(il: &[u8]) -> Option<(u8, u8)> {
+    if il.len() < 8 { return None; }
+    let word = |i: usize| [il[i], il[i + 1], il[i + 2], il[i + 3]];
+
+    // Modules may be stored with either endianness:
+    let (magic_le, version_le) = (word(0), word(4));
+    let le = |w: [u8; 4]| (w[0] as u32) | (w[1] as u32) << 8 | (w[2] as u32) << 16 |
+        (w[3] as u32) << 24;
+    let be = |w: [u8; 4]| (w[3] as u32) | (w[2] as u32) << 8 | (w[1] as u32) << 16 |
+        (w[0] as u32) << 24;
+
+    let version = if le(magic_le) == SPIRV_MAGIC {
+        le(version_le)
+    } else if be(magic_le) == SPIRV_MAGIC {
+        be(version_le)
+    } else {
+        return None;
+    };
+
+    Some(((version >> 16) as u8, (version >> 8) as u8))
+}
+
+/// Verifies that every device in `devices` lists the intermediate language
+/// of `il` in its `DeviceInfo::IlVersion`.
+#[cfg(feature = "opencl_version_2_1")]
+fn verify_il_support(il: &[u8], devices: &[Device]) -> OclResult<()> {
+    for device in devices {
+        // Devices predating OpenCL 2.1 do not recognize the query:
+        let il_versions = match device.info(DeviceInfo::IlVersion) {
+            Ok(DeviceInfoResult::IlVersion(versions)) => versions,
+            _ => String::new(),
+        };
+
+        let supported = match spirv_version(il) {
+            Some((major, minor)) => {
+                let required = format!("SPIR-V_{}.{}", major, minor);
+                if il_versions.split_whitespace().any(|v| v == required) { continue; }
+                format!("SPIR-V {}.{}", major, minor)
+            },
+            None => {
+                if !il_versions.trim().is_empty() { continue; }
+                "intermediate language programs".to_owned()
+            },
+        };
+
+        return Err(format!("Device '{}' does not support {} (supported IL versions: '{}').",
+            device.name()?, supported, il_versions.trim()).into());
+    }
+    Ok(())
+}
+
+
+/// A builder for `Program`.
 #[must_use = "builders do nothing unless '::build' is called"]
 #[derive(Clone, Debug)]
 pub struct ProgramBuilder {
     options: Vec<BuildOpt>,
     src_files: Vec<PathBuf>,
-    il: Option<Vec<u8>>,
+    il: Option<IlSource>,
+    spec_constants: Vec<(u32, Vec<u8>)>,
     device_spec: Option<DeviceSpecifier>,
+    headers: Vec<(String, Program)>,
+    binaries: Option<Vec<Vec<u8>>>,
+    binary_cache: Option<PathBuf>,
+    arg_info_source: ArgInfoSource,
 }
 
 impl ProgramBuilder {
@@ -179,7 +933,12 @@ impl ProgramBuilder {
             options: Vec::with_capacity(64),
             src_files: Vec::with_capacity(16),
             il: None,
+            spec_constants: Vec::new(),
             device_spec: None,
+            headers: Vec::new(),
+            binaries: None,
+            binary_cache: None,
+            arg_info_source: ArgInfoSource::default(),
         }
     }
 
@@ -195,23 +954,21 @@ impl ProgramBuilder {
     /// * TODO: Check for duplicate devices in the final device list.
     #[cfg(not(feature = "opencl_version_2_1"))]
     pub fn build(self, context: &Context) -> OclResult<Program> {
+        if self.headers.len() > 0 { return Err("ProgramBuilder::build: Embedded headers \
+            may only be used with '::compile'.".into()); }
+
         let device_list = match self.device_spec {
             Some(ref ds) => ds.to_device_list(context.platform()?)?,
             None => context.devices(),
         };
 
+        if self.binaries.is_some() { return self.build_with_binaries(context, &device_list); }
+
         match self.il {
             Some(_) => {
                 return Err("ocl::ProgramBuilder::build: Unreachable section (IL).".into());
             },
-            None => {
-                Program::new(
-                    context,
-                    self.get_src_strings().map_err(|e| e.to_string())?,
-                    Some(&device_list[..]),
-                    self.get_compiler_options().map_err(|e| e.to_string())?,
-                ).map_err(OclError::from)
-            },
+            None => self.build_with_source(context, &device_list),
         }
     }
 
@@ -224,38 +981,277 @@ impl ProgramBuilder {
     /// * TODO: Check for duplicate devices in the final device list.
     #[cfg(feature = "opencl_version_2_1")]
     pub fn build(mut self, context: &Context) -> OclResult<Program> {
+        if self.headers.len() > 0 { return Err("ProgramBuilder::build: Embedded headers \
+            may only be used with '::compile'.".into()); }
+
         let device_list = match self.device_spec {
             Some(ref ds) => ds.to_device_list(context.platform()?)?,
             None => context.devices().to_owned(),
         };
 
-        match self.il.take() {
-            Some(il) => {
-                if cfg!(feature = "opencl_version_2_1") {
-                    if self.src_files.len() > 0 { return Err("ProgramBuilder::build: \
-                        No source files may be set when building with IR.".into()); }
-
-                    Program::with_il(
-                        il,
-                        Some(&device_list[..]),
-                        self.get_compiler_options().map_err(|e| e.to_string())?,
-                        context
-                    )
-                } else {
-                    return Err("ocl::ProgramBuilder::build: Unreachable section.".into());
-                }
-            },
-            None => {
-                Program::new(
-                    context,
-                    self.get_src_strings().map_err(|e| e.to_string())?,
-                    Some(&device_list[..]),
-                    self.get_compiler_options().map_err(|e| e.to_string())?,
-                )
-            },
+        if self.binaries.is_some() { return self.build_with_binaries(context, &device_list); }
+
+        if self.il.is_some() {
+            self.build_with_il(context, &device_list)
+        } else {
+            self.build_with_source(context, &device_list)
         }
     }
 
+    /// Begins building a new Program, returning a future which resolves to
+    /// it once the build has completed.
+    ///
+    /// The calling thread is not blocked while the program compiles, allowing
+    /// several programs to be built at once. Build failures, along with the
+    /// build log, are returned when the future resolves.
+    ///
+    /// Programs found in the binary cache (see `::binary_cache`) are loaded
+    /// immediately.
+    pub fn build_async(mut self, context: &Context) -> OclResult<FutureProgram> {
+        if self.headers.len() > 0 { return Err("ProgramBuilder::build_async: Embedded headers \
+            may only be used with '::compile'.".into()); }
+
+        let device_list = match self.device_spec {
+            Some(ref ds) => ds.to_device_list(context.platform()?)?,
+            None => context.devices(),
+        };
+
+        let cmplr_opts = self.get_compiler_options().map_err(|e| e.to_string())?;
+
+        let (program, cache, source_map) = if self.binaries.is_some() {
+            (Program::from_core(self.create_with_binaries(context, &device_list)?), None, None)
+        } else if self.il.is_some() {
+            (Program::from_core(self.create_with_il(context, &device_list)?), None, None)
+        } else {
+            if !self.spec_constants.is_empty() { return Err("ProgramBuilder::build_async: \
+                Specialization constants may only be used when building with IL.".into()); }
+
+            let (src_strings, source_map) = self.get_src_strings_mapped()?;
+
+            let cache = match self.binary_cache {
+                Some(ref dir) => Some(BinaryCache::new(dir, &src_strings, &cmplr_opts, &device_list)?),
+                None => None,
+            };
+
+            if let Some(program) = cache.as_ref()
+                    .and_then(|cache| cache.load_program(context, &device_list, &cmplr_opts)) {
+                return Ok(FutureProgram::ready(program.with_src_strings(&src_strings)
+                    .with_arg_info_source(self.arg_info_source)));
+            }
+
+            let program = Program::from_core(core::create_program_with_source(context,
+                &src_strings)?).with_src_strings(&src_strings);
+            (program, cache, Some(source_map))
+        };
+
+        let program = program.with_arg_info_source(self.arg_info_source);
+        Ok(FutureProgram::new(program, device_list, &cmplr_opts, cache, source_map))
+    }
+
+    /// Builds from the IL set with `::il` or `::il_file`.
+    #[cfg(feature = "opencl_version_2_1")]
+    fn build_with_il(&mut self, context: &Context, device_list: &[Device]) -> OclResult<Program> {
+        let obj_core = self.create_with_il(context, device_list)?;
+
+        core::build_program(&obj_core, Some(device_list),
+            &self.get_compiler_options().map_err(|e| e.to_string())?, None, None)?;
+
+        Ok(Program::from_core(obj_core))
+    }
+
+    /// Creates, without building, a program from the IL set with `::il` or
+    /// `::il_file`, applying any specialization constants.
+    #[cfg(feature = "opencl_version_2_1")]
+    fn create_with_il(&mut self, context: &Context, device_list: &[Device])
+            -> OclResult<ProgramCore> {
+        if self.has_source() { return Err("ProgramBuilder::build: \
+            No source may be set when building with IL.".into()); }
+
+        let il = match self.il.take() {
+            Some(il) => il.load()?,
+            None => unreachable!(),
+        };
+
+        verify_il_support(&il, device_list)?;
+
+        let device_versions = context.device_versions()?;
+        let obj_core = core::create_program_with_il(context, &il, Some(&device_versions))?;
+
+        #[cfg(feature = "opencl_version_2_2")]
+        for &(spec_id, ref value) in &self.spec_constants {
+            core::set_program_specialization_constant(&obj_core, spec_id, value,
+                Some(&device_versions))?;
+        }
+
+        Ok(obj_core)
+    }
+
+    #[cfg(not(feature = "opencl_version_2_1"))]
+    fn create_with_il(&mut self, _: &Context, _: &[Device]) -> OclResult<ProgramCore> {
+        Err("ocl::ProgramBuilder::build_async: Unreachable section (IL).".into())
+    }
+
+    /// Builds from the binaries set with `::binaries`.
+    fn build_with_binaries(&self, context: &Context, device_list: &[Device])
+            -> OclResult<Program> {
+        let obj_core = self.create_with_binaries(context, device_list)?;
+
+        core::build_program(&obj_core, Some(device_list), &self.get_compiler_options()?,
+            None, None)?;
+
+        Ok(Program::from_core(obj_core))
+    }
+
+    /// Creates, without building, a program from the binaries set with
+    /// `::binaries`.
+    fn create_with_binaries(&self, context: &Context, device_list: &[Device])
+            -> OclResult<ProgramCore> {
+        if self.il.is_some() || self.has_source() { return Err("ProgramBuilder::build: \
+            No source or IL may be set when building with binaries.".into()); }
+
+        let binaries: Vec<&[u8]> = match self.binaries {
+            Some(ref bins) => bins.iter().map(|bin| &bin[..]).collect(),
+            None => unreachable!(),
+        };
+
+        if binaries.len() != device_list.len() { return Err(format!("ProgramBuilder::build: \
+            The number of binaries ({}) must match the number of devices ({}).",
+            binaries.len(), device_list.len()).into()); }
+
+        core::create_program_with_binary(context, device_list, &binaries).map_err(OclError::from)
+    }
+
+    /// Builds from source, using the binary cache (if enabled) when possible.
+    fn build_with_source(&self, context: &Context, device_list: &[Device])
+            -> OclResult<Program> {
+        if !self.spec_constants.is_empty() { return Err("ProgramBuilder::build: \
+            Specialization constants may only be used when building with IL.".into()); }
+
+        let (src_strings, source_map) = self.get_src_strings_mapped()?;
+        let cmplr_opts = self.get_compiler_options().map_err(|e| e.to_string())?;
+
+        let cache = match self.binary_cache {
+            Some(ref dir) => Some(BinaryCache::new(dir, &src_strings, &cmplr_opts, device_list)?),
+            None => None,
+        };
+
+        if let Some(program) = cache.as_ref()
+                .and_then(|cache| cache.load_program(context, device_list, &cmplr_opts)) {
+            return Ok(program.with_src_strings(&src_strings)
+                .with_arg_info_source(self.arg_info_source));
+        }
+
+        let program = Program::new(context, src_strings, Some(device_list), cmplr_opts)
+            .map_err(|err| source_map.map_err(err))?
+            .with_arg_info_source(self.arg_info_source);
+
+        // Caching is best-effort:
+        if let Some(cache) = cache { cache.store(&program, device_list).ok(); }
+
+        Ok(program)
+    }
+
+    /// Returns a newly compiled, but not yet linked, Program.
+    ///
+    /// Embedded headers added with `::header` are available to `#include`
+    /// directives within the program source. Use `Program::link` or
+    /// `Program::link_library` to link the result with other compiled
+    /// programs and libraries.
+    ///
+    pub fn compile(self, context: &Context) -> OclResult<Program> {
+        if self.il.is_some() { return Err("ProgramBuilder::compile: Programs created with IL \
+            can not be compiled separately.".into()); }
+
+        let device_list = match self.device_spec {
+            Some(ref ds) => ds.to_device_list(context.platform()?)?,
+            None => context.devices(),
+        };
+
+        let headers = self.headers.iter()
+            .map(|&(ref name, ref program)| Ok((CString::new(name.clone())?, program.clone())))
+            .collect::<OclResult<Vec<_>>>()?;
+
+        let (src_strings, source_map) = self.get_src_strings_mapped()?;
+
+        Program::compile(
+            context,
+            src_strings,
+            Some(&device_list[..]),
+            self.get_compiler_options().map_err(|e| e.to_string())?,
+            &headers,
+        ).map(|program| program.with_arg_info_source(self.arg_info_source))
+            .map_err(|err| source_map.map_err(err))
+    }
+
+    /// Adds an embedded header, available to `#include "{name}"` directives
+    /// when compiling with `::compile`.
+    ///
+    /// Header programs are usually created with `Program::with_source`.
+    ///
+    /// ## Example
+    ///
+    /// `...header("util.h", &util_header)...`
+    ///
+    pub fn header<S: Into<String>>(mut self, name: S, header: &Program) -> ProgramBuilder {
+        self.headers.push((name.into(), header.clone()));
+        self
+    }
+
+    /// Sets the binaries to build this program from, one for each device
+    /// (in the same order as the devices specified with `::devices` or those
+    /// of the context if none are specified).
+    ///
+    /// Binaries are usually retrieved from a previously built program using
+    /// `Program::binaries`. Any source files, source text or IL added to
+    /// this build will cause an error upon building.
+    ///
+    pub fn binaries(mut self, binaries: Vec<Vec<u8>>) -> ProgramBuilder {
+        self.binaries = Some(binaries);
+        self
+    }
+
+    /// Sets where kernels created from this program take their argument
+    /// names and types from.
+    ///
+    /// Kernel signatures are always parsed from the program source (when
+    /// building from source) and are used whenever the platform does not
+    /// report argument info (OpenCL 1.1), so that argument types are checked
+    /// on every platform. Use `ArgInfoSource::Source` to prefer the parsed
+    /// signatures on platforms which report argument info incorrectly.
+    ///
+    /// Defaults to `ArgInfoSource::Metadata`.
+    ///
+    pub fn arg_info_source(mut self, arg_info_source: ArgInfoSource) -> ProgramBuilder {
+        self.arg_info_source = arg_info_source;
+        self
+    }
+
+    /// Enables caching of program binaries in the directory `dir`.
+    ///
+    /// When building from source, binaries are looked up by a hash of the
+    /// program source, compiler options, device name, driver version and
+    /// platform. If a binary is found for every device, compilation is
+    /// skipped. Otherwise the program is built from source and its binaries
+    /// are stored for later builds. The directory is created if necessary.
+    ///
+    /// Files pulled in with `#include` are hashed as well when found in the
+    /// directories given with `-I` (see `BuildOpt::CmplrInclDir`), in the
+    /// directory of the file including them or relative to the current
+    /// directory. Changes to headers found elsewhere, such as in the
+    /// compiler's own search paths, are not detected; remove the directory
+    /// to invalidate the cache.
+    ///
+    /// Failures to read or write the cache are not treated as errors.
+    ///
+    /// ## Example
+    ///
+    /// `...binary_cache("target/ocl-cache")...`
+    ///
+    pub fn binary_cache<P: Into<PathBuf>>(mut self, dir: P) -> ProgramBuilder {
+        self.binary_cache = Some(dir.into());
+        self
+    }
+
     /// Adds a build option containing a compiler command line definition.
     /// Formatted as `-D {name}={val}`.
     ///
@@ -304,16 +1300,48 @@ impl ProgramBuilder {
     /// Adds SPIR-V or an implementation-defined intermediate language to this program.
     ///
     /// Any source files or source text added to this build will cause an
-    /// error upon building.
+    /// error upon building. Building fails with a descriptive error if any
+    /// device does not list the SPIR-V version of the module in its
+    /// `DeviceInfo::IlVersion`.
     ///
     /// Use the `include_bytes!` macro to include source code from a file statically.
     ///
-    /// * TODO: Future addition: Allow IL to be loaded directly from a file
-    /// in the same way that text source is.
-    ///
     #[cfg(feature = "opencl_version_2_1")]
     pub fn il(mut self, il: Vec<u8>) -> ProgramBuilder {
-        self.il = Some(il);
+        self.il = Some(IlSource::Bytes(il));
+        self
+    }
+
+    /// Adds the contents of an intermediate language file (such as a SPIR-V
+    /// module) to this program. The file is read when the program is built.
+    ///
+    /// See `::il` for more information.
+    #[cfg(feature = "opencl_version_2_1")]
+    pub fn il_file<P: Into<PathBuf>>(mut self, file_path: P) -> ProgramBuilder {
+        let file_path = file_path.into();
+        assert!(file_path.is_file(), "ProgramBuilder::il_file(): IL file error: \
+            '{}' does not exist.", file_path.display());
+        self.il = Some(IlSource::File(file_path));
+        self
+    }
+
+    /// Sets the value of the SPIR-V specialization constant with the
+    /// `SpecId` decoration `spec_id`, overriding its default.
+    ///
+    /// `value` must have the same size as the constant's type. Use a `u8`
+    /// (`0` or `1`) for boolean constants. Only valid when building from IL.
+    ///
+    /// ## Example
+    ///
+    /// `...spec_constant(0, 64u32)...`
+    ///
+    #[cfg(feature = "opencl_version_2_2")]
+    pub fn spec_constant<T: OclPrm>(mut self, spec_id: u32, value: T) -> ProgramBuilder {
+        let bytes = unsafe {
+            slice::from_raw_parts(&value as *const T as *const u8, mem::size_of::<T>())
+        };
+        self.spec_constants.retain(|&(id, _)| id != spec_id);
+        self.spec_constants.push((spec_id, bytes.to_vec()));
         self
     }
 
@@ -383,10 +1411,16 @@ impl ProgramBuilder {
     ///   `BuildOpt::IncludeRawEof` via `::bo`
     ///
     pub fn get_src_strings(&self) -> OclResult<Vec<CString>> {
-        let mut src_strings: Vec<CString> = Vec::with_capacity(64);
+        Ok(self.get_src_chunks()?.into_iter().map(|(_, src)| src).collect())
+    }
+
+    /// Returns the final program source strings, each paired with its
+    /// origin (`None` for padding).
+    fn get_src_chunks(&self) -> OclResult<Vec<(Option<SourceOrigin>, CString)>> {
+        let mut src_chunks = Vec::with_capacity(64);
         let mut src_file_history: HashSet<PathBuf> = HashSet::with_capacity(64);
 
-        src_strings.extend_from_slice(&self.get_includes()?);
+        src_chunks.extend(self.get_includes()?);
 
         for srcpath in &self.src_files {
             let mut src_bytes: Vec<u8> = Vec::with_capacity(100000);
@@ -398,12 +1432,29 @@ impl ProgramBuilder {
 
             src_file_handle.read_to_end(&mut src_bytes)?;
             src_bytes.shrink_to_fit();
-            src_strings.push(CString::new(src_bytes)?);
+            src_chunks.push((Some(SourceOrigin::File(srcpath.clone())), CString::new(src_bytes)?));
         }
 
-        src_strings.extend_from_slice(&self.get_includes_eof()?);
-        src_strings.shrink_to_fit();
-        Ok(src_strings)
+        src_chunks.extend(self.get_includes_eof()?);
+        src_chunks.shrink_to_fit();
+        Ok(src_chunks)
+    }
+
+    /// Returns the final program source strings along with a map of their
+    /// origins.
+    pub(crate) fn get_src_strings_mapped(&self) -> OclResult<(Vec<CString>, SourceMap)> {
+        let src_chunks = self.get_src_chunks().map_err(|e| e.to_string())?;
+        let source_map = SourceMap::new(&src_chunks);
+        Ok((src_chunks.into_iter().map(|(_, src)| src).collect(), source_map))
+    }
+
+    /// Returns true if any source files or source text have been added.
+    fn has_source(&self) -> bool {
+        self.src_files.len() > 0 || self.options.iter().any(|opt| match *opt {
+            BuildOpt::IncludeDefine { .. } | BuildOpt::IncludeRaw(_) |
+                BuildOpt::IncludeRawEof(_) => true,
+            _ => false,
+        })
     }
 
     /// Parses `self.options` for options intended for inclusion at the beginning of
@@ -411,18 +1462,21 @@ impl ProgramBuilder {
     ///
     /// Generally used for #define directives, constants, etc. Normally called from
     /// `::get_src_strings()`.
-    fn get_includes(&self) -> OclResult<Vec<CString>> {
+    fn get_includes(&self) -> OclResult<Vec<(Option<SourceOrigin>, CString)>> {
         let mut strings = Vec::with_capacity(64);
-        strings.push(CString::new("\n".as_bytes())?);
+        strings.push((None, CString::new("\n".as_bytes())?));
+        let mut raw_idx = 0;
 
         for option in &self.options {
             match *option {
                 BuildOpt::IncludeDefine { ref ident, ref val } => {
-                    strings.push(CString::new(format!("#define {}  {}\n", ident, val)
-                        .into_bytes())?);
+                    strings.push((Some(SourceOrigin::IncludeDefine(ident.clone())),
+                        CString::new(format!("#define {}  {}\n", ident, val).into_bytes())?));
                 },
                 BuildOpt::IncludeRaw(ref text) => {
-                    strings.push(CString::new(text.clone().into_bytes())?);
+                    strings.push((Some(SourceOrigin::IncludeRaw(raw_idx)),
+                        CString::new(text.clone().into_bytes())?));
+                    raw_idx += 1;
                 },
                 _ => (),
             };
@@ -435,14 +1489,18 @@ impl ProgramBuilder {
 
     /// Parses `self.options` for options intended for inclusion at the end of
     /// the final program source and returns them as a list of strings.
-    fn get_includes_eof(&self) -> OclResult<Vec<CString>> {
+    fn get_includes_eof(&self) -> OclResult<Vec<(Option<SourceOrigin>, CString)>> {
         let mut strings = Vec::with_capacity(64);
-        strings.push(CString::new("\n".as_bytes())?);
+        strings.push((None, CString::new("\n".as_bytes())?));
 
-        for option in &self.options {
-            if let BuildOpt::IncludeRawEof(ref text) = *option {
-                strings.push(CString::new(text.clone().into_bytes())?);
-            }
+        let texts = self.options.iter().filter_map(|option| match *option {
+            BuildOpt::IncludeRawEof(ref text) => Some(text),
+            _ => None,
+        });
+
+        for (idx, text) in texts.enumerate() {
+            strings.push((Some(SourceOrigin::IncludeRawEof(idx)),
+                CString::new(text.clone().into_bytes())?));
         }
 
         strings.shrink_to_fit();
@@ -450,3 +1508,174 @@ impl ProgramBuilder {
     }
 }
 
+
+/// The completion state of an asynchronous build, shared with the build
+/// callback.
+#[derive(Debug)]
+struct BuildState {
+    complete: AtomicBool,
+    task: Mutex<Option<Task>>,
+}
+
+impl BuildState {
+    fn new(complete: bool) -> BuildState {
+        BuildState {
+            complete: AtomicBool::new(complete),
+            task: Mutex::new(None),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.complete.load(Ordering::SeqCst)
+    }
+
+    /// Marks the build complete and notifies the waiting task, if any.
+    fn set_complete(&self) {
+        self.complete.store(true, Ordering::SeqCst);
+
+        // Never panic here, this is called from within the driver:
+        let task = match self.task.lock() {
+            Ok(mut task) => task.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        };
+        if let Some(task) = task { task.notify(); }
+    }
+}
+
+/// Called by the driver once a build started by `FutureProgram::new` has
+/// completed, successfully or not.
+#[cfg(not(feature = "async_block"))]
+extern "C" fn _complete_build(_program: *mut c_void, user_data: *mut c_void) {
+    let state = unsafe { Arc::from_raw(user_data as *const BuildState) };
+    state.set_complete();
+}
+
+/// Begins building `program` for `devices`, marking `state` complete when
+/// finished.
+#[cfg(not(feature = "async_block"))]
+fn start_build(program: &Program, devices: &[Device], cmplr_opts: &CString,
+        state: &Arc<BuildState>) -> OclResult<()> {
+    // Whether the callback is still called when the build fails to begin
+    // varies between platforms. The callback's reference is leaked in that
+    // case rather than risk it being released twice.
+    let user_data = Arc::into_raw(state.clone()) as *mut c_void;
+
+    core::build_program(&program.obj_core, Some(devices), cmplr_opts, Some(_complete_build),
+        Some(user_data)).map_err(OclError::from)
+}
+
+/// Builds `program` for `devices`, blocking until finished.
+#[cfg(feature = "async_block")]
+fn start_build(program: &Program, devices: &[Device], cmplr_opts: &CString,
+        state: &Arc<BuildState>) -> OclResult<()> {
+    let result = core::build_program(&program.obj_core, Some(devices), cmplr_opts, None, None);
+    state.set_complete();
+    result.map_err(OclError::from)
+}
+
+
+/// A future which resolves to a `Program` once its build has completed.
+///
+/// Created by `ProgramBuilder::build_async`.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct FutureProgram {
+    program: Option<Program>,
+    devices: Vec<Device>,
+    cache: Option<BinaryCache>,
+    source_map: Option<SourceMap>,
+    state: Arc<BuildState>,
+    error: Option<OclError>,
+}
+
+impl FutureProgram {
+    /// Begins building `program` for `devices`, storing the resulting
+    /// binaries in `cache` (if any) once complete.
+    ///
+    /// Build errors are mapped back to the program's source using
+    /// `source_map` (if any).
+    fn new(program: Program, devices: Vec<Device>, cmplr_opts: &CString,
+            cache: Option<BinaryCache>, source_map: Option<SourceMap>) -> FutureProgram {
+        let state = Arc::new(BuildState::new(false));
+        let error = start_build(&program, &devices, cmplr_opts, &state).err();
+
+        FutureProgram {
+            program: Some(program),
+            devices: devices,
+            cache: cache,
+            source_map: source_map,
+            state: state,
+            error: error,
+        }
+    }
+
+    /// Returns a future which resolves immediately to an already built
+    /// program.
+    fn ready(program: Program) -> FutureProgram {
+        FutureProgram {
+            program: Some(program),
+            devices: Vec::new(),
+            cache: None,
+            source_map: None,
+            state: Arc::new(BuildState::new(true)),
+            error: None,
+        }
+    }
+
+    /// Returns true if the build has completed.
+    pub fn is_complete(&self) -> bool {
+        self.error.is_some() || self.state.is_complete()
+    }
+
+    /// Maps build errors back to the program's source.
+    fn build_err(&self, err: OclError) -> OclError {
+        match self.source_map {
+            Some(ref source_map) => source_map.map_err(err),
+            None => err,
+        }
+    }
+
+    /// Returns an error containing the build log if the build failed on any
+    /// device.
+    fn verify_build(&self, program: &Program) -> OclResult<()> {
+        for &device in &self.devices {
+            match program.build_info(device, ProgramBuildInfo::BuildStatus)? {
+                ProgramBuildInfoResult::BuildStatus(ProgramBuildStatus::Success) => (),
+                ProgramBuildInfoResult::BuildStatus(status) => {
+                    core::program_build_err(&program.obj_core, &self.devices).map_err(OclCoreError::from)?;
+                    return Err(format!("FutureProgram::poll: Build failed on device '{}' \
+                        (status: {:?}).", device.name()?, status).into());
+                },
+                _ => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Future for FutureProgram {
+    type Item = Program;
+    type Error = OclError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if let Some(err) = self.error.take() { return Err(self.build_err(err)); }
+
+        if !self.state.is_complete() {
+            let task = task::current();
+            *self.state.task.lock().unwrap() = Some(task);
+
+            // The build may have completed before the task was stored:
+            if !self.state.is_complete() { return Ok(Async::NotReady); }
+        }
+
+        let program = self.program.take()
+            .expect("FutureProgram::poll: Program has already been returned.");
+
+        self.verify_build(&program).map_err(|err| self.build_err(err))?;
+
+        // Caching is best-effort:
+        if let Some(cache) = self.cache.take() { cache.store(&program, &self.devices).ok(); }
+
+        Ok(Async::Ready(program))
+    }
+}
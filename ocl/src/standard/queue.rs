@@ -1,11 +1,18 @@
 //! An `OpenCL` command queue.
 
 use std;
+use std::mem;
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::ops::{Deref, DerefMut};
 use core::{self, Result as OclCoreResult, CommandQueue as CommandQueueCore, CommandQueueInfo,
-    CommandQueueInfoResult, OpenclVersion, CommandQueueProperties, ClWaitListPtr, ClContextPtr};
+    CommandQueueInfoResult, OpenclVersion, CommandQueueProperties, QueueProperties, QueuePriority,
+    QueueThrottle, ClWaitListPtr, ClContextPtr, Mem as MemCore, OclPrm};
+use core::ffi::c_void;
 use error::{Error as OclError, Result as OclResult};
-use standard::{Context, Device, Event};
+use standard::{Context, Device, Event, Buffer, ClWaitListPtrEnum, ClNullEventPtrEnum};
 
 /// A command queue which manages all actions taken on kernels, buffers, and
 /// images.
@@ -23,6 +30,12 @@ pub struct Queue {
 }
 
 impl Queue {
+    /// Returns a new `QueueBuilder`, used to create a queue with scheduling
+    /// hints or on-device properties.
+    pub fn builder() -> QueueBuilder {
+        QueueBuilder::new()
+    }
+
     /// Returns a new Queue on the device specified by `device`.
     pub fn new(context: &Context, device: Device, properties: Option<CommandQueueProperties>)
             -> OclResult<Queue> {
@@ -56,6 +69,34 @@ impl Queue {
             .map_err(OclError::from)
     }
 
+    /// Returns a command builder used to enqueue a closure as a native
+    /// kernel, executed on the host once the events it waits on complete.
+    ///
+    /// Buffers added with `NativeKernelCmd::mem` are made available to the
+    /// closure as host slices. The device associated with this queue must
+    /// support native kernels (`DeviceExecCapabilities::NATIVE_KERNEL`).
+    ///
+    /// A panic within the closure is caught (the command still completes)
+    /// and reported by the `NativeKernelStatus` returned when enqueuing.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,ignore
+    /// let mut event = Event::empty();
+    /// let status = unsafe {
+    ///     queue.native_kernel(|mem| {
+    ///         for val in mem.get_mut::<f32>(0) { *val *= 2.0; }
+    ///     }).mem(&buffer).ewait(&kernel_event).enew(&mut event).enq()?
+    /// };
+    /// event.wait_for()?;
+    /// status.result()?;
+    /// ```
+    pub fn native_kernel<F>(&self, func: F) -> NativeKernelCmd
+            where F: FnOnce(&mut NativeKernelMem) + Send + 'static
+    {
+        NativeKernelCmd::new(self, func)
+    }
+
     /// Returns a reference to the core pointer wrapper, usable by functions in
     /// the `core` module.
     #[inline]
@@ -130,4 +171,331 @@ unsafe impl<'a> ClContextPtr for &'a Queue {
         self.context_ptr().expect("<&Queue as ClContextPtr>::as_ptr: \
             Unable to obtain a context pointer.")
     }
-}
\ No newline at end of file
+}
+
+/// A builder for `Queue`.
+///
+/// Properties other than the `CommandQueueProperties` bitfield (on-device
+/// queue sizes and scheduling hints) require the `opencl_version_2_0`
+/// feature and an OpenCL 2.0+ device.
+///
+/// ## Example
+///
+/// ```rust,ignore
+/// let interactive = Queue::builder()
+///     .priority(QueuePriority::High)
+///     .build(&context)?;
+/// ```
+#[must_use = "builders do nothing unless '::build' is called"]
+#[derive(Clone, Debug)]
+pub struct QueueBuilder {
+    device: Option<Device>,
+    properties: QueueProperties,
+}
+
+impl QueueBuilder {
+    /// Returns a new `QueueBuilder`.
+    ///
+    /// ## Defaults
+    ///
+    /// * The first device associated with the context passed to `::build`
+    /// * An in-order, host-side queue with no scheduling hints
+    ///
+    pub fn new() -> QueueBuilder {
+        QueueBuilder {
+            device: None,
+            properties: QueueProperties::new(),
+        }
+    }
+
+    /// Specifies the device on which to create the queue.
+    pub fn device(mut self, device: Device) -> QueueBuilder {
+        self.device = Some(device);
+        self
+    }
+
+    /// Specifies the command queue property bitfield, overwriting any
+    /// previously specified flags.
+    pub fn properties(mut self, properties: CommandQueueProperties) -> QueueBuilder {
+        self.properties.set_properties(properties);
+        self
+    }
+
+    /// Enables out-of-order execution.
+    pub fn out_of_order(mut self) -> QueueBuilder {
+        let props = self.properties.get_properties().out_of_order();
+        self.properties.set_properties(props);
+        self
+    }
+
+    /// Enables profiling.
+    pub fn profiling(mut self) -> QueueBuilder {
+        let props = self.properties.get_properties().profiling();
+        self.properties.set_properties(props);
+        self
+    }
+
+    /// Creates an on-device queue, to which kernels can enqueue child
+    /// kernels. On-device queues are always out-of-order.
+    ///
+    /// When `default` is true, the queue becomes the default on-device queue
+    /// for its device.
+    pub fn on_device(mut self, default: bool) -> QueueBuilder {
+        let mut props = self.properties.get_properties().out_of_order() |
+            CommandQueueProperties::ON_DEVICE;
+        if default { props |= CommandQueueProperties::ON_DEVICE_DEFAULT; }
+        self.properties.set_properties(props);
+        self
+    }
+
+    /// Specifies the size of an on-device queue in bytes.
+    ///
+    /// Defaults to the preferred on-device queue size of the device.
+    pub fn size(mut self, size: u32) -> QueueBuilder {
+        self.properties.set_size(size);
+        self
+    }
+
+    /// Specifies a priority hint (`cl_khr_priority_hints`).
+    pub fn priority(mut self, priority: QueuePriority) -> QueueBuilder {
+        self.properties.set_priority(priority);
+        self
+    }
+
+    /// Specifies a throttle hint (`cl_khr_throttle_hints`).
+    pub fn throttle(mut self, throttle: QueueThrottle) -> QueueBuilder {
+        self.properties.set_throttle(throttle);
+        self
+    }
+
+    /// Returns a new `Queue` associated with `context`.
+    pub fn build(self, context: &Context) -> OclResult<Queue> {
+        let device = match self.device {
+            Some(d) => d,
+            None => *context.devices().first()
+                .ok_or("QueueBuilder::build: The context has no devices.")?,
+        };
+
+        if self.properties.is_bitfield_only() &&
+                !self.properties.get_properties().contains(CommandQueueProperties::ON_DEVICE) {
+            let props = self.properties.get_properties();
+            return Queue::new(context, device, if props.is_empty() { None } else { Some(props) });
+        }
+
+        QueueBuilder::build_with_properties(context, device, &self.properties)
+    }
+
+    #[cfg(feature = "opencl_version_2_0")]
+    fn build_with_properties(context: &Context, device: Device, properties: &QueueProperties)
+            -> OclResult<Queue> {
+        let device_version = device.version()?;
+        let obj_core = core::create_command_queue_with_properties(context, &device, properties,
+            Some(&device_version))?;
+
+        Ok(Queue {
+            obj_core: obj_core,
+            device_version: device_version,
+        })
+    }
+
+    #[cfg(not(feature = "opencl_version_2_0"))]
+    fn build_with_properties(_: &Context, _: Device, _: &QueueProperties) -> OclResult<Queue> {
+        Err("QueueBuilder::build: On-device queues and scheduling hints require the \
+            'opencl_version_2_0' feature.".into())
+    }
+}
+
+
+/// The memory objects passed to a native kernel, accessible as host slices.
+pub struct NativeKernelMem {
+    slices: Vec<(*mut u8, usize)>,
+}
+
+impl NativeKernelMem {
+    /// Returns the number of memory objects.
+    pub fn len(&self) -> usize {
+        self.slices.len()
+    }
+
+    /// Returns the contents of the memory object at `idx`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `idx` is out of range or if the memory object's size or
+    /// alignment is incompatible with `T`.
+    pub fn get<T: OclPrm>(&self, idx: usize) -> &[T] {
+        let (ptr, len) = self.typed::<T>(idx);
+        unsafe { slice::from_raw_parts(ptr, len) }
+    }
+
+    /// Returns the contents of the memory object at `idx` mutably.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `idx` is out of range or if the memory object's size or
+    /// alignment is incompatible with `T`.
+    pub fn get_mut<T: OclPrm>(&mut self, idx: usize) -> &mut [T] {
+        let (ptr, len) = self.typed::<T>(idx);
+        unsafe { slice::from_raw_parts_mut(ptr, len) }
+    }
+
+    fn typed<T: OclPrm>(&self, idx: usize) -> (*mut T, usize) {
+        let (ptr, len_bytes) = self.slices[idx];
+        assert!(len_bytes % mem::size_of::<T>() == 0 && ptr as usize % mem::align_of::<T>() == 0,
+            "ocl::NativeKernelMem: Memory object [{}] is not a valid slice of the requested type.",
+            idx);
+        (ptr as *mut T, len_bytes / mem::size_of::<T>())
+    }
+}
+
+
+/// Whether a native kernel has panicked.
+///
+/// Returned by `NativeKernelCmd::enq`. Only meaningful once the command's
+/// event has completed.
+#[derive(Clone, Debug)]
+pub struct NativeKernelStatus {
+    panicked: Arc<AtomicBool>,
+}
+
+impl NativeKernelStatus {
+    /// Returns true if the native kernel panicked.
+    pub fn panicked(&self) -> bool {
+        self.panicked.load(Ordering::SeqCst)
+    }
+
+    /// Returns an error if the native kernel panicked.
+    pub fn result(&self) -> OclResult<()> {
+        if self.panicked() {
+            Err("NativeKernelStatus: Native kernel panicked.".into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+
+/// The closure and memory object sizes of a native kernel, owned by the
+/// native kernel argument block until the kernel runs.
+struct NativeKernelPayload {
+    func: Box<FnMut(&mut NativeKernelMem) + Send>,
+    mem_lens: Vec<usize>,
+    panicked: Arc<AtomicBool>,
+}
+
+/// Reconstructs the payload and memory slices from a copy of the argument
+/// block created by `NativeKernelCmd::enq` and calls the closure.
+///
+/// The block contains a pointer to the payload followed by one pointer per
+/// memory object.
+extern "C" fn native_kernel_trampoline(args: *mut c_void) {
+    unsafe {
+        let words = args as *const usize;
+        let mut payload = Box::from_raw(ptr::read_unaligned(words) as *mut NativeKernelPayload);
+
+        let slices = payload.mem_lens.iter().enumerate().map(|(i, &len)| {
+            (ptr::read_unaligned(words.offset(1 + i as isize)) as *mut u8, len)
+        }).collect();
+        let mut mem = NativeKernelMem { slices: slices };
+
+        if !super::call_from_runtime(|| (payload.func)(&mut mem)) {
+            payload.panicked.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+
+/// A native kernel command builder.
+///
+/// Created with `Queue::native_kernel`.
+#[must_use = "commands do nothing unless enqueued"]
+pub struct NativeKernelCmd<'c> {
+    queue: &'c Queue,
+    func: Box<FnMut(&mut NativeKernelMem) + Send>,
+    mem: Vec<(MemCore, usize)>,
+    wait_events: Option<ClWaitListPtrEnum<'c>>,
+    new_event: Option<ClNullEventPtrEnum<'c>>,
+}
+
+impl<'c> NativeKernelCmd<'c> {
+    fn new<F>(queue: &'c Queue, func: F) -> NativeKernelCmd<'c>
+            where F: FnOnce(&mut NativeKernelMem) + Send + 'static
+    {
+        let mut func = Some(func);
+
+        NativeKernelCmd {
+            queue: queue,
+            func: Box::new(move |mem| {
+                if let Some(func) = func.take() { func(mem) }
+            }),
+            mem: Vec::new(),
+            wait_events: None,
+            new_event: None,
+        }
+    }
+
+    /// Adds a buffer to the list of memory objects passed to the native
+    /// kernel, available within it by index in the order added.
+    pub fn mem<T: OclPrm>(mut self, buffer: &Buffer<T>) -> NativeKernelCmd<'c> {
+        self.mem.push((buffer.as_core().clone(), buffer.len() * mem::size_of::<T>()));
+        self
+    }
+
+    /// Specifies a list of events to wait on before the command will run.
+    pub fn ewait<'e, Ewl>(mut self, ewait: Ewl) -> NativeKernelCmd<'c>
+            where 'e: 'c, Ewl: Into<ClWaitListPtrEnum<'e>> {
+        self.wait_events = Some(ewait.into());
+        self
+    }
+
+    /// Specifies the destination list or empty event for a new, optionally
+    /// created event associated with this command.
+    pub fn enew<'e, En>(mut self, new_event_dest: En) -> NativeKernelCmd<'c>
+            where 'e: 'c, En: Into<ClNullEventPtrEnum<'e>> {
+        self.new_event = Some(new_event_dest.into());
+        self
+    }
+
+    /// Enqueues this native kernel command.
+    ///
+    /// # Safety
+    ///
+    /// The closure receives the contents of each buffer added with `::mem`
+    /// as a host slice (mutably with `NativeKernelMem::get_mut`). Nothing
+    /// else may read or write those buffers while it runs: no kernel or
+    /// other command on any queue and no host access through a mapping or
+    /// the host pointer of a buffer created with `MEM_USE_HOST_PTR`.
+    /// Those commands must either complete before the native kernel starts
+    /// (use `::ewait`, or enqueue them earlier on this queue if it is
+    /// in-order) or wait on its event (`::enew`).
+    ///
+    /// The closure itself can not cause undefined behavior otherwise: it
+    /// must be `'static` (so it can not borrow anything which may be gone by
+    /// the time it runs) and a panic within it is caught.
+    pub unsafe fn enq(self) -> OclResult<NativeKernelStatus> {
+        let word_size = mem::size_of::<usize>();
+        let status = NativeKernelStatus { panicked: Arc::new(AtomicBool::new(false)) };
+        let payload = Box::into_raw(Box::new(NativeKernelPayload {
+            func: self.func,
+            mem_lens: self.mem.iter().map(|&(_, len)| len).collect(),
+            panicked: status.panicked.clone(),
+        }));
+
+        let mut args = Vec::with_capacity(word_size * (1 + self.mem.len()));
+        args.extend_from_slice(slice::from_raw_parts(&(payload as usize) as *const usize
+            as *const u8, word_size));
+        args.resize(word_size * (1 + self.mem.len()), 0);
+
+        let mem_list: Vec<&MemCore> = self.mem.iter().map(|&(ref mem, _)| mem).collect();
+        let mem_locs: Vec<usize> = (0..self.mem.len()).map(|i| word_size * (1 + i)).collect();
+
+        core::enqueue_native_kernel(&self.queue.obj_core, native_kernel_trampoline, &args,
+                &mem_list, &mem_locs, self.wait_events, self.new_event)
+            .map(|_| status)
+            .map_err(|err| {
+                // The kernel will never run:
+                drop(Box::from_raw(payload));
+                OclError::from(err)
+            })
+    }
+}
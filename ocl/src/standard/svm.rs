@@ -0,0 +1,721 @@
+//! Shared virtual memory (OpenCL 2.0+).
+//!
+//! Shared virtual memory (SVM) allocations live in an address space shared
+//! by the host and every device in a context. Pointers stored within them
+//! remain valid on both, making them suitable for pointer-based data
+//! structures such as trees and linked lists.
+
+use std;
+use std::mem;
+use std::slice;
+use std::sync::Arc;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use core::{self, OclPrm, MemFlags, MapFlags, DeviceInfo, DeviceInfoResult, DeviceSvmCapabilities,
+    OpenclVersion};
+use core::ffi::c_void;
+use error::{Error as OclError, Result as OclResult};
+use standard::{Context, Device, Queue, ClWaitListPtrEnum, ClNullEventPtrEnum};
+
+
+/// The kind of a shared virtual memory allocation.
+///
+/// Use `SvmKind::best_supported` to determine the most capable kind
+/// supported by a set of devices (see `DeviceInfo::SvmCapabilities`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SvmKind {
+    /// Must be mapped (see `SvmVec::map`) or accessed with SVM commands to
+    /// be accessed by the host. Supported by all OpenCL 2.0 devices.
+    CoarseGrain,
+    /// May be accessed by the host and devices concurrently without mapping.
+    FineGrain,
+    /// Fine-grained, with atomic operations visible across the host and
+    /// devices.
+    FineGrainAtomics,
+}
+
+impl SvmKind {
+    /// Returns the most capable kind supported by every device in `devices`
+    /// or `None` if any of them do not support shared virtual memory.
+    pub fn best_supported(devices: &[Device]) -> OclResult<Option<SvmKind>> {
+        let mut best = Some(SvmKind::FineGrainAtomics);
+
+        for device in devices {
+            if device.version()? < OpenclVersion::new(2, 0) { return Ok(None); }
+
+            let caps = match device.info(DeviceInfo::SvmCapabilities)? {
+                DeviceInfoResult::SvmCapabilities(caps) => caps,
+                _ => unreachable!(),
+            };
+
+            let supported = [SvmKind::FineGrainAtomics, SvmKind::FineGrain, SvmKind::CoarseGrain]
+                .iter().cloned().find(|kind| kind.is_supported_by(caps));
+            best = std::cmp::min(best, supported);
+        }
+        Ok(best)
+    }
+
+    /// Returns true if this kind of allocation is supported by a device with
+    /// the capabilities, `caps`.
+    pub fn is_supported_by(&self, caps: DeviceSvmCapabilities) -> bool {
+        match *self {
+            SvmKind::CoarseGrain => caps.contains(DeviceSvmCapabilities::COARSE_GRAIN_BUFFER),
+            SvmKind::FineGrain => caps.contains(DeviceSvmCapabilities::FINE_GRAIN_BUFFER),
+            SvmKind::FineGrainAtomics => caps.contains(DeviceSvmCapabilities::FINE_GRAIN_BUFFER |
+                DeviceSvmCapabilities::ATOMICS),
+        }
+    }
+
+    /// Returns true if allocations of this kind may be accessed by the host
+    /// without being mapped.
+    pub fn is_fine_grained(&self) -> bool {
+        *self != SvmKind::CoarseGrain
+    }
+
+    /// Returns the flags used to allocate this kind of allocation.
+    pub fn flags(&self) -> MemFlags {
+        match *self {
+            SvmKind::CoarseGrain => MemFlags::new().read_write(),
+            SvmKind::FineGrain => MemFlags::new().read_write().svm_fine_grain_buffer(),
+            SvmKind::FineGrainAtomics => MemFlags::new().read_write().svm_fine_grain_buffer()
+                .svm_atomics(),
+        }
+    }
+}
+
+
+/// An SVM allocation.
+///
+/// Freed using the queue it was allocated with once all previously enqueued
+/// commands have completed.
+#[derive(Debug)]
+struct SvmAlloc {
+    ptr: *mut c_void,
+    size: usize,
+    kind: SvmKind,
+    context: Context,
+    queue: Queue,
+}
+
+impl Drop for SvmAlloc {
+    fn drop(&mut self) {
+        unsafe {
+            if core::enqueue_svm_free(self.queue.as_core(), &[self.ptr], None::<()>, None::<()>,
+                    Some(&self.queue.device_version())).is_err() {
+                self.queue.finish().ok();
+                core::svm_free(&self.context, self.ptr);
+            }
+        }
+    }
+}
+
+unsafe impl Send for SvmAlloc {}
+unsafe impl Sync for SvmAlloc {}
+
+
+/// A reference counted, untyped reference to a shared virtual memory
+/// allocation.
+///
+/// Keeps the allocation alive. Used to specify allocations which a kernel
+/// accesses indirectly (see `Kernel::set_svm_indirect`).
+#[derive(Clone, Debug)]
+pub struct SvmRef(Arc<SvmAlloc>);
+
+impl SvmRef {
+    /// Returns a pointer to the start of the allocation.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.0.ptr
+    }
+
+    /// Returns the size of the allocation in bytes.
+    pub fn size(&self) -> usize {
+        self.0.size
+    }
+
+    /// Returns the kind of the allocation.
+    pub fn kind(&self) -> SvmKind {
+        self.0.kind
+    }
+}
+
+
+/// A fixed-length array of `T` in shared virtual memory.
+///
+/// Pass to kernels with `Kernel::arg_svm`. Pointers to elements (see
+/// `::as_ptr`) may be stored within other SVM allocations and followed by
+/// kernels.
+///
+/// Fine-grained arrays may be accessed by the host directly using
+/// `::as_slice` and `::as_mut_slice`. Coarse-grained arrays must first be
+/// mapped using `::map`. As kernels access the array through a raw pointer,
+/// all three are unsafe: the caller must ensure no kernel accessing the array
+/// is running while the host holds a reference to its contents.
+///
+/// ## Destruction
+///
+/// The allocation is freed once this array (and any kernels it has been
+/// passed to) has been dropped and all commands previously enqueued on its
+/// queue have completed.
+///
+#[derive(Debug)]
+pub struct SvmVec<T: OclPrm> {
+    alloc: Arc<SvmAlloc>,
+    len: usize,
+    _data: PhantomData<T>,
+}
+
+impl<T: OclPrm> SvmVec<T> {
+    /// Returns a new array of `len` default valued elements of the most
+    /// capable kind supported by every device in the queue's context.
+    pub fn new(queue: &Queue, len: usize) -> OclResult<SvmVec<T>> {
+        let kind = best_supported_kind(queue)?;
+        SvmVec::with_kind(queue, len, kind)
+    }
+
+    /// Returns a new array of `len` default valued elements of the kind,
+    /// `kind`.
+    pub fn with_kind(queue: &Queue, len: usize, kind: SvmKind) -> OclResult<SvmVec<T>> {
+        let mut svm_vec = unsafe { SvmVec::uninitialized(queue, len, kind)? };
+        // Written rather than filled as fill patterns are limited in size:
+        svm_vec.write(&vec![T::default(); len]).enq()?;
+        Ok(svm_vec)
+    }
+
+    /// Returns a new array containing a copy of `data` of the most capable
+    /// kind supported by every device in the queue's context.
+    pub fn from_slice(queue: &Queue, data: &[T]) -> OclResult<SvmVec<T>> {
+        let kind = best_supported_kind(queue)?;
+        let mut svm_vec = unsafe { SvmVec::uninitialized(queue, data.len(), kind)? };
+        svm_vec.write(data).enq()?;
+        Ok(svm_vec)
+    }
+
+    /// Returns a new array of `len` uninitialized elements.
+    pub unsafe fn uninitialized(queue: &Queue, len: usize, kind: SvmKind)
+            -> OclResult<SvmVec<T>> {
+        if len == 0 { return Err("SvmVec::new: Length must be greater than zero.".into()); }
+
+        let context = queue.context();
+        let size = len * mem::size_of::<T>();
+        let ptr = core::svm_alloc(&context, kind.flags(), size, 0,
+            Some(&context.device_versions()?))?;
+
+        Ok(SvmVec {
+            alloc: Arc::new(SvmAlloc {
+                ptr: ptr,
+                size: size,
+                kind: kind,
+                context: context,
+                queue: queue.clone(),
+            }),
+            len: len,
+            _data: PhantomData,
+        })
+    }
+
+    /// Returns the number of elements.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if this array contains no elements (never the case).
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the kind of this allocation.
+    #[inline]
+    pub fn kind(&self) -> SvmKind {
+        self.alloc.kind
+    }
+
+    /// Returns the queue used by commands and to free this allocation.
+    #[inline]
+    pub fn default_queue(&self) -> &Queue {
+        &self.alloc.queue
+    }
+
+    /// Returns a pointer to the first element, valid on the host and all
+    /// devices in the context.
+    #[inline]
+    pub fn as_ptr(&self) -> *const T {
+        self.alloc.ptr as *const T
+    }
+
+    /// Returns a mutable pointer to the first element, valid on the host and
+    /// all devices in the context.
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.alloc.ptr as *mut T
+    }
+
+    /// Returns an untyped reference which keeps this allocation alive.
+    pub fn svm_ref(&self) -> SvmRef {
+        SvmRef(self.alloc.clone())
+    }
+
+    /// Returns the contents of a fine-grained array as a slice.
+    ///
+    /// Returns an error if this array is coarse-grained (use `::map`
+    /// instead).
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure that no kernel or other command which writes
+    /// to this array (through a pointer obtained from `::as_ptr`, an argument
+    /// set with `Kernel::arg_svm`, or an `SvmRef`) runs while the returned
+    /// slice is alive.
+    pub unsafe fn as_slice(&self) -> OclResult<&[T]> {
+        self.verify_fine_grained()?;
+        Ok(slice::from_raw_parts(self.as_ptr(), self.len))
+    }
+
+    /// Returns the contents of a fine-grained array as a mutable slice.
+    ///
+    /// Returns an error if this array is coarse-grained (use `::map`
+    /// instead).
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure that no kernel or other command which accesses
+    /// this array (through a pointer obtained from `::as_ptr`, an argument
+    /// set with `Kernel::arg_svm`, or an `SvmRef`) runs while the returned
+    /// slice is alive.
+    pub unsafe fn as_mut_slice(&mut self) -> OclResult<&mut [T]> {
+        self.verify_fine_grained()?;
+        Ok(slice::from_raw_parts_mut(self.as_mut_ptr(), self.len))
+    }
+
+    /// Returns a command builder used to map this array for host access.
+    pub fn map(&mut self) -> SvmMapCmd<T> {
+        SvmMapCmd {
+            svm_vec: self,
+            flags: MapFlags::new().read().write(),
+            ewait: None,
+            enew: None,
+        }
+    }
+
+    /// Returns a command builder used to read this array into `dst`.
+    ///
+    /// Reads `dst.len()` elements unless otherwise specified.
+    pub fn read<'c>(&'c self, dst: &'c mut [T]) -> SvmCopyCmd<'c> {
+        let dst_len = dst.len();
+        SvmCopyCmd::new(&self.alloc.queue, dst.as_mut_ptr() as *mut c_void,
+            self.alloc.ptr as *const c_void, self.len, dst_len, Some(dst_len),
+            mem::size_of::<T>(), true)
+    }
+
+    /// Returns a command builder used to write `src` into this array.
+    ///
+    /// Writes `src.len()` elements unless otherwise specified.
+    pub fn write<'c>(&'c mut self, src: &'c [T]) -> SvmCopyCmd<'c> {
+        SvmCopyCmd::new(&self.alloc.queue, self.alloc.ptr, src.as_ptr() as *const c_void,
+            src.len(), self.len, Some(src.len()), mem::size_of::<T>(), true)
+    }
+
+    /// Returns a command builder used to copy this array into `dst`.
+    ///
+    /// Unlike reads and writes, copies do not block. Unless the number of
+    /// elements to copy is specified, both arrays must be of the same
+    /// length.
+    pub fn copy<'c>(&'c self, dst: &'c mut SvmVec<T>) -> SvmCopyCmd<'c> {
+        let len = if self.len == dst.len { Some(self.len) } else { None };
+        SvmCopyCmd::new(&self.alloc.queue, dst.alloc.ptr, self.alloc.ptr as *const c_void,
+            self.len, dst.len, len, mem::size_of::<T>(), false)
+    }
+
+    /// Returns a command builder used to fill this array with `val`.
+    ///
+    /// The size of `T` must be a power of two no larger than 128 bytes.
+    pub fn fill(&mut self, val: T) -> SvmFillCmd<T> {
+        SvmFillCmd {
+            queue: &self.alloc.queue,
+            ptr: self.alloc.ptr,
+            val: val,
+            len: self.len,
+            ewait: None,
+            enew: None,
+        }
+    }
+
+    fn verify_fine_grained(&self) -> OclResult<()> {
+        if self.kind().is_fine_grained() {
+            Ok(())
+        } else {
+            Err("SvmVec: Coarse-grained SVM must be mapped before being accessed by the \
+                host. Use '::map'.".into())
+        }
+    }
+}
+
+
+/// A single `T` in shared virtual memory.
+///
+/// Derefs to a single element `SvmVec` for access to commands and kernel
+/// arguments.
+#[derive(Debug)]
+pub struct SvmBox<T: OclPrm>(SvmVec<T>);
+
+impl<T: OclPrm> SvmBox<T> {
+    /// Returns a new box containing `val` of the most capable kind supported
+    /// by every device in the queue's context.
+    pub fn new(queue: &Queue, val: T) -> OclResult<SvmBox<T>> {
+        SvmVec::from_slice(queue, &[val]).map(SvmBox)
+    }
+
+    /// Returns a new box containing `val` of the kind, `kind`.
+    pub fn with_kind(queue: &Queue, val: T, kind: SvmKind) -> OclResult<SvmBox<T>> {
+        let mut svm_vec = unsafe { SvmVec::uninitialized(queue, 1, kind)? };
+        svm_vec.write(&[val]).enq()?;
+        Ok(SvmBox(svm_vec))
+    }
+
+    /// Returns a reference to the contained value of a fine-grained box.
+    ///
+    /// ## Safety
+    ///
+    /// See `SvmVec::as_slice`.
+    pub unsafe fn get(&self) -> OclResult<&T> {
+        self.0.as_slice().map(|s| &s[0])
+    }
+
+    /// Returns a mutable reference to the contained value of a fine-grained
+    /// box.
+    ///
+    /// ## Safety
+    ///
+    /// See `SvmVec::as_mut_slice`.
+    pub unsafe fn get_mut(&mut self) -> OclResult<&mut T> {
+        self.0.as_mut_slice().map(|s| &mut s[0])
+    }
+
+    /// Returns the contained value (blocking until it has been read).
+    pub fn value(&self) -> OclResult<T> {
+        let mut val = [T::default()];
+        self.0.read(&mut val).enq()?;
+        Ok(val[0])
+    }
+
+    /// Returns the underlying single element array.
+    pub fn into_vec(self) -> SvmVec<T> {
+        self.0
+    }
+}
+
+impl<T: OclPrm> Deref for SvmBox<T> {
+    type Target = SvmVec<T>;
+
+    fn deref(&self) -> &SvmVec<T> {
+        &self.0
+    }
+}
+
+impl<T: OclPrm> DerefMut for SvmBox<T> {
+    fn deref_mut(&mut self) -> &mut SvmVec<T> {
+        &mut self.0
+    }
+}
+
+
+/// A command builder used to map an `SvmVec` for host access.
+#[must_use = "commands do nothing unless enqueued"]
+pub struct SvmMapCmd<'c, T: 'c + OclPrm> {
+    svm_vec: &'c mut SvmVec<T>,
+    flags: MapFlags,
+    ewait: Option<ClWaitListPtrEnum<'c>>,
+    enew: Option<ClNullEventPtrEnum<'c>>,
+}
+
+impl<'c, T: OclPrm> SvmMapCmd<'c, T> {
+    /// Maps for reading only.
+    pub fn read(mut self) -> SvmMapCmd<'c, T> {
+        self.flags = MapFlags::new().read();
+        self
+    }
+
+    /// Maps for writing only.
+    pub fn write(mut self) -> SvmMapCmd<'c, T> {
+        self.flags = MapFlags::new().write();
+        self
+    }
+
+    /// Maps for writing, discarding the current contents.
+    pub fn write_invalidate(mut self) -> SvmMapCmd<'c, T> {
+        self.flags = MapFlags::new().write_invalidate_region();
+        self
+    }
+
+    /// Specifies a list of events to wait on before the command will run.
+    pub fn ewait<'e, Ewl>(mut self, ewait: Ewl) -> SvmMapCmd<'c, T>
+            where 'e: 'c, Ewl: Into<ClWaitListPtrEnum<'e>> {
+        self.ewait = Some(ewait.into());
+        self
+    }
+
+    /// Specifies the destination for a new, optionally created event
+    /// associated with this command.
+    pub fn enew<'e, En>(mut self, enew: En) -> SvmMapCmd<'c, T>
+            where 'e: 'c, En: Into<ClNullEventPtrEnum<'e>> {
+        self.enew = Some(enew.into());
+        self
+    }
+
+    /// Enqueues this command, blocking the current thread until the array
+    /// has been mapped.
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure that no kernel or other command which accesses
+    /// this array (through a pointer obtained from `SvmVec::as_ptr`, an
+    /// argument set with `Kernel::arg_svm`, or an `SvmRef`) runs while the
+    /// returned view is alive.
+    pub unsafe fn enq(self) -> OclResult<SvmMap<'c, T>> {
+        let queue = self.svm_vec.alloc.queue.clone();
+
+        core::enqueue_svm_map(queue.as_core(), true, self.flags,
+            self.svm_vec.alloc.ptr, self.svm_vec.alloc.size, self.ewait, self.enew,
+            Some(&queue.device_version()))?;
+
+        Ok(SvmMap { svm_vec: self.svm_vec, unmapped: false })
+    }
+}
+
+
+/// A view of a mapped `SvmVec`, unmapped when dropped or when `::unmap` is
+/// called.
+#[derive(Debug)]
+pub struct SvmMap<'a, T: 'a + OclPrm> {
+    svm_vec: &'a mut SvmVec<T>,
+    unmapped: bool,
+}
+
+impl<'a, T: OclPrm> SvmMap<'a, T> {
+    /// Returns a command builder used to unmap this view.
+    pub fn unmap<'c>(mut self) -> SvmUnmapCmd<'c> where 'a: 'c {
+        self.unmapped = true;
+        SvmUnmapCmd {
+            queue: self.svm_vec.alloc.queue.clone(),
+            ptr: self.svm_vec.alloc.ptr,
+            ewait: None,
+            enew: None,
+            enqueued: false,
+        }
+    }
+}
+
+impl<'a, T: OclPrm> Deref for SvmMap<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.svm_vec.as_ptr(), self.svm_vec.len()) }
+    }
+}
+
+impl<'a, T: OclPrm> DerefMut for SvmMap<'a, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        let len = self.svm_vec.len();
+        unsafe { slice::from_raw_parts_mut(self.svm_vec.as_mut_ptr(), len) }
+    }
+}
+
+impl<'a, T: OclPrm> Drop for SvmMap<'a, T> {
+    fn drop(&mut self) {
+        if !self.unmapped {
+            let queue = &self.svm_vec.alloc.queue;
+            unsafe {
+                core::enqueue_svm_unmap(queue.as_core(), self.svm_vec.alloc.ptr, None::<()>,
+                    None::<()>, Some(&queue.device_version())).ok();
+            }
+        }
+    }
+}
+
+
+/// A command builder used to unmap an `SvmMap`.
+///
+/// The view is unmapped (without events) if dropped before being enqueued.
+#[must_use = "commands do nothing unless enqueued"]
+pub struct SvmUnmapCmd<'c> {
+    queue: Queue,
+    ptr: *mut c_void,
+    ewait: Option<ClWaitListPtrEnum<'c>>,
+    enew: Option<ClNullEventPtrEnum<'c>>,
+    enqueued: bool,
+}
+
+impl<'c> SvmUnmapCmd<'c> {
+    /// Specifies a list of events to wait on before the command will run.
+    pub fn ewait<'e, Ewl>(mut self, ewait: Ewl) -> SvmUnmapCmd<'c>
+            where 'e: 'c, Ewl: Into<ClWaitListPtrEnum<'e>> {
+        self.ewait = Some(ewait.into());
+        self
+    }
+
+    /// Specifies the destination for a new, optionally created event
+    /// associated with this command.
+    pub fn enew<'e, En>(mut self, enew: En) -> SvmUnmapCmd<'c>
+            where 'e: 'c, En: Into<ClNullEventPtrEnum<'e>> {
+        self.enew = Some(enew.into());
+        self
+    }
+
+    /// Enqueues this command.
+    pub fn enq(mut self) -> OclResult<()> {
+        self.enqueued = true;
+        unsafe {
+            core::enqueue_svm_unmap(self.queue.as_core(), self.ptr, self.ewait.take(),
+                self.enew.take(), Some(&self.queue.device_version())).map_err(OclError::from)
+        }
+    }
+}
+
+impl<'c> Drop for SvmUnmapCmd<'c> {
+    fn drop(&mut self) {
+        if !self.enqueued {
+            unsafe {
+                core::enqueue_svm_unmap(self.queue.as_core(), self.ptr, None::<()>, None::<()>,
+                    Some(&self.queue.device_version())).ok();
+            }
+        }
+    }
+}
+
+
+/// A command builder used to copy to, from, or between `SvmVec`s.
+#[must_use = "commands do nothing unless enqueued"]
+pub struct SvmCopyCmd<'c> {
+    queue: &'c Queue,
+    dst: *mut c_void,
+    src: *const c_void,
+    src_len: usize,
+    dst_len: usize,
+    elem_size: usize,
+    block: bool,
+    len: Option<usize>,
+    default_len: Option<usize>,
+    ewait: Option<ClWaitListPtrEnum<'c>>,
+    enew: Option<ClNullEventPtrEnum<'c>>,
+}
+
+impl<'c> SvmCopyCmd<'c> {
+    fn new(queue: &'c Queue, dst: *mut c_void, src: *const c_void, src_len: usize,
+            dst_len: usize, default_len: Option<usize>, elem_size: usize, block: bool)
+            -> SvmCopyCmd<'c> {
+        SvmCopyCmd {
+            queue: queue,
+            dst: dst,
+            src: src,
+            src_len: src_len,
+            dst_len: dst_len,
+            elem_size: elem_size,
+            block: block,
+            len: None,
+            default_len: default_len,
+            ewait: None,
+            enew: None,
+        }
+    }
+
+    /// Specifies the number of elements to copy. Defaults to the length of
+    /// the host slice for reads and writes and to the common length of the
+    /// source and destination for copies.
+    pub fn len(mut self, len: usize) -> SvmCopyCmd<'c> {
+        self.len = Some(len);
+        self
+    }
+
+    /// Specifies a list of events to wait on before the command will run.
+    pub fn ewait<'e, Ewl>(mut self, ewait: Ewl) -> SvmCopyCmd<'c>
+            where 'e: 'c, Ewl: Into<ClWaitListPtrEnum<'e>> {
+        self.ewait = Some(ewait.into());
+        self
+    }
+
+    /// Specifies the destination for a new, optionally created event
+    /// associated with this command.
+    pub fn enew<'e, En>(mut self, enew: En) -> SvmCopyCmd<'c>
+            where 'e: 'c, En: Into<ClNullEventPtrEnum<'e>> {
+        self.enew = Some(enew.into());
+        self
+    }
+
+    /// Enqueues this command.
+    ///
+    /// Reads and writes block the current thread until complete.
+    pub fn enq(self) -> OclResult<()> {
+        let max_len = std::cmp::min(self.src_len, self.dst_len);
+        let len = match self.len.or(self.default_len) {
+            Some(len) => len,
+            None => return Err(format!("SvmCopyCmd::enq: Source length ({}) differs from \
+                destination length ({}). Specify the number of elements to copy with '::len'.",
+                self.src_len, self.dst_len).into()),
+        };
+        if len > max_len { return Err(format!("SvmCopyCmd::enq: Length ({}) out of range \
+            (max: {}).", len, max_len).into()); }
+
+        unsafe {
+            core::enqueue_svm_memcpy(self.queue.as_core(), self.block, self.dst, self.src,
+                len * self.elem_size, self.ewait, self.enew, Some(&self.queue.device_version()))
+                .map_err(OclError::from)
+        }
+    }
+}
+
+
+/// A command builder used to fill an `SvmVec`.
+#[must_use = "commands do nothing unless enqueued"]
+pub struct SvmFillCmd<'c, T: OclPrm> {
+    queue: &'c Queue,
+    ptr: *mut c_void,
+    val: T,
+    len: usize,
+    ewait: Option<ClWaitListPtrEnum<'c>>,
+    enew: Option<ClNullEventPtrEnum<'c>>,
+}
+
+impl<'c, T: OclPrm> SvmFillCmd<'c, T> {
+    /// Specifies a list of events to wait on before the command will run.
+    pub fn ewait<'e, Ewl>(mut self, ewait: Ewl) -> SvmFillCmd<'c, T>
+            where 'e: 'c, Ewl: Into<ClWaitListPtrEnum<'e>> {
+        self.ewait = Some(ewait.into());
+        self
+    }
+
+    /// Specifies the destination for a new, optionally created event
+    /// associated with this command.
+    pub fn enew<'e, En>(mut self, enew: En) -> SvmFillCmd<'c, T>
+            where 'e: 'c, En: Into<ClNullEventPtrEnum<'e>> {
+        self.enew = Some(enew.into());
+        self
+    }
+
+    /// Enqueues this command.
+    ///
+    /// Returns an error if the size of `T` is not a power of two no larger
+    /// than 128 bytes, as required of fill patterns.
+    pub fn enq(self) -> OclResult<()> {
+        let size = mem::size_of::<T>();
+        if !size.is_power_of_two() || size > 128 {
+            return Err(format!("SvmFillCmd::enq: Pattern size ({} bytes) must be a power of \
+                two no larger than 128 bytes. Use '::write' instead.", size).into());
+        }
+
+        unsafe {
+            core::enqueue_svm_mem_fill(self.queue.as_core(), self.ptr, self.val, self.len,
+                self.ewait, self.enew, Some(&self.queue.device_version()))
+                .map_err(OclError::from)
+        }
+    }
+}
+
+
+/// Returns the most capable kind supported by every device in the queue's
+/// context.
+fn best_supported_kind(queue: &Queue) -> OclResult<SvmKind> {
+    SvmKind::best_supported(&queue.context().devices())?
+        .ok_or_else(|| "SvmVec::new: Shared virtual memory is not supported by every device \
+            in the context.".into())
+}
@@ -380,6 +380,7 @@ fn create_queue(context: &Context, device: Device, flags: Option<CommandQueuePro
 //
 #[test]
 pub fn rw_vec() {
+    ::tests::init();
     // if cfg!(not(feature = "async_block")) { panic!("'async_block' disabled!"); }
 
     // let platform = Platform::default();
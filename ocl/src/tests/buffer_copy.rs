@@ -5,6 +5,7 @@ const DATASET_SIZE: usize = 1 << 20;
 
 #[test]
 fn buffer_copy_core() {
+    ::tests::init();
     use std::ffi::CString;
     use core::{self, ContextProperties};
     use flags;
@@ -77,6 +78,7 @@ fn buffer_copy_core() {
 
 #[test]
 fn buffer_copy_standard() {
+    ::tests::init();
     use standard::ProQue;
     let src = r#"
         __kernel void add(__global float* buffer, float addend) {
@@ -5,6 +5,7 @@ const DATASET_SIZE: usize = 1 << 20;
 
 #[test]
 fn fill() {
+    ::tests::init();
     let src = r#"
         __kernel void add(__global float* buffer, float addend) {
             buffer[get_global_id(0)] += addend;
@@ -43,6 +44,7 @@ fn fill() {
 
 #[test]
 fn fill_with_float4() {
+    ::tests::init();
     use prm::Float4;
 
     let src = r#"
@@ -14,6 +14,7 @@ const TEST_ITERS: i32 = 220;
 
 #[test]
 fn buffer_ops_rect() {
+    tests::init();
     let src = r#"
         __kernel void add(__global float* buffer, float addend) {
             uint idx = (get_global_id(0) * get_global_size(1) * get_global_size(2)) +
@@ -461,6 +461,7 @@ pub fn verify_add(dst_buf_stream: &BufferStream<Int4>,
 ///
 #[test]
 pub fn buffer_sink_stream_cycles() {
+    ::tests::init();
     let platform = Platform::default();
     println!("Platform: {}", platform.name().unwrap());
     let device = Device::first(platform).unwrap();
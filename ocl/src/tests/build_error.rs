@@ -1,7 +1,9 @@
 use super::super::ProQue;
 
+// The mock only fails builds on '#error' directives:
 #[test]
 #[should_panic]
+#[cfg_attr(feature = "mock", ignore)]
 #[allow(unused_variables)]
 fn bad_kernel_variable_names() {
     let kernel = r#"
@@ -2,6 +2,7 @@ use standard::{ProQue, EventList};
 
 #[test]
 fn clear_completed() {
+    ::tests::init();
     let src = r#"
         __kernel void add(__global float* buffer, float addend) {
             buffer[get_global_id(0)] += addend;
@@ -23,6 +23,7 @@ const THREAD_COUNT: u32 = 2;
 
 #[test]
 fn concurrent() {
+    ::tests::init();
     let mut rng = rand::weak_rng();
     let data_set_size = 1 << 10;
     let dims = [data_set_size];
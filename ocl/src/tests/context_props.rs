@@ -11,6 +11,7 @@ use core::{ContextPropertyValue};
 
 #[test]
 fn test_context_props() {
+    ::tests::init();
     // let dims = [2048];
     let platforms = Platform::list();
 
@@ -15,7 +15,9 @@ use tests;
 const DIMS: [usize; 3] = [64, 128, 4];
 const TEST_ITERS: i32 = 4;
 
+// Mock kernels can not sample images:
 #[test]
+#[cfg_attr(feature = "mock", ignore)]
 fn image_ops() {
     #[allow(non_snake_case)]
     let ADDEND: Int4 = Int4::new(1, 1, 1, 1);
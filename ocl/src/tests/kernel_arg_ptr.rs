@@ -17,6 +17,7 @@ static SRC: &'static str = r#"
 ///
 #[test]
 fn kernel_arg_ptr_out_of_scope() {
+    ::tests::init();
     let pro_que = ProQue::builder()
         .src(SRC)
         .dims([1024])
@@ -5,9 +5,13 @@
 //!
 //! * TODO: port some of bismit's tests over.
 //!
+//! Enable the `mock` feature to run these against the in-process mock
+//! platform (`ocl-mock`) rather than an OpenCL driver.
 //!
 
 extern crate rand;
+#[cfg(feature = "mock")]
+extern crate ocl_mock;
 
 pub mod build_error;
 pub mod buffer_copy;
@@ -31,6 +35,74 @@ const PRINT_SLICES_MAX: usize = 16;
 const PRINT: bool = false;
 
 
+/// Installs the mock platform and registers implementations of the kernels
+/// used by these tests if the `mock` feature is enabled.
+///
+/// Must be called at the start of every test which uses OpenCL, as the mock
+/// can not be installed once the system library has been loaded. Panics if
+/// that has already happened.
+#[cfg(feature = "mock")]
+pub fn init() {
+    use std::sync::Once;
+    use prm::{Float4, Int4};
+    use self::ocl_mock::WorkItem;
+
+    static INIT: Once = Once::new();
+
+    // The index used by kernels which treat buffers as three dimensional
+    // (the work size of unused dimensions is one):
+    fn linear_idx(wi: &WorkItem) -> usize {
+        (wi.global_id(0) * wi.global_size(1) * wi.global_size(2)) +
+            (wi.global_id(1) * wi.global_size(2)) + wi.global_id(2)
+    }
+
+    // Installing again is a no-op once the mock is in place. Doing so on
+    // every call (rather than once) keeps a failure here from poisoning
+    // `INIT` and obscuring the cause in every test that follows:
+    if let Err(err) = ocl_mock::install() {
+        panic!("ocl::tests::init: Unable to install the mock platform: {} \
+            A test which does not call `init` has most likely used OpenCL \
+            first and loaded the system library.", err);
+    }
+
+    INIT.call_once(|| {
+        ocl_mock::register_kernel("add", |wi| {
+            let idx = linear_idx(wi);
+            let val: f32 = wi.read(0, idx);
+            let addend: f32 = wi.scalar(1);
+            wi.write(0, idx, val + addend);
+        });
+        ocl_mock::register_kernel("eq", |wi| {
+            let idx = linear_idx(wi);
+            let val: f32 = wi.scalar(1);
+            wi.write(0, idx, val);
+        });
+        ocl_mock::register_kernel("add_float4", |wi| {
+            let idx = wi.global_id(0);
+            let val: Float4 = wi.read(0, idx);
+            let addend: Float4 = wi.scalar(1);
+            wi.write(0, idx, val + addend);
+        });
+        ocl_mock::register_kernel("add_int4", |wi| {
+            let idx = wi.global_id(0);
+            let val: Int4 = wi.read(0, idx);
+            let addend: Int4 = wi.scalar(1);
+            wi.write(2, idx, val + addend + Int4::splat(idx as i32));
+        });
+        ocl_mock::register_kernel("add_slowly", |wi| {
+            let idx = wi.global_id(0);
+            let val: Int4 = wi.read(0, idx);
+            let addend: i32 = wi.scalar(1);
+            wi.write(2, idx, val + Int4::splat(addend));
+        });
+    });
+}
+
+/// Does nothing (see the `mock` feature).
+#[cfg(not(feature = "mock"))]
+pub fn init() {}
+
+
 fn gen_region_origin(dims: &[usize; 3]) -> ([usize; 3], [usize; 3]) {
     let mut rng = rand::weak_rng();
 
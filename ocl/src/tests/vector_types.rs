@@ -9,6 +9,7 @@ const DATASET_SIZE: usize = 1 << 20;
 
 #[test]
 fn test_vector_types() {
+    ::tests::init();
 
     let src = r#"
         __kernel void add_int4(__global int4* in_buffer, int4 addend, __global int4* out_buffer) {